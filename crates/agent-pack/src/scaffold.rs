@@ -571,7 +571,7 @@ pub extern "Rust" fn agent_main(_ctx: &AgentContext, _opaque_inputs: &[u8]) -> A
     // Example: Create actions with CallBuilder
     // use kernel_sdk::actions::CallBuilder;
     // let action = CallBuilder::new(input.target_address)
-    //     .selector(0x12345678)
+    //     .selector(kernel_sdk::selector!("deposit(uint256)"))
     //     .param_u256_from_u64(input.amount)
     //     .build();
 
@@ -608,6 +608,8 @@ mod tests {
             constraint_set_hash: [0u8; 32],
             input_root: [0u8; 32],
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: StepMeter::default(),
         }
     }
 
@@ -683,8 +685,8 @@ include!(concat!(env!("OUT_DIR"), "/agent_hash.rs"));
 // Constants
 // ============================================================================
 
-/// Withdraw function selector: keccak256("withdraw(address)")[:4]
-const WITHDRAW_SELECTOR: u32 = 0x51cff8d9;
+/// Withdraw function selector.
+const WITHDRAW_SELECTOR: u32 = kernel_sdk::selector!("withdraw(address)");
 
 // ============================================================================
 // Input Parsing
@@ -772,6 +774,8 @@ mod tests {
             constraint_set_hash: [0xbb; 32],
             input_root: [0xcc; 32],
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: StepMeter::default(),
         }
     }
 
@@ -886,6 +890,8 @@ fn make_test_context() -> AgentContext {{
         constraint_set_hash: [0u8; 32],
         input_root: [0u8; 32],
         execution_nonce: 1,
+        state_snapshot: None,
+        step_meter: StepMeter::default(),
     }}
 }}
 
@@ -40,11 +40,11 @@ include!(concat!(env!("OUT_DIR"), "/agent_hash.rs"));
 // Constants
 // ============================================================================
 
-/// AAVE supply function selector: keccak256("supply(address,uint256,address,uint16)")[:4]
-const SUPPLY_SELECTOR: u32 = 0x617ba037;
+/// AAVE supply function selector.
+const SUPPLY_SELECTOR: u32 = kernel_sdk::selector!("supply(address,uint256,address,uint16)");
 
-/// AAVE withdraw function selector: keccak256("withdraw(address,uint256,address)")[:4]
-const WITHDRAW_SELECTOR: u32 = 0x69328dec;
+/// AAVE withdraw function selector.
+const WITHDRAW_SELECTOR: u32 = kernel_sdk::selector!("withdraw(address,uint256,address)");
 
 /// Action flag: evaluate market conditions and decide
 const FLAG_EVALUATE: u8 = 0;
@@ -85,26 +85,29 @@ kernel_sdk::agent_input! {
 /// Note: `#[no_mangle]` is intentionally omitted to avoid symbol collisions
 /// when multiple agent crates are linked into the same binary (e.g., tests).
 /// The wrapper crate calls this through the Rust module path.
-pub extern "Rust" fn agent_main(_ctx: &AgentContext, opaque_inputs: &[u8]) -> AgentOutput {
-    let market = match MarketInput::decode(opaque_inputs) {
-        Some(m) => m,
-        None => return AgentOutput { actions: Vec::new() },
-    };
+///
+/// Inputs that fail to decode or carry an unknown action flag are rejected
+/// with `AgentError::InvalidInput`.
+pub extern "Rust" fn agent_main(
+    _ctx: &AgentContext,
+    opaque_inputs: &[u8],
+) -> Result<AgentOutput, AgentError> {
+    let market = MarketInput::decode(opaque_inputs).ok_or(AgentError::InvalidInput)?;
 
     match market.action_flag {
-        FLAG_FORCE_SUPPLY => force_supply(&market),
-        FLAG_FORCE_WITHDRAW => force_withdraw(&market),
-        FLAG_EVALUATE => evaluate_and_act(&market),
-        FLAG_APPROVE_AND_SUPPLY => approve_and_supply(&market),
-        _ => AgentOutput { actions: Vec::new() }, // Unknown flag -> no-op
+        FLAG_FORCE_SUPPLY => Ok(force_supply(&market)),
+        FLAG_FORCE_WITHDRAW => Ok(force_withdraw(&market)),
+        FLAG_EVALUATE => Ok(evaluate_and_act(&market)),
+        FLAG_APPROVE_AND_SUPPLY => Ok(approve_and_supply(&market)),
+        _ => Err(AgentError::InvalidInput),
     }
 }
 
-/// Compile-time check that agent_main matches the canonical AgentEntrypoint type.
-const _: AgentEntrypoint = agent_main;
+/// Compile-time check that agent_main matches the fallible TryAgentEntrypoint type.
+const _: TryAgentEntrypoint = agent_main;
 
 // Generate kernel_main, kernel_main_with_constraints, and KernelError re-export.
-kernel_sdk::agent_entrypoint!(agent_main);
+kernel_sdk::agent_entrypoint!(try agent_main);
 
 // ============================================================================
 // Strategy Logic
@@ -235,7 +238,8 @@ mod tests {
     use super::*;
 
     /// Approve selector bytes for test assertions.
-    const APPROVE_SELECTOR_BYTES: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+    const APPROVE_SELECTOR_BYTES: [u8; 4] =
+        kernel_sdk::selector!("approve(address,uint256)").to_be_bytes();
 
     fn make_market_input(
         lending_pool: [u8; 20],
@@ -270,15 +274,16 @@ mod tests {
             constraint_set_hash: [0xbb; 32],
             input_root: [0xcc; 32],
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: StepMeter::default(),
         }
     }
 
     #[test]
-    fn test_invalid_input_returns_empty() {
+    fn test_invalid_input_is_rejected() {
         let ctx = test_ctx();
         let short = alloc::vec![0u8; 10];
-        let output = agent_main(&ctx, &short);
-        assert!(output.actions.is_empty());
+        assert_eq!(agent_main(&ctx, &short), Err(AgentError::InvalidInput));
     }
 
     #[test]
@@ -295,7 +300,7 @@ mod tests {
             8000,          // target_utilization: 80%
             0,             // action_flag: evaluate
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         // Rate (500 bps) > min (200 bps) and vault has balance -> should supply
         assert_eq!(output.actions.len(), 1, "Should produce 1 supply action");
         assert_eq!(output.actions[0].action_type, ACTION_TYPE_CALL);
@@ -315,7 +320,7 @@ mod tests {
             8000,
             0,
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         assert!(output.actions.is_empty(), "Rate below threshold -> no action");
     }
 
@@ -333,7 +338,7 @@ mod tests {
             8000,
             0,
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         // Rate dropped below min while we have supplied -> should withdraw
         assert_eq!(output.actions.len(), 1, "Should produce 1 withdraw action");
         assert_eq!(output.actions[0].action_type, ACTION_TYPE_CALL);
@@ -353,7 +358,7 @@ mod tests {
             8000,
             1,        // FLAG_FORCE_SUPPLY
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         assert_eq!(output.actions.len(), 1);
         assert_eq!(output.actions[0].action_type, ACTION_TYPE_CALL);
     }
@@ -372,7 +377,7 @@ mod tests {
             8000,
             1,        // FLAG_FORCE_SUPPLY
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         assert!(output.actions.is_empty());
     }
 
@@ -390,7 +395,7 @@ mod tests {
             8000,
             2,        // FLAG_FORCE_WITHDRAW
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         assert_eq!(output.actions.len(), 1);
         assert_eq!(output.actions[0].action_type, ACTION_TYPE_CALL);
     }
@@ -409,7 +414,7 @@ mod tests {
             8000,
             2,        // FLAG_FORCE_WITHDRAW
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         assert!(output.actions.is_empty());
     }
 
@@ -427,7 +432,7 @@ mod tests {
             8000,       // target: 80%
             0,
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         // Already at target (800K / 1M = 80%) -> no additional supply needed
         assert!(output.actions.is_empty());
     }
@@ -446,7 +451,7 @@ mod tests {
             8000,       // target: 80% of 800K total = 640K
             0,
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         // Need: 640K - 300K = 340K, have 500K available -> supply 340K
         assert_eq!(output.actions.len(), 1);
     }
@@ -465,12 +470,12 @@ mod tests {
             8000,
             0,
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         assert!(output.actions.is_empty());
     }
 
     #[test]
-    fn test_unknown_flag_no_action() {
+    fn test_unknown_flag_is_rejected() {
         let ctx = test_ctx();
         let input = make_market_input(
             [0x11u8; 20],
@@ -483,8 +488,7 @@ mod tests {
             8000,
             99,  // unknown flag
         );
-        let output = agent_main(&ctx, &input);
-        assert!(output.actions.is_empty());
+        assert_eq!(agent_main(&ctx, &input), Err(AgentError::InvalidInput));
     }
 
     #[test]
@@ -501,7 +505,7 @@ mod tests {
             8000,
             1,  // force supply
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         assert_eq!(output.actions.len(), 1);
 
         let payload = &output.actions[0].payload;
@@ -533,8 +537,8 @@ mod tests {
             8000,
             0,
         );
-        let output1 = agent_main(&ctx, &input);
-        let output2 = agent_main(&ctx, &input);
+        let output1 = agent_main(&ctx, &input).unwrap();
+        let output2 = agent_main(&ctx, &input).unwrap();
         assert_eq!(output1.actions.len(), output2.actions.len());
         for (a, b) in output1.actions.iter().zip(output2.actions.iter()) {
             assert_eq!(a.action_type, b.action_type);
@@ -557,7 +561,7 @@ mod tests {
             8000,
             3,             // FLAG_APPROVE_AND_SUPPLY
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         // Should produce 2 actions: approve + supply
         assert_eq!(output.actions.len(), 2);
         assert_eq!(output.actions[0].action_type, ACTION_TYPE_CALL); // approve
@@ -594,7 +598,7 @@ mod tests {
             8000,
             3,        // FLAG_APPROVE_AND_SUPPLY
         );
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         assert!(output.actions.is_empty());
     }
 }
@@ -6,8 +6,9 @@ fn main() {
     use risc0_zkvm::guest::env;
 
     let input_bytes: Vec<u8> = env::read();
+    let constraint_set_bytes: Vec<u8> = env::read();
 
-    match defi_yield_farmer::kernel_main(&input_bytes) {
+    match defi_yield_farmer::kernel_main_with_constraint_bytes(&input_bytes, &constraint_set_bytes) {
         Ok(journal_bytes) => {
             env::commit_slice(&journal_bytes);
         }
@@ -40,8 +40,8 @@ include!(concat!(env!("OUT_DIR"), "/agent_hash.rs"));
 // Constants
 // ============================================================================
 
-/// Withdraw function selector: keccak256("withdraw(address)")[:4]
-const WITHDRAW_SELECTOR: u32 = 0x51cff8d9;
+/// Withdraw function selector.
+const WITHDRAW_SELECTOR: u32 = kernel_sdk::selector!("withdraw(address)");
 
 // ============================================================================
 // Input Parsing
@@ -130,6 +130,8 @@ mod tests {
             constraint_set_hash: [0xbb; 32],
             input_root: [0xcc; 32],
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: StepMeter::default(),
         };
 
         let vault = [0x11u8; 20];
@@ -167,6 +169,8 @@ mod tests {
             constraint_set_hash: [0xbb; 32],
             input_root: [0xcc; 32],
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: StepMeter::default(),
         };
 
         let vault = [0x11u8; 20];
@@ -209,6 +213,8 @@ mod tests {
             constraint_set_hash: [0xbb; 32],
             input_root: [0xcc; 32],
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: StepMeter::default(),
         };
 
         let vault = [0x11u8; 20];
@@ -287,6 +293,8 @@ mod tests {
             constraint_set_hash: [0xbb; 32],
             input_root: [0xcc; 32],
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: StepMeter::default(),
         };
 
         // Test too short
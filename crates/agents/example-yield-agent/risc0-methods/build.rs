@@ -1,7 +1,8 @@
 //! Build script for the risc0-methods crate.
 //!
-//! This compiles zkvm-guest as a RISC Zero guest program and generates
-//! Rust source code with the ELF binary and IMAGE_ID embedded.
+//! This compiles zkvm-guest and zkvm-guest-batch as RISC Zero guest programs
+//! and generates Rust source code with their ELF binaries and IMAGE_IDs
+//! embedded.
 //!
 //! zkvm-guest is a thin wrapper that uses a binding crate to bind
 //! a specific agent to the kernel-guest library.
@@ -9,6 +10,7 @@
 //! The generated code exports:
 //! - `ZKVM_GUEST_ELF`: The compiled guest ELF binary
 //! - `ZKVM_GUEST_ID`: The IMAGE_ID (hash of the ELF)
+//! - `ZKVM_GUEST_BATCH_ELF`, `ZKVM_GUEST_BATCH_ID`: The same for the batch guest
 //!
 //! # Build Environment
 //!
@@ -24,6 +26,7 @@ fn main() {
 
     // Paths to rebuild triggers (relative to crates/runtime/risc0-methods)
     let zkvm_guest_dir = manifest_dir.join("zkvm-guest");
+    let zkvm_guest_batch_dir = manifest_dir.join("zkvm-guest-batch");
     let kernel_guest_dir = manifest_dir.join("../kernel-guest");
     let wrapper_dir = manifest_dir.join("../../agents/wrappers/kernel-guest-binding-yield");
     let yield_agent_dir = manifest_dir.join("../../agents/examples/example-yield-agent");
@@ -38,6 +41,16 @@ fn main() {
         zkvm_guest_dir.join("Cargo.toml").display()
     );
 
+    // Rebuild if the batch guest wrapper changes
+    println!(
+        "cargo:rerun-if-changed={}",
+        zkvm_guest_batch_dir.join("src").display()
+    );
+    println!(
+        "cargo:rerun-if-changed={}",
+        zkvm_guest_batch_dir.join("Cargo.toml").display()
+    );
+
     // Rebuild if kernel-guest (the library) changes
     println!(
         "cargo:rerun-if-changed={}",
@@ -7,6 +7,8 @@
 //!
 //! - [`ZKVM_GUEST_ELF`]: The compiled RISC Zero guest ELF binary
 //! - [`ZKVM_GUEST_ID`]: The IMAGE_ID (32-byte hash identifying the guest)
+//! - [`ZKVM_GUEST_BATCH_ELF`], [`ZKVM_GUEST_BATCH_ID`]: The batch guest, which
+//!   proves a `KernelBatchInputV1` instead of a single input
 //!
 //! # Usage
 //!
@@ -15,7 +17,10 @@
 //! use risc0_zkvm::{default_prover, ExecutorEnv};
 //!
 //! let env = ExecutorEnv::builder()
-//!     .write_slice(&input_bytes)
+//!     .write(&input_bytes)
+//!     .unwrap()
+//!     .write(&constraint_set_bytes)
+//!     .unwrap()
 //!     .build()
 //!     .unwrap();
 //!
@@ -30,10 +35,15 @@
 //!
 //! The guest is built from `zkvm-guest/`, a thin wrapper around `kernel-guest`
 //! that provides the main() entry point for zkVM execution. The wrapper:
-//! 1. Reads input bytes from the host via `env::read()`
-//! 2. Calls `kernel_guest::kernel_main()` to execute the kernel
+//! 1. Reads input bytes and constraint set bytes from the host via `env::read()`
+//! 2. Calls `example_yield_agent::kernel_main_with_constraint_bytes()` to execute the kernel
 //! 3. Commits the resulting journal to the proof via `env::commit_slice()`
 //!
+//! The batch guest is built from `zkvm-guest-batch/` the same way. It reads
+//! batch bytes instead of input bytes, calls
+//! `example_yield_agent::kernel_batch_main_with_constraint_bytes()` and commits
+//! a `KernelBatchJournalV1`.
+//!
 //! # Determinism
 //!
 //! For reproducible builds, set `RISC0_USE_DOCKER=1` before building.
@@ -41,5 +51,5 @@
 //! toolchain, producing identical binaries across environments.
 
 // Include the generated code from build.rs
-// This provides ZKVM_GUEST_ELF and ZKVM_GUEST_ID constants
+// This provides the ZKVM_GUEST_* and ZKVM_GUEST_BATCH_* constants
 include!(concat!(env!("OUT_DIR"), "/methods.rs"));
@@ -0,0 +1,46 @@
+//! RISC Zero zkVM Batch Guest Entry Point
+//!
+//! Proves up to `MAX_BATCH_STEPS` executions of the example yield agent at
+//! once. The agent crate generates the batch entry point via the
+//! agent_entrypoint! macro.
+//!
+//! # Execution Flow
+//!
+//! 1. Read `KernelBatchInputV1` bytes from the host via `env::read()`
+//! 2. Read `ConstraintSetV1` bytes from the host via `env::read()`
+//! 3. Execute `kernel_batch_main_with_constraint_bytes()` which runs every
+//!    step in place and enforces the constraint set on each
+//! 4. Commit the `KernelBatchJournalV1` bytes to the journal via `env::commit_slice()`
+//!
+//! # Error Handling
+//!
+//! If any step fails (e.g., version mismatch, agent code hash mismatch,
+//! constraint set hash mismatch) or the batch is malformed, the guest panics.
+//! This aborts proof generation - no valid receipt is produced. The same
+//! happens when the agent allocates more than `MAX_ALLOCATION_BYTES` of
+//! risc0's heap in any step.
+
+fn main() {
+    use risc0_zkvm::guest::env;
+
+    // Read batch bytes from the host
+    let batch_bytes: Vec<u8> = env::read();
+
+    // Read the constraint set to enforce; it must hash to constraint_set_hash
+    let constraint_set_bytes: Vec<u8> = env::read();
+
+    // Execute every step via the agent crate (which binds the specific agent)
+    match example_yield_agent::kernel_batch_main_with_constraint_bytes(
+        &batch_bytes,
+        &constraint_set_bytes,
+    ) {
+        Ok(journal_bytes) => {
+            // Commit batch journal to the proof receipt
+            env::commit_slice(&journal_bytes);
+        }
+        Err(error) => {
+            // Panic aborts proof generation - this is intentional
+            panic!("Kernel batch execution failed: {:?}", error);
+        }
+    }
+}
@@ -6,13 +6,18 @@
 //! # Execution Flow
 //!
 //! 1. Read `KernelInputV1` bytes from the host via `env::read()`
-//! 2. Execute `kernel_main()` which runs the agent and enforces constraints
-//! 3. Commit the `KernelJournalV1` bytes to the journal via `env::commit_slice()`
+//! 2. Read `ConstraintSetV1` bytes from the host via `env::read()`
+//! 3. Execute `kernel_main_with_constraint_bytes()` which runs the agent and
+//!    enforces the constraint set bound by `constraint_set_hash`
+//! 4. Commit the `KernelJournalV1` bytes to the journal via `env::commit_slice()`
 //!
 //! # Error Handling
 //!
-//! If kernel execution fails (e.g., version mismatch, agent code hash mismatch),
+//! If kernel execution fails (e.g., version mismatch, agent code hash mismatch,
+//! constraint set hash mismatch),
 //! the guest panics. This aborts proof generation - no valid receipt is produced.
+//! The same happens when the agent allocates more than `MAX_ALLOCATION_BYTES`
+//! of risc0's heap, which the kernel checks once the agent returns.
 
 fn main() {
     use risc0_zkvm::guest::env;
@@ -20,8 +25,11 @@ fn main() {
     // Read input bytes from the host
     let input_bytes: Vec<u8> = env::read();
 
+    // Read the constraint set to enforce; it must hash to constraint_set_hash
+    let constraint_set_bytes: Vec<u8> = env::read();
+
     // Execute kernel via the agent crate (which binds the specific agent)
-    match example_yield_agent::kernel_main(&input_bytes) {
+    match example_yield_agent::kernel_main_with_constraint_bytes(&input_bytes, &constraint_set_bytes) {
         Ok(journal_bytes) => {
             // Commit journal to the proof receipt
             env::commit_slice(&journal_bytes);
@@ -7,12 +7,15 @@
 //!
 //! # Input Format (variable length)
 //!
-//! The `opaque_agent_inputs` field is split into three parts:
+//! The agent reads three parts:
 //!
-//! ## Part 1: StateSnapshotV1 (first 36 bytes)
+//! ## Part 1: StateSnapshotV1 (36 bytes)
 //!
 //! Used by the constraint engine for drawdown/cooldown checks AND by the agent
-//! for its own internal drawdown circuit breaker.
+//! for its own internal drawdown circuit breaker. Kernel version 5 inputs carry
+//! it in their typed snapshot slot (`ctx.state_snapshot`), and
+//! `opaque_agent_inputs` starts directly with Part 2. Earlier input versions
+//! carry it as the first 36 bytes of `opaque_agent_inputs`:
 //!
 //! ```text
 //! [0:4]    snapshot_version (u32 LE, must be 1)
@@ -69,7 +72,6 @@
 extern crate alloc;
 
 use alloc::vec::Vec;
-use constraints::StateSnapshotV1;
 use kernel_sdk::actions::CallBuilder;
 use kernel_sdk::prelude::*;
 
@@ -102,20 +104,28 @@ const LIQUIDATION_PROXIMITY_BPS: u64 = 300;
 /// Exit when funding rate exceeds threshold * this multiplier against position direction.
 const FUNDING_REVERSAL_MULTIPLIER: u64 = 3;
 
+/// Close price offset from mark in basis points (3%).
+/// Stays inside HyperCore's ~5% oracle price band.
+const CLOSE_PRICE_OFFSET_BPS: u64 = 300;
+
+/// Price tick for close orders: $1 in 1e8 scale (BTC).
+const PRICE_TICK: Fixed8 = Fixed8::from_raw(100);
+
 
 /// HyperliquidAdapter.openPosition(bool isBuy, uint256 marginAmount, uint256 orderSize, uint256 limitPrice)
-/// Selector: keccak256("openPosition(bool,uint256,uint256,uint256)")[:4] = 0x04ba41cb
-const OPEN_POSITION_SELECTOR: u32 = 0x04ba41cb;
+/// Selector: 0x04ba41cb
+const OPEN_POSITION_SELECTOR: u32 =
+    kernel_sdk::selector!("openPosition(bool,uint256,uint256,uint256)");
 
 /// HyperliquidAdapter.depositMargin(uint256 amount)
-/// Selector: keccak256("depositMargin(uint256)")[:4] = 0x19bd1776
+/// Selector: 0x19bd1776
 /// Deposits USDC from vault to HyperCore perp margin (no order placed).
-const DEPOSIT_MARGIN_SELECTOR: u32 = 0x19bd1776;
+const DEPOSIT_MARGIN_SELECTOR: u32 = kernel_sdk::selector!("depositMargin(uint256)");
 
 /// HyperliquidAdapter.closePositionAtPrice(uint64 px)
-/// Selector: keccak256("closePositionAtPrice(uint64)")[:4] = 0x2c0f36da
+/// Selector: 0x2c0f36da
 /// Uses agent-computed price within HyperCore's oracle band.
-const CLOSE_AT_PRICE_SELECTOR: u32 = 0x2c0f36da;
+const CLOSE_AT_PRICE_SELECTOR: u32 = kernel_sdk::selector!("closePositionAtPrice(uint64)");
 
 /// Action flag: evaluate market conditions and decide
 const FLAG_EVALUATE: u8 = 0;
@@ -149,6 +159,12 @@ const ORACLE_ASSET_ID_MARK: u32 = 1;
 /// than snapshot.current_ts - this value, the agent refuses to act.
 const MAX_ORACLE_STALENESS_SECONDS: u64 = 120;
 
+/// Agent error code: the oracle feed hash does not match `ctx.input_root`.
+pub const ERR_FEED_COMMITMENT_MISMATCH: u32 = 1;
+
+/// Agent error code: the oracle feed is older than `MAX_ORACLE_STALENESS_SECONDS`.
+pub const ERR_STALE_FEED: u32 = 2;
+
 // ============================================================================
 // Input Parsing
 // ============================================================================
@@ -219,59 +235,63 @@ kernel_sdk::agent_input! {
 /// Canonical agent entrypoint.
 ///
 /// Parses three-part input:
-///   1. StateSnapshotV1 (36 bytes) — drawdown/cooldown state
+///   1. StateSnapshotV1 (36 bytes) — drawdown/cooldown state, from
+///      `ctx.state_snapshot` if the kernel provides it, else the first 36
+///      bytes of `opaque_inputs`
 ///   2. OraclePriceFeed (variable, 111..607 bytes) — verified market prices
 ///   3. PerpInput (238 bytes) — strategy parameters, position state
 ///
 /// The oracle feed hash is verified against `ctx.input_root` to bind the
 /// price data to the ZK proof. Staleness is checked against the snapshot timestamp.
-pub extern "Rust" fn agent_main(ctx: &AgentContext, opaque_inputs: &[u8]) -> AgentOutput {
+///
+/// Inputs that cannot be parsed are rejected with `AgentError::InvalidInput`
+/// (or `MissingStateSnapshot`), and a feed that fails the commitment or
+/// staleness check with `AgentError::Custom(ERR_FEED_COMMITMENT_MISMATCH)` or
+/// `AgentError::Custom(ERR_STALE_FEED)`.
+pub extern "Rust" fn agent_main(
+    ctx: &AgentContext,
+    opaque_inputs: &[u8],
+) -> Result<AgentOutput, AgentError> {
     let empty = AgentOutput { actions: Vec::new() };
 
-    // Minimum length: snapshot + smallest oracle feed (1 price) + perp input
-    let min_len = StateSnapshotV1::ENCODED_SIZE
-        + kernel_sdk::oracle::feed_wire_len(1)
-        + PerpInput::ENCODED_SIZE;
-    if opaque_inputs.len() < min_len {
-        return empty;
-    }
-
-    // Part 1: Parse StateSnapshotV1 (first 36 bytes)
-    let snapshot = match StateSnapshotV1::decode(&opaque_inputs[..StateSnapshotV1::ENCODED_SIZE]) {
-        Some(s) => s,
-        None => return empty,
+    // Part 1: state snapshot from the typed slot, or else the first 36 bytes
+    let (snapshot, agent_inputs) = match ctx.state_snapshot {
+        Some(s) => (s, opaque_inputs),
+        None => match StateSnapshotV1::decode(opaque_inputs) {
+            Some(s) => (s.into(), &opaque_inputs[StateSnapshotV1::ENCODED_SIZE..]),
+            None => return Err(AgentError::MissingStateSnapshot),
+        },
     };
 
+    // Minimum length: smallest oracle feed (1 price) + perp input
+    let min_len = kernel_sdk::oracle::feed_wire_len(1) + PerpInput::ENCODED_SIZE;
+    if agent_inputs.len() < min_len {
+        return Err(AgentError::InvalidInput);
+    }
+
     // Part 2: Decode OraclePriceFeed from the middle section
-    let oracle_start = StateSnapshotV1::ENCODED_SIZE;
-    let oracle_section = &opaque_inputs[oracle_start..];
-    let feed = match decode_price_feed(oracle_section) {
-        Some(f) => f,
-        None => return empty,
-    };
+    let feed = decode_price_feed(agent_inputs).ok_or(AgentError::InvalidInput)?;
 
     // Verify feed commitment: SHA256(feed body) must equal ctx.input_root
     if !verify_feed_commitment(&feed, ctx) {
-        return empty;
+        return Err(AgentError::Custom(ERR_FEED_COMMITMENT_MISMATCH));
     }
 
     // Staleness check: feed must not be older than MAX_ORACLE_STALENESS_SECONDS
-    if snapshot.current_ts > feed.timestamp {
-        if snapshot.current_ts - feed.timestamp > MAX_ORACLE_STALENESS_SECONDS {
-            return empty;
+    if snapshot.current_ts() > feed.timestamp {
+        if snapshot.current_ts() - feed.timestamp > MAX_ORACLE_STALENESS_SECONDS {
+            return Err(AgentError::Custom(ERR_STALE_FEED));
         }
     }
 
     // Part 3: Parse PerpInput from bytes after the oracle feed
-    let oracle_len = kernel_sdk::oracle::feed_wire_len(feed.price_count);
-    let perp_start = oracle_start + oracle_len;
-    if opaque_inputs.len() < perp_start + PerpInput::ENCODED_SIZE {
-        return empty;
-    }
-    let mut input = match PerpInput::decode(&opaque_inputs[perp_start..perp_start + PerpInput::ENCODED_SIZE]) {
-        Some(m) => m,
-        None => return empty,
-    };
+    let perp_start = kernel_sdk::oracle::feed_wire_len(feed.price_count);
+    if agent_inputs.len() < perp_start + PerpInput::ENCODED_SIZE {
+        return Err(AgentError::InvalidInput);
+    }
+    let mut input =
+        PerpInput::decode(&agent_inputs[perp_start..perp_start + PerpInput::ENCODED_SIZE])
+            .ok_or(AgentError::InvalidInput)?;
 
     // Override mark_price with verified oracle price (if available)
     if let Some(verified_price) = get_price(&feed, ORACLE_ASSET_ID_MARK) {
@@ -282,22 +302,22 @@ pub extern "Rust" fn agent_main(ctx: &AgentContext, opaque_inputs: &[u8]) -> Age
     match input.action_flag {
         FLAG_FORCE_CLOSE => {
             if has_position(&input) {
-                build_close_actions(&input)
+                Ok(build_close_actions(&input))
             } else {
-                empty
+                Ok(empty)
             }
         }
-        FLAG_FORCE_FLAT => empty,
-        FLAG_EVALUATE => evaluate_and_act(&snapshot, &input),
-        _ => empty,
+        FLAG_FORCE_FLAT => Ok(empty),
+        FLAG_EVALUATE => Ok(evaluate_and_act(&snapshot, &input)),
+        _ => Err(AgentError::InvalidInput),
     }
 }
 
-/// Compile-time check that agent_main matches the canonical AgentEntrypoint type.
-const _: AgentEntrypoint = agent_main;
+/// Compile-time check that agent_main matches the fallible TryAgentEntrypoint type.
+const _: TryAgentEntrypoint = agent_main;
 
 // Generate kernel_main, kernel_main_with_constraints, and KernelError re-export.
-kernel_sdk::agent_entrypoint!(agent_main);
+kernel_sdk::agent_entrypoint!(try agent_main);
 
 // ============================================================================
 // Strategy Logic
@@ -321,7 +341,7 @@ fn effective_max_drawdown_bps(input: &PerpInput) -> u64 {
 }
 
 /// Main strategy evaluation: risk checks, exit conditions, entry signals.
-fn evaluate_and_act(snapshot: &StateSnapshotV1, input: &PerpInput) -> AgentOutput {
+fn evaluate_and_act(snapshot: &StateSnapshot, input: &PerpInput) -> AgentOutput {
     // Sanity: need valid mark price
     if input.mark_price == 0 {
         return AgentOutput { actions: Vec::new() };
@@ -340,8 +360,8 @@ fn evaluate_and_act(snapshot: &StateSnapshotV1, input: &PerpInput) -> AgentOutpu
 
     // 1. Drawdown circuit breaker (configurable, default 5%)
     let max_dd = effective_max_drawdown_bps(input);
-    if snapshot.peak_equity > 0 {
-        if let Some(dd) = drawdown_bps(snapshot.current_equity, snapshot.peak_equity) {
+    if snapshot.peak_equity() > 0 {
+        if let Some(dd) = drawdown_bps(snapshot.current_equity(), snapshot.peak_equity()) {
             if dd >= max_dd && has_position(input) {
                 return build_close_actions(input);
             }
@@ -607,8 +627,8 @@ fn compute_margin_amount(input: &PerpInput) -> u64 {
 
 /// Convert USDC margin to base-asset order size for Hyperliquid CoreWriter.
 ///
-/// Formula (using u128 to avoid overflow):
-///   order_sz = margin * max_leverage_bps * 10^(sz_decimals + 2) / (mark_price * 10_000)
+/// Formula:
+///   order_sz = margin * max_leverage_bps / 10_000 / mark_price, at sz_decimals
 ///
 /// Where:
 ///   - margin is USDC in 6-decimal raw units (100 USDC = 100_000_000)
@@ -616,21 +636,32 @@ fn compute_margin_amount(input: &PerpInput) -> u64 {
 ///   - sz_decimals is Hyperliquid's szDecimals for the asset (BTC=5, ETH=4)
 ///   - max_leverage_bps is leverage in bps (30_000 = 3x)
 ///
-/// The factor 10^2 bridges the USDC 1e6 scale to the price 1e8 scale.
+/// Every step is carried out in 18-decimal fixed point and rounds down, so the
+/// size never exceeds the margin. Returns 0 if the size does not fit in u64.
 fn compute_order_size(margin: u64, mark_price: u64, sz_decimals: u8, max_leverage_bps: u32) -> u64 {
     if mark_price == 0 || margin == 0 {
         return 0;
     }
     // Cap sz_decimals to 8 to prevent unreasonable exponent
-    let dec = if sz_decimals > 8 { 8 } else { sz_decimals };
-    let scale = 10u128.pow(dec as u32 + 2);
-    let numerator = (margin as u128) * (max_leverage_bps as u128) * scale;
-    let denominator = (mark_price as u128) * 10_000u128;
-    let result = numerator / denominator;
-    if result > u64::MAX as u128 {
-        return 0;
-    }
-    result as u64
+    let dec = core::cmp::min(sz_decimals, 8);
+    order_size_raw(margin, mark_price, dec, max_leverage_bps)
+        .and_then(|sz| u64::try_from(sz).ok())
+        .unwrap_or(0)
+}
+
+/// Order size in raw sz_decimals units, or `None` on overflow.
+fn order_size_raw(margin: u64, mark_price: u64, sz_decimals: u8, max_leverage_bps: u32) -> Option<u128> {
+    let margin = Fixed6::from_raw(margin.into()).rescale::<18>(Rounding::Down)?;
+    let price = Fixed8::from_raw(mark_price.into()).rescale::<18>(Rounding::Down)?;
+    let leverage = Fixed18::from_ratio(
+        max_leverage_bps.into(),
+        BPS_DENOMINATOR.into(),
+        Rounding::Down,
+    )?;
+    margin
+        .checked_mul(leverage, Rounding::Down)?
+        .checked_div(price, Rounding::Down)?
+        .to_scaled(sz_decimals.into(), Rounding::Down)
 }
 
 /// Compute a close limit price within HyperCore's oracle price band (~5%).
@@ -642,19 +673,24 @@ fn compute_order_size(margin: u64, mark_price: u64, sz_decimals: u8, max_leverag
 /// ZK proof generation (~8-10 min). If the price drifts more than 3%, the host
 /// falls back to the REST API which uses real-time L2 orderbook prices.
 ///
-/// BTC tick size is $1 = 100 in 1e8 scale. Price is rounded to tick.
+/// BTC tick size is $1 = 100 in 1e8 scale. Price is rounded down to tick.
 fn compute_close_price(mark_price: u64, is_short: bool) -> u64 {
-    if is_short {
+    let mark = Fixed8::from_raw(mark_price.into());
+    // 3% of a u64 price always fits
+    let delta = mark
+        .checked_mul_bps(CLOSE_PRICE_OFFSET_BPS, Rounding::Down)
+        .unwrap_or_default();
+    let px = if is_short {
         // Closing a short = buying → aggressive price above mark
-        let delta = mark_price * 3 / 100; // 3%
-        let px = saturating_add_u64(mark_price, delta);
-        (px / 100) * 100 // round down to tick ($1 = 100 in 1e8)
+        mark.saturating_add(delta)
     } else {
         // Closing a long = selling → aggressive price below mark
-        let delta = mark_price * 3 / 100; // 3%
-        let px = saturating_sub_u64(mark_price, delta);
-        (px / 100) * 100 // round down to tick
-    }
+        mark.saturating_sub(delta)
+    };
+    // Saturate at the largest u64 price, as the order field is uint64
+    let px = core::cmp::min(px, Fixed8::from_raw(u64::MAX.into()));
+    px.round_to_tick(PRICE_TICK, Rounding::Down)
+        .map_or(0, |px| px.raw() as u64)
 }
 
 // ============================================================================
@@ -859,6 +895,7 @@ mod tests {
             in_drawdown_cooldown: false,
             strategy_mode: STRATEGY_SMA_CROSSOVER,
             sz_decimals: 5,                // BTC default
+            open_phase: OPEN_PHASE_NORMAL,
         }
     }
 
@@ -900,6 +937,7 @@ mod tests {
         in_drawdown_cooldown: bool,
         strategy_mode: u8,
         sz_decimals: u8,
+        open_phase: u8,
     }
 
     impl PerpInputBuilder {
@@ -942,6 +980,7 @@ mod tests {
             buf.push(if self.in_drawdown_cooldown { 1 } else { 0 });
             buf.push(self.strategy_mode);
             buf.push(self.sz_decimals);
+            buf.push(self.open_phase);
             buf
         }
     }
@@ -967,6 +1006,8 @@ mod tests {
             constraint_set_hash: [0xbb; 32],
             input_root: feed_hash,
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: StepMeter::default(),
         }
     }
 
@@ -982,16 +1023,31 @@ mod tests {
 
     #[test]
     fn test_perp_input_encoded_size() {
-        // Original 228 + 10 new bytes + 1 sz_decimals = 239
-        assert_eq!(PerpInput::ENCODED_SIZE, 239);
+        // Original 228 + 10 new bytes + 1 sz_decimals + 1 open_phase = 240
+        assert_eq!(PerpInput::ENCODED_SIZE, 240);
     }
 
     #[test]
-    fn test_invalid_input_returns_empty() {
+    fn test_input_without_snapshot_is_rejected() {
         let ctx = test_ctx([0u8; 32]);
         let short = alloc::vec![0u8; 10];
-        let output = agent_main(&ctx, &short);
-        assert!(output.actions.is_empty());
+        assert_eq!(agent_main(&ctx, &short), Err(AgentError::MissingStateSnapshot));
+    }
+
+    #[test]
+    fn test_typed_snapshot_matches_prefixed_snapshot() {
+        let perp = make_default_perp_input();
+        let (ctx, prefixed) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
+        let snapshot = StateSnapshotV1::decode(&prefixed).unwrap();
+
+        // Kernel v5: snapshot in the context, opaque inputs start at the feed
+        let typed_ctx = AgentContext {
+            kernel_version: 5,
+            state_snapshot: Some(snapshot.into()),
+            ..ctx.clone()
+        };
+        let typed = agent_main(&typed_ctx, &prefixed[StateSnapshotV1::ENCODED_SIZE..]).unwrap();
+        assert_eq!(typed, agent_main(&ctx, &prefixed).unwrap());
     }
 
     // ====================================================================
@@ -1012,7 +1068,7 @@ mod tests {
         perp.funding_rate_is_neg = false;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         // Should produce 2 actions: approve + open long
         assert_eq!(output.actions.len(), 2, "Should produce approve + open long");
@@ -1042,7 +1098,7 @@ mod tests {
         perp.funding_rate_is_neg = false;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 2, "Should produce approve + open short");
         assert_eq!(output.actions[0].action_type, ACTION_TYPE_CALL);
@@ -1059,7 +1115,7 @@ mod tests {
         perp.sma_slow = 50_000 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert!(output.actions.is_empty(), "No crossover -> no action");
     }
@@ -1080,7 +1136,7 @@ mod tests {
         perp.mark_price = 48_500 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Should close on stop loss");
         assert_eq!(output.actions[0].action_type, ACTION_TYPE_CALL); // close
@@ -1098,7 +1154,7 @@ mod tests {
         perp.mark_price = 52_500 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Should close on take profit");
     }
@@ -1115,7 +1171,7 @@ mod tests {
         // Effective threshold = 500 - 100 (safety margin) = 400 bps (4%)
         // 5% drawdown: current=95K, peak=100K -> 500 bps > 400 -> triggers
         let (ctx, input) = build_test(95_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "5% drawdown should trigger close");
     }
@@ -1131,13 +1187,13 @@ mod tests {
         // Effective threshold = 800 - 100 = 700 bps (7%)
         // 5% drawdown: current=95K, peak=100K -> 500 bps < 700 -> does NOT trigger
         let (ctx, input) = build_test(95_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert!(output.actions.is_empty(), "5% drawdown should not trigger with 8% limit");
 
         // 8% drawdown: current=92K, peak=100K -> 800 bps > 700 -> triggers
         let (ctx, input2) = build_test(92_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output2 = agent_main(&ctx, &input2);
+        let output2 = agent_main(&ctx, &input2).unwrap();
 
         assert_eq!(output2.actions.len(), 1, "8% drawdown should trigger close");
     }
@@ -1153,7 +1209,7 @@ mod tests {
         // Default effective = 500 - 100 = 400 bps (4%)
         // 5% drawdown: 500 bps > 400 -> triggers
         let (ctx, input) = build_test(95_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Default 5% drawdown should trigger close");
     }
@@ -1173,7 +1229,7 @@ mod tests {
 
         // 5% drawdown with no position -> should NOT enter new position
         let (ctx, input) = build_test(95_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert!(output.actions.is_empty(), "Should not enter new positions during drawdown");
     }
@@ -1187,7 +1243,7 @@ mod tests {
         perp.entry_price = 50_000 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Cooldown should close position");
     }
@@ -1206,7 +1262,7 @@ mod tests {
         perp.funding_rate_abs = 0;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert!(output.actions.is_empty(), "Cooldown should prevent new entries");
     }
@@ -1223,7 +1279,7 @@ mod tests {
         perp.liquidation_price = 49_000 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Should close on liquidation proximity");
     }
@@ -1241,7 +1297,7 @@ mod tests {
         perp.funding_rate_is_neg = false;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Should close on funding reversal");
     }
@@ -1260,7 +1316,7 @@ mod tests {
         perp.sma_slow = 50_000 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Should close on trend reversal");
     }
@@ -1278,7 +1334,7 @@ mod tests {
         perp.entry_price = 50_000 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Force close should produce close action");
     }
@@ -1290,7 +1346,7 @@ mod tests {
         perp.position_size_abs = 0; // no position
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert!(output.actions.is_empty(), "Force close with no position -> no-op");
     }
@@ -1306,20 +1362,18 @@ mod tests {
         perp.sma_slow = 50_000 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert!(output.actions.is_empty(), "Force flat -> no-op");
     }
 
     #[test]
-    fn test_unknown_flag_no_action() {
+    fn test_unknown_flag_is_rejected() {
         let mut perp = make_default_perp_input();
         perp.action_flag = 99;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
-
-        assert!(output.actions.is_empty());
+        assert_eq!(agent_main(&ctx, &input), Err(AgentError::InvalidInput));
     }
 
     // ====================================================================
@@ -1343,7 +1397,7 @@ mod tests {
         perp.max_position_bps = 5_000; // 50%
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         // Should still trade but with capped size
         assert_eq!(output.actions.len(), 2, "Should open position with capped size");
@@ -1365,8 +1419,8 @@ mod tests {
         perp.funding_rate_abs = 0;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output1 = agent_main(&ctx, &input);
-        let output2 = agent_main(&ctx, &input);
+        let output1 = agent_main(&ctx, &input).unwrap();
+        let output2 = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output1.actions.len(), output2.actions.len());
         for (a, b) in output1.actions.iter().zip(output2.actions.iter()) {
@@ -1392,7 +1446,7 @@ mod tests {
         perp.mark_price = 51_500 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Short stop loss should trigger close");
     }
@@ -1409,7 +1463,7 @@ mod tests {
         perp.mark_price = 47_500 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Short take profit should trigger close");
     }
@@ -1427,7 +1481,7 @@ mod tests {
         perp.funding_rate_is_neg = false;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 2, "Should open short in funding arb mode");
         // Verify it's a short: isBuy should be false (param_bool(false))
@@ -1445,7 +1499,7 @@ mod tests {
         perp.funding_rate_is_neg = true;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 2, "Should open long in funding arb mode");
     }
@@ -1459,7 +1513,7 @@ mod tests {
         perp.funding_threshold = 10_000;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert!(output.actions.is_empty(), "No entry when funding below threshold");
     }
@@ -1472,7 +1526,7 @@ mod tests {
         perp.funding_rate_abs = 50_000;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert!(output.actions.is_empty(), "No entry when threshold is zero");
     }
@@ -1489,7 +1543,7 @@ mod tests {
         perp.mark_price = 51_500 * PRICE_SCALE; // 3% above entry
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Arb mode should still exit on stop loss");
     }
@@ -1509,7 +1563,7 @@ mod tests {
         perp.sma_slow = 50_000 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         // In funding arb mode, SMA trend reversal should NOT trigger exit
         assert!(output.actions.is_empty(), "Arb mode should not exit on SMA trend reversal");
@@ -1530,7 +1584,7 @@ mod tests {
         perp.mark_price = 48_500 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         // Close emits only 1 action (no withdraw — HyperCore async settlement)
         assert_eq!(output.actions.len(), 1);
@@ -1547,7 +1601,7 @@ mod tests {
         perp.entry_price = 50_000 * PRICE_SCALE;
 
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
 
         assert_eq!(output.actions.len(), 1, "Force close emits only close action");
     }
@@ -1568,12 +1622,12 @@ mod tests {
         perp.take_profit_bps = 400; // 4% = $52,000
         // Oracle price at $55,000 > TP level -> should trigger take profit
         let (ctx, input) = build_test(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
-        let output = agent_main(&ctx, &input);
+        let output = agent_main(&ctx, &input).unwrap();
         assert_eq!(output.actions.len(), 1, "Oracle-verified price should trigger take profit");
     }
 
     #[test]
-    fn test_stale_oracle_feed_returns_empty() {
+    fn test_stale_oracle_feed_is_rejected() {
         let perp = make_default_perp_input();
         // Build oracle feed with stale timestamp
         let mut feed = make_oracle_feed(perp.mark_price);
@@ -1585,18 +1639,24 @@ mod tests {
         buf.extend_from_slice(&feed_bytes);
         buf.extend_from_slice(&perp.encode());
         let ctx = test_ctx(feed_hash);
-        let output = agent_main(&ctx, &buf);
-        assert!(output.actions.is_empty(), "Stale oracle feed should return empty");
+        assert_eq!(
+            agent_main(&ctx, &buf),
+            Err(AgentError::Custom(ERR_STALE_FEED)),
+            "Stale oracle feed should be rejected"
+        );
     }
 
     #[test]
-    fn test_oracle_commitment_mismatch_returns_empty() {
+    fn test_oracle_commitment_mismatch_is_rejected() {
         let perp = make_default_perp_input();
         // Build valid opaque inputs but with wrong input_root
         let (input, _feed_hash) = build_opaque_inputs(100_000 * PRICE_SCALE, 100_000 * PRICE_SCALE, &perp);
         let ctx = test_ctx([0xFF; 32]); // Wrong feed hash
-        let output = agent_main(&ctx, &input);
-        assert!(output.actions.is_empty(), "Commitment mismatch should return empty");
+        assert_eq!(
+            agent_main(&ctx, &input),
+            Err(AgentError::Custom(ERR_FEED_COMMITMENT_MISMATCH)),
+            "Commitment mismatch should be rejected"
+        );
     }
 
     #[test]
@@ -1630,7 +1690,7 @@ mod tests {
         buf2.extend_from_slice(&feed_bytes2);
         buf2.extend_from_slice(&perp2.encode());
         let ctx2 = test_ctx(feed_hash2);
-        let output2 = agent_main(&ctx2, &buf2);
+        let output2 = agent_main(&ctx2, &buf2).unwrap();
         assert_eq!(output2.actions.len(), 2, "Feed at exact boundary should still work");
     }
 
@@ -1652,7 +1712,37 @@ mod tests {
         buf.extend_from_slice(&feed_bytes);
         buf.extend_from_slice(&perp.encode());
         let ctx = test_ctx(feed_hash);
-        let output = agent_main(&ctx, &buf);
-        assert!(output.actions.is_empty(), "Feed 1 second past staleness should return empty");
+        assert_eq!(
+            agent_main(&ctx, &buf),
+            Err(AgentError::Custom(ERR_STALE_FEED)),
+            "Feed 1 second past staleness should be rejected"
+        );
+    }
+
+    // ========================================================================
+    // Price and Size Arithmetic
+    // ========================================================================
+
+    #[test]
+    fn test_compute_order_size() {
+        // 100 USDC at 3x into BTC at $97,000 (szDecimals 5)
+        assert_eq!(compute_order_size(100_000_000, 9_700_000_000_000, 5, 30_000), 309);
+        assert_eq!(compute_order_size(12_345_678, 250_012_345_678, 4, 15_000), 74);
+        assert_eq!(compute_order_size(1, 3, 2, 12_345), 4115);
+        // sz_decimals is capped at 8
+        assert_eq!(compute_order_size(5_000_000, 100_000_000, 12, 10_000), 500_000_000);
+        assert_eq!(compute_order_size(999_999, 7, 8, 50_000), 7_142_850_000_000_000);
+        assert_eq!(compute_order_size(0, 100_000_000, 5, 30_000), 0);
+        assert_eq!(compute_order_size(100_000_000, 0, 5, 30_000), 0);
+        // Sizes beyond u64 are rejected
+        assert_eq!(compute_order_size(u64::MAX, 1, 8, u32::MAX), 0);
+    }
+
+    #[test]
+    fn test_compute_close_price() {
+        assert_eq!(compute_close_price(5_000_012_345, true), 5_150_012_700);
+        assert_eq!(compute_close_price(5_000_012_345, false), 4_850_011_900);
+        assert_eq!(compute_close_price(99, true), 100);
+        assert_eq!(compute_close_price(199, false), 100);
     }
 }
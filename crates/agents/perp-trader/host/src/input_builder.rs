@@ -8,7 +8,7 @@ use crate::indicators::IndicatorSet;
 use crate::market::MarketSnapshot;
 use crate::onchain::VaultState;
 use crate::oracle_signer::{to_scaled_u64, SignedFeed};
-use constraints::StateSnapshotV1;
+use constraints::{StateSnapshotV1, DEFAULT_CONSTRAINT_SET_HASH};
 use kernel_core::{CanonicalEncode, KernelInputV1};
 use reference_integrator::{build_kernel_input, InputParams, LoadedBundle};
 
@@ -75,7 +75,8 @@ pub fn build_input_with_phase(
 
     // Build KernelInputV1 via reference-integrator
     let params = InputParams {
-        constraint_set_hash: [0u8; 32], // TODO: compute from actual constraints
+        // The perp-trader guest enforces ConstraintSetV1::default()
+        constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
         input_root: signed_feed.feed_hash,
         execution_nonce: vault_state.last_execution_nonce + 1,
         opaque_agent_inputs: opaque,
@@ -936,10 +936,10 @@ fn run_pipeline(
         }
         let deposit_proof = prove::generate_proof(&bundle, &deposit_input_bytes, cli.dev_mode)?;
 
-        if deposit_proof.journal.action_commitment != deposit_commitment {
+        if deposit_proof.journal.action_commitment() != deposit_commitment {
             return Err(anyhow::anyhow!(
                 "Deposit proof commitment mismatch: proof={}, reconstructed={}",
-                hex::encode(deposit_proof.journal.action_commitment),
+                hex::encode(deposit_proof.journal.action_commitment()),
                 hex::encode(deposit_commitment)
             ));
         }
@@ -1049,10 +1049,10 @@ fn run_pipeline(
         }
         let order_proof = prove::generate_proof(&bundle, &order_input_bytes, cli.dev_mode)?;
 
-        if order_proof.journal.action_commitment != order_commitment {
+        if order_proof.journal.action_commitment() != order_commitment {
             return Err(anyhow::anyhow!(
                 "Order proof commitment mismatch: proof={}, reconstructed={}",
-                hex::encode(order_proof.journal.action_commitment),
+                hex::encode(order_proof.journal.action_commitment()),
                 hex::encode(order_commitment)
             ));
         }
@@ -1075,10 +1075,10 @@ fn run_pipeline(
             );
         }
 
-        if proof_result.journal.action_commitment != action_commitment {
+        if proof_result.journal.action_commitment() != action_commitment {
             return Err(anyhow::anyhow!(
                 "Action commitment mismatch: proof={}, reconstructed={}",
-                hex::encode(proof_result.journal.action_commitment),
+                hex::encode(proof_result.journal.action_commitment()),
                 hex::encode(action_commitment)
             ));
         }
@@ -5,8 +5,8 @@
 //! commits to SHA256(agent_output_bytes), so the host must reconstruct them.
 
 use crate::error::{Error, Result};
-use kernel_core::{CanonicalDecode, CanonicalEncode, KernelInputV1};
-use kernel_sdk::agent::AgentContext;
+use kernel_core::{AgentOutput, CanonicalDecode, CanonicalEncode, KernelInputV1};
+use kernel_sdk::agent::{AgentContext, StepMeter};
 use sha2::{Digest, Sha256};
 
 /// Re-run the perp-trader agent and return (encoded_output_bytes, action_commitment).
@@ -23,10 +23,16 @@ pub fn reconstruct_output(
         constraint_set_hash: kernel_input.constraint_set_hash,
         input_root: kernel_input.input_root,
         execution_nonce: kernel_input.execution_nonce,
+        state_snapshot: None,
+        step_meter: StepMeter::default(),
     };
 
-    // Call the agent's entry point directly
-    let output = perp_trader::agent_main(&ctx, &kernel_input.opaque_agent_inputs);
+    // Call the agent's entry point directly. A rejected input commits to the
+    // empty output, as in the kernel.
+    let output =
+        perp_trader::agent_main(&ctx, &kernel_input.opaque_agent_inputs).unwrap_or(AgentOutput {
+            actions: Vec::new(),
+        });
 
     // Encode the output using canonical encoding
     let output_bytes = output
@@ -58,7 +64,7 @@ mod tests {
             constraint_set_hash: [0; 32],
             input_root: [0; 32],
             execution_nonce: 1,
-            opaque_agent_inputs: vec![0u8; 10], // Too short → agent returns empty
+            opaque_agent_inputs: vec![0u8; 10], // Too short → agent rejects it
         };
         let input_bytes = input.encode().unwrap();
 
@@ -1,9 +1,13 @@
 //! Thin wrapper over reference-integrator's proving functionality.
 
 use crate::error::{Error, Result};
-use reference_integrator::{LoadedBundle, ProveResult, ProvingMode};
+use kernel_core::CanonicalEncode;
+use reference_integrator::{ConstraintSetV1, LoadedBundle, ProveResult, ProvingMode};
 
 /// Generate a ZK proof of kernel execution.
+///
+/// The guest enforces the default constraint set, which the input binds via
+/// `DEFAULT_CONSTRAINT_SET_HASH`.
 pub fn generate_proof(
     bundle: &LoadedBundle,
     input_bytes: &[u8],
@@ -19,6 +23,10 @@ pub fn generate_proof(
         ProvingMode::Groth16
     };
 
-    reference_integrator::prove::prove(&elf, input_bytes, mode)
+    let constraint_set_bytes = ConstraintSetV1::default()
+        .encode()
+        .map_err(|e| Error::Proving(format!("Failed to encode constraint set: {:?}", e)))?;
+
+    reference_integrator::prove::prove(&elf, input_bytes, &constraint_set_bytes, mode)
         .map_err(|e| Error::Proving(format!("Proof generation failed: {}", e)))
 }
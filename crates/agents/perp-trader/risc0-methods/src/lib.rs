@@ -15,7 +15,10 @@
 //! use risc0_zkvm::{default_prover, ExecutorEnv};
 //!
 //! let env = ExecutorEnv::builder()
-//!     .write_slice(&input_bytes)
+//!     .write(&input_bytes)
+//!     .unwrap()
+//!     .write(&constraint_set_bytes)
+//!     .unwrap()
 //!     .build()
 //!     .unwrap();
 //!
@@ -30,8 +33,8 @@
 //!
 //! The guest is built from `zkvm-guest/`, a thin wrapper that binds the
 //! perp-trader agent to the kernel-guest runtime. The wrapper:
-//! 1. Reads input bytes from the host via `env::read()`
-//! 2. Calls `perp_trader::kernel_main()` to execute the kernel
+//! 1. Reads input bytes and constraint set bytes from the host via `env::read()`
+//! 2. Calls `perp_trader::kernel_main_with_constraint_bytes()` to execute the kernel
 //! 3. Commits the resulting journal to the proof via `env::commit_slice()`
 //!
 //! # Determinism
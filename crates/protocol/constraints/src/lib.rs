@@ -20,10 +20,42 @@
 //! Higher-level strategy concepts (e.g., "open position", "swap") are agent
 //! abstractions that must be compiled down to CALL or TRANSFER_ERC20 actions.
 //! The constraint engine only validates executable action types.
+//!
+//! # Custom Rules
+//!
+//! Deployments can enforce their own policies alongside a [`ConstraintSet`]
+//! by implementing [`ConstraintRule`] and composing the rules into a
+//! [`RuleSet`], whose hash commits to every rule.
+//!
+//! # no_std Support
+//!
+//! This crate is `#![no_std]` and uses `alloc`, so the engine and custom
+//! rules run unchanged inside the zkVM guest.
+//!
+//! # Policy Files
+//!
+//! The host-only `policy` feature adds [`policy`], which compiles TOML or
+//! JSON policy files into canonical constraint set bytes and their hash.
+
+#![no_std]
+
+extern crate alloc;
+
+#[cfg(feature = "policy")]
+extern crate std;
+
+#[cfg(feature = "policy")]
+pub mod policy;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
 
 use kernel_core::{
-    ActionV1, AgentOutput, ConstraintError, ConstraintViolation, ConstraintViolationReason,
-    KernelInputV1, MAX_ACTIONS_PER_OUTPUT, MAX_ACTION_PAYLOAD_BYTES,
+    ensure_no_trailing_bytes, get_bytes32, get_slice, get_u32_le, get_u64_le, get_u8, put_bytes32,
+    put_u32_le, put_u64_le, sha256, ActionV1, AgentOutput, CanonicalDecode, CanonicalEncode,
+    CodecError, ConstraintError, ConstraintViolation, ConstraintViolationReason, KernelInputRef,
+    KernelInputV1, KERNEL_VERSION_V5, MAX_ACTIONS_PER_OUTPUT, MAX_ACTION_PAYLOAD_BYTES,
 };
 
 // ============================================================================
@@ -67,12 +99,16 @@ pub const EMPTY_OUTPUT_COMMITMENT: [u8; 32] = [
 /// Defines economic safety parameters for agent execution.
 /// Size: 60 bytes when encoded.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintSetV1 {
     /// Version (must be 1)
     pub version: u32,
-    /// Maximum position size in base units (reserved for future use)
+    /// Maximum notional of a single action in token base units
+    /// (see [`action_notional`]; `u64::MAX` = unlimited)
+    #[cfg_attr(feature = "serde", serde(with = "u64_string"))]
     pub max_position_notional: u64,
-    /// Maximum leverage in basis points (reserved for future use)
+    /// Maximum margin of a position-opening action relative to the
+    /// snapshot's `current_equity`, in basis points
     pub max_leverage_bps: u32,
     /// Maximum drawdown in basis points (10000 = 100%)
     pub max_drawdown_bps: u32,
@@ -81,17 +117,54 @@ pub struct ConstraintSetV1 {
     /// Maximum actions per output
     pub max_actions_per_output: u32,
     /// Single allowed asset ID (reserved for future use)
+    #[cfg_attr(feature = "serde", serde(with = "kernel_core::serde_hex::bytes32"))]
     pub allowed_asset_id: [u8; 32],
 }
 
+/// `u64` as a decimal string in JSON.
+///
+/// `max_position_notional` defaults to `u64::MAX`, which JSON consumers that
+/// parse numbers as doubles would silently round.
+#[cfg(feature = "serde")]
+mod u64_string {
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let s = alloc::string::String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+/// `u128` as a decimal string in JSON, for the same reason as
+/// [`u64_string`](self::u64_string).
+#[cfg(feature = "serde")]
+mod u128_string {
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        let s = alloc::string::String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
 impl Default for ConstraintSetV1 {
     /// Default permissive constraint set for P0.3.
     fn default() -> Self {
         Self {
             version: 1,
-            max_position_notional: u64::MAX,
-            max_leverage_bps: 100_000, // 10x max leverage
-            max_drawdown_bps: 10_000,  // 100% (disabled)
+            max_position_notional: u64::MAX, // unlimited
+            max_leverage_bps: 100_000,       // 10x max leverage
+            max_drawdown_bps: 10_000,        // 100% (disabled)
             cooldown_seconds: 0,
             max_actions_per_output: MAX_ACTIONS_PER_OUTPUT as u32,
             allowed_asset_id: [0u8; 32], // All assets allowed
@@ -99,709 +172,3943 @@ impl Default for ConstraintSetV1 {
     }
 }
 
-// ============================================================================
-// State Snapshot
-// ============================================================================
+impl ConstraintSetV1 {
+    /// Size of the canonical encoding in bytes.
+    pub const ENCODED_SIZE: usize = 60;
+}
 
-/// State snapshot for cooldown and drawdown checks.
+/// ConstraintSetV1 encoding layout (little-endian):
+/// - version: u32 (4 bytes)
+/// - max_position_notional: u64 (8 bytes)
+/// - max_leverage_bps: u32 (4 bytes)
+/// - max_drawdown_bps: u32 (4 bytes)
+/// - cooldown_seconds: u32 (4 bytes)
+/// - max_actions_per_output: u32 (4 bytes)
+/// - allowed_asset_id: [u8; 32] (32 bytes)
 ///
-/// Size: 36 bytes when encoded.
-#[derive(Clone, Debug, PartialEq)]
-pub struct StateSnapshotV1 {
-    /// Version (must be 1)
-    pub snapshot_version: u32,
-    /// Timestamp of last execution
-    pub last_execution_ts: u64,
-    /// Current timestamp (from input)
-    pub current_ts: u64,
-    /// Current portfolio equity
-    pub current_equity: u64,
-    /// Peak portfolio equity
-    pub peak_equity: u64,
-}
-
-impl StateSnapshotV1 {
-    /// Minimum size of encoded snapshot
-    pub const ENCODED_SIZE: usize = 36;
-
-    /// Decode a state snapshot from bytes.
-    ///
-    /// Returns None if bytes are too short or version is wrong.
-    pub fn decode(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() < Self::ENCODED_SIZE {
-            return None;
+/// Total fixed size: 4+8+4+4+4+4+32 = 60 bytes
+impl CanonicalEncode for ConstraintSetV1 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        Ok(Self::ENCODED_SIZE)
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        if self.version != 1 {
+            return Err(CodecError::InvalidVersion {
+                expected: 1,
+                actual: self.version,
+            });
         }
 
-        let snapshot_version = u32::from_le_bytes(bytes[0..4].try_into().ok()?);
-        if snapshot_version != 1 {
-            return None;
+        let before = out.len();
+
+        put_u32_le(out, self.version);
+        put_u64_le(out, self.max_position_notional);
+        put_u32_le(out, self.max_leverage_bps);
+        put_u32_le(out, self.max_drawdown_bps);
+        put_u32_le(out, self.cooldown_seconds);
+        put_u32_le(out, self.max_actions_per_output);
+        put_bytes32(out, &self.allowed_asset_id);
+
+        debug_assert_eq!(
+            out.len() - before,
+            Self::ENCODED_SIZE,
+            "ConstraintSetV1: encoded_len() / encode_into() mismatch"
+        );
+        Ok(())
+    }
+}
+
+impl CanonicalDecode for ConstraintSetV1 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.len() != Self::ENCODED_SIZE {
+            return Err(CodecError::InvalidLength);
         }
 
-        Some(Self {
-            snapshot_version,
-            last_execution_ts: u64::from_le_bytes(bytes[4..12].try_into().ok()?),
-            current_ts: u64::from_le_bytes(bytes[12..20].try_into().ok()?),
-            current_equity: u64::from_le_bytes(bytes[20..28].try_into().ok()?),
-            peak_equity: u64::from_le_bytes(bytes[28..36].try_into().ok()?),
+        let mut offset = 0;
+
+        let version = get_u32_le(bytes, &mut offset)?;
+        if version != 1 {
+            return Err(CodecError::InvalidVersion {
+                expected: 1,
+                actual: version,
+            });
+        }
+
+        let max_position_notional = get_u64_le(bytes, &mut offset)?;
+        let max_leverage_bps = get_u32_le(bytes, &mut offset)?;
+        let max_drawdown_bps = get_u32_le(bytes, &mut offset)?;
+        let cooldown_seconds = get_u32_le(bytes, &mut offset)?;
+        let max_actions_per_output = get_u32_le(bytes, &mut offset)?;
+        let allowed_asset_id = get_bytes32(bytes, &mut offset)?;
+
+        ensure_no_trailing_bytes(bytes, offset)?;
+
+        Ok(Self {
+            version,
+            max_position_notional,
+            max_leverage_bps,
+            max_drawdown_bps,
+            cooldown_seconds,
+            max_actions_per_output,
+            allowed_asset_id,
         })
     }
 }
 
-// ============================================================================
-// Constraint Metadata (Legacy compatibility)
-// ============================================================================
-
-/// Metadata for constraint checking (legacy API).
-#[derive(Clone, Debug)]
-pub struct ConstraintMeta {
-    pub agent_id: [u8; 32],
-    pub agent_code_hash: [u8; 32],
-    pub constraint_set_hash: [u8; 32],
-    pub input_root: [u8; 32],
-    pub execution_nonce: u64,
+/// Compute the `constraint_set_hash` for a constraint set.
+///
+/// This is the SHA-256 of the canonical encoding of any constraint set
+/// version ([`ConstraintSetV1`], [`ConstraintSetV2`] or [`ConstraintSet`]).
+/// The kernel recomputes it over the constraint set it enforces and rejects
+/// inputs whose `constraint_set_hash` does not match.
+pub fn compute_constraint_set_hash<C: CanonicalEncode + ?Sized>(
+    constraint_set: &C,
+) -> Result<[u8; 32], CodecError> {
+    let bytes = constraint_set.encode()?;
+    Ok(sha256(&bytes))
 }
 
+/// `constraint_set_hash` of [`ConstraintSetV1::default()`].
+///
+/// Inputs executed through the default kernel entrypoints must carry this hash.
+pub const DEFAULT_CONSTRAINT_SET_HASH: [u8; 32] = [
+    0x97, 0x07, 0x25, 0xcc, 0xb7, 0x9c, 0x55, 0xb2, 0xfc, 0x44, 0xf7, 0x45, 0x3c, 0x63, 0xfa, 0x1c,
+    0xd4, 0xfa, 0x4c, 0x02, 0x9c, 0x7b, 0x4f, 0x80, 0x97, 0xd8, 0x52, 0x12, 0xb7, 0xec, 0xc7, 0xa9,
+];
+
 // ============================================================================
-// Constraint Enforcement
+// Constraint Set V2 (allowlists)
 // ============================================================================
 
-/// Enforce all constraints on the proposed agent output.
-///
-/// This is the main entry point for constraint checking. It validates:
-/// 1. Output structure (action count, payload sizes)
-/// 2. Per-action constraints (action type validity, payload format)
-/// 3. Global constraints (cooldown, drawdown)
-///
-/// # Supported Action Types
+/// Maximum number of entries in [`ConstraintSetV2::allowed_tokens`].
+pub const MAX_ALLOWED_TOKENS: usize = 32;
+
+/// Maximum number of entries in [`ConstraintSetV2::allowed_call_targets`].
+pub const MAX_ALLOWED_CALL_TARGETS: usize = 32;
+
+/// Maximum number of entries in [`ConstraintSetV2::allowed_calls`].
+pub const MAX_ALLOWED_CALLS: usize = 64;
+
+/// A single contract function that CALL actions may invoke.
 ///
-/// For protocol v1, only on-chain executable action types are allowed:
-/// - `ACTION_TYPE_CALL` (0x00000002) - Must have valid ABI-encoded payload
-/// - `ACTION_TYPE_TRANSFER_ERC20` (0x00000003) - Must have 96-byte payload
-/// - `ACTION_TYPE_NO_OP` (0x00000004) - Must have empty payload
+/// Ordered by `target`, then `selector`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllowedCall {
+    /// Contract address
+    #[cfg_attr(feature = "serde", serde(with = "kernel_core::serde_hex::array"))]
+    pub target: [u8; 20],
+    /// Function selector (first 4 bytes of the calldata)
+    #[cfg_attr(feature = "serde", serde(with = "kernel_core::serde_hex::array"))]
+    pub selector: [u8; 4],
+}
+
+/// Constraint set with token and call allowlists.
 ///
-/// Any other action type triggers `UnknownActionType` violation.
+/// Carries the limits of [`ConstraintSetV1`] and replaces its unchecked
+/// `allowed_asset_id` with bounded allowlists enforced on every action:
 ///
-/// # Arguments
-/// * `input` - The kernel input containing state snapshot
-/// * `proposed` - The proposed agent output to validate
-/// * `constraint_set` - The constraint set to enforce
+/// - TRANSFER_ERC20: the token must be in `allowed_tokens`, otherwise
+///   `AssetNotWhitelisted`
+/// - CALL: the target must be in `allowed_call_targets` (any function), or
+///   the (target, selector) pair must be in `allowed_calls`, otherwise
+///   `CallNotAllowed`. Calls with fewer than 4 bytes of calldata have no
+///   selector and need a target-wide entry.
 ///
-/// # Returns
-/// * `Ok(AgentOutput)` - The validated output (same as proposed if valid)
-/// * `Err(ConstraintViolation)` - The first constraint violation encountered
-pub fn enforce_constraints(
-    input: &KernelInputV1,
-    proposed: &AgentOutput,
-    constraint_set: &ConstraintSetV1,
-) -> Result<AgentOutput, ConstraintViolation> {
-    // 1. Validate constraint set version and invariants
-    if constraint_set.version != 1 {
-        return Err(ConstraintViolation::global(
-            ConstraintViolationReason::InvalidConstraintSet,
-        ));
+/// Allowlists are exhaustive: an empty list allows nothing. Entries must be
+/// strictly ascending so that each policy has exactly one encoding (and
+/// hash); encoding and decoding reject anything else.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstraintSetV2 {
+    /// Version (must be 2)
+    pub version: u32,
+    /// Maximum notional of a single action in token base units
+    /// (see [`action_notional`]; `u64::MAX` = unlimited)
+    #[cfg_attr(feature = "serde", serde(with = "u64_string"))]
+    pub max_position_notional: u64,
+    /// Maximum margin of a position-opening action relative to the
+    /// snapshot's `current_equity`, in basis points
+    pub max_leverage_bps: u32,
+    /// Maximum drawdown in basis points (10000 = 100%)
+    pub max_drawdown_bps: u32,
+    /// Minimum seconds between executions
+    pub cooldown_seconds: u32,
+    /// Maximum actions per output
+    pub max_actions_per_output: u32,
+    /// ERC20 tokens TRANSFER_ERC20 actions may move (max `MAX_ALLOWED_TOKENS`)
+    #[cfg_attr(feature = "serde", serde(with = "kernel_core::serde_hex::array_vec"))]
+    pub allowed_tokens: Vec<[u8; 20]>,
+    /// Contracts CALL actions may invoke with any calldata
+    /// (max `MAX_ALLOWED_CALL_TARGETS`)
+    #[cfg_attr(feature = "serde", serde(with = "kernel_core::serde_hex::array_vec"))]
+    pub allowed_call_targets: Vec<[u8; 20]>,
+    /// Individual functions CALL actions may invoke (max `MAX_ALLOWED_CALLS`)
+    pub allowed_calls: Vec<AllowedCall>,
+}
+
+impl Default for ConstraintSetV2 {
+    /// Default limits of [`ConstraintSetV1`] with empty allowlists, so only
+    /// NO_OP actions pass until entries are added.
+    fn default() -> Self {
+        let v1 = ConstraintSetV1::default();
+        Self {
+            version: 2,
+            max_position_notional: v1.max_position_notional,
+            max_leverage_bps: v1.max_leverage_bps,
+            max_drawdown_bps: v1.max_drawdown_bps,
+            cooldown_seconds: v1.cooldown_seconds,
+            max_actions_per_output: v1.max_actions_per_output,
+            allowed_tokens: Vec::new(),
+            allowed_call_targets: Vec::new(),
+            allowed_calls: Vec::new(),
+        }
     }
+}
 
-    // 1b. Validate constraint set invariants
-    // max_actions_per_output must not exceed protocol limit
-    if constraint_set.max_actions_per_output > MAX_ACTIONS_PER_OUTPUT as u32 {
-        return Err(ConstraintViolation::global(
-            ConstraintViolationReason::InvalidConstraintSet,
-        ));
+impl ConstraintSetV2 {
+    /// Size of the fixed fields and the three list counts in bytes.
+    pub const MIN_ENCODED_SIZE: usize = 40;
+
+    /// The set's token and call allowlists.
+    pub fn allowlists(&self) -> Allowlists<'_> {
+        Allowlists {
+            tokens: &self.allowed_tokens,
+            call_targets: &self.allowed_call_targets,
+            calls: &self.allowed_calls,
+        }
     }
 
-    // max_drawdown_bps must be <= 10_000 (100%)
-    if constraint_set.max_drawdown_bps > 10_000 {
-        return Err(ConstraintViolation::global(
-            ConstraintViolationReason::InvalidConstraintSet,
-        ));
+    /// Whether TRANSFER_ERC20 actions may move `token`.
+    pub fn allows_token(&self, token: &[u8; 20]) -> bool {
+        self.allowlists().allows_token(token)
+    }
+
+    /// Whether a CALL to `target` with `selector` (`None` for calldata
+    /// shorter than 4 bytes) is allowed.
+    pub fn allows_call(&self, target: &[u8; 20], selector: Option<[u8; 4]>) -> bool {
+        self.allowlists().allows_call(target, selector)
     }
+}
 
-    // 2. Validate output structure
-    check_output_structure(proposed, constraint_set)?;
+/// Borrowed token and call allowlists of a version 2 or 3 constraint set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Allowlists<'a> {
+    /// ERC20 tokens TRANSFER_ERC20 actions may move
+    pub tokens: &'a [[u8; 20]],
+    /// Contracts CALL actions may invoke with any calldata
+    pub call_targets: &'a [[u8; 20]],
+    /// Individual functions CALL actions may invoke
+    pub calls: &'a [AllowedCall],
+}
 
-    // 3. Validate each action
-    for (index, action) in proposed.actions.iter().enumerate() {
-        validate_action(action, index)?;
+impl Allowlists<'_> {
+    /// Whether TRANSFER_ERC20 actions may move `token`.
+    pub fn allows_token(&self, token: &[u8; 20]) -> bool {
+        self.tokens.contains(token)
     }
 
-    // 4. Parse state snapshot (optional)
-    let snapshot = StateSnapshotV1::decode(&input.opaque_agent_inputs);
+    /// Whether a CALL to `target` with `selector` (`None` for calldata
+    /// shorter than 4 bytes) is allowed.
+    pub fn allows_call(&self, target: &[u8; 20], selector: Option<[u8; 4]>) -> bool {
+        self.call_targets.contains(target)
+            || selector.is_some_and(|selector| {
+                self.calls.contains(&AllowedCall {
+                    target: *target,
+                    selector,
+                })
+            })
+    }
 
-    // 5. Check if snapshot is required but missing
-    let cooldown_enabled = constraint_set.cooldown_seconds > 0;
-    let drawdown_enabled = constraint_set.max_drawdown_bps < 10_000;
+    /// Check the allowlist size bounds and canonical ordering.
+    fn check(&self) -> Result<(), CodecError> {
+        check_allowlist(self.tokens, MAX_ALLOWED_TOKENS)?;
+        check_allowlist(self.call_targets, MAX_ALLOWED_CALL_TARGETS)?;
+        check_allowlist(self.calls, MAX_ALLOWED_CALLS)
+    }
 
-    if snapshot.is_none() && (cooldown_enabled || drawdown_enabled) {
-        return Err(ConstraintViolation::global(
-            ConstraintViolationReason::InvalidStateSnapshot,
-        ));
+    /// Append each list as a u32 count followed by its entries.
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        // Counts are bounded by check()
+        put_u32_le(out, self.tokens.len() as u32);
+        for token in self.tokens {
+            out.extend_from_slice(token);
+        }
+        put_u32_le(out, self.call_targets.len() as u32);
+        for target in self.call_targets {
+            out.extend_from_slice(target);
+        }
+        put_u32_le(out, self.calls.len() as u32);
+        for call in self.calls {
+            out.extend_from_slice(&call.target);
+            out.extend_from_slice(&call.selector);
+        }
     }
 
-    // 6. Validate global constraints (if snapshot present)
-    if let Some(ref snap) = snapshot {
-        validate_global_constraints(snap, constraint_set)?;
+    /// Size of the encoded list entries, excluding their counts.
+    fn entries_len(&self) -> usize {
+        20 * self.tokens.len() + 20 * self.call_targets.len() + 24 * self.calls.len()
     }
+}
 
-    // All constraints passed - return the validated output
-    Ok(proposed.clone())
+/// Decoded token, call target and call allowlists, in encoding order.
+type OwnedAllowlists = (Vec<[u8; 20]>, Vec<[u8; 20]>, Vec<AllowedCall>);
+
+/// Decode the three allowlists written by [`Allowlists::encode_into`].
+fn get_allowlists(bytes: &[u8], offset: &mut usize) -> Result<OwnedAllowlists, CodecError> {
+    let address = |entry: &[u8]| -> [u8; 20] { entry.try_into().expect("20-byte entry") };
+    let tokens = get_allowlist(bytes, offset, MAX_ALLOWED_TOKENS, 20, address)?;
+    let call_targets = get_allowlist(bytes, offset, MAX_ALLOWED_CALL_TARGETS, 20, address)?;
+    let calls = get_allowlist(bytes, offset, MAX_ALLOWED_CALLS, 24, |entry| AllowedCall {
+        target: address(&entry[..20]),
+        selector: entry[20..24].try_into().expect("4-byte selector"),
+    })?;
+    Ok((tokens, call_targets, calls))
 }
 
-/// Validate output structure (internal, with constraint set).
-fn check_output_structure(
-    output: &AgentOutput,
-    constraint_set: &ConstraintSetV1,
-) -> Result<(), ConstraintViolation> {
-    // Check action count
-    let max_actions = constraint_set.max_actions_per_output as usize;
-    if output.actions.len() > max_actions {
-        return Err(ConstraintViolation::global(
-            ConstraintViolationReason::InvalidOutputStructure,
-        ));
+/// Check that an allowlist has at most `limit` strictly ascending entries.
+fn check_allowlist<T: Ord>(entries: &[T], limit: usize) -> Result<(), CodecError> {
+    if entries.len() > limit {
+        return Err(CodecError::AllowlistTooLarge {
+            count: entries.len().min(u32::MAX as usize) as u32,
+            limit,
+        });
+    }
+    if entries.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(CodecError::NonCanonicalAllowlist);
     }
+    Ok(())
+}
 
-    // Check each action's payload size
-    for (index, action) in output.actions.iter().enumerate() {
-        if action.payload.len() > MAX_ACTION_PAYLOAD_BYTES {
-            return Err(ConstraintViolation::action(
-                ConstraintViolationReason::InvalidOutputStructure,
-                index,
-            ));
+/// Decode a u32 count prefix and `count` fixed-size allowlist entries.
+fn get_allowlist<T: Ord>(
+    bytes: &[u8],
+    offset: &mut usize,
+    limit: usize,
+    entry_size: usize,
+    parse: impl Fn(&[u8]) -> T,
+) -> Result<Vec<T>, CodecError> {
+    let count = get_u32_le(bytes, offset)?;
+    if count as usize > limit {
+        return Err(CodecError::AllowlistTooLarge { count, limit });
+    }
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        entries.push(parse(get_slice(bytes, offset, entry_size)?));
+    }
+    check_allowlist(&entries, limit)?;
+    Ok(entries)
+}
+
+/// ConstraintSetV2 encoding layout (little-endian):
+/// - version: u32 (4 bytes)
+/// - max_position_notional: u64 (8 bytes)
+/// - max_leverage_bps: u32 (4 bytes)
+/// - max_drawdown_bps: u32 (4 bytes)
+/// - cooldown_seconds: u32 (4 bytes)
+/// - max_actions_per_output: u32 (4 bytes)
+/// - token_count: u32, then token_count * address (20 bytes)
+/// - call_target_count: u32, then call_target_count * address (20 bytes)
+/// - call_count: u32, then call_count * (address (20 bytes) || selector (4 bytes))
+///
+/// Size: 40 + 20 * tokens + 20 * call_targets + 24 * calls bytes
+impl CanonicalEncode for ConstraintSetV2 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        Ok(Self::MIN_ENCODED_SIZE + self.allowlists().entries_len())
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        if self.version != 2 {
+            return Err(CodecError::InvalidVersion {
+                expected: 2,
+                actual: self.version,
+            });
         }
+        self.allowlists().check()?;
+
+        put_u32_le(out, self.version);
+        put_u64_le(out, self.max_position_notional);
+        put_u32_le(out, self.max_leverage_bps);
+        put_u32_le(out, self.max_drawdown_bps);
+        put_u32_le(out, self.cooldown_seconds);
+        put_u32_le(out, self.max_actions_per_output);
+        self.allowlists().encode_into(out);
+        Ok(())
     }
+}
 
-    Ok(())
+impl CanonicalDecode for ConstraintSetV2 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut offset = 0;
+
+        let version = get_u32_le(bytes, &mut offset)?;
+        if version != 2 {
+            return Err(CodecError::InvalidVersion {
+                expected: 2,
+                actual: version,
+            });
+        }
+
+        let max_position_notional = get_u64_le(bytes, &mut offset)?;
+        let max_leverage_bps = get_u32_le(bytes, &mut offset)?;
+        let max_drawdown_bps = get_u32_le(bytes, &mut offset)?;
+        let cooldown_seconds = get_u32_le(bytes, &mut offset)?;
+        let max_actions_per_output = get_u32_le(bytes, &mut offset)?;
+        let (allowed_tokens, allowed_call_targets, allowed_calls) =
+            get_allowlists(bytes, &mut offset)?;
+
+        ensure_no_trailing_bytes(bytes, offset)?;
+
+        Ok(Self {
+            version,
+            max_position_notional,
+            max_leverage_bps,
+            max_drawdown_bps,
+            cooldown_seconds,
+            max_actions_per_output,
+            allowed_tokens,
+            allowed_call_targets,
+            allowed_calls,
+        })
+    }
 }
 
-/// Validate a single action.
+// ============================================================================
+// Constraint Set V3 (extensions)
+// ============================================================================
+
+/// Rolling-window limits of a [`ConstraintSetV3`].
 ///
-/// For protocol v1, only on-chain executable action types are allowed:
-/// - CALL (0x02): ABI-encoded (uint256 value, bytes callData), min 96 bytes
-/// - TRANSFER_ERC20 (0x03): ABI-encoded (address token, address to, uint256 amount), exactly 96 bytes
-/// - NO_OP (0x04): empty payload
+/// A window opens at the first execution after the previous window elapsed
+/// and lasts `window_seconds`. Every execution in the window counts towards
+/// `max_executions`, and the notional its actions move (see
+/// [`transferred_notional`]) towards `max_notional`, so an agent cannot drain a
+/// vault through many small executions. The window's usage so far is read
+/// from a [`StateSnapshotV2`].
 ///
-/// ECHO (0x01) is only allowed in test builds.
-fn validate_action(action: &ActionV1, index: usize) -> Result<(), ConstraintViolation> {
-    // Note: ECHO (0x01) is only valid in test/testing builds
-    #[cfg(any(test, feature = "testing"))]
-    if action.action_type == ACTION_TYPE_ECHO {
-        return Ok(());
-    }
+/// `u32::MAX` and `u64::MAX` disable the respective limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RollingWindowLimits {
+    /// Window length in seconds (must be non-zero)
+    pub window_seconds: u32,
+    /// Maximum executions per window, including the one being checked
+    pub max_executions: u32,
+    /// Maximum total action notional per window, in token base units
+    #[cfg_attr(feature = "serde", serde(with = "u64_string"))]
+    pub max_notional: u64,
+}
 
-    match action.action_type {
-        x if x == ACTION_TYPE_CALL => validate_call_action(action, index),
-        x if x == ACTION_TYPE_TRANSFER_ERC20 => validate_transfer_erc20_action(action, index),
-        x if x == ACTION_TYPE_NO_OP => validate_no_op_action(action, index),
-        _ => {
-            // Unknown action type - not executable on-chain
-            Err(ConstraintViolation::action(
-                ConstraintViolationReason::UnknownActionType,
-                index,
-            ))
+/// Usage of a rolling window once an execution is counted.
+///
+/// After a successful execution, the host carries these values into the
+/// next snapshot's `window_start_ts`, `window_executions` and
+/// `window_notional`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowUsage {
+    /// Timestamp at which the window started
+    pub window_start_ts: u64,
+    /// Executions in the window, including this one
+    pub executions: u32,
+    /// Total action notional in the window, including this execution's
+    pub notional: u64,
+}
+
+impl RollingWindowLimits {
+    /// Size of the encoded limits in bytes.
+    pub const ENCODED_SIZE: usize = 16;
+
+    /// Window usage after an execution committing `notional` at the
+    /// snapshot's `current_ts`.
+    ///
+    /// Starts a new window at `current_ts` if the snapshot's window has
+    /// elapsed. Returns `None` for a snapshot whose `current_ts` precedes its
+    /// `window_start_ts` or whose window end overflows. Counts saturate.
+    pub fn usage_after(&self, snapshot: &StateSnapshotV2, notional: u64) -> Option<WindowUsage> {
+        if snapshot.current_ts < snapshot.window_start_ts {
+            return None;
         }
+        let window_end = snapshot
+            .window_start_ts
+            .checked_add(self.window_seconds as u64)?;
+        let (window_start_ts, executions, prior_notional) = if snapshot.current_ts >= window_end {
+            (snapshot.current_ts, 0, 0)
+        } else {
+            (
+                snapshot.window_start_ts,
+                snapshot.window_executions,
+                snapshot.window_notional,
+            )
+        };
+        Some(WindowUsage {
+            window_start_ts,
+            executions: executions.saturating_add(1),
+            notional: prior_notional.saturating_add(notional),
+        })
     }
 }
 
-/// Validate CALL action (on-chain execution).
+/// Maximum number of entries in [`ApprovalPolicy::allowed_spenders`].
+pub const MAX_ALLOWED_SPENDERS: usize = 32;
+
+/// ERC20 approval policy of a [`ConstraintSetV3`].
 ///
-/// Payload format: abi.encode(uint256 value, bytes callData)
-/// Minimum size: 96 bytes (32 value + 32 offset + 32 length + 0 calldata)
-fn validate_call_action(action: &ActionV1, index: usize) -> Result<(), ConstraintViolation> {
-    // Minimum payload size check
-    if action.payload.len() < 96 {
-        return Err(ConstraintViolation::action(
-            ConstraintViolationReason::InvalidActionPayload,
-            index,
-        ));
-    }
+/// Applies to every CALL whose calldata starts with
+/// [`ERC20_APPROVE_SELECTOR`], on any token the call allowlists admit:
+///
+/// - the spender must be in `allowed_spenders`
+/// - the allowance must be at most `max_amount`, compared as a full 256-bit
+///   integer (so an allowance above `u128::MAX` exceeds any cap), except
+///   that `type(uint256).max` (an unlimited approval) is allowed if and only
+///   if `allow_unlimited` is set
+///
+/// `increaseAllowance(address,uint256)` calls are rejected outright: they
+/// add to an allowance the kernel cannot see, so repeated calls would
+/// escape `max_amount`.
+///
+/// Violations are `ApprovalNotAllowed`. Approve calldata that does not
+/// decode as [`Erc20Approval`] is an `InvalidActionPayload`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ApprovalPolicy {
+    /// Addresses approvals may name as spender, strictly ascending
+    /// (max `MAX_ALLOWED_SPENDERS`)
+    #[cfg_attr(feature = "serde", serde(with = "kernel_core::serde_hex::array_vec"))]
+    pub allowed_spenders: Vec<[u8; 20]>,
+    /// Largest allowance an approval may grant, in token base units
+    #[cfg_attr(feature = "serde", serde(with = "u128_string"))]
+    pub max_amount: u128,
+    /// Whether `type(uint256).max` allowances are allowed
+    pub allow_unlimited: bool,
+}
 
-    // Validate target is a valid EVM address (upper 12 bytes must be zero)
-    if action.target[0..12] != [0u8; 12] {
-        return Err(ConstraintViolation::action(
-            ConstraintViolationReason::InvalidActionPayload,
-            index,
-        ));
+impl ApprovalPolicy {
+    /// Whether the policy allows `approval`.
+    pub fn allows(&self, approval: &Erc20Approval) -> bool {
+        if !self.allowed_spenders.contains(&approval.spender) {
+            return false;
+        }
+        if approval.is_unlimited() {
+            return self.allow_unlimited;
+        }
+        u128_from_u256(&approval.amount).is_some_and(|amount| amount <= self.max_amount)
     }
 
-    // Basic ABI structure validation:
-    // bytes 32-63 should contain offset (should be 64 = 0x40)
-    // bytes 64-95 should contain length of calldata
-    let offset = u256_from_be_bytes(&action.payload[32..64]);
-    if offset != 64 {
-        return Err(ConstraintViolation::action(
-            ConstraintViolationReason::InvalidActionPayload,
-            index,
-        ));
+    /// Size of the encoded policy in bytes.
+    fn encoded_len(&self) -> usize {
+        4 + 20 * self.allowed_spenders.len() + 16 + 1
     }
 
-    let calldata_len = u256_from_be_bytes(&action.payload[64..96]);
-    // Verify payload length matches declared calldata length (with 32-byte padding)
-    let expected_len = 96 + (calldata_len as usize).div_ceil(32) * 32;
-    if action.payload.len() != expected_len {
-        return Err(ConstraintViolation::action(
-            ConstraintViolationReason::InvalidActionPayload,
-            index,
-        ));
+    /// Append spender_count: u32, the spenders, max_amount: u128 and
+    /// allow_unlimited: u8.
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        check_allowlist(&self.allowed_spenders, MAX_ALLOWED_SPENDERS)?;
+        put_u32_le(out, self.allowed_spenders.len() as u32);
+        for spender in &self.allowed_spenders {
+            out.extend_from_slice(spender);
+        }
+        out.extend_from_slice(&self.max_amount.to_le_bytes());
+        out.push(self.allow_unlimited as u8);
+        Ok(())
     }
 
-    Ok(())
+    /// Decode the layout written by [`ApprovalPolicy::encode_into`].
+    fn decode_from(bytes: &[u8], offset: &mut usize) -> Result<Self, CodecError> {
+        let allowed_spenders = get_allowlist(bytes, offset, MAX_ALLOWED_SPENDERS, 20, |entry| {
+            entry.try_into().expect("20-byte entry")
+        })?;
+        let max_amount = get_u128_le(bytes, offset)?;
+        let allow_unlimited = match get_u8(bytes, offset)? {
+            0 => false,
+            1 => true,
+            _ => return Err(CodecError::InvalidApprovalPolicy),
+        };
+        Ok(Self {
+            allowed_spenders,
+            max_amount,
+            allow_unlimited,
+        })
+    }
 }
 
-/// Validate TRANSFER_ERC20 action (on-chain execution).
+/// Native-value caps of a [`ConstraintSetV3`].
 ///
-/// Payload format: abi.encode(address token, address to, uint256 amount)
-/// Size: exactly 96 bytes
-fn validate_transfer_erc20_action(
-    action: &ActionV1,
-    index: usize,
-) -> Result<(), ConstraintViolation> {
-    if action.payload.len() != 96 {
-        return Err(ConstraintViolation::action(
-            ConstraintViolationReason::InvalidActionPayload,
-            index,
-        ));
-    }
+/// A CALL sends the `uint256 value` word of its payload (see
+/// [`call_value`]) to its target. Each CALL may send at most
+/// `max_value_per_action` and the CALLs of one output together at most
+/// `max_value_per_output`, both in wei. Values are compared as full 256-bit
+/// integers, so a value above `u128::MAX` exceeds any cap. Violations are
+/// `NativeValueExceeded`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NativeValueLimits {
+    /// Maximum value a single CALL may send, in wei
+    #[cfg_attr(feature = "serde", serde(with = "u128_string"))]
+    pub max_value_per_action: u128,
+    /// Maximum total value the CALLs of one output may send, in wei
+    #[cfg_attr(feature = "serde", serde(with = "u128_string"))]
+    pub max_value_per_output: u128,
+}
 
-    // Validate addresses have proper padding (upper 12 bytes should be zero)
-    // Token address (bytes 0-31)
-    if action.payload[0..12] != [0u8; 12] {
-        return Err(ConstraintViolation::action(
-            ConstraintViolationReason::InvalidActionPayload,
-            index,
-        ));
-    }
+impl NativeValueLimits {
+    /// Size of the encoded limits in bytes.
+    pub const ENCODED_SIZE: usize = 32;
 
-    // To address (bytes 32-63)
-    if action.payload[32..44] != [0u8; 12] {
-        return Err(ConstraintViolation::action(
-            ConstraintViolationReason::InvalidActionPayload,
-            index,
-        ));
+    /// Whether a single CALL may send `value`.
+    pub fn allows_action_value(&self, value: &[u8; 32]) -> bool {
+        u128_from_u256(value).is_some_and(|value| value <= self.max_value_per_action)
     }
 
-    Ok(())
+    /// Whether the CALLs of `output` together stay within the per-output
+    /// cap.
+    pub fn allows_output_value(&self, output: &AgentOutput) -> bool {
+        output
+            .actions
+            .iter()
+            .filter_map(call_value)
+            .try_fold(0u128, |total, value| {
+                total.checked_add(u128_from_u256(&value)?)
+            })
+            .is_some_and(|total| total <= self.max_value_per_output)
+    }
 }
 
-/// Validate NO_OP action.
+/// Extension flag of [`ConstraintSetV3::rolling_window`].
+const EXTENSION_ROLLING_WINDOW: u32 = 1 << 0;
+
+/// Extension flag of [`ConstraintSetV3::approval_policy`].
+const EXTENSION_APPROVAL_POLICY: u32 = 1 << 1;
+
+/// Extension flag of [`ConstraintSetV3::native_value`].
+const EXTENSION_NATIVE_VALUE: u32 = 1 << 2;
+
+/// Extension flag of [`ConstraintSetV3::step_budget`].
+const EXTENSION_STEP_BUDGET: u32 = 1 << 3;
+
+/// Every extension flag this version understands.
+const KNOWN_EXTENSIONS: u32 = EXTENSION_ROLLING_WINDOW
+    | EXTENSION_APPROVAL_POLICY
+    | EXTENSION_NATIVE_VALUE
+    | EXTENSION_STEP_BUDGET;
+
+/// Constraint set with optional limit extensions.
 ///
-/// Payload must be empty.
-fn validate_no_op_action(action: &ActionV1, index: usize) -> Result<(), ConstraintViolation> {
-    if !action.payload.is_empty() {
-        return Err(ConstraintViolation::action(
-            ConstraintViolationReason::InvalidActionPayload,
-            index,
-        ));
+/// Carries everything in [`ConstraintSetV2`] (limits and allowlists, with
+/// the same semantics) plus optional extensions, each of which is disabled
+/// when `None`:
+///
+/// - `rolling_window`: per-window execution and notional limits, checked
+///   against a [`StateSnapshotV2`]
+/// - `approval_policy`: spender and allowance limits for ERC20 `approve`
+///   calls
+/// - `native_value`: per-action and per-output caps on the native value
+///   CALLs send
+/// - `step_budget`: the most steps the agent may charge to its
+///   `StepMeter` in one execution. The kernel, not this engine, enforces it
+///
+/// The encoding records which extensions are present in a flags word, so a
+/// set with no extensions enforces exactly what its V2 counterpart does
+/// (with a different hash).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstraintSetV3 {
+    /// Version (must be 3)
+    pub version: u32,
+    /// Maximum notional of a single action in token base units
+    /// (see [`action_notional`]; `u64::MAX` = unlimited)
+    #[cfg_attr(feature = "serde", serde(with = "u64_string"))]
+    pub max_position_notional: u64,
+    /// Maximum margin of a position-opening action relative to the
+    /// snapshot's `current_equity`, in basis points
+    pub max_leverage_bps: u32,
+    /// Maximum drawdown in basis points (10000 = 100%)
+    pub max_drawdown_bps: u32,
+    /// Minimum seconds between executions
+    pub cooldown_seconds: u32,
+    /// Maximum actions per output
+    pub max_actions_per_output: u32,
+    /// ERC20 tokens TRANSFER_ERC20 actions may move (max `MAX_ALLOWED_TOKENS`)
+    #[cfg_attr(feature = "serde", serde(with = "kernel_core::serde_hex::array_vec"))]
+    pub allowed_tokens: Vec<[u8; 20]>,
+    /// Contracts CALL actions may invoke with any calldata
+    /// (max `MAX_ALLOWED_CALL_TARGETS`)
+    #[cfg_attr(feature = "serde", serde(with = "kernel_core::serde_hex::array_vec"))]
+    pub allowed_call_targets: Vec<[u8; 20]>,
+    /// Individual functions CALL actions may invoke (max `MAX_ALLOWED_CALLS`)
+    pub allowed_calls: Vec<AllowedCall>,
+    /// Rolling-window execution and notional limits
+    pub rolling_window: Option<RollingWindowLimits>,
+    /// ERC20 approval policy
+    pub approval_policy: Option<ApprovalPolicy>,
+    /// Caps on the native value CALL actions send
+    pub native_value: Option<NativeValueLimits>,
+    /// Maximum steps the agent may charge per execution (must be non-zero)
+    pub step_budget: Option<u64>,
+}
+
+impl Default for ConstraintSetV3 {
+    /// Default limits and empty allowlists of [`ConstraintSetV2`], with no
+    /// extensions.
+    fn default() -> Self {
+        let v2 = ConstraintSetV2::default();
+        Self {
+            version: 3,
+            max_position_notional: v2.max_position_notional,
+            max_leverage_bps: v2.max_leverage_bps,
+            max_drawdown_bps: v2.max_drawdown_bps,
+            cooldown_seconds: v2.cooldown_seconds,
+            max_actions_per_output: v2.max_actions_per_output,
+            allowed_tokens: v2.allowed_tokens,
+            allowed_call_targets: v2.allowed_call_targets,
+            allowed_calls: v2.allowed_calls,
+            rolling_window: None,
+            approval_policy: None,
+            native_value: None,
+            step_budget: None,
+        }
     }
-    Ok(())
 }
 
-/// Helper to read a u256 from big-endian bytes (only reads lower 64 bits for practical values)
-fn u256_from_be_bytes(bytes: &[u8]) -> u64 {
-    // For practical values, we only need to check if upper bytes are zero
-    // and read the lower 8 bytes
-    if bytes.len() != 32 {
-        return u64::MAX; // Invalid
+impl ConstraintSetV3 {
+    /// Size of the fixed fields, the three list counts and the extension
+    /// flags in bytes.
+    pub const MIN_ENCODED_SIZE: usize = 44;
+
+    /// The set's token and call allowlists.
+    pub fn allowlists(&self) -> Allowlists<'_> {
+        Allowlists {
+            tokens: &self.allowed_tokens,
+            call_targets: &self.allowed_call_targets,
+            calls: &self.allowed_calls,
+        }
     }
-    // Check upper 24 bytes are zero (for values that fit in u64)
-    if bytes[0..24] != [0u8; 24] {
-        return u64::MAX; // Value too large
+
+    /// Flags word recording which extensions are present.
+    fn extensions(&self) -> u32 {
+        let mut extensions = 0;
+        if self.rolling_window.is_some() {
+            extensions |= EXTENSION_ROLLING_WINDOW;
+        }
+        if self.approval_policy.is_some() {
+            extensions |= EXTENSION_APPROVAL_POLICY;
+        }
+        if self.native_value.is_some() {
+            extensions |= EXTENSION_NATIVE_VALUE;
+        }
+        if self.step_budget.is_some() {
+            extensions |= EXTENSION_STEP_BUDGET;
+        }
+        extensions
     }
-    u64::from_be_bytes(bytes[24..32].try_into().unwrap())
 }
 
-/// Validate global constraints (cooldown, drawdown).
-fn validate_global_constraints(
-    snapshot: &StateSnapshotV1,
-    constraint_set: &ConstraintSetV1,
-) -> Result<(), ConstraintViolation> {
-    // Check cooldown
-    if constraint_set.cooldown_seconds > 0 {
-        // Use checked_add to detect maliciously large last_execution_ts values.
-        // Overflow would indicate an invalid snapshot (timestamp cannot be that large).
-        let required_ts = snapshot
-            .last_execution_ts
-            .checked_add(constraint_set.cooldown_seconds as u64)
-            .ok_or_else(|| {
-                ConstraintViolation::global(ConstraintViolationReason::InvalidStateSnapshot)
-            })?;
-        if snapshot.current_ts < required_ts {
-            return Err(ConstraintViolation::global(
-                ConstraintViolationReason::CooldownNotElapsed,
-            ));
+/// ConstraintSetV3 encoding layout (little-endian):
+/// - ConstraintSetV2 layout with version = 3
+/// - extensions: u32 (4 bytes, bit 0 = rolling_window, bit 1 =
+///   approval_policy, bit 2 = native_value, bit 3 = step_budget; other
+///   bits must be zero)
+/// - rolling_window, if bit 0 is set: window_seconds: u32, max_executions:
+///   u32, max_notional: u64 (16 bytes)
+/// - approval_policy, if bit 1 is set: spender_count: u32, then
+///   spender_count * address (20 bytes), max_amount: u128, allow_unlimited:
+///   u8 (0 or 1)
+/// - native_value, if bit 2 is set: max_value_per_action: u128,
+///   max_value_per_output: u128 (32 bytes)
+/// - step_budget, if bit 3 is set: u64 (8 bytes)
+///
+/// Extensions follow in flag-bit order.
+///
+/// Size: 44 + 20 * tokens + 20 * call_targets + 24 * calls bytes, plus the
+/// present extensions
+impl CanonicalEncode for ConstraintSetV3 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        let mut len = Self::MIN_ENCODED_SIZE + self.allowlists().entries_len();
+        if self.rolling_window.is_some() {
+            len += RollingWindowLimits::ENCODED_SIZE;
+        }
+        if let Some(policy) = &self.approval_policy {
+            len += policy.encoded_len();
+        }
+        if self.native_value.is_some() {
+            len += NativeValueLimits::ENCODED_SIZE;
         }
+        if self.step_budget.is_some() {
+            len += 8;
+        }
+        Ok(len)
     }
 
-    // Check drawdown
-    if constraint_set.max_drawdown_bps < 10_000 {
-        // Only check if drawdown limit is meaningful (< 100%)
-        if snapshot.peak_equity == 0 {
-            return Err(ConstraintViolation::global(
-                ConstraintViolationReason::InvalidStateSnapshot,
-            ));
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        if self.version != 3 {
+            return Err(CodecError::InvalidVersion {
+                expected: 3,
+                actual: self.version,
+            });
         }
+        self.allowlists().check()?;
 
-        // Calculate drawdown in basis points
-        // drawdown_bps = (peak - current) * 10000 / peak
-        let drawdown = snapshot.peak_equity.saturating_sub(snapshot.current_equity);
-        // SAFETY: peak_equity != 0 is verified above, so division cannot fail
-        let drawdown_bps = drawdown
-            .saturating_mul(10_000)
-            .checked_div(snapshot.peak_equity)
-            .expect("peak_equity != 0 checked above");
+        put_u32_le(out, self.version);
+        put_u64_le(out, self.max_position_notional);
+        put_u32_le(out, self.max_leverage_bps);
+        put_u32_le(out, self.max_drawdown_bps);
+        put_u32_le(out, self.cooldown_seconds);
+        put_u32_le(out, self.max_actions_per_output);
+        self.allowlists().encode_into(out);
 
-        if drawdown_bps > constraint_set.max_drawdown_bps as u64 {
-            return Err(ConstraintViolation::global(
-                ConstraintViolationReason::DrawdownExceeded,
-            ));
+        put_u32_le(out, self.extensions());
+        if let Some(window) = &self.rolling_window {
+            put_u32_le(out, window.window_seconds);
+            put_u32_le(out, window.max_executions);
+            put_u64_le(out, window.max_notional);
+        }
+        if let Some(policy) = &self.approval_policy {
+            policy.encode_into(out)?;
+        }
+        if let Some(limits) = &self.native_value {
+            out.extend_from_slice(&limits.max_value_per_action.to_le_bytes());
+            out.extend_from_slice(&limits.max_value_per_output.to_le_bytes());
         }
+        if let Some(budget) = self.step_budget {
+            put_u64_le(out, budget);
+        }
+        Ok(())
     }
+}
 
-    Ok(())
+impl CanonicalDecode for ConstraintSetV3 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut offset = 0;
+
+        let version = get_u32_le(bytes, &mut offset)?;
+        if version != 3 {
+            return Err(CodecError::InvalidVersion {
+                expected: 3,
+                actual: version,
+            });
+        }
+
+        let max_position_notional = get_u64_le(bytes, &mut offset)?;
+        let max_leverage_bps = get_u32_le(bytes, &mut offset)?;
+        let max_drawdown_bps = get_u32_le(bytes, &mut offset)?;
+        let cooldown_seconds = get_u32_le(bytes, &mut offset)?;
+        let max_actions_per_output = get_u32_le(bytes, &mut offset)?;
+        let (allowed_tokens, allowed_call_targets, allowed_calls) =
+            get_allowlists(bytes, &mut offset)?;
+
+        let extensions = get_u32_le(bytes, &mut offset)?;
+        if extensions & !KNOWN_EXTENSIONS != 0 {
+            return Err(CodecError::UnknownConstraintExtension(
+                extensions & !KNOWN_EXTENSIONS,
+            ));
+        }
+        let rolling_window = if extensions & EXTENSION_ROLLING_WINDOW != 0 {
+            Some(RollingWindowLimits {
+                window_seconds: get_u32_le(bytes, &mut offset)?,
+                max_executions: get_u32_le(bytes, &mut offset)?,
+                max_notional: get_u64_le(bytes, &mut offset)?,
+            })
+        } else {
+            None
+        };
+        let approval_policy = if extensions & EXTENSION_APPROVAL_POLICY != 0 {
+            Some(ApprovalPolicy::decode_from(bytes, &mut offset)?)
+        } else {
+            None
+        };
+        let native_value = if extensions & EXTENSION_NATIVE_VALUE != 0 {
+            Some(NativeValueLimits {
+                max_value_per_action: get_u128_le(bytes, &mut offset)?,
+                max_value_per_output: get_u128_le(bytes, &mut offset)?,
+            })
+        } else {
+            None
+        };
+        let step_budget = if extensions & EXTENSION_STEP_BUDGET != 0 {
+            Some(get_u64_le(bytes, &mut offset)?)
+        } else {
+            None
+        };
+
+        ensure_no_trailing_bytes(bytes, offset)?;
+
+        Ok(Self {
+            version,
+            max_position_notional,
+            max_leverage_bps,
+            max_drawdown_bps,
+            cooldown_seconds,
+            max_actions_per_output,
+            allowed_tokens,
+            allowed_call_targets,
+            allowed_calls,
+            rolling_window,
+            approval_policy,
+            native_value,
+            step_budget,
+        })
+    }
 }
 
 // ============================================================================
-// Legacy API (backward compatibility)
+// Versioned Constraint Set
 // ============================================================================
 
-/// Check agent output against constraint set (legacy API).
+/// A constraint set of any supported version.
 ///
-/// This function uses the default constraint set for backward compatibility.
-pub fn check(_output: &AgentOutput, _meta: &ConstraintMeta) -> Result<(), ConstraintError> {
-    // For backward compatibility, always pass with default constraints.
-    // The new enforce_constraints function should be used instead.
-    Ok(())
+/// Decoding dispatches on the leading `version` field. The kernel and the
+/// enforcement functions take this type, so the host can supply either
+/// version; the encoding (and hash) is that of the wrapped set.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstraintSet {
+    /// Limits only, no allowlists (60 bytes)
+    V1(ConstraintSetV1),
+    /// Limits plus token and call allowlists
+    V2(ConstraintSetV2),
+    /// Limits and allowlists plus optional extensions
+    V3(ConstraintSetV3),
 }
 
-/// Validate that output is well-formed (legacy API).
-pub fn validate_output_structure_legacy(output: &AgentOutput) -> Result<(), ConstraintError> {
-    if output.actions.len() > MAX_ACTIONS_PER_OUTPUT {
-        return Err(ConstraintError::InvalidOutput);
+impl Default for ConstraintSet {
+    /// The default [`ConstraintSetV1`], hashing to
+    /// [`DEFAULT_CONSTRAINT_SET_HASH`].
+    fn default() -> Self {
+        ConstraintSet::V1(ConstraintSetV1::default())
     }
+}
 
-    for action in &output.actions {
-        if action.payload.len() > MAX_ACTION_PAYLOAD_BYTES {
-            return Err(ConstraintError::InvalidOutput);
+impl From<ConstraintSetV1> for ConstraintSet {
+    fn from(constraint_set: ConstraintSetV1) -> Self {
+        ConstraintSet::V1(constraint_set)
+    }
+}
+
+impl From<ConstraintSetV2> for ConstraintSet {
+    fn from(constraint_set: ConstraintSetV2) -> Self {
+        ConstraintSet::V2(constraint_set)
+    }
+}
+
+impl From<ConstraintSetV3> for ConstraintSet {
+    fn from(constraint_set: ConstraintSetV3) -> Self {
+        ConstraintSet::V3(constraint_set)
+    }
+}
+
+impl ConstraintSet {
+    /// Constraint set version.
+    pub fn version(&self) -> u32 {
+        match self {
+            ConstraintSet::V1(cs) => cs.version,
+            ConstraintSet::V2(cs) => cs.version,
+            ConstraintSet::V3(cs) => cs.version,
         }
     }
 
-    Ok(())
+    /// Maximum position size in base units.
+    pub fn max_position_notional(&self) -> u64 {
+        match self {
+            ConstraintSet::V1(cs) => cs.max_position_notional,
+            ConstraintSet::V2(cs) => cs.max_position_notional,
+            ConstraintSet::V3(cs) => cs.max_position_notional,
+        }
+    }
+
+    /// Maximum leverage in basis points.
+    pub fn max_leverage_bps(&self) -> u32 {
+        match self {
+            ConstraintSet::V1(cs) => cs.max_leverage_bps,
+            ConstraintSet::V2(cs) => cs.max_leverage_bps,
+            ConstraintSet::V3(cs) => cs.max_leverage_bps,
+        }
+    }
+
+    /// Maximum drawdown in basis points (10000 = 100%).
+    pub fn max_drawdown_bps(&self) -> u32 {
+        match self {
+            ConstraintSet::V1(cs) => cs.max_drawdown_bps,
+            ConstraintSet::V2(cs) => cs.max_drawdown_bps,
+            ConstraintSet::V3(cs) => cs.max_drawdown_bps,
+        }
+    }
+
+    /// Minimum seconds between executions.
+    pub fn cooldown_seconds(&self) -> u32 {
+        match self {
+            ConstraintSet::V1(cs) => cs.cooldown_seconds,
+            ConstraintSet::V2(cs) => cs.cooldown_seconds,
+            ConstraintSet::V3(cs) => cs.cooldown_seconds,
+        }
+    }
+
+    /// Maximum actions per output.
+    pub fn max_actions_per_output(&self) -> u32 {
+        match self {
+            ConstraintSet::V1(cs) => cs.max_actions_per_output,
+            ConstraintSet::V2(cs) => cs.max_actions_per_output,
+            ConstraintSet::V3(cs) => cs.max_actions_per_output,
+        }
+    }
+
+    /// Token and call allowlists (`None` for version 1, which allows any
+    /// token and call target).
+    pub fn allowlists(&self) -> Option<Allowlists<'_>> {
+        match self {
+            ConstraintSet::V1(_) => None,
+            ConstraintSet::V2(cs) => Some(cs.allowlists()),
+            ConstraintSet::V3(cs) => Some(cs.allowlists()),
+        }
+    }
+
+    /// Rolling-window limits (`None` unless a version 3 set enables them).
+    pub fn rolling_window(&self) -> Option<&RollingWindowLimits> {
+        match self {
+            ConstraintSet::V3(cs) => cs.rolling_window.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// ERC20 approval policy (`None` unless a version 3 set enables it).
+    pub fn approval_policy(&self) -> Option<&ApprovalPolicy> {
+        match self {
+            ConstraintSet::V3(cs) => cs.approval_policy.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Native-value caps (`None` unless a version 3 set enables them).
+    pub fn native_value(&self) -> Option<&NativeValueLimits> {
+        match self {
+            ConstraintSet::V3(cs) => cs.native_value.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Agent step budget (`None` unless a version 3 set declares one).
+    pub fn step_budget(&self) -> Option<u64> {
+        match self {
+            ConstraintSet::V3(cs) => cs.step_budget,
+            _ => None,
+        }
+    }
 }
 
-// Re-export the legacy validate_output_structure under its original name
-pub use validate_output_structure_legacy as validate_output_structure;
+impl CanonicalEncode for ConstraintSet {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        match self {
+            ConstraintSet::V1(cs) => cs.encoded_len(),
+            ConstraintSet::V2(cs) => cs.encoded_len(),
+            ConstraintSet::V3(cs) => cs.encoded_len(),
+        }
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        match self {
+            ConstraintSet::V1(cs) => cs.encode_into(out),
+            ConstraintSet::V2(cs) => cs.encode_into(out),
+            ConstraintSet::V3(cs) => cs.encode_into(out),
+        }
+    }
+}
+
+impl CanonicalDecode for ConstraintSet {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut offset = 0;
+        match get_u32_le(bytes, &mut offset)? {
+            1 => ConstraintSetV1::decode(bytes).map(ConstraintSet::V1),
+            2 => ConstraintSetV2::decode(bytes).map(ConstraintSet::V2),
+            3 => ConstraintSetV3::decode(bytes).map(ConstraintSet::V3),
+            actual => Err(CodecError::InvalidVersion {
+                expected: 3,
+                actual,
+            }),
+        }
+    }
+}
 
 // ============================================================================
-// Tests
+// State Snapshot
 // ============================================================================
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// State snapshots for cooldown, drawdown and rolling-window checks
+/// (defined in kernel-core).
+pub use kernel_core::{StateSnapshot, StateSnapshotV1, StateSnapshotV2};
 
-    fn make_test_input() -> KernelInputV1 {
-        KernelInputV1 {
-            protocol_version: 1,
-            kernel_version: 1,
-            agent_id: [0x42; 32],
-            agent_code_hash: [0xaa; 32],
-            constraint_set_hash: [0xbb; 32],
-            input_root: [0xcc; 32],
-            execution_nonce: 1,
-            opaque_agent_inputs: vec![],
+// ============================================================================
+// Position Notional
+// ============================================================================
+
+/// ERC20 `transfer(address,uint256)` selector.
+pub const ERC20_TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+
+/// ERC20 `transferFrom(address,address,uint256)` selector.
+pub const ERC20_TRANSFER_FROM_SELECTOR: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+
+/// ERC20 `approve(address,uint256)` selector.
+pub const ERC20_APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+
+/// `increaseAllowance(address,uint256)` selector (OpenZeppelin ERC20).
+pub const ERC20_INCREASE_ALLOWANCE_SELECTOR: [u8; 4] = [0x39, 0x50, 0x93, 0x51];
+
+/// HyperliquidAdapter `openPosition(bool,uint256,uint256,uint256)` selector.
+pub const HYPERLIQUID_OPEN_POSITION_SELECTOR: [u8; 4] = [0x04, 0xba, 0x41, 0xcb];
+
+/// Arguments of ERC20 `approve(address spender, uint256 amount)` calldata.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Erc20Approval {
+    /// Address allowed to spend the caller's tokens
+    pub spender: [u8; 20],
+    /// Allowance as a big-endian uint256
+    pub amount: [u8; 32],
+}
+
+impl Erc20Approval {
+    /// Decode `approve(address,uint256)` calldata.
+    ///
+    /// Returns `None` for another selector, for calldata other than the
+    /// selector and exactly two words, and for a spender word whose upper
+    /// 12 bytes are not zero.
+    pub fn decode(calldata: &[u8]) -> Option<Self> {
+        if calldata.len() != 4 + 64 || calldata[0..4] != ERC20_APPROVE_SELECTOR {
+            return None;
         }
+        if calldata[4..16] != [0u8; 12] {
+            return None;
+        }
+        Some(Self {
+            spender: calldata[16..36].try_into().expect("20-byte address"),
+            amount: calldata[36..68].try_into().expect("32-byte word"),
+        })
     }
 
-    fn make_echo_action() -> ActionV1 {
-        ActionV1 {
-            action_type: ACTION_TYPE_ECHO,
-            target: [0x11; 32],
-            payload: vec![1, 2, 3],
+    /// Whether the allowance is `type(uint256).max`, which ERC20 tokens
+    /// conventionally treat as unlimited.
+    pub fn is_unlimited(&self) -> bool {
+        self.amount == [0xff; 32]
+    }
+}
+
+/// Notional value an action commits, in token base units.
+///
+/// Recognised actions:
+/// - TRANSFER_ERC20: the transferred `amount`
+/// - CALL `transfer(address to, uint256 amount)`: `amount`
+/// - CALL `transferFrom(address from, address to, uint256 amount)`: `amount`
+/// - CALL `approve(address spender, uint256 amount)`: `amount`
+/// - CALL HyperliquidAdapter `openPosition(bool isBuy, uint256 marginAmount,
+///   uint256 orderSize, uint256 limitPrice)`: `marginAmount`, the USDC the
+///   adapter pulls from the vault. The order's leverage on the venue is not
+///   visible to the engine.
+///
+/// Returns `None` for any other action, including calls whose calldata is
+/// too short for the recognised function. Amounts above `u128::MAX`
+/// saturate to `u128::MAX`.
+pub fn action_notional(action: &ActionV1) -> Option<u128> {
+    match action.action_type {
+        x if x == ACTION_TYPE_TRANSFER_ERC20 => {
+            action.payload.get(64..96).map(u128_saturating_from_u256)
+        }
+        x if x == ACTION_TYPE_CALL => {
+            let calldata = call_calldata(&action.payload)?;
+            let (selector, args) = (calldata.get(0..4)?, &calldata[4..]);
+            // (index of the amount argument, number of static arguments)
+            let (amount_arg, arg_count) = match selector {
+                s if s == ERC20_TRANSFER_SELECTOR || s == ERC20_APPROVE_SELECTOR => (1, 2),
+                s if s == ERC20_TRANSFER_FROM_SELECTOR => (2, 3),
+                s if s == HYPERLIQUID_OPEN_POSITION_SELECTOR => (1, 4),
+                _ => return None,
+            };
+            if args.len() < arg_count * 32 {
+                return None;
+            }
+            Some(u128_saturating_from_u256(
+                &args[amount_arg * 32..(amount_arg + 1) * 32],
+            ))
         }
+        _ => None,
     }
+}
 
-    /// Create a valid CALL action with proper ABI encoding
-    fn make_call_action(target_addr: [u8; 20], value: u128, calldata: &[u8]) -> ActionV1 {
-        let mut target = [0u8; 32];
-        target[12..32].copy_from_slice(&target_addr);
+/// Notional an action moves out of the vault, in token base units.
+///
+/// [`action_notional`] without ERC20 `approve` calls: an approval grants an
+/// allowance but moves nothing, and the transfer or `openPosition` that
+/// spends it is counted on its own. Rolling-window limits sum this value.
+pub fn transferred_notional(action: &ActionV1) -> Option<u128> {
+    let is_approval = action.action_type == ACTION_TYPE_CALL
+        && call_calldata(&action.payload)
+            .is_some_and(|calldata| calldata.starts_with(&ERC20_APPROVE_SELECTOR));
+    if is_approval {
+        return None;
+    }
+    action_notional(action)
+}
 
-        let data_len = calldata.len();
-        let padded_len = data_len.div_ceil(32) * 32;
-        let total_size = 96 + padded_len;
+/// Whether an action is a CALL to HyperliquidAdapter `openPosition`, the
+/// only recognised action whose notional is margin for a leveraged position.
+fn opens_position(action: &ActionV1) -> bool {
+    action.action_type == ACTION_TYPE_CALL
+        && call_calldata(&action.payload)
+            .is_some_and(|calldata| calldata.starts_with(&HYPERLIQUID_OPEN_POSITION_SELECTOR))
+}
 
-        let mut payload = vec![0u8; total_size];
+/// Native value a CALL action sends, as a big-endian uint256 in wei.
+///
+/// This is the first word of the payload `abi.encode(uint256 value, bytes
+/// callData)`, decoded in full. Returns `None` for other action types and
+/// for payloads shorter than one word.
+pub fn call_value(action: &ActionV1) -> Option<[u8; 32]> {
+    if action.action_type != ACTION_TYPE_CALL {
+        return None;
+    }
+    action.payload.get(0..32)?.try_into().ok()
+}
 
-        // value (uint256, big-endian)
-        payload[16..32].copy_from_slice(&value.to_be_bytes());
+/// Calldata of a CALL payload `abi.encode(uint256 value, bytes callData)`.
+///
+/// Returns `None` if the payload is too short for its declared calldata
+/// length.
+pub fn call_calldata(payload: &[u8]) -> Option<&[u8]> {
+    let len = usize::try_from(u256_from_be_bytes(payload.get(64..96)?)).ok()?;
+    payload.get(96..96usize.checked_add(len)?)
+}
 
-        // offset (64 = 0x40)
-        payload[63] = 64;
+// ============================================================================
+// Custom Rules
+// ============================================================================
 
-        // length
-        payload[95] = data_len as u8;
+/// What a [`ConstraintRule`] can inspect during evaluation.
+#[derive(Clone, Copy, Debug)]
+pub struct RuleContext<'a> {
+    /// The kernel input being executed
+    pub input: &'a KernelInputRef<'a>,
+    /// The agent's proposed output
+    pub output: &'a AgentOutput,
+    /// The built-in constraint set enforced alongside the rule
+    pub constraint_set: &'a ConstraintSet,
+    /// State snapshot decoded from the input, if present
+    pub snapshot: Option<&'a StateSnapshot>,
+}
+
+/// A policy check enforced in addition to the built-in constraints.
+///
+/// Rules are composed with a [`ConstraintSet`] into a [`RuleSet`]. They run
+/// after the built-in checks and report violations with the existing
+/// [`ConstraintViolationReason`] codes, so journals stay decodable by any
+/// verifier. Both checks default to passing; implement the one(s) the
+/// policy needs.
+///
+/// Rules must be deterministic: the same input and output always produce
+/// the same result.
+pub trait ConstraintRule {
+    /// Stable identifier committed to the constraint set hash (e.g.
+    /// `"vault.min-equity"`).
+    fn id(&self) -> &str;
+
+    /// Canonical encoding of the rule's parameters, committed to the
+    /// constraint set hash alongside [`id`](Self::id). Rules whose behaviour
+    /// depends on configuration must encode all of it here.
+    fn params(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Check one action. Only called for actions that passed every built-in
+    /// per-action check, so the payload is well-formed.
+    fn check_action(
+        &self,
+        _ctx: &RuleContext<'_>,
+        _action: &ActionV1,
+        _index: usize,
+    ) -> Result<(), ConstraintViolation> {
+        Ok(())
+    }
+
+    /// Check the output as a whole, after every per-action check.
+    fn check_output(&self, _ctx: &RuleContext<'_>) -> Result<(), ConstraintViolation> {
+        Ok(())
+    }
+}
+
+/// A [`ConstraintSet`] composed with custom [`ConstraintRule`]s.
+///
+/// Rules are evaluated in the order they were added. The canonical encoding
+/// appends the rule list to the constraint set's encoding:
+///
+/// - rule_count: u32
+/// - per rule: id_len: u32, id bytes, params_len: u32, params bytes
+///
+/// A rule set with no rules encodes (and hashes) exactly like its
+/// constraint set. Rule sets cannot be decoded, since the rule
+/// implementations are code: host and guest each build the same rule set and
+/// the kernel checks its hash against the input's `constraint_set_hash`.
+pub struct RuleSet {
+    constraint_set: ConstraintSet,
+    rules: Vec<Box<dyn ConstraintRule>>,
+}
+
+impl RuleSet {
+    /// Rule set enforcing only the built-in constraints of `constraint_set`.
+    pub fn new(constraint_set: impl Into<ConstraintSet>) -> Self {
+        Self {
+            constraint_set: constraint_set.into(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Append a rule, evaluated after those already added.
+    pub fn with_rule(mut self, rule: impl ConstraintRule + 'static) -> Self {
+        self.push(rule);
+        self
+    }
+
+    /// Append a rule, evaluated after those already added.
+    pub fn push(&mut self, rule: impl ConstraintRule + 'static) {
+        self.rules.push(Box::new(rule));
+    }
+
+    /// The built-in constraints.
+    pub fn constraint_set(&self) -> &ConstraintSet {
+        &self.constraint_set
+    }
+
+    /// Custom rules in evaluation order.
+    pub fn rules(&self) -> &[Box<dyn ConstraintRule>] {
+        &self.rules
+    }
+}
+
+impl From<ConstraintSet> for RuleSet {
+    fn from(constraint_set: ConstraintSet) -> Self {
+        Self::new(constraint_set)
+    }
+}
+
+impl fmt::Debug for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuleSet")
+            .field("constraint_set", &self.constraint_set)
+            .field(
+                "rules",
+                &self.rules.iter().map(|rule| rule.id()).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl CanonicalEncode for RuleSet {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        let mut len = self.constraint_set.encoded_len()?;
+        if !self.rules.is_empty() {
+            len += 4;
+            for rule in &self.rules {
+                len += 4 + rule.id().len() + 4 + rule.params().len();
+            }
+        }
+        Ok(len)
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        self.constraint_set.encode_into(out)?;
+        if self.rules.is_empty() {
+            return Ok(());
+        }
+
+        put_u32_le(out, encoded_count(self.rules.len())?);
+        for rule in &self.rules {
+            let id = rule.id().as_bytes();
+            put_u32_le(out, encoded_count(id.len())?);
+            out.extend_from_slice(id);
+            let params = rule.params();
+            put_u32_le(out, encoded_count(params.len())?);
+            out.extend_from_slice(&params);
+        }
+        Ok(())
+    }
+}
+
+/// Length prefix for a rule list, id or parameter encoding.
+fn encoded_count(len: usize) -> Result<u32, CodecError> {
+    u32::try_from(len).map_err(|_| CodecError::InvalidLength)
+}
+
+// ============================================================================
+// Constraint Metadata (Legacy compatibility)
+// ============================================================================
+
+/// Metadata for constraint checking (legacy API).
+#[derive(Clone, Debug)]
+pub struct ConstraintMeta {
+    pub agent_id: [u8; 32],
+    pub agent_code_hash: [u8; 32],
+    pub constraint_set_hash: [u8; 32],
+    pub input_root: [u8; 32],
+    pub execution_nonce: u64,
+}
+
+// ============================================================================
+// Constraint Enforcement
+// ============================================================================
+
+/// Enforce all constraints on the proposed agent output.
+///
+/// This is the main entry point for constraint checking. It validates:
+/// 1. Output structure (action count, payload sizes)
+/// 2. Per-action constraints (action type validity, payload format,
+///    position size and leverage)
+/// 3. Global constraints (cooldown, drawdown, rolling window)
+///
+/// # Supported Action Types
+///
+/// For protocol v1, only on-chain executable action types are allowed:
+/// - `ACTION_TYPE_CALL` (0x00000002) - Must have valid ABI-encoded payload
+/// - `ACTION_TYPE_TRANSFER_ERC20` (0x00000003) - Must have 96-byte payload
+/// - `ACTION_TYPE_NO_OP` (0x00000004) - Must have empty payload
+///
+/// Any other action type triggers `UnknownActionType` violation.
+///
+/// # Arguments
+/// * `input` - The kernel input containing state snapshot
+/// * `proposed` - The proposed agent output to validate
+/// * `constraint_set` - The constraint set to enforce
+///
+/// # Returns
+/// * `Ok(AgentOutput)` - The validated output (same as proposed if valid)
+/// * `Err(ConstraintViolation)` - The first constraint violation encountered
+pub fn enforce_constraints(
+    input: &KernelInputV1,
+    proposed: &AgentOutput,
+    constraint_set: &ConstraintSet,
+) -> Result<AgentOutput, ConstraintViolation> {
+    enforce_constraints_ref(&KernelInputRef::from(input), proposed, constraint_set)
+}
+
+/// Enforce all constraints on an input that expires at `valid_until`.
+///
+/// Kernel v4 inputs carry a `valid_until` unix timestamp. The kernel has no
+/// clock of its own, so expiry is checked against the state snapshot: if the
+/// snapshot's `current_ts` is later than `valid_until`, the execution fails
+/// with `InputExpired` before any other constraint is evaluated. Inputs
+/// without a snapshot are not rejected here; the committed `valid_until` is
+/// still enforced on-chain.
+///
+/// Otherwise behaves exactly like [`enforce_constraints`].
+pub fn enforce_constraints_with_expiry(
+    input: &KernelInputV1,
+    proposed: &AgentOutput,
+    constraint_set: &ConstraintSet,
+    valid_until: u64,
+) -> Result<AgentOutput, ConstraintViolation> {
+    let input = KernelInputRef {
+        valid_until: Some(valid_until),
+        ..KernelInputRef::from(input)
+    };
+    enforce_constraints_ref(&input, proposed, constraint_set)
+}
+
+/// Enforce all constraints on a borrowed input of any kernel version.
+///
+/// Zero-copy entry point used by the kernel guest: the state snapshot is
+/// taken from the input's typed slot, or for inputs before kernel version 5
+/// read straight from the borrowed `opaque_agent_inputs` (see
+/// [`input_state_snapshot`]). If the input carries a
+/// `valid_until`, it is checked first as in
+/// [`enforce_constraints_with_expiry`]; otherwise this behaves exactly like
+/// [`enforce_constraints`].
+pub fn enforce_constraints_ref(
+    input: &KernelInputRef<'_>,
+    proposed: &AgentOutput,
+    constraint_set: &ConstraintSet,
+) -> Result<AgentOutput, ConstraintViolation> {
+    evaluate_constraints(
+        input,
+        proposed,
+        constraint_set,
+        &[],
+        &mut Violations::FirstFailure,
+    )?;
+
+    // All constraints passed - return the validated output
+    Ok(proposed.clone())
+}
+
+/// Enforce a [`RuleSet`]: its built-in constraints, then its custom rules.
+///
+/// Behaves like [`enforce_constraints_ref`] on the rule set's constraint
+/// set, except that each action which passes the built-in per-action checks
+/// is also passed to every rule's [`ConstraintRule::check_action`], and
+/// every rule's [`ConstraintRule::check_output`] runs after the global
+/// checks. Returns the first violation, built-in or custom.
+pub fn enforce_rule_set_ref(
+    input: &KernelInputRef<'_>,
+    proposed: &AgentOutput,
+    rule_set: &RuleSet,
+) -> Result<AgentOutput, ConstraintViolation> {
+    evaluate_constraints(
+        input,
+        proposed,
+        &rule_set.constraint_set,
+        &rule_set.rules,
+        &mut Violations::FirstFailure,
+    )?;
+    Ok(proposed.clone())
+}
+
+/// Evaluate every constraint rule and return all violations (diagnostic mode).
+///
+/// Unlike [`enforce_constraints`], evaluation does not stop at the first
+/// violation: every structural, per-action and global rule is checked and
+/// each failure is reported, in evaluation order. Within a single action,
+/// checking stops at that action's first violation (a malformed payload has
+/// no notional to check). An invalid constraint set is reported alone, since
+/// no other rule can be evaluated against it.
+///
+/// The first entry is always the violation [`enforce_constraints`] returns,
+/// and the list is empty exactly when it succeeds. This is a host-side
+/// debugging aid; the kernel commits only the first violation.
+pub fn diagnose_constraints(
+    input: &KernelInputV1,
+    proposed: &AgentOutput,
+    constraint_set: &ConstraintSet,
+) -> Vec<ConstraintViolation> {
+    diagnose_constraints_ref(&KernelInputRef::from(input), proposed, constraint_set)
+}
+
+/// [`diagnose_constraints`] on a borrowed input of any kernel version.
+///
+/// Checks `valid_until` as in [`enforce_constraints_ref`].
+pub fn diagnose_constraints_ref(
+    input: &KernelInputRef<'_>,
+    proposed: &AgentOutput,
+    constraint_set: &ConstraintSet,
+) -> Vec<ConstraintViolation> {
+    diagnose(input, proposed, constraint_set, &[])
+}
+
+/// [`diagnose_constraints_ref`] for a [`RuleSet`], reporting custom rule
+/// violations alongside built-in ones in the order
+/// [`enforce_rule_set_ref`] evaluates them.
+pub fn diagnose_rule_set_ref(
+    input: &KernelInputRef<'_>,
+    proposed: &AgentOutput,
+    rule_set: &RuleSet,
+) -> Vec<ConstraintViolation> {
+    diagnose(input, proposed, &rule_set.constraint_set, &rule_set.rules)
+}
+
+fn diagnose(
+    input: &KernelInputRef<'_>,
+    proposed: &AgentOutput,
+    constraint_set: &ConstraintSet,
+    rules: &[Box<dyn ConstraintRule>],
+) -> Vec<ConstraintViolation> {
+    let mut violations = Violations::All(Vec::new());
+    evaluate_constraints(input, proposed, constraint_set, rules, &mut violations)
+        .expect("diagnostic mode records violations instead of returning them");
+    match violations {
+        Violations::All(all) => all,
+        Violations::FirstFailure => unreachable!(),
+    }
+}
+
+/// How rule failures are handled during evaluation.
+enum Violations {
+    /// Return the first violation (consensus path).
+    FirstFailure,
+    /// Record every violation and keep evaluating (diagnostic mode).
+    All(Vec<ConstraintViolation>),
+}
+
+impl Violations {
+    /// Handle the outcome of one rule.
+    ///
+    /// Returns `Ok(true)` if the rule passed, `Ok(false)` if its violation
+    /// was recorded, and the violation itself in first-failure mode.
+    fn report(
+        &mut self,
+        result: Result<(), ConstraintViolation>,
+    ) -> Result<bool, ConstraintViolation> {
+        match (result, self) {
+            (Ok(()), _) => Ok(true),
+            (Err(violation), Violations::FirstFailure) => Err(violation),
+            (Err(violation), Violations::All(all)) => {
+                all.push(violation);
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// Evaluate all rules in order, reporting each failure to `violations`.
+///
+/// Custom `rules` run after the built-in checks they extend: per action,
+/// then over the whole output.
+fn evaluate_constraints(
+    input: &KernelInputRef<'_>,
+    proposed: &AgentOutput,
+    constraint_set: &ConstraintSet,
+    rules: &[Box<dyn ConstraintRule>],
+    violations: &mut Violations,
+) -> Result<(), ConstraintViolation> {
+    // 0. Resolve the state snapshot (optional)
+    let snapshot = input_state_snapshot(input);
+
+    // 1. Reject inputs whose snapshot is past their expiry
+    violations.report(check_expiry(input.valid_until, snapshot.as_ref()))?;
+
+    // 2. Validate constraint set version and invariants
+    if !violations.report(check_constraint_set(constraint_set))? {
+        return Ok(());
+    }
+
+    // 3. Validate output structure
+    check_output_structure(proposed, constraint_set, violations)?;
+
+    let ctx = RuleContext {
+        input,
+        output: proposed,
+        constraint_set,
+        snapshot: snapshot.as_ref(),
+    };
+
+    // 4. Validate each action, stopping at its first violation
+    for (index, action) in proposed.actions.iter().enumerate() {
+        if !violations.report(validate_action(action, index, constraint_set))?
+            || !violations.report(check_position_limits(
+                action,
+                index,
+                constraint_set,
+                snapshot.as_ref(),
+            ))?
+        {
+            continue;
+        }
+        for rule in rules {
+            if !violations.report(rule.check_action(&ctx, action, index))? {
+                break;
+            }
+        }
+    }
+
+    // 5. Validate the output's total native value
+    if let Some(limits) = constraint_set.native_value() {
+        if !limits.allows_output_value(proposed) {
+            violations.report(Err(ConstraintViolation::global(
+                ConstraintViolationReason::NativeValueExceeded,
+            )))?;
+        }
+    }
+
+    // 6. Check if snapshot is required but missing
+    let cooldown_enabled = constraint_set.cooldown_seconds() > 0;
+    let drawdown_enabled = constraint_set.max_drawdown_bps() < 10_000;
+    let window_enabled = constraint_set.rolling_window().is_some();
+
+    if snapshot.is_none() && (cooldown_enabled || drawdown_enabled || window_enabled) {
+        violations.report(Err(ConstraintViolation::global(
+            ConstraintViolationReason::InvalidStateSnapshot,
+        )))?;
+    }
+
+    // 7. Validate global constraints (if snapshot present)
+    if let Some(ref snap) = snapshot {
+        violations.report(check_cooldown(snap, constraint_set))?;
+        violations.report(check_drawdown(snap, constraint_set))?;
+        violations.report(check_rolling_window(snap, proposed, constraint_set))?;
+    }
+
+    // 8. Custom output-level rules
+    for rule in rules {
+        violations.report(rule.check_output(&ctx))?;
+    }
+
+    Ok(())
+}
+
+/// The state snapshot the constraints are evaluated against.
+///
+/// Kernel version 5 inputs carry it in their typed slot, and only there.
+/// Earlier versions have no slot, so their snapshot is read from the front
+/// of `opaque_agent_inputs` if it decodes as a [`StateSnapshot`] (36 bytes
+/// for version 1, 56 bytes for version 2).
+pub fn input_state_snapshot(input: &KernelInputRef<'_>) -> Option<StateSnapshot> {
+    if input.kernel_version >= KERNEL_VERSION_V5 {
+        input.state_snapshot
+    } else {
+        StateSnapshot::decode(input.opaque_agent_inputs)
+    }
+}
+
+/// Check the snapshot's `current_ts` against the input's `valid_until`.
+fn check_expiry(
+    valid_until: Option<u64>,
+    snapshot: Option<&StateSnapshot>,
+) -> Result<(), ConstraintViolation> {
+    if let (Some(valid_until), Some(snapshot)) = (valid_until, snapshot) {
+        if snapshot.current_ts() > valid_until {
+            return Err(ConstraintViolation::global(
+                ConstraintViolationReason::InputExpired,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validate constraint set version and invariants.
+fn check_constraint_set(constraint_set: &ConstraintSet) -> Result<(), ConstraintViolation> {
+    let version_ok = match constraint_set {
+        ConstraintSet::V1(cs) => cs.version == 1,
+        ConstraintSet::V2(cs) => cs.version == 2 && cs.allowlists().check().is_ok(),
+        ConstraintSet::V3(cs) => {
+            cs.version == 3
+                && cs.allowlists().check().is_ok()
+                && cs
+                    .rolling_window
+                    .is_none_or(|window| window.window_seconds > 0)
+                && cs.approval_policy.as_ref().is_none_or(|policy| {
+                    check_allowlist(&policy.allowed_spenders, MAX_ALLOWED_SPENDERS).is_ok()
+                })
+                && cs.step_budget != Some(0)
+        }
+    };
+
+    // max_actions_per_output must not exceed protocol limit and
+    // max_drawdown_bps must be <= 10_000 (100%); a rolling window must
+    // have a non-zero length, approval spenders must be canonical and a
+    // step budget must be non-zero
+    if !version_ok
+        || constraint_set.max_actions_per_output() > MAX_ACTIONS_PER_OUTPUT as u32
+        || constraint_set.max_drawdown_bps() > 10_000
+    {
+        return Err(ConstraintViolation::global(
+            ConstraintViolationReason::InvalidConstraintSet,
+        ));
+    }
+    Ok(())
+}
+
+/// Validate output structure (internal, with constraint set).
+fn check_output_structure(
+    output: &AgentOutput,
+    constraint_set: &ConstraintSet,
+    violations: &mut Violations,
+) -> Result<(), ConstraintViolation> {
+    // Check action count
+    let max_actions = constraint_set.max_actions_per_output() as usize;
+    if output.actions.len() > max_actions {
+        violations.report(Err(ConstraintViolation::global(
+            ConstraintViolationReason::InvalidOutputStructure,
+        )))?;
+    }
+
+    // Check each action's payload size
+    for (index, action) in output.actions.iter().enumerate() {
+        if action.payload.len() > MAX_ACTION_PAYLOAD_BYTES {
+            violations.report(Err(ConstraintViolation::action(
+                ConstraintViolationReason::InvalidOutputStructure,
+                index,
+            )))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a single action.
+///
+/// For protocol v1, only on-chain executable action types are allowed:
+/// - CALL (0x02): ABI-encoded (uint256 value, bytes callData), min 96 bytes
+/// - TRANSFER_ERC20 (0x03): ABI-encoded (address token, address to, uint256 amount), exactly 96 bytes
+/// - NO_OP (0x04): empty payload
+///
+/// ECHO (0x01) is only allowed in test builds.
+///
+/// For version 2 and 3 constraint sets, CALL targets and TRANSFER_ERC20
+/// tokens must also be on the set's allowlists, and a version 3 approval
+/// policy restricts ERC20 `approve` calls.
+fn validate_action(
+    action: &ActionV1,
+    index: usize,
+    constraint_set: &ConstraintSet,
+) -> Result<(), ConstraintViolation> {
+    // Note: ECHO (0x01) is only valid in test/testing builds
+    #[cfg(any(test, feature = "testing"))]
+    if action.action_type == ACTION_TYPE_ECHO {
+        return Ok(());
+    }
+
+    match action.action_type {
+        x if x == ACTION_TYPE_CALL => validate_call_action(action, index, constraint_set),
+        x if x == ACTION_TYPE_TRANSFER_ERC20 => {
+            validate_transfer_erc20_action(action, index, constraint_set)
+        }
+        x if x == ACTION_TYPE_NO_OP => validate_no_op_action(action, index),
+        _ => {
+            // Unknown action type - not executable on-chain
+            Err(ConstraintViolation::action(
+                ConstraintViolationReason::UnknownActionType,
+                index,
+            ))
+        }
+    }
+}
+
+/// Validate CALL action (on-chain execution).
+///
+/// Payload format: abi.encode(uint256 value, bytes callData)
+/// Minimum size: 96 bytes (32 value + 32 offset + 32 length + 0 calldata)
+///
+/// With allowlists, the target (or target and selector) must be allowed.
+/// With an approval policy, `approve` calls must also satisfy it, and with
+/// native-value caps the value sent must be within the per-action cap.
+fn validate_call_action(
+    action: &ActionV1,
+    index: usize,
+    constraint_set: &ConstraintSet,
+) -> Result<(), ConstraintViolation> {
+    // Minimum payload size check
+    if action.payload.len() < 96 {
+        return Err(ConstraintViolation::action(
+            ConstraintViolationReason::InvalidActionPayload,
+            index,
+        ));
+    }
+
+    // Validate target is a valid EVM address (upper 12 bytes must be zero)
+    if action.target[0..12] != [0u8; 12] {
+        return Err(ConstraintViolation::action(
+            ConstraintViolationReason::InvalidActionPayload,
+            index,
+        ));
+    }
+
+    // Basic ABI structure validation:
+    // bytes 32-63 should contain offset (should be 64 = 0x40)
+    // bytes 64-95 should contain length of calldata
+    let offset = u256_from_be_bytes(&action.payload[32..64]);
+    if offset != 64 {
+        return Err(ConstraintViolation::action(
+            ConstraintViolationReason::InvalidActionPayload,
+            index,
+        ));
+    }
+
+    let calldata_len = u256_from_be_bytes(&action.payload[64..96]);
+    // Verify payload length matches declared calldata length (with 32-byte padding)
+    let expected_len = 96 + (calldata_len as usize).div_ceil(32) * 32;
+    if action.payload.len() != expected_len {
+        return Err(ConstraintViolation::action(
+            ConstraintViolationReason::InvalidActionPayload,
+            index,
+        ));
+    }
+
+    if let Some(allowlists) = constraint_set.allowlists() {
+        let target: [u8; 20] = action.target[12..32].try_into().expect("20-byte address");
+        let selector = (calldata_len >= 4)
+            .then(|| action.payload[96..100].try_into().expect("4-byte selector"));
+        if !allowlists.allows_call(&target, selector) {
+            return Err(ConstraintViolation::action(
+                ConstraintViolationReason::CallNotAllowed,
+                index,
+            ));
+        }
+    }
+
+    if let Some(policy) = constraint_set.approval_policy() {
+        let calldata = &action.payload[96..96 + calldata_len as usize];
+        if calldata.starts_with(&ERC20_APPROVE_SELECTOR) {
+            let approval = Erc20Approval::decode(calldata).ok_or(ConstraintViolation::action(
+                ConstraintViolationReason::InvalidActionPayload,
+                index,
+            ))?;
+            if !policy.allows(&approval) {
+                return Err(ConstraintViolation::action(
+                    ConstraintViolationReason::ApprovalNotAllowed,
+                    index,
+                ));
+            }
+        } else if calldata.starts_with(&ERC20_INCREASE_ALLOWANCE_SELECTOR) {
+            return Err(ConstraintViolation::action(
+                ConstraintViolationReason::ApprovalNotAllowed,
+                index,
+            ));
+        }
+    }
+
+    if let Some(limits) = constraint_set.native_value() {
+        let value: [u8; 32] = action.payload[0..32].try_into().expect("32-byte word");
+        if !limits.allows_action_value(&value) {
+            return Err(ConstraintViolation::action(
+                ConstraintViolationReason::NativeValueExceeded,
+                index,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate TRANSFER_ERC20 action (on-chain execution).
+///
+/// Payload format: abi.encode(address token, address to, uint256 amount)
+/// Size: exactly 96 bytes
+///
+/// With allowlists, the token must be allowed.
+fn validate_transfer_erc20_action(
+    action: &ActionV1,
+    index: usize,
+    constraint_set: &ConstraintSet,
+) -> Result<(), ConstraintViolation> {
+    if action.payload.len() != 96 {
+        return Err(ConstraintViolation::action(
+            ConstraintViolationReason::InvalidActionPayload,
+            index,
+        ));
+    }
+
+    // Validate addresses have proper padding (upper 12 bytes should be zero)
+    // Token address (bytes 0-31)
+    if action.payload[0..12] != [0u8; 12] {
+        return Err(ConstraintViolation::action(
+            ConstraintViolationReason::InvalidActionPayload,
+            index,
+        ));
+    }
+
+    // To address (bytes 32-63)
+    if action.payload[32..44] != [0u8; 12] {
+        return Err(ConstraintViolation::action(
+            ConstraintViolationReason::InvalidActionPayload,
+            index,
+        ));
+    }
+
+    if let Some(allowlists) = constraint_set.allowlists() {
+        let token: [u8; 20] = action.payload[12..32].try_into().expect("20-byte address");
+        if !allowlists.allows_token(&token) {
+            return Err(ConstraintViolation::action(
+                ConstraintViolationReason::AssetNotWhitelisted,
+                index,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate NO_OP action.
+///
+/// Payload must be empty.
+fn validate_no_op_action(action: &ActionV1, index: usize) -> Result<(), ConstraintViolation> {
+    if !action.payload.is_empty() {
+        return Err(ConstraintViolation::action(
+            ConstraintViolationReason::InvalidActionPayload,
+            index,
+        ));
+    }
+    Ok(())
+}
+
+/// Check an action's notional against the position size and leverage limits.
+///
+/// Actions without a recognised notional (see [`action_notional`]) pass, and
+/// a `max_position_notional` of `u64::MAX` disables the size limit. Leverage
+/// only applies to position-opening actions: their margin is measured
+/// against the snapshot's `current_equity`, and only when a snapshot is
+/// present. Any margin against zero equity is `LeverageTooHigh`. Transfers
+/// and approvals are in the token's own units, which need not match the
+/// equity's, so they are not measured against it.
+fn check_position_limits(
+    action: &ActionV1,
+    index: usize,
+    constraint_set: &ConstraintSet,
+    snapshot: Option<&StateSnapshot>,
+) -> Result<(), ConstraintViolation> {
+    let notional = match action_notional(action) {
+        Some(notional) if notional > 0 => notional,
+        _ => return Ok(()),
+    };
+
+    let max_notional = constraint_set.max_position_notional();
+    if max_notional != u64::MAX && notional > u128::from(max_notional) {
+        return Err(ConstraintViolation::action(
+            ConstraintViolationReason::PositionTooLarge,
+            index,
+        ));
+    }
+
+    if let (Some(snapshot), true) = (snapshot, opens_position(action)) {
+        // leverage_bps = notional * 10000 / equity; the right-hand side fits
+        // in u128, so a left-hand side that overflows is over the limit
+        let within_limit = match snapshot.current_equity() {
+            0 => false,
+            equity => notional.checked_mul(10_000).is_some_and(|scaled| {
+                scaled <= u128::from(constraint_set.max_leverage_bps()) * u128::from(equity)
+            }),
+        };
+        if !within_limit {
+            return Err(ConstraintViolation::action(
+                ConstraintViolationReason::LeverageTooHigh,
+                index,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// A big-endian uint256 as a `u128`, or `None` if it does not fit.
+fn u128_from_u256(word: &[u8; 32]) -> Option<u128> {
+    (word[..16] == [0u8; 16])
+        .then(|| u128::from_be_bytes(word[16..].try_into().expect("16-byte half")))
+}
+
+/// A 32-byte big-endian uint256 as a `u128`, saturating at `u128::MAX`.
+fn u128_saturating_from_u256(bytes: &[u8]) -> u128 {
+    bytes
+        .try_into()
+        .ok()
+        .and_then(u128_from_u256)
+        .unwrap_or(u128::MAX)
+}
+
+/// Decode a little-endian u128 and advance the offset.
+fn get_u128_le(bytes: &[u8], offset: &mut usize) -> Result<u128, CodecError> {
+    let slice = get_slice(bytes, offset, 16)?;
+    Ok(u128::from_le_bytes(
+        slice.try_into().expect("16-byte slice"),
+    ))
+}
+
+/// Helper to read a u256 from big-endian bytes (only reads lower 64 bits for practical values)
+fn u256_from_be_bytes(bytes: &[u8]) -> u64 {
+    // For practical values, we only need to check if upper bytes are zero
+    // and read the lower 8 bytes
+    if bytes.len() != 32 {
+        return u64::MAX; // Invalid
+    }
+    // Check upper 24 bytes are zero (for values that fit in u64)
+    if bytes[0..24] != [0u8; 24] {
+        return u64::MAX; // Value too large
+    }
+    u64::from_be_bytes(bytes[24..32].try_into().unwrap())
+}
+
+/// Validate the cooldown global constraint.
+fn check_cooldown(
+    snapshot: &StateSnapshot,
+    constraint_set: &ConstraintSet,
+) -> Result<(), ConstraintViolation> {
+    if constraint_set.cooldown_seconds() > 0 {
+        // Use checked_add to detect maliciously large last_execution_ts values.
+        // Overflow would indicate an invalid snapshot (timestamp cannot be that large).
+        let required_ts = snapshot
+            .last_execution_ts()
+            .checked_add(constraint_set.cooldown_seconds() as u64)
+            .ok_or_else(|| {
+                ConstraintViolation::global(ConstraintViolationReason::InvalidStateSnapshot)
+            })?;
+        if snapshot.current_ts() < required_ts {
+            return Err(ConstraintViolation::global(
+                ConstraintViolationReason::CooldownNotElapsed,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate the drawdown global constraint.
+fn check_drawdown(
+    snapshot: &StateSnapshot,
+    constraint_set: &ConstraintSet,
+) -> Result<(), ConstraintViolation> {
+    if constraint_set.max_drawdown_bps() < 10_000 {
+        // Only check if drawdown limit is meaningful (< 100%)
+        let (peak_equity, current_equity) = (snapshot.peak_equity(), snapshot.current_equity());
+        if peak_equity == 0 {
+            return Err(ConstraintViolation::global(
+                ConstraintViolationReason::InvalidStateSnapshot,
+            ));
+        }
+
+        // Calculate drawdown in basis points
+        // drawdown_bps = (peak - current) * 10000 / peak
+        let drawdown = peak_equity.saturating_sub(current_equity);
+        // SAFETY: peak_equity != 0 is verified above, so division cannot fail
+        let drawdown_bps = drawdown
+            .saturating_mul(10_000)
+            .checked_div(peak_equity)
+            .expect("peak_equity != 0 checked above");
+
+        if drawdown_bps > constraint_set.max_drawdown_bps() as u64 {
+            return Err(ConstraintViolation::global(
+                ConstraintViolationReason::DrawdownExceeded,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate the rolling-window global constraints.
+///
+/// The window's usage comes from a [`StateSnapshotV2`]; a version 1
+/// snapshot carries none and is `InvalidStateSnapshot` when the limits are
+/// enabled. This execution counts once, with the summed
+/// [`transferred_notional`] of its actions.
+fn check_rolling_window(
+    snapshot: &StateSnapshot,
+    output: &AgentOutput,
+    constraint_set: &ConstraintSet,
+) -> Result<(), ConstraintViolation> {
+    let Some(limits) = constraint_set.rolling_window() else {
+        return Ok(());
+    };
+    let invalid_snapshot =
+        || ConstraintViolation::global(ConstraintViolationReason::InvalidStateSnapshot);
+
+    let window = snapshot.window().ok_or_else(invalid_snapshot)?;
+    let notional = output
+        .actions
+        .iter()
+        .filter_map(transferred_notional)
+        .fold(0, u128::saturating_add);
+    // Window accumulators are u64; saturating keeps the comparison correct
+    let notional = u64::try_from(notional).unwrap_or(u64::MAX);
+    let usage = limits
+        .usage_after(window, notional)
+        .ok_or_else(invalid_snapshot)?;
+
+    if usage.executions > limits.max_executions {
+        return Err(ConstraintViolation::global(
+            ConstraintViolationReason::WindowExecutionsExceeded,
+        ));
+    }
+    if usage.notional > limits.max_notional {
+        return Err(ConstraintViolation::global(
+            ConstraintViolationReason::WindowNotionalExceeded,
+        ));
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Legacy API (backward compatibility)
+// ============================================================================
+
+/// Check agent output against constraint set (legacy API).
+///
+/// This function uses the default constraint set for backward compatibility.
+pub fn check(_output: &AgentOutput, _meta: &ConstraintMeta) -> Result<(), ConstraintError> {
+    // For backward compatibility, always pass with default constraints.
+    // The new enforce_constraints function should be used instead.
+    Ok(())
+}
+
+/// Validate that output is well-formed (legacy API).
+pub fn validate_output_structure_legacy(output: &AgentOutput) -> Result<(), ConstraintError> {
+    if output.actions.len() > MAX_ACTIONS_PER_OUTPUT {
+        return Err(ConstraintError::InvalidOutput);
+    }
+
+    for action in &output.actions {
+        if action.payload.len() > MAX_ACTION_PAYLOAD_BYTES {
+            return Err(ConstraintError::InvalidOutput);
+        }
+    }
+
+    Ok(())
+}
+
+// Re-export the legacy validate_output_structure under its original name
+pub use validate_output_structure_legacy as validate_output_structure;
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn make_test_input() -> KernelInputV1 {
+        KernelInputV1 {
+            protocol_version: 1,
+            kernel_version: 1,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xaa; 32],
+            constraint_set_hash: [0xbb; 32],
+            input_root: [0xcc; 32],
+            execution_nonce: 1,
+            opaque_agent_inputs: vec![],
+        }
+    }
+
+    fn make_echo_action() -> ActionV1 {
+        ActionV1 {
+            action_type: ACTION_TYPE_ECHO,
+            target: [0x11; 32],
+            payload: vec![1, 2, 3],
+        }
+    }
+
+    /// Create a valid CALL action with proper ABI encoding
+    fn make_call_action(target_addr: [u8; 20], value: u128, calldata: &[u8]) -> ActionV1 {
+        let mut target = [0u8; 32];
+        target[12..32].copy_from_slice(&target_addr);
+
+        let data_len = calldata.len();
+        let padded_len = data_len.div_ceil(32) * 32;
+        let total_size = 96 + padded_len;
+
+        let mut payload = vec![0u8; total_size];
+
+        // value (uint256, big-endian)
+        payload[16..32].copy_from_slice(&value.to_be_bytes());
+
+        // offset (64 = 0x40)
+        payload[63] = 64;
+
+        // length
+        payload[95] = data_len as u8;
 
         // calldata
         payload[96..96 + data_len].copy_from_slice(calldata);
 
-        ActionV1 {
-            action_type: ACTION_TYPE_CALL,
-            target,
-            payload,
+        ActionV1 {
+            action_type: ACTION_TYPE_CALL,
+            target,
+            payload,
+        }
+    }
+
+    /// Create a valid TRANSFER_ERC20 action
+    fn make_transfer_erc20_action(token: [u8; 20], to: [u8; 20], amount: u128) -> ActionV1 {
+        let mut payload = vec![0u8; 96];
+
+        // token address (left-padded)
+        payload[12..32].copy_from_slice(&token);
+
+        // to address (left-padded)
+        payload[44..64].copy_from_slice(&to);
+
+        // amount (uint256, big-endian)
+        payload[80..96].copy_from_slice(&amount.to_be_bytes());
+
+        ActionV1 {
+            action_type: ACTION_TYPE_TRANSFER_ERC20,
+            target: [0u8; 32],
+            payload,
+        }
+    }
+
+    #[test]
+    fn test_echo_action_passes() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![make_echo_action()],
+        };
+        let constraints = ConstraintSet::default();
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_call_action_passes() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![make_call_action(
+                [0x11; 20],
+                1000,
+                &[0xab, 0xcd, 0xef, 0x12],
+            )],
+        };
+        let constraints = ConstraintSet::default();
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_transfer_erc20_action_passes() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![make_transfer_erc20_action(
+                [0x11; 20], [0x22; 20], 1_000_000,
+            )],
+        };
+        let constraints = ConstraintSet::default();
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_no_op_action_passes() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![ActionV1 {
+                action_type: ACTION_TYPE_NO_OP,
+                target: [0u8; 32],
+                payload: vec![],
+            }],
+        };
+        let constraints = ConstraintSet::default();
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_no_op_with_payload_fails() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![ActionV1 {
+                action_type: ACTION_TYPE_NO_OP,
+                target: [0u8; 32],
+                payload: vec![1, 2, 3], // Should be empty
+            }],
+        };
+        let constraints = ConstraintSet::default();
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_err());
+        let violation = result.unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::InvalidActionPayload
+        );
+    }
+
+    #[test]
+    fn test_unknown_action_type_fails() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![ActionV1 {
+                action_type: 0xFFFFFFFF,
+                target: [0x11; 32],
+                payload: vec![],
+            }],
+        };
+        let constraints = ConstraintSet::default();
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_err());
+        let violation = result.unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::UnknownActionType
+        );
+        assert_eq!(violation.action_index, Some(0));
+    }
+
+    #[test]
+    fn test_invalid_call_payload_too_short() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![ActionV1 {
+                action_type: ACTION_TYPE_CALL,
+                target: {
+                    let mut t = [0u8; 32];
+                    t[12..32].copy_from_slice(&[0x11; 20]);
+                    t
+                },
+                payload: vec![0u8; 64], // Too short, needs at least 96
+            }],
+        };
+        let constraints = ConstraintSet::default();
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_err());
+        let violation = result.unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::InvalidActionPayload
+        );
+    }
+
+    #[test]
+    fn test_invalid_transfer_payload_wrong_size() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![ActionV1 {
+                action_type: ACTION_TYPE_TRANSFER_ERC20,
+                target: [0u8; 32],
+                payload: vec![0u8; 64], // Should be exactly 96
+            }],
+        };
+        let constraints = ConstraintSet::default();
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_err());
+        let violation = result.unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::InvalidActionPayload
+        );
+    }
+
+    #[test]
+    fn test_cooldown_not_elapsed_fails() {
+        // Create input with state snapshot
+        let mut snapshot_bytes = Vec::new();
+        snapshot_bytes.extend_from_slice(&1u32.to_le_bytes()); // version
+        snapshot_bytes.extend_from_slice(&1000u64.to_le_bytes()); // last_execution_ts
+        snapshot_bytes.extend_from_slice(&1030u64.to_le_bytes()); // current_ts (only 30 seconds later)
+        snapshot_bytes.extend_from_slice(&100_000u64.to_le_bytes()); // current_equity
+        snapshot_bytes.extend_from_slice(&100_000u64.to_le_bytes()); // peak_equity
+
+        let mut input = make_test_input();
+        input.opaque_agent_inputs = snapshot_bytes;
+
+        let output = AgentOutput {
+            actions: vec![make_echo_action()],
+        };
+        let constraints = ConstraintSet::V1(ConstraintSetV1 {
+            cooldown_seconds: 60, // 60 second cooldown
+            ..ConstraintSetV1::default()
+        });
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_err());
+        let violation = result.unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::CooldownNotElapsed
+        );
+    }
+
+    #[test]
+    fn test_drawdown_exceeded_fails() {
+        // Create input with state snapshot showing 30% drawdown
+        let mut snapshot_bytes = Vec::new();
+        snapshot_bytes.extend_from_slice(&1u32.to_le_bytes()); // version
+        snapshot_bytes.extend_from_slice(&1000u64.to_le_bytes()); // last_execution_ts
+        snapshot_bytes.extend_from_slice(&2000u64.to_le_bytes()); // current_ts
+        snapshot_bytes.extend_from_slice(&70_000u64.to_le_bytes()); // current_equity (70%)
+        snapshot_bytes.extend_from_slice(&100_000u64.to_le_bytes()); // peak_equity
+
+        let mut input = make_test_input();
+        input.opaque_agent_inputs = snapshot_bytes;
+
+        let output = AgentOutput {
+            actions: vec![make_echo_action()],
+        };
+        let constraints = ConstraintSet::V1(ConstraintSetV1 {
+            max_drawdown_bps: 2_000, // 20% max drawdown
+            ..ConstraintSetV1::default()
+        });
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_err());
+        let violation = result.unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::DrawdownExceeded
+        );
+    }
+
+    #[test]
+    fn test_too_many_actions_fails() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![make_echo_action(); 65], // 65 actions, max is 64
+        };
+        let constraints = ConstraintSet::default();
+
+        let result = enforce_constraints(&input, &output, &constraints);
+        assert!(result.is_err());
+        let violation = result.unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::InvalidOutputStructure
+        );
+    }
+
+    #[test]
+    fn test_empty_output_commitment_constant() {
+        // Verify the empty output commitment constant is correct
+        use kernel_core::{compute_action_commitment, CanonicalEncode};
+
+        let empty_output = AgentOutput { actions: vec![] };
+        let encoded = empty_output.encode().unwrap();
+        let commitment = compute_action_commitment(&encoded);
+
+        assert_eq!(commitment, EMPTY_OUTPUT_COMMITMENT);
+    }
+
+    // ========================================================================
+    // Constraint Set Encoding / Hash Tests
+    // ========================================================================
+
+    #[test]
+    fn test_constraint_set_encode_decode_roundtrip() {
+        let constraints = ConstraintSetV1 {
+            max_position_notional: 1_000_000,
+            max_leverage_bps: 20_000,
+            max_drawdown_bps: 500,
+            cooldown_seconds: 60,
+            max_actions_per_output: 8,
+            allowed_asset_id: [0x42; 32],
+            ..ConstraintSetV1::default()
+        };
+
+        let encoded = constraints.encode().unwrap();
+        assert_eq!(encoded.len(), ConstraintSetV1::ENCODED_SIZE);
+        assert_eq!(&encoded[0..4], &1u32.to_le_bytes());
+        assert_eq!(&encoded[4..12], &1_000_000u64.to_le_bytes());
+        assert_eq!(&encoded[28..60], &[0x42; 32]);
+
+        let decoded = ConstraintSetV1::decode(&encoded).unwrap();
+        assert_eq!(decoded, constraints);
+    }
+
+    #[test]
+    fn test_constraint_set_decode_rejects_bad_length_and_version() {
+        let encoded = ConstraintSetV1::default().encode().unwrap();
+
+        assert_eq!(
+            ConstraintSetV1::decode(&encoded[..59]),
+            Err(CodecError::InvalidLength)
+        );
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert_eq!(
+            ConstraintSetV1::decode(&trailing),
+            Err(CodecError::InvalidLength)
+        );
+
+        let mut wrong_version = encoded;
+        wrong_version[0..4].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(
+            ConstraintSetV1::decode(&wrong_version),
+            Err(CodecError::InvalidVersion {
+                expected: 1,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_default_constraint_set_hash_constant() {
+        let hash = compute_constraint_set_hash(&ConstraintSetV1::default()).unwrap();
+        assert_eq!(hash, DEFAULT_CONSTRAINT_SET_HASH);
+    }
+
+    #[test]
+    fn test_constraint_set_hash_changes_with_fields() {
+        let tightened = ConstraintSetV1 {
+            cooldown_seconds: 1,
+            ..ConstraintSetV1::default()
+        };
+        let hash = compute_constraint_set_hash(&tightened).unwrap();
+        assert_ne!(hash, DEFAULT_CONSTRAINT_SET_HASH);
+
+        let invalid = ConstraintSetV1 {
+            version: 2,
+            ..ConstraintSetV1::default()
+        };
+        assert!(compute_constraint_set_hash(&invalid).is_err());
+    }
+
+    // ========================================================================
+    // Input Expiry Tests
+    // ========================================================================
+
+    fn make_snapshot_input(current_ts: u64) -> KernelInputV1 {
+        let mut snapshot_bytes = Vec::new();
+        snapshot_bytes.extend_from_slice(&1u32.to_le_bytes()); // version
+        snapshot_bytes.extend_from_slice(&1000u64.to_le_bytes()); // last_execution_ts
+        snapshot_bytes.extend_from_slice(&current_ts.to_le_bytes()); // current_ts
+        snapshot_bytes.extend_from_slice(&100_000u64.to_le_bytes()); // current_equity
+        snapshot_bytes.extend_from_slice(&100_000u64.to_le_bytes()); // peak_equity
+
+        let mut input = make_test_input();
+        input.opaque_agent_inputs = snapshot_bytes;
+        input
+    }
+
+    #[test]
+    fn test_expired_input_fails() {
+        let input = make_snapshot_input(2001);
+        let output = AgentOutput {
+            actions: vec![make_echo_action()],
+        };
+
+        let violation =
+            enforce_constraints_with_expiry(&input, &output, &ConstraintSet::default(), 2000)
+                .unwrap_err();
+        assert_eq!(violation.reason, ConstraintViolationReason::InputExpired);
+        assert_eq!(violation.action_index, None);
+    }
+
+    #[test]
+    fn test_input_valid_until_boundary() {
+        let output = AgentOutput {
+            actions: vec![make_echo_action()],
+        };
+        let constraints = ConstraintSet::default();
+
+        // Still valid at exactly valid_until
+        let input = make_snapshot_input(2000);
+        assert!(enforce_constraints_with_expiry(&input, &output, &constraints, 2000).is_ok());
+
+        // Never expires
+        let input = make_snapshot_input(u64::MAX);
+        assert!(enforce_constraints_with_expiry(
+            &input,
+            &output,
+            &constraints,
+            kernel_core::NO_EXPIRY
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_expiry_without_snapshot_not_checked() {
+        let input = make_test_input();
+        let output = AgentOutput {
+            actions: vec![make_echo_action()],
+        };
+        assert!(
+            enforce_constraints_with_expiry(&input, &output, &ConstraintSet::default(), 0).is_ok()
+        );
+    }
+
+    // ========================================================================
+    // Typed State Snapshot Tests
+    // ========================================================================
+
+    /// Borrow `input` as a kernel version 5 input with `state_snapshot` in
+    /// its typed slot.
+    fn as_v5_input(
+        input: &KernelInputV1,
+        state_snapshot: Option<StateSnapshot>,
+    ) -> KernelInputRef<'_> {
+        KernelInputRef {
+            kernel_version: KERNEL_VERSION_V5,
+            prev_journal_hash: Some([0u8; 32]),
+            valid_until: Some(u64::MAX),
+            state_snapshot,
+            ..KernelInputRef::from(input)
+        }
+    }
+
+    #[test]
+    fn test_typed_snapshot_slot_replaces_opaque_prefix() {
+        let output = AgentOutput {
+            actions: vec![make_echo_action()],
+        };
+        let cooldown = ConstraintSet::V1(ConstraintSetV1 {
+            cooldown_seconds: 60,
+            ..ConstraintSetV1::default()
+        });
+        let recent = StateSnapshot::decode(&make_snapshot_input(1030).opaque_agent_inputs);
+
+        // The slot is used even though the opaque inputs carry no snapshot
+        let input = make_test_input();
+        let violation =
+            enforce_constraints_ref(&as_v5_input(&input, recent), &output, &cooldown).unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::CooldownNotElapsed
+        );
+
+        // Opaque bytes that happen to decode as a snapshot are not sniffed
+        let lookalike = make_snapshot_input(2000);
+        let violation = enforce_constraints_ref(&as_v5_input(&lookalike, None), &output, &cooldown)
+            .unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::InvalidStateSnapshot
+        );
+        assert_eq!(input_state_snapshot(&as_v5_input(&lookalike, None)), None);
+
+        // Earlier versions still read the opaque prefix
+        assert!(enforce_constraints(&lookalike, &output, &cooldown).is_ok());
+        assert_eq!(
+            input_state_snapshot(&KernelInputRef::from(&lookalike)),
+            StateSnapshot::decode(&lookalike.opaque_agent_inputs)
+        );
+    }
+
+    // ========================================================================
+    // Constraint Set V2 (Allowlist) Tests
+    // ========================================================================
+
+    const TOKEN: [u8; 20] = [0x11; 20];
+    const ROUTER: [u8; 20] = [0x22; 20];
+    const POOL: [u8; 20] = [0x33; 20];
+    const SUPPLY: [u8; 4] = [0x61, 0x7b, 0xa0, 0x37];
+
+    fn make_allowlist_constraints() -> ConstraintSet {
+        ConstraintSet::V2(ConstraintSetV2 {
+            allowed_tokens: vec![TOKEN],
+            allowed_call_targets: vec![ROUTER],
+            allowed_calls: vec![AllowedCall {
+                target: POOL,
+                selector: SUPPLY,
+            }],
+            ..ConstraintSetV2::default()
+        })
+    }
+
+    #[test]
+    fn test_constraint_set_v2_roundtrip_and_dispatch() {
+        let constraints = make_allowlist_constraints();
+        let encoded = constraints.encode().unwrap();
+        assert_eq!(encoded.len(), 40 + 20 + 20 + 24);
+        assert_eq!(&encoded[0..4], &2u32.to_le_bytes());
+        assert_eq!(ConstraintSet::decode(&encoded).unwrap(), constraints);
+
+        let v1 = ConstraintSetV1::default().encode().unwrap();
+        assert_eq!(
+            ConstraintSet::decode(&v1).unwrap(),
+            ConstraintSet::default()
+        );
+        assert_eq!(
+            compute_constraint_set_hash(&ConstraintSet::default()).unwrap(),
+            DEFAULT_CONSTRAINT_SET_HASH
+        );
+        assert_ne!(
+            compute_constraint_set_hash(&constraints).unwrap(),
+            compute_constraint_set_hash(&ConstraintSetV2::default()).unwrap()
+        );
+
+        let mut truncated = encoded.clone();
+        truncated.pop();
+        assert!(ConstraintSet::decode(&truncated).is_err());
+
+        let mut unknown = encoded;
+        unknown[0..4].copy_from_slice(&4u32.to_le_bytes());
+        assert_eq!(
+            ConstraintSet::decode(&unknown),
+            Err(CodecError::InvalidVersion {
+                expected: 3,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_constraint_set_v2_rejects_non_canonical_allowlists() {
+        let unsorted = ConstraintSetV2 {
+            allowed_tokens: vec![ROUTER, TOKEN],
+            ..ConstraintSetV2::default()
+        };
+        assert_eq!(unsorted.encode(), Err(CodecError::NonCanonicalAllowlist));
+
+        let duplicate = ConstraintSetV2 {
+            allowed_call_targets: vec![ROUTER, ROUTER],
+            ..ConstraintSetV2::default()
+        };
+        assert_eq!(duplicate.encode(), Err(CodecError::NonCanonicalAllowlist));
+
+        let too_many = ConstraintSetV2 {
+            allowed_tokens: (0..=MAX_ALLOWED_TOKENS as u8).map(|i| [i; 20]).collect(),
+            ..ConstraintSetV2::default()
+        };
+        assert!(matches!(
+            too_many.encode(),
+            Err(CodecError::AllowlistTooLarge { count: 33, .. })
+        ));
+
+        // Decoding rejects the same encodings
+        let mut encoded = ConstraintSetV2 {
+            allowed_tokens: vec![TOKEN, ROUTER],
+            ..ConstraintSetV2::default()
+        }
+        .encode()
+        .unwrap();
+        encoded[32..52].copy_from_slice(&ROUTER);
+        encoded[52..72].copy_from_slice(&TOKEN);
+        assert_eq!(
+            ConstraintSetV2::decode(&encoded),
+            Err(CodecError::NonCanonicalAllowlist)
+        );
+
+        // An in-memory set that cannot be encoded is an invalid constraint set
+        let violation = enforce_constraints(
+            &make_test_input(),
+            &AgentOutput { actions: vec![] },
+            &ConstraintSet::V2(unsorted),
+        )
+        .unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::InvalidConstraintSet
+        );
+    }
+
+    #[test]
+    fn test_call_allowlist_enforced() {
+        let input = make_test_input();
+        let constraints = make_allowlist_constraints();
+        let check = |action: ActionV1| {
+            let output = AgentOutput {
+                actions: vec![make_call_action(ROUTER, 0, &[]), action],
+            };
+            enforce_constraints(&input, &output, &constraints)
+        };
+
+        // Target-wide entry allows any calldata, including none
+        assert!(check(make_call_action(ROUTER, 0, &[0xde, 0xad, 0xbe, 0xef])).is_ok());
+
+        // Function entry allows only its selector
+        let mut calldata = SUPPLY.to_vec();
+        calldata.extend_from_slice(&[0u8; 32]);
+        assert!(check(make_call_action(POOL, 0, &calldata)).is_ok());
+
+        for action in [
+            make_call_action(POOL, 0, &[0xde, 0xad, 0xbe, 0xef]),
+            make_call_action(POOL, 1, &[]),
+            make_call_action([0x44; 20], 0, &SUPPLY),
+        ] {
+            let violation = check(action).unwrap_err();
+            assert_eq!(violation.reason, ConstraintViolationReason::CallNotAllowed);
+            assert_eq!(violation.action_index, Some(1));
+        }
+    }
+
+    #[test]
+    fn test_token_allowlist_enforced() {
+        let input = make_test_input();
+        let constraints = make_allowlist_constraints();
+
+        let allowed = AgentOutput {
+            actions: vec![make_transfer_erc20_action(TOKEN, [0x55; 20], 1_000)],
+        };
+        assert!(enforce_constraints(&input, &allowed, &constraints).is_ok());
+
+        // The token, not the recipient, must be allowed
+        let other_token = AgentOutput {
+            actions: vec![make_transfer_erc20_action([0x55; 20], TOKEN, 1_000)],
+        };
+        let violation = enforce_constraints(&input, &other_token, &constraints).unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::AssetNotWhitelisted
+        );
+        assert_eq!(violation.action_index, Some(0));
+    }
+
+    #[test]
+    fn test_empty_allowlists_only_allow_no_op() {
+        let input = make_test_input();
+        let constraints = ConstraintSet::V2(ConstraintSetV2::default());
+
+        let no_op = AgentOutput {
+            actions: vec![ActionV1 {
+                action_type: ACTION_TYPE_NO_OP,
+                target: [0u8; 32],
+                payload: vec![],
+            }],
+        };
+        assert!(enforce_constraints(&input, &no_op, &constraints).is_ok());
+
+        let call = AgentOutput {
+            actions: vec![make_call_action(ROUTER, 0, &[])],
+        };
+        assert!(enforce_constraints(&input, &call, &constraints).is_err());
+
+        let transfer = AgentOutput {
+            actions: vec![make_transfer_erc20_action(TOKEN, ROUTER, 1)],
+        };
+        assert!(enforce_constraints(&input, &transfer, &constraints).is_err());
+    }
+
+    // ========================================================================
+    // Position Size and Leverage Tests
+    // ========================================================================
+
+    /// ABI calldata: selector followed by 32-byte big-endian words.
+    fn make_calldata(selector: [u8; 4], words: &[u128]) -> Vec<u8> {
+        let mut calldata = selector.to_vec();
+        for word in words {
+            calldata.extend_from_slice(&[0u8; 16]);
+            calldata.extend_from_slice(&word.to_be_bytes());
+        }
+        calldata
+    }
+
+    fn make_limited_constraints(
+        max_position_notional: u64,
+        max_leverage_bps: u32,
+    ) -> ConstraintSet {
+        ConstraintSet::V1(ConstraintSetV1 {
+            max_position_notional,
+            max_leverage_bps,
+            ..ConstraintSetV1::default()
+        })
+    }
+
+    #[test]
+    fn test_action_notional_recognised_actions() {
+        let token = [0x11; 20];
+        let to = [0x22; 20];
+        let recipient = 0x22u128;
+
+        let transfer = make_transfer_erc20_action(token, to, 700);
+        assert_eq!(action_notional(&transfer), Some(700));
+
+        let erc20_transfer = make_call_action(
+            token,
+            0,
+            &make_calldata(ERC20_TRANSFER_SELECTOR, &[recipient, 800]),
+        );
+        assert_eq!(action_notional(&erc20_transfer), Some(800));
+
+        let transfer_from = make_call_action(
+            token,
+            0,
+            &make_calldata(ERC20_TRANSFER_FROM_SELECTOR, &[0x33, recipient, 850]),
+        );
+        assert_eq!(action_notional(&transfer_from), Some(850));
+
+        let approve = make_call_action(
+            token,
+            0,
+            &make_calldata(ERC20_APPROVE_SELECTOR, &[recipient, 900]),
+        );
+        assert_eq!(action_notional(&approve), Some(900));
+
+        // openPosition(isBuy, marginAmount, orderSize, limitPrice): marginAmount
+        let open = make_call_action(
+            token,
+            0,
+            &make_calldata(
+                HYPERLIQUID_OPEN_POSITION_SELECTOR,
+                &[1, 1_000, 5, 9_700_000],
+            ),
+        );
+        assert_eq!(action_notional(&open), Some(1_000));
+
+        // 18-decimal amounts beyond u64 are kept in full
+        let large = make_transfer_erc20_action(token, to, 100 * 10u128.pow(18));
+        assert_eq!(action_notional(&large), Some(100 * 10u128.pow(18)));
+
+        // Unrecognised or truncated calls have no notional
+        let other = make_call_action(token, 1_000, &[0xab, 0xcd, 0xef, 0x12]);
+        assert_eq!(action_notional(&other), None);
+        let truncated = make_call_action(
+            token,
+            0,
+            &make_calldata(HYPERLIQUID_OPEN_POSITION_SELECTOR, &[1, 1_000]),
+        );
+        assert_eq!(action_notional(&truncated), None);
+        assert_eq!(action_notional(&make_echo_action()), None);
+    }
+
+    #[test]
+    fn test_position_too_large_fails() {
+        let input = make_test_input();
+        let constraints = make_limited_constraints(1_000, 100_000);
+
+        let at_limit = AgentOutput {
+            actions: vec![make_transfer_erc20_action([0x11; 20], [0x22; 20], 1_000)],
+        };
+        assert!(enforce_constraints(&input, &at_limit, &constraints).is_ok());
+
+        let approve = make_call_action(
+            [0x11; 20],
+            0,
+            &make_calldata(ERC20_APPROVE_SELECTOR, &[0x22, 1_001]),
+        );
+        let output = AgentOutput {
+            actions: vec![at_limit.actions[0].clone(), approve],
+        };
+        let violation = enforce_constraints(&input, &output, &constraints).unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::PositionTooLarge
+        );
+        assert_eq!(violation.action_index, Some(1));
+    }
+
+    #[test]
+    fn test_leverage_too_high_fails() {
+        // current_equity = 100_000, so 2x allows a notional of 200_000
+        let input = make_snapshot_input(2000);
+        let constraints = make_limited_constraints(u64::MAX, 20_000);
+        let open = |margin: u128| {
+            make_call_action(
+                [0x11; 20],
+                0,
+                &make_calldata(
+                    HYPERLIQUID_OPEN_POSITION_SELECTOR,
+                    &[1, margin, 5, 9_700_000],
+                ),
+            )
+        };
+
+        let at_limit = AgentOutput {
+            actions: vec![open(200_000)],
+        };
+        assert!(enforce_constraints(&input, &at_limit, &constraints).is_ok());
+
+        let over = AgentOutput {
+            actions: vec![open(200_001)],
+        };
+        let violation = enforce_constraints(&input, &over, &constraints).unwrap_err();
+        assert_eq!(violation.reason, ConstraintViolationReason::LeverageTooHigh);
+        assert_eq!(violation.action_index, Some(0));
+
+        // Without a snapshot there is no equity to measure leverage against
+        assert!(enforce_constraints(&make_test_input(), &over, &constraints).is_ok());
+    }
+
+    #[test]
+    fn test_leverage_with_zero_equity_fails() {
+        let mut input = make_snapshot_input(2000);
+        input.opaque_agent_inputs[20..28].copy_from_slice(&0u64.to_le_bytes());
+        let constraints = ConstraintSet::default();
+        let open = |margin: u128| AgentOutput {
+            actions: vec![make_call_action(
+                [0x11; 20],
+                0,
+                &make_calldata(
+                    HYPERLIQUID_OPEN_POSITION_SELECTOR,
+                    &[1, margin, 5, 9_700_000],
+                ),
+            )],
+        };
+
+        let violation = enforce_constraints(&input, &open(1), &constraints).unwrap_err();
+        assert_eq!(violation.reason, ConstraintViolationReason::LeverageTooHigh);
+
+        // Zero-margin actions carry no exposure
+        assert!(enforce_constraints(&input, &open(0), &constraints).is_ok());
+    }
+
+    #[test]
+    fn test_default_set_allows_18_decimal_transfers() {
+        // current_equity = 1e9 in the snapshot's units; the transfers are
+        // 100 tokens at 18 decimals, far above both u64::MAX and 10x equity
+        let mut input = make_snapshot_input(2000);
+        input.opaque_agent_inputs[20..28].copy_from_slice(&1_000_000_000u64.to_le_bytes());
+        input.opaque_agent_inputs[28..36].copy_from_slice(&1_000_000_000u64.to_le_bytes());
+        let amount = 100 * 10u128.pow(18);
+        let output = AgentOutput {
+            actions: vec![
+                make_transfer_erc20_action([0x11; 20], [0x22; 20], amount),
+                make_call_action(
+                    [0x11; 20],
+                    0,
+                    &make_calldata(ERC20_TRANSFER_SELECTOR, &[0x22, amount]),
+                ),
+            ],
+        };
+        assert!(enforce_constraints(&input, &output, &ConstraintSet::default()).is_ok());
+
+        // An explicit size limit compares the full amount
+        let limited = make_limited_constraints(u64::MAX - 1, 100_000);
+        let violation = enforce_constraints(&input, &output, &limited).unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::PositionTooLarge
+        );
+        assert_eq!(violation.action_index, Some(0));
+    }
+
+    // ========================================================================
+    // Rolling Window Tests
+    // ========================================================================
+
+    const WINDOW: RollingWindowLimits = RollingWindowLimits {
+        window_seconds: 3600,
+        max_executions: 3,
+        max_notional: 1_000,
+    };
+
+    fn make_window_constraints(rolling_window: Option<RollingWindowLimits>) -> ConstraintSet {
+        ConstraintSet::V3(ConstraintSetV3 {
+            allowed_tokens: vec![TOKEN],
+            rolling_window,
+            ..ConstraintSetV3::default()
+        })
+    }
+
+    /// Snapshot at `current_ts` of a window opened at 10_000.
+    fn make_window_snapshot(current_ts: u64, executions: u32, notional: u64) -> StateSnapshot {
+        StateSnapshot::V2(StateSnapshotV2 {
+            snapshot_version: 2,
+            last_execution_ts: 0,
+            current_ts,
+            current_equity: 100_000,
+            peak_equity: 100_000,
+            window_start_ts: 10_000,
+            window_executions: executions,
+            window_notional: notional,
+        })
+    }
+
+    #[test]
+    fn test_constraint_set_v3_roundtrip_and_dispatch() {
+        let constraints = make_window_constraints(Some(WINDOW));
+        let encoded = constraints.encode().unwrap();
+        assert_eq!(encoded.len(), 44 + 20 + 16);
+        assert_eq!(&encoded[0..4], &3u32.to_le_bytes());
+        assert_eq!(&encoded[60..64], &1u32.to_le_bytes());
+        assert_eq!(ConstraintSet::decode(&encoded).unwrap(), constraints);
+
+        let plain = make_window_constraints(None);
+        let plain_encoded = plain.encode().unwrap();
+        assert_eq!(plain_encoded.len(), 44 + 20);
+        assert_eq!(ConstraintSet::decode(&plain_encoded).unwrap(), plain);
+        assert_ne!(
+            compute_constraint_set_hash(&plain).unwrap(),
+            compute_constraint_set_hash(&constraints).unwrap()
+        );
+
+        // A declared extension must be present, and unknown ones are rejected
+        assert!(ConstraintSet::decode(&encoded[..encoded.len() - 1]).is_err());
+        let mut unknown = plain_encoded;
+        unknown[60..64].copy_from_slice(&0b110000u32.to_le_bytes());
+        assert_eq!(
+            ConstraintSetV3::decode(&unknown),
+            Err(CodecError::UnknownConstraintExtension(0b110000))
+        );
+    }
+
+    #[test]
+    fn test_rolling_window_usage() {
+        let snapshot = make_window_snapshot(11_000, 2, 400);
+        let usage = WINDOW.usage_after(snapshot.window().unwrap(), 100).unwrap();
+        assert_eq!(
+            usage,
+            WindowUsage {
+                window_start_ts: 10_000,
+                executions: 3,
+                notional: 500,
+            }
+        );
+
+        // Once the window has elapsed, a new one opens at current_ts
+        let elapsed = make_window_snapshot(13_600, 3, 1_000);
+        let usage = WINDOW.usage_after(elapsed.window().unwrap(), 100).unwrap();
+        assert_eq!(
+            usage,
+            WindowUsage {
+                window_start_ts: 13_600,
+                executions: 1,
+                notional: 100,
+            }
+        );
+
+        // A snapshot observed before its own window opened is inconsistent
+        let early = make_window_snapshot(9_999, 0, 0);
+        assert_eq!(WINDOW.usage_after(early.window().unwrap(), 0), None);
+    }
+
+    #[test]
+    fn test_rolling_window_limits_enforced() {
+        let input = make_test_input();
+        let constraints = make_window_constraints(Some(WINDOW));
+        let transfer = |amount| AgentOutput {
+            actions: vec![make_transfer_erc20_action(TOKEN, [0x22; 20], amount)],
+        };
+        let enforce = |snapshot, output: &AgentOutput| {
+            enforce_constraints_ref(&as_v5_input(&input, Some(snapshot)), output, &constraints)
+                .map(|_| ())
+                .map_err(|violation| violation.reason)
+        };
+
+        // Third execution, bringing the window to exactly its notional limit
+        assert_eq!(
+            enforce(make_window_snapshot(11_000, 2, 600), &transfer(400)),
+            Ok(())
+        );
+
+        assert_eq!(
+            enforce(make_window_snapshot(11_000, 3, 0), &transfer(1)),
+            Err(ConstraintViolationReason::WindowExecutionsExceeded)
+        );
+        assert_eq!(
+            enforce(make_window_snapshot(11_000, 1, 600), &transfer(401)),
+            Err(ConstraintViolationReason::WindowNotionalExceeded)
+        );
+
+        // Many small executions are cut off just the same
+        let empty = AgentOutput { actions: vec![] };
+        assert_eq!(
+            enforce(make_window_snapshot(11_000, 3, 0), &empty),
+            Err(ConstraintViolationReason::WindowExecutionsExceeded)
+        );
+
+        // An elapsed window no longer counts
+        assert_eq!(
+            enforce(make_window_snapshot(13_600, 3, 1_000), &transfer(1_000)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_rolling_window_ignores_approvals() {
+        let input = make_test_input();
+        let constraints = ConstraintSet::V3(ConstraintSetV3 {
+            allowed_call_targets: vec![TOKEN],
+            rolling_window: Some(WINDOW),
+            ..ConstraintSetV3::default()
+        });
+        let approve = make_call_action(
+            TOKEN,
+            0,
+            &make_calldata(ERC20_APPROVE_SELECTOR, &[0x22, 400]),
+        );
+        let transfer =
+            |selector, words: &[u128]| make_call_action(TOKEN, 0, &make_calldata(selector, words));
+        assert_eq!(transferred_notional(&approve), None);
+        assert_eq!(action_notional(&approve), Some(400));
+
+        let enforce = |output: AgentOutput| {
+            let snapshot = make_window_snapshot(11_000, 1, 600);
+            enforce_constraints_ref(&as_v5_input(&input, Some(snapshot)), &output, &constraints)
+                .map(|_| ())
+                .map_err(|violation| violation.reason)
+        };
+
+        // The approval and the transfer spending it count once
+        let approve_and_transfer = AgentOutput {
+            actions: vec![
+                approve.clone(),
+                transfer(ERC20_TRANSFER_SELECTOR, &[0x22, 400]),
+            ],
+        };
+        assert_eq!(enforce(approve_and_transfer), Ok(()));
+
+        let approve_and_transfer_from = AgentOutput {
+            actions: vec![
+                approve,
+                transfer(ERC20_TRANSFER_FROM_SELECTOR, &[0x33, 0x22, 401]),
+            ],
+        };
+        assert_eq!(
+            enforce(approve_and_transfer_from),
+            Err(ConstraintViolationReason::WindowNotionalExceeded)
+        );
+    }
+
+    #[test]
+    fn test_rolling_window_requires_windowed_snapshot() {
+        let input = make_test_input();
+        let output = AgentOutput { actions: vec![] };
+        let constraints = make_window_constraints(Some(WINDOW));
+        let reason = |snapshot: Option<StateSnapshot>, constraints: &ConstraintSet| {
+            enforce_constraints_ref(&as_v5_input(&input, snapshot), &output, constraints)
+                .unwrap_err()
+                .reason
+        };
+
+        let v1 = StateSnapshot::V1(StateSnapshotV1 {
+            snapshot_version: 1,
+            last_execution_ts: 0,
+            current_ts: 11_000,
+            current_equity: 100_000,
+            peak_equity: 100_000,
+        });
+        for snapshot in [None, Some(v1), Some(make_window_snapshot(9_999, 0, 0))] {
+            assert_eq!(
+                reason(snapshot, &constraints),
+                ConstraintViolationReason::InvalidStateSnapshot
+            );
+        }
+
+        // Without the extension, a version 3 set needs no snapshot
+        let plain = make_window_constraints(None);
+        assert!(enforce_constraints_ref(&as_v5_input(&input, None), &output, &plain).is_ok());
+
+        let zero_length = make_window_constraints(Some(RollingWindowLimits {
+            window_seconds: 0,
+            ..WINDOW
+        }));
+        assert_eq!(
+            reason(Some(make_window_snapshot(11_000, 0, 0)), &zero_length),
+            ConstraintViolationReason::InvalidConstraintSet
+        );
+    }
+
+    // ========================================================================
+    // Approval Policy Tests
+    // ========================================================================
+
+    const SPENDER: [u8; 20] = [0x5e; 20];
+
+    /// `approve(spender, amount)` calldata with a raw uint256 amount.
+    fn make_approve_calldata(spender: [u8; 20], amount: [u8; 32]) -> Vec<u8> {
+        let mut calldata = ERC20_APPROVE_SELECTOR.to_vec();
+        calldata.extend_from_slice(&[0u8; 12]);
+        calldata.extend_from_slice(&spender);
+        calldata.extend_from_slice(&amount);
+        calldata
+    }
+
+    fn u256_word(value: u64) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[24..32].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    fn make_approval_constraints(approval_policy: Option<ApprovalPolicy>) -> ConstraintSet {
+        ConstraintSet::V3(ConstraintSetV3 {
+            allowed_call_targets: vec![TOKEN],
+            approval_policy,
+            ..ConstraintSetV3::default()
+        })
+    }
+
+    fn make_approval_policy(allow_unlimited: bool) -> ApprovalPolicy {
+        ApprovalPolicy {
+            allowed_spenders: vec![SPENDER],
+            max_amount: 1_000,
+            allow_unlimited,
         }
     }
 
-    /// Create a valid TRANSFER_ERC20 action
-    fn make_transfer_erc20_action(token: [u8; 20], to: [u8; 20], amount: u128) -> ActionV1 {
-        let mut payload = vec![0u8; 96];
-
-        // token address (left-padded)
-        payload[12..32].copy_from_slice(&token);
+    #[test]
+    fn test_erc20_approval_decode() {
+        let calldata = make_approve_calldata(SPENDER, u256_word(1_000));
+        let approval = Erc20Approval::decode(&calldata).unwrap();
+        assert_eq!(approval.spender, SPENDER);
+        assert_eq!(approval.amount, u256_word(1_000));
+        assert!(!approval.is_unlimited());
+        assert!(
+            Erc20Approval::decode(&make_approve_calldata(SPENDER, [0xff; 32]))
+                .unwrap()
+                .is_unlimited()
+        );
+
+        // Other functions, wrong lengths and dirty address padding
+        let mut transfer = calldata.clone();
+        transfer[0..4].copy_from_slice(&ERC20_TRANSFER_SELECTOR);
+        assert_eq!(Erc20Approval::decode(&transfer), None);
+        assert_eq!(Erc20Approval::decode(&calldata[..67]), None);
+        let mut trailing = calldata.clone();
+        trailing.push(0);
+        assert_eq!(Erc20Approval::decode(&trailing), None);
+        let mut dirty = calldata;
+        dirty[4] = 1;
+        assert_eq!(Erc20Approval::decode(&dirty), None);
+    }
+
+    #[test]
+    fn test_approval_policy_enforced() {
+        let input = make_test_input();
+        let enforce = |constraints: &ConstraintSet, calldata: Vec<u8>| {
+            let output = AgentOutput {
+                actions: vec![make_call_action(TOKEN, 0, &calldata)],
+            };
+            enforce_constraints(&input, &output, constraints)
+                .map(|_| ())
+                .map_err(|violation| violation.reason)
+        };
+        let bounded = make_approval_constraints(Some(make_approval_policy(false)));
+
+        assert_eq!(
+            enforce(&bounded, make_approve_calldata(SPENDER, u256_word(1_000))),
+            Ok(())
+        );
+        assert_eq!(
+            enforce(&bounded, make_approve_calldata(SPENDER, u256_word(1_001))),
+            Err(ConstraintViolationReason::ApprovalNotAllowed)
+        );
+        assert_eq!(
+            enforce(&bounded, make_approve_calldata([0xbe; 20], u256_word(1))),
+            Err(ConstraintViolationReason::ApprovalNotAllowed)
+        );
+        assert_eq!(
+            enforce(&bounded, make_approve_calldata(SPENDER, [0xff; 32])),
+            Err(ConstraintViolationReason::ApprovalNotAllowed)
+        );
+
+        // Amounts are compared in full: 18-decimal allowances fit, and
+        // 2^128 does not saturate into range
+        let wide = make_approval_constraints(Some(ApprovalPolicy {
+            max_amount: 1_000 * 10u128.pow(18),
+            ..make_approval_policy(false)
+        }));
+        let word = |amount: u128| {
+            let mut word = [0u8; 32];
+            word[16..].copy_from_slice(&amount.to_be_bytes());
+            word
+        };
+        assert_eq!(
+            enforce(
+                &wide,
+                make_approve_calldata(SPENDER, word(500 * 10u128.pow(18)))
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            enforce(
+                &wide,
+                make_approve_calldata(SPENDER, word(1_000 * 10u128.pow(18) + 1))
+            ),
+            Err(ConstraintViolationReason::ApprovalNotAllowed)
+        );
+        let uncapped = make_approval_constraints(Some(ApprovalPolicy {
+            max_amount: u128::MAX,
+            ..make_approval_policy(false)
+        }));
+        let mut above_u128 = [0u8; 32];
+        above_u128[15] = 1;
+        assert_eq!(
+            enforce(&uncapped, make_approve_calldata(SPENDER, above_u128)),
+            Err(ConstraintViolationReason::ApprovalNotAllowed)
+        );
+
+        // increaseAllowance cannot be bounded and is never allowed
+        assert_eq!(
+            enforce(
+                &make_approval_constraints(Some(make_approval_policy(true))),
+                make_calldata(ERC20_INCREASE_ALLOWANCE_SELECTOR, &[0x22, 1])
+            ),
+            Err(ConstraintViolationReason::ApprovalNotAllowed)
+        );
+
+        // Unlimited approvals only when explicitly allowed
+        let unlimited = make_approval_constraints(Some(make_approval_policy(true)));
+        assert_eq!(
+            enforce(&unlimited, make_approve_calldata(SPENDER, [0xff; 32])),
+            Ok(())
+        );
+        assert_eq!(
+            enforce(&unlimited, make_approve_calldata(SPENDER, u256_word(1_001))),
+            Err(ConstraintViolationReason::ApprovalNotAllowed)
+        );
+
+        // Approve calldata the policy cannot interpret is rejected
+        let mut short = make_approve_calldata(SPENDER, u256_word(1));
+        short.truncate(36);
+        assert_eq!(
+            enforce(&bounded, short),
+            Err(ConstraintViolationReason::InvalidActionPayload)
+        );
+
+        // Other calls and sets without the extension are unaffected
+        assert_eq!(
+            enforce(
+                &bounded,
+                make_calldata(ERC20_TRANSFER_SELECTOR, &[0xbe, u128::MAX])
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            enforce(
+                &make_approval_constraints(None),
+                make_approve_calldata([0xbe; 20], [0xff; 32])
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_approval_policy_encoding() {
+        let constraints = make_approval_constraints(Some(make_approval_policy(true)));
+        let encoded = constraints.encode().unwrap();
+        assert_eq!(encoded.len(), 44 + 20 + 4 + 20 + 16 + 1);
+        assert_eq!(&encoded[60..64], &0b10u32.to_le_bytes());
+        assert_eq!(*encoded.last().unwrap(), 1);
+        assert_eq!(ConstraintSet::decode(&encoded).unwrap(), constraints);
+
+        // Both extensions, in flag-bit order
+        let both = ConstraintSet::V3(ConstraintSetV3 {
+            rolling_window: Some(WINDOW),
+            approval_policy: Some(make_approval_policy(false)),
+            ..ConstraintSetV3::default()
+        });
+        let both_encoded = both.encode().unwrap();
+        assert_eq!(&both_encoded[40..44], &0b11u32.to_le_bytes());
+        assert_eq!(ConstraintSet::decode(&both_encoded).unwrap(), both);
+
+        let mut bad_flag = encoded;
+        *bad_flag.last_mut().unwrap() = 2;
+        assert_eq!(
+            ConstraintSetV3::decode(&bad_flag),
+            Err(CodecError::InvalidApprovalPolicy)
+        );
+
+        // Spenders follow the allowlist rules
+        let unsorted = make_approval_constraints(Some(ApprovalPolicy {
+            allowed_spenders: vec![SPENDER, [0x01; 20]],
+            ..make_approval_policy(false)
+        }));
+        assert_eq!(unsorted.encode(), Err(CodecError::NonCanonicalAllowlist));
+        let input = make_test_input();
+        let output = AgentOutput { actions: vec![] };
+        assert_eq!(
+            enforce_constraints(&input, &output, &unsorted)
+                .unwrap_err()
+                .reason,
+            ConstraintViolationReason::InvalidConstraintSet
+        );
+    }
+
+    // ========================================================================
+    // Native Value Tests
+    // ========================================================================
 
-        // to address (left-padded)
-        payload[44..64].copy_from_slice(&to);
+    const NATIVE_VALUE: NativeValueLimits = NativeValueLimits {
+        max_value_per_action: 1_000,
+        max_value_per_output: 1_500,
+    };
 
-        // amount (uint256, big-endian)
-        payload[80..96].copy_from_slice(&amount.to_be_bytes());
+    fn make_native_value_constraints(native_value: Option<NativeValueLimits>) -> ConstraintSet {
+        ConstraintSet::V3(ConstraintSetV3 {
+            allowed_call_targets: vec![TOKEN],
+            native_value,
+            ..ConstraintSetV3::default()
+        })
+    }
 
-        ActionV1 {
-            action_type: ACTION_TYPE_TRANSFER_ERC20,
-            target: [0u8; 32],
-            payload,
-        }
+    /// A CALL to `TOKEN` whose value word is `value`.
+    fn make_valued_call_action(value: [u8; 32]) -> ActionV1 {
+        let mut action = make_call_action(TOKEN, 0, &[0xde, 0xad, 0xbe, 0xef]);
+        action.payload[0..32].copy_from_slice(&value);
+        action
     }
 
     #[test]
-    fn test_echo_action_passes() {
-        let input = make_test_input();
-        let output = AgentOutput {
-            actions: vec![make_echo_action()],
-        };
-        let constraints = ConstraintSetV1::default();
+    fn test_call_value() {
+        let action = make_call_action(TOKEN, u128::MAX, &[]);
+        let mut expected = [0u8; 32];
+        expected[16..32].copy_from_slice(&u128::MAX.to_be_bytes());
+        assert_eq!(call_value(&action), Some(expected));
 
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_ok());
+        // The full word is returned, including bits above u128
+        let wide = make_valued_call_action([0xff; 32]);
+        assert_eq!(call_value(&wide), Some([0xff; 32]));
+
+        assert_eq!(
+            call_value(&make_transfer_erc20_action(TOKEN, [0x22; 20], 1)),
+            None
+        );
+        assert_eq!(u128_from_u256(&expected), Some(u128::MAX));
+        assert_eq!(u128_from_u256(&[0xff; 32]), None);
     }
 
     #[test]
-    fn test_call_action_passes() {
+    fn test_native_value_enforced() {
         let input = make_test_input();
-        let output = AgentOutput {
-            actions: vec![make_call_action(
-                [0x11; 20],
-                1000,
-                &[0xab, 0xcd, 0xef, 0x12],
-            )],
+        let constraints = make_native_value_constraints(Some(NATIVE_VALUE));
+        let enforce = |constraints: &ConstraintSet, values: &[[u8; 32]]| {
+            let output = AgentOutput {
+                actions: values
+                    .iter()
+                    .copied()
+                    .map(make_valued_call_action)
+                    .collect(),
+            };
+            enforce_constraints(&input, &output, constraints)
+                .map(|_| ())
+                .map_err(|violation| (violation.reason, violation.action_index))
         };
-        let constraints = ConstraintSetV1::default();
 
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_ok());
-    }
+        assert_eq!(enforce(&constraints, &[u256_word(1_000)]), Ok(()));
+        assert_eq!(
+            enforce(&constraints, &[u256_word(0), u256_word(1_001)]),
+            Err((ConstraintViolationReason::NativeValueExceeded, Some(1)))
+        );
 
-    #[test]
-    fn test_transfer_erc20_action_passes() {
-        let input = make_test_input();
-        let output = AgentOutput {
-            actions: vec![make_transfer_erc20_action(
-                [0x11; 20], [0x22; 20], 1_000_000,
-            )],
-        };
-        let constraints = ConstraintSetV1::default();
+        // Values are compared in full: neither 2^64 nor 2^128 saturates or
+        // wraps into range
+        let uncapped = make_native_value_constraints(Some(NativeValueLimits {
+            max_value_per_action: u64::MAX.into(),
+            max_value_per_output: u128::MAX,
+        }));
+        let mut above_u64 = [0u8; 32];
+        above_u64[23] = 1;
+        assert_eq!(
+            enforce(&uncapped, &[above_u64]),
+            Err((ConstraintViolationReason::NativeValueExceeded, Some(0)))
+        );
+        let mut above_u128 = [0u8; 32];
+        above_u128[15] = 1;
+        let unbounded = make_native_value_constraints(Some(NativeValueLimits {
+            max_value_per_action: u128::MAX,
+            max_value_per_output: u128::MAX,
+        }));
+        assert_eq!(
+            enforce(&unbounded, &[above_u128]),
+            Err((ConstraintViolationReason::NativeValueExceeded, Some(0)))
+        );
 
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_ok());
+        // The per-output cap applies to the sum, including on overflow
+        assert_eq!(
+            enforce(&constraints, &[u256_word(750), u256_word(750)]),
+            Ok(())
+        );
+        assert_eq!(
+            enforce(&constraints, &[u256_word(1_000), u256_word(501)]),
+            Err((ConstraintViolationReason::NativeValueExceeded, None))
+        );
+        let mut max_u128 = [0u8; 32];
+        max_u128[16..32].fill(0xff);
+        assert_eq!(
+            enforce(&unbounded, &[max_u128, u256_word(1)]),
+            Err((ConstraintViolationReason::NativeValueExceeded, None))
+        );
+
+        // Sets without the extension are unaffected
+        assert_eq!(
+            enforce(&make_native_value_constraints(None), &[above_u128]),
+            Ok(())
+        );
     }
 
     #[test]
-    fn test_no_op_action_passes() {
-        let input = make_test_input();
-        let output = AgentOutput {
-            actions: vec![ActionV1 {
-                action_type: ACTION_TYPE_NO_OP,
-                target: [0u8; 32],
-                payload: vec![],
-            }],
-        };
-        let constraints = ConstraintSetV1::default();
+    fn test_native_value_encoding() {
+        let constraints = make_native_value_constraints(Some(NATIVE_VALUE));
+        let encoded = constraints.encode().unwrap();
+        assert_eq!(encoded.len(), 44 + 20 + NativeValueLimits::ENCODED_SIZE);
+        assert_eq!(&encoded[60..64], &0b100u32.to_le_bytes());
+        assert_eq!(&encoded[64..80], &1_000u128.to_le_bytes());
+        assert_eq!(&encoded[80..96], &1_500u128.to_le_bytes());
+        assert_eq!(ConstraintSet::decode(&encoded).unwrap(), constraints);
+        assert_eq!(constraints.native_value(), Some(&NATIVE_VALUE));
+        assert!(ConstraintSet::decode(&encoded[..encoded.len() - 1]).is_err());
 
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_ok());
+        // All extensions, in flag-bit order
+        let all = ConstraintSet::V3(ConstraintSetV3 {
+            rolling_window: Some(WINDOW),
+            approval_policy: Some(make_approval_policy(false)),
+            native_value: Some(NATIVE_VALUE),
+            ..ConstraintSetV3::default()
+        });
+        let all_encoded = all.encode().unwrap();
+        assert_eq!(&all_encoded[40..44], &0b111u32.to_le_bytes());
+        assert_eq!(
+            &all_encoded[all_encoded.len() - NativeValueLimits::ENCODED_SIZE..],
+            &[&1_000u128.to_le_bytes()[..], &1_500u128.to_le_bytes()[..]].concat()
+        );
+        assert_eq!(ConstraintSet::decode(&all_encoded).unwrap(), all);
     }
 
+    // ========================================================================
+    // Step Budget Tests
+    // ========================================================================
+
     #[test]
-    fn test_no_op_with_payload_fails() {
-        let input = make_test_input();
-        let output = AgentOutput {
-            actions: vec![ActionV1 {
-                action_type: ACTION_TYPE_NO_OP,
-                target: [0u8; 32],
-                payload: vec![1, 2, 3], // Should be empty
-            }],
-        };
-        let constraints = ConstraintSetV1::default();
+    fn test_step_budget_encoding() {
+        let constraints = ConstraintSet::V3(ConstraintSetV3 {
+            step_budget: Some(50_000_000),
+            ..ConstraintSetV3::default()
+        });
+        let encoded = constraints.encode().unwrap();
+        assert_eq!(encoded.len(), 44 + 8);
+        assert_eq!(&encoded[40..44], &0b1000u32.to_le_bytes());
+        assert_eq!(&encoded[44..52], &50_000_000u64.to_le_bytes());
+        assert_eq!(ConstraintSet::decode(&encoded).unwrap(), constraints);
+        assert_eq!(constraints.step_budget(), Some(50_000_000));
+        assert_eq!(ConstraintSet::default().step_budget(), None);
 
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_err());
-        let violation = result.unwrap_err();
+        // The budget follows the other extensions
+        let all = ConstraintSet::V3(ConstraintSetV3 {
+            native_value: Some(NATIVE_VALUE),
+            step_budget: Some(50_000_000),
+            ..ConstraintSetV3::default()
+        });
+        let all_encoded = all.encode().unwrap();
+        assert_eq!(&all_encoded[40..44], &0b1100u32.to_le_bytes());
         assert_eq!(
-            violation.reason,
-            ConstraintViolationReason::InvalidActionPayload
+            &all_encoded[all_encoded.len() - 8..],
+            &50_000_000u64.to_le_bytes()
         );
-    }
+        assert_eq!(ConstraintSet::decode(&all_encoded).unwrap(), all);
 
-    #[test]
-    fn test_unknown_action_type_fails() {
+        // A zero budget could never be met
+        let zero = ConstraintSet::V3(ConstraintSetV3 {
+            step_budget: Some(0),
+            ..ConstraintSetV3::default()
+        });
         let input = make_test_input();
-        let output = AgentOutput {
-            actions: vec![ActionV1 {
-                action_type: 0xFFFFFFFF,
-                target: [0x11; 32],
-                payload: vec![],
-            }],
-        };
-        let constraints = ConstraintSetV1::default();
-
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_err());
-        let violation = result.unwrap_err();
+        let output = AgentOutput { actions: vec![] };
         assert_eq!(
-            violation.reason,
-            ConstraintViolationReason::UnknownActionType
+            enforce_constraints(&input, &output, &zero)
+                .unwrap_err()
+                .reason,
+            ConstraintViolationReason::InvalidConstraintSet
         );
-        assert_eq!(violation.action_index, Some(0));
     }
 
+    // ========================================================================
+    // Diagnostic Mode Tests
+    // ========================================================================
+
     #[test]
-    fn test_invalid_call_payload_too_short() {
-        let input = make_test_input();
+    fn test_diagnose_reports_every_violation() {
+        // current_equity 100_000 and peak 100_000, last execution at 1000
+        let mut input = make_snapshot_input(1010);
+        input.opaque_agent_inputs[20..28].copy_from_slice(&50_000u64.to_le_bytes());
+        let constraints = ConstraintSet::V1(ConstraintSetV1 {
+            max_position_notional: 1_000,
+            max_drawdown_bps: 1_000,
+            cooldown_seconds: 60,
+            ..ConstraintSetV1::default()
+        });
         let output = AgentOutput {
-            actions: vec![ActionV1 {
-                action_type: ACTION_TYPE_CALL,
-                target: {
-                    let mut t = [0u8; 32];
-                    t[12..32].copy_from_slice(&[0x11; 20]);
-                    t
+            actions: vec![
+                ActionV1 {
+                    action_type: 0xFF,
+                    target: [0u8; 32],
+                    payload: vec![],
                 },
-                payload: vec![0u8; 64], // Too short, needs at least 96
-            }],
+                make_transfer_erc20_action([0x11; 20], [0x22; 20], 500),
+                ActionV1 {
+                    action_type: ACTION_TYPE_NO_OP,
+                    target: [0u8; 32],
+                    payload: vec![0x01],
+                },
+                make_transfer_erc20_action([0x11; 20], [0x22; 20], 5_000),
+            ],
         };
-        let constraints = ConstraintSetV1::default();
 
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_err());
-        let violation = result.unwrap_err();
+        let violations = diagnose_constraints(&input, &output, &constraints);
         assert_eq!(
-            violation.reason,
-            ConstraintViolationReason::InvalidActionPayload
+            violations,
+            vec![
+                ConstraintViolation::action(ConstraintViolationReason::UnknownActionType, 0),
+                ConstraintViolation::action(ConstraintViolationReason::InvalidActionPayload, 2),
+                ConstraintViolation::action(ConstraintViolationReason::PositionTooLarge, 3),
+                ConstraintViolation::global(ConstraintViolationReason::CooldownNotElapsed),
+                ConstraintViolation::global(ConstraintViolationReason::DrawdownExceeded),
+            ]
+        );
+
+        // The consensus path reports only the first
+        assert_eq!(
+            enforce_constraints(&input, &output, &constraints).unwrap_err(),
+            violations[0]
         );
     }
 
     #[test]
-    fn test_invalid_transfer_payload_wrong_size() {
+    fn test_diagnose_matches_enforce() {
         let input = make_test_input();
-        let output = AgentOutput {
-            actions: vec![ActionV1 {
-                action_type: ACTION_TYPE_TRANSFER_ERC20,
-                target: [0u8; 32],
-                payload: vec![0u8; 64], // Should be exactly 96
-            }],
+        let constraints = ConstraintSet::default();
+
+        let valid = AgentOutput {
+            actions: vec![make_transfer_erc20_action([0x11; 20], [0x22; 20], 500)],
         };
-        let constraints = ConstraintSetV1::default();
+        assert!(diagnose_constraints(&input, &valid, &constraints).is_empty());
+        assert!(enforce_constraints(&input, &valid, &constraints).is_ok());
 
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_err());
-        let violation = result.unwrap_err();
+        // Expiry is reported alongside later violations
+        let expired = make_snapshot_input(2001);
+        let input_ref = KernelInputRef {
+            valid_until: Some(2000),
+            ..KernelInputRef::from(&expired)
+        };
+        let invalid = AgentOutput {
+            actions: vec![
+                make_call_action([0x11; 20], 0, &[]),
+                valid.actions[0].clone(),
+            ],
+        };
+        let mut oversized = invalid.clone();
+        oversized.actions[0].payload = vec![0u8; MAX_ACTION_PAYLOAD_BYTES + 1];
         assert_eq!(
-            violation.reason,
-            ConstraintViolationReason::InvalidActionPayload
+            diagnose_constraints_ref(&input_ref, &oversized, &constraints),
+            vec![
+                ConstraintViolation::global(ConstraintViolationReason::InputExpired),
+                ConstraintViolation::action(ConstraintViolationReason::InvalidOutputStructure, 0),
+                ConstraintViolation::action(ConstraintViolationReason::InvalidActionPayload, 0),
+            ]
+        );
+
+        // An invalid constraint set is the only violation reported
+        let bad_set = ConstraintSet::V1(ConstraintSetV1 {
+            max_drawdown_bps: 10_001,
+            ..ConstraintSetV1::default()
+        });
+        assert_eq!(
+            diagnose_constraints(&input, &invalid, &bad_set),
+            vec![ConstraintViolation::global(
+                ConstraintViolationReason::InvalidConstraintSet
+            )]
         );
     }
 
-    #[test]
-    fn test_cooldown_not_elapsed_fails() {
-        // Create input with state snapshot
-        let mut snapshot_bytes = Vec::new();
-        snapshot_bytes.extend_from_slice(&1u32.to_le_bytes()); // version
-        snapshot_bytes.extend_from_slice(&1000u64.to_le_bytes()); // last_execution_ts
-        snapshot_bytes.extend_from_slice(&1030u64.to_le_bytes()); // current_ts (only 30 seconds later)
-        snapshot_bytes.extend_from_slice(&100_000u64.to_le_bytes()); // current_equity
-        snapshot_bytes.extend_from_slice(&100_000u64.to_le_bytes()); // peak_equity
+    // ========================================================================
+    // Custom Rule Tests
+    // ========================================================================
 
-        let mut input = make_test_input();
-        input.opaque_agent_inputs = snapshot_bytes;
+    /// Only allow ERC20 approvals to a single spender.
+    struct OnlyApproveSpender([u8; 20]);
 
-        let output = AgentOutput {
-            actions: vec![make_echo_action()],
-        };
-        let constraints = ConstraintSetV1 {
-            cooldown_seconds: 60, // 60 second cooldown
-            ..ConstraintSetV1::default()
-        };
+    impl ConstraintRule for OnlyApproveSpender {
+        fn id(&self) -> &str {
+            "test.only-approve-spender"
+        }
 
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_err());
-        let violation = result.unwrap_err();
+        fn params(&self) -> Vec<u8> {
+            self.0.to_vec()
+        }
+
+        fn check_action(
+            &self,
+            _ctx: &RuleContext<'_>,
+            action: &ActionV1,
+            index: usize,
+        ) -> Result<(), ConstraintViolation> {
+            if action.action_type != ACTION_TYPE_CALL {
+                return Ok(());
+            }
+            let calldata = call_calldata(&action.payload).unwrap_or_default();
+            if calldata.starts_with(&ERC20_APPROVE_SELECTOR) && calldata[16..36] != self.0 {
+                return Err(ConstraintViolation::action(
+                    ConstraintViolationReason::CallNotAllowed,
+                    index,
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    /// Never let ERC20 transfers take equity below a floor.
+    struct MinEquity(u64);
+
+    impl ConstraintRule for MinEquity {
+        fn id(&self) -> &str {
+            "test.min-equity"
+        }
+
+        fn params(&self) -> Vec<u8> {
+            self.0.to_le_bytes().to_vec()
+        }
+
+        fn check_output(&self, ctx: &RuleContext<'_>) -> Result<(), ConstraintViolation> {
+            let snapshot = ctx.snapshot.ok_or(ConstraintViolation::global(
+                ConstraintViolationReason::InvalidStateSnapshot,
+            ))?;
+            let withdrawn: u128 = ctx
+                .output
+                .actions
+                .iter()
+                .filter(|action| action.action_type == ACTION_TYPE_TRANSFER_ERC20)
+                .filter_map(action_notional)
+                .fold(0, u128::saturating_add);
+            if u128::from(snapshot.current_equity()).saturating_sub(withdrawn) < u128::from(self.0)
+            {
+                return Err(ConstraintViolation::global(
+                    ConstraintViolationReason::DrawdownExceeded,
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    const ADAPTER: u128 = 0xad;
+
+    fn make_adapter_address() -> [u8; 20] {
+        let mut address = [0u8; 20];
+        address[19] = ADAPTER as u8;
+        address
+    }
+
+    fn make_vault_rules() -> RuleSet {
+        RuleSet::new(ConstraintSetV1::default())
+            .with_rule(OnlyApproveSpender(make_adapter_address()))
+            .with_rule(MinEquity(60_000))
+    }
+
+    #[test]
+    fn test_rule_set_hash_commits_to_rules() {
+        let base = ConstraintSet::default();
+        let empty = RuleSet::new(base.clone());
         assert_eq!(
-            violation.reason,
-            ConstraintViolationReason::CooldownNotElapsed
+            compute_constraint_set_hash(&empty).unwrap(),
+            DEFAULT_CONSTRAINT_SET_HASH
         );
+
+        let rules = make_vault_rules();
+        let encoded = rules.encode().unwrap();
+        assert_eq!(encoded.len(), rules.encoded_len().unwrap());
+        assert_eq!(
+            &encoded[..ConstraintSetV1::ENCODED_SIZE],
+            &base.encode().unwrap()[..]
+        );
+        assert_eq!(
+            &encoded[ConstraintSetV1::ENCODED_SIZE..][..4],
+            &2u32.to_le_bytes()
+        );
+
+        // Rules, their parameters and their order are all committed
+        let hash = compute_constraint_set_hash(&rules).unwrap();
+        assert_ne!(hash, DEFAULT_CONSTRAINT_SET_HASH);
+        let other_floor = RuleSet::new(base.clone())
+            .with_rule(OnlyApproveSpender(make_adapter_address()))
+            .with_rule(MinEquity(50_000));
+        assert_ne!(compute_constraint_set_hash(&other_floor).unwrap(), hash);
+        let reordered = RuleSet::new(base)
+            .with_rule(MinEquity(60_000))
+            .with_rule(OnlyApproveSpender(make_adapter_address()));
+        assert_ne!(compute_constraint_set_hash(&reordered).unwrap(), hash);
     }
 
     #[test]
-    fn test_drawdown_exceeded_fails() {
-        // Create input with state snapshot showing 30% drawdown
-        let mut snapshot_bytes = Vec::new();
-        snapshot_bytes.extend_from_slice(&1u32.to_le_bytes()); // version
-        snapshot_bytes.extend_from_slice(&1000u64.to_le_bytes()); // last_execution_ts
-        snapshot_bytes.extend_from_slice(&2000u64.to_le_bytes()); // current_ts
-        snapshot_bytes.extend_from_slice(&70_000u64.to_le_bytes()); // current_equity (70%)
-        snapshot_bytes.extend_from_slice(&100_000u64.to_le_bytes()); // peak_equity
+    fn test_custom_rules_enforced() {
+        let input = make_snapshot_input(2000);
+        let input_ref = KernelInputRef::from(&input);
+        let rules = make_vault_rules();
+        let token = [0x11; 20];
 
-        let mut input = make_test_input();
-        input.opaque_agent_inputs = snapshot_bytes;
+        let approve_adapter = make_call_action(
+            token,
+            0,
+            &make_calldata(ERC20_APPROVE_SELECTOR, &[ADAPTER, 1_000]),
+        );
+        let withdraw = make_transfer_erc20_action(token, [0x22; 20], 40_000);
+        let valid = AgentOutput {
+            actions: vec![approve_adapter, withdraw.clone()],
+        };
+        assert!(enforce_rule_set_ref(&input_ref, &valid, &rules).is_ok());
 
+        // Approving another spender
+        let approve_other = make_call_action(
+            token,
+            0,
+            &make_calldata(ERC20_APPROVE_SELECTOR, &[0xbe, 1_000]),
+        );
         let output = AgentOutput {
-            actions: vec![make_echo_action()],
-        };
-        let constraints = ConstraintSetV1 {
-            max_drawdown_bps: 2_000, // 20% max drawdown
-            ..ConstraintSetV1::default()
+            actions: vec![withdraw.clone(), approve_other.clone()],
         };
-
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_err());
-        let violation = result.unwrap_err();
         assert_eq!(
-            violation.reason,
-            ConstraintViolationReason::DrawdownExceeded
+            enforce_rule_set_ref(&input_ref, &output, &rules).unwrap_err(),
+            ConstraintViolation::action(ConstraintViolationReason::CallNotAllowed, 1)
         );
-    }
+        // Plain constraint enforcement ignores the custom rules
+        assert!(enforce_constraints_ref(&input_ref, &output, rules.constraint_set()).is_ok());
 
-    #[test]
-    fn test_too_many_actions_fails() {
-        let input = make_test_input();
+        // Withdrawing below the floor
+        let overdraw = make_transfer_erc20_action(token, [0x22; 20], 40_001);
         let output = AgentOutput {
-            actions: vec![make_echo_action(); 65], // 65 actions, max is 64
+            actions: vec![overdraw.clone()],
         };
-        let constraints = ConstraintSetV1::default();
-
-        let result = enforce_constraints(&input, &output, &constraints);
-        assert!(result.is_err());
-        let violation = result.unwrap_err();
         assert_eq!(
-            violation.reason,
-            ConstraintViolationReason::InvalidOutputStructure
+            enforce_rule_set_ref(&input_ref, &output, &rules).unwrap_err(),
+            ConstraintViolation::global(ConstraintViolationReason::DrawdownExceeded)
         );
-    }
-
-    #[test]
-    fn test_empty_output_commitment_constant() {
-        // Verify the empty output commitment constant is correct
-        use kernel_core::{compute_action_commitment, CanonicalEncode};
-
-        let empty_output = AgentOutput { actions: vec![] };
-        let encoded = empty_output.encode().unwrap();
-        let commitment = compute_action_commitment(&encoded);
 
-        assert_eq!(commitment, EMPTY_OUTPUT_COMMITMENT);
+        // Diagnostic mode reports built-in and custom violations in order;
+        // a malformed action never reaches the custom rules
+        let mut truncated = make_call_action(token, 0, &[]);
+        truncated.payload.truncate(64);
+        let output = AgentOutput {
+            actions: vec![approve_other, truncated, overdraw],
+        };
+        assert_eq!(
+            diagnose_rule_set_ref(&input_ref, &output, &rules),
+            vec![
+                ConstraintViolation::action(ConstraintViolationReason::CallNotAllowed, 0),
+                ConstraintViolation::action(ConstraintViolationReason::InvalidActionPayload, 1),
+                ConstraintViolation::global(ConstraintViolationReason::DrawdownExceeded),
+            ]
+        );
     }
 
     // ========================================================================
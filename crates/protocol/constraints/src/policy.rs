@@ -0,0 +1,904 @@
+//! Declarative constraint policies (`policy` feature).
+//!
+//! A policy file is a reviewable TOML or JSON description of a constraint
+//! set. [`compile_policy`] parses it, validates it against the same
+//! invariants the kernel enforces, and produces the canonical constraint set
+//! bytes and `constraint_set_hash` to publish alongside it.
+//!
+//! ```toml
+//! # Optional: 1 without allowlists, 2 with, 3 with a rolling window,
+//! # approvals, native-value caps or a step budget
+//! version = 3
+//!
+//! [limits]
+//! max_position_notional = "1000000000"   # integer or decimal string
+//! max_leverage_bps = 30000
+//! max_actions_per_output = 8
+//!
+//! [cooldown]
+//! seconds = 300
+//!
+//! [drawdown]
+//! max_bps = 2000
+//!
+//! [allowlists]
+//! tokens = ["0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"]
+//! call_targets = []
+//! calls = [
+//!   { target = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", selector = "0x095ea7b3" },
+//! ]
+//!
+//! [window]
+//! seconds = 86400
+//! max_executions = 24
+//! max_notional = "5000000000"
+//!
+//! [approvals]
+//! spenders = ["0x1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d"]
+//! max_amount = "1000000000"
+//! allow_unlimited = false
+//!
+//! [native_value]
+//! max_per_action = "1000000000000000000"   # wei
+//! max_per_output = "2000000000000000000"
+//!
+//! [steps]
+//! budget = 10000                           # steps the agent may charge
+//! ```
+//!
+//! Omitted fields take the permissive [`ConstraintSetV1::default`] values.
+//! Unknown fields are rejected so that a misspelt limit cannot silently
+//! fall back to its default. Allowlist entries may be listed in any order
+//! (they are sorted into canonical order) but must not repeat. The same
+//! applies to approval spenders.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use kernel_core::{CanonicalEncode, CodecError, MAX_ACTIONS_PER_OUTPUT};
+use serde::de::Visitor;
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    compute_constraint_set_hash, AllowedCall, ApprovalPolicy, ConstraintSet, ConstraintSetV1,
+    ConstraintSetV2, ConstraintSetV3, NativeValueLimits, RollingWindowLimits, MAX_ALLOWED_CALLS,
+    MAX_ALLOWED_CALL_TARGETS, MAX_ALLOWED_SPENDERS, MAX_ALLOWED_TOKENS,
+};
+
+/// Source format of a policy file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolicyFormat {
+    Toml,
+    Json,
+}
+
+impl PolicyFormat {
+    /// Format implied by a file extension (`toml` or `json`).
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "toml" => Some(PolicyFormat::Toml),
+            "json" => Some(PolicyFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Error compiling a policy file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolicyError {
+    /// The file is not valid TOML/JSON or does not match the policy schema.
+    Parse {
+        format: PolicyFormat,
+        message: String,
+    },
+    /// `version` is not 1, 2 or 3.
+    UnsupportedVersion(u32),
+    /// A version 1 policy declares allowlists.
+    AllowlistsRequireVersion2,
+    /// A version 2 or 3 policy sets `allowed_asset_id`, which only version 1
+    /// has.
+    AssetIdRequiresVersion1,
+    /// A version 1 or 2 policy declares a rolling window.
+    WindowRequiresVersion3,
+    /// `window.seconds` is zero.
+    EmptyWindow,
+    /// A version 1 or 2 policy declares an approval policy.
+    ApprovalsRequireVersion3,
+    /// A version 1 or 2 policy declares native-value caps.
+    NativeValueRequiresVersion3,
+    /// A version 1 or 2 policy declares a step budget.
+    StepBudgetRequiresVersion3,
+    /// `steps.budget` is zero.
+    EmptyStepBudget,
+    /// `max_actions_per_output` exceeds the protocol limit.
+    TooManyActions { count: u32, limit: usize },
+    /// `drawdown.max_bps` exceeds 10000 (100%).
+    DrawdownOutOfRange(u32),
+    /// An allowlist has more entries than the constraint set allows.
+    AllowlistTooLarge {
+        list: &'static str,
+        count: usize,
+        limit: usize,
+    },
+    /// An allowlist entry appears more than once.
+    DuplicateAllowlistEntry { list: &'static str, entry: String },
+    /// The compiled constraint set could not be encoded.
+    Encoding(CodecError),
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::Parse { format, message } => {
+                write!(f, "invalid {:?} policy: {}", format, message)
+            }
+            PolicyError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "unsupported policy version {} (expected 1, 2 or 3)",
+                    version
+                )
+            }
+            PolicyError::AllowlistsRequireVersion2 => {
+                write!(f, "allowlists require policy version 2 or later")
+            }
+            PolicyError::AssetIdRequiresVersion1 => {
+                write!(f, "allowed_asset_id is only supported by policy version 1")
+            }
+            PolicyError::WindowRequiresVersion3 => {
+                write!(f, "a rolling window requires policy version 3")
+            }
+            PolicyError::EmptyWindow => write!(f, "window.seconds must be non-zero"),
+            PolicyError::ApprovalsRequireVersion3 => {
+                write!(f, "an approval policy requires policy version 3")
+            }
+            PolicyError::NativeValueRequiresVersion3 => {
+                write!(f, "native-value caps require policy version 3")
+            }
+            PolicyError::StepBudgetRequiresVersion3 => {
+                write!(f, "a step budget requires policy version 3")
+            }
+            PolicyError::EmptyStepBudget => write!(f, "steps.budget must be non-zero"),
+            PolicyError::TooManyActions { count, limit } => write!(
+                f,
+                "max_actions_per_output {} exceeds the protocol limit of {}",
+                count, limit
+            ),
+            PolicyError::DrawdownOutOfRange(bps) => {
+                write!(f, "drawdown.max_bps {} exceeds 10000 (100%)", bps)
+            }
+            PolicyError::AllowlistTooLarge { list, count, limit } => write!(
+                f,
+                "{} has {} entries, more than the limit of {}",
+                list, count, limit
+            ),
+            PolicyError::DuplicateAllowlistEntry { list, entry } => {
+                write!(f, "{} lists {} more than once", list, entry)
+            }
+            PolicyError::Encoding(e) => write!(f, "failed to encode constraint set: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+/// A parsed (not yet validated) policy file.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Constraint set version; inferred from `window`, `approvals`,
+    /// `native_value`, `steps` and `allowlists` when omitted
+    pub version: Option<u32>,
+    /// Per-action and per-output limits
+    #[serde(default)]
+    pub limits: PolicyLimits,
+    /// Minimum time between executions
+    #[serde(default)]
+    pub cooldown: PolicyCooldown,
+    /// Maximum drawdown from peak equity
+    #[serde(default)]
+    pub drawdown: PolicyDrawdown,
+    /// Version 1 `allowed_asset_id`
+    #[serde(default, deserialize_with = "bytes32_opt")]
+    pub allowed_asset_id: Option<[u8; 32]>,
+    /// Token and call allowlists (version 2 and later)
+    pub allowlists: Option<PolicyAllowlists>,
+    /// Rolling-window limits (version 3)
+    pub window: Option<PolicyWindow>,
+    /// ERC20 approval policy (version 3)
+    pub approvals: Option<PolicyApprovals>,
+    /// Caps on the native value CALL actions send (version 3)
+    pub native_value: Option<PolicyNativeValue>,
+    /// Agent step budget (version 3)
+    pub steps: Option<PolicySteps>,
+}
+
+/// `[limits]` section of a policy.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyLimits {
+    /// Maximum notional of a single action in token base units
+    #[serde(default, deserialize_with = "u64_int_or_string")]
+    pub max_position_notional: Option<u64>,
+    /// Maximum notional relative to current equity, in basis points
+    pub max_leverage_bps: Option<u32>,
+    /// Maximum actions per output
+    pub max_actions_per_output: Option<u32>,
+}
+
+/// `[cooldown]` section of a policy.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyCooldown {
+    /// Minimum seconds between executions (0 disables the check)
+    pub seconds: Option<u32>,
+}
+
+/// `[drawdown]` section of a policy.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDrawdown {
+    /// Maximum drawdown in basis points (10000 disables the check)
+    pub max_bps: Option<u32>,
+}
+
+/// `[allowlists]` section of a policy. Omitted lists are empty.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyAllowlists {
+    /// ERC20 tokens TRANSFER_ERC20 actions may move
+    #[serde(default, with = "kernel_core::serde_hex::array_vec")]
+    pub tokens: Vec<[u8; 20]>,
+    /// Contracts CALL actions may invoke with any calldata
+    #[serde(default, with = "kernel_core::serde_hex::array_vec")]
+    pub call_targets: Vec<[u8; 20]>,
+    /// Individual functions CALL actions may invoke
+    #[serde(default)]
+    pub calls: Vec<AllowedCall>,
+}
+
+/// `[window]` section of a policy. Omitted limits are unbounded.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyWindow {
+    /// Window length in seconds
+    pub seconds: u32,
+    /// Maximum executions per window
+    pub max_executions: Option<u32>,
+    /// Maximum total action notional per window in token base units
+    #[serde(default, deserialize_with = "u64_int_or_string")]
+    pub max_notional: Option<u64>,
+}
+
+/// `[approvals]` section of a policy.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyApprovals {
+    /// Addresses ERC20 `approve` calls may name as spender
+    #[serde(default, with = "kernel_core::serde_hex::array_vec")]
+    pub spenders: Vec<[u8; 20]>,
+    /// Largest allowance in token base units (`u128::MAX` when omitted)
+    #[serde(default, deserialize_with = "u128_int_or_string")]
+    pub max_amount: Option<u128>,
+    /// Whether unlimited (`type(uint256).max`) allowances are allowed
+    #[serde(default)]
+    pub allow_unlimited: bool,
+}
+
+/// `[native_value]` section of a policy. Omitted caps are unbounded.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyNativeValue {
+    /// Maximum value a single CALL may send, in wei
+    #[serde(default, deserialize_with = "u128_int_or_string")]
+    pub max_per_action: Option<u128>,
+    /// Maximum total value the CALLs of one output may send, in wei
+    #[serde(default, deserialize_with = "u128_int_or_string")]
+    pub max_per_output: Option<u128>,
+}
+
+/// `[steps]` section of a policy.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicySteps {
+    /// Maximum steps the agent may charge per execution
+    pub budget: u64,
+}
+
+/// A compiled policy: the constraint set, its canonical encoding and hash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompiledPolicy {
+    /// The validated constraint set
+    pub constraint_set: ConstraintSet,
+    /// Canonical encoding, as read by the kernel guest
+    pub bytes: Vec<u8>,
+    /// `constraint_set_hash` inputs must carry
+    pub hash: [u8; 32],
+}
+
+/// Parse, validate and compile a policy file.
+pub fn compile_policy(source: &str, format: PolicyFormat) -> Result<CompiledPolicy, PolicyError> {
+    Policy::parse(source, format)?.compile()
+}
+
+impl Policy {
+    /// Parse a policy without validating it.
+    pub fn parse(source: &str, format: PolicyFormat) -> Result<Self, PolicyError> {
+        let parsed = match format {
+            PolicyFormat::Toml => toml::from_str(source).map_err(|e| e.to_string()),
+            PolicyFormat::Json => serde_json::from_str(source).map_err(|e| e.to_string()),
+        };
+        parsed.map_err(|message| PolicyError::Parse { format, message })
+    }
+
+    /// Validate the policy and build its constraint set.
+    pub fn to_constraint_set(&self) -> Result<ConstraintSet, PolicyError> {
+        let defaults = ConstraintSetV1::default();
+        let max_position_notional = self
+            .limits
+            .max_position_notional
+            .unwrap_or(defaults.max_position_notional);
+        let max_leverage_bps = self
+            .limits
+            .max_leverage_bps
+            .unwrap_or(defaults.max_leverage_bps);
+        let max_drawdown_bps = self.drawdown.max_bps.unwrap_or(defaults.max_drawdown_bps);
+        let cooldown_seconds = self.cooldown.seconds.unwrap_or(defaults.cooldown_seconds);
+        let max_actions_per_output = self
+            .limits
+            .max_actions_per_output
+            .unwrap_or(defaults.max_actions_per_output);
+
+        if max_actions_per_output > MAX_ACTIONS_PER_OUTPUT as u32 {
+            return Err(PolicyError::TooManyActions {
+                count: max_actions_per_output,
+                limit: MAX_ACTIONS_PER_OUTPUT,
+            });
+        }
+        if max_drawdown_bps > 10_000 {
+            return Err(PolicyError::DrawdownOutOfRange(max_drawdown_bps));
+        }
+
+        let requires_version3 = self.window.is_some()
+            || self.approvals.is_some()
+            || self.native_value.is_some()
+            || self.steps.is_some();
+        let version = self.version.unwrap_or(if requires_version3 {
+            3
+        } else if self.allowlists.is_some() {
+            2
+        } else {
+            1
+        });
+        if version < 3 && self.window.is_some() {
+            return Err(PolicyError::WindowRequiresVersion3);
+        }
+        if version < 3 && self.approvals.is_some() {
+            return Err(PolicyError::ApprovalsRequireVersion3);
+        }
+        if version < 3 && self.native_value.is_some() {
+            return Err(PolicyError::NativeValueRequiresVersion3);
+        }
+        if version < 3 && self.steps.is_some() {
+            return Err(PolicyError::StepBudgetRequiresVersion3);
+        }
+        match version {
+            1 => {
+                if self.allowlists.is_some() {
+                    return Err(PolicyError::AllowlistsRequireVersion2);
+                }
+                Ok(ConstraintSet::V1(ConstraintSetV1 {
+                    version: 1,
+                    max_position_notional,
+                    max_leverage_bps,
+                    max_drawdown_bps,
+                    cooldown_seconds,
+                    max_actions_per_output,
+                    allowed_asset_id: self.allowed_asset_id.unwrap_or(defaults.allowed_asset_id),
+                }))
+            }
+            2 => {
+                if self.allowed_asset_id.is_some() {
+                    return Err(PolicyError::AssetIdRequiresVersion1);
+                }
+                let (allowed_tokens, allowed_call_targets, allowed_calls) =
+                    self.canonical_allowlists()?;
+                Ok(ConstraintSet::V2(ConstraintSetV2 {
+                    version: 2,
+                    max_position_notional,
+                    max_leverage_bps,
+                    max_drawdown_bps,
+                    cooldown_seconds,
+                    max_actions_per_output,
+                    allowed_tokens,
+                    allowed_call_targets,
+                    allowed_calls,
+                }))
+            }
+            3 => {
+                if self.allowed_asset_id.is_some() {
+                    return Err(PolicyError::AssetIdRequiresVersion1);
+                }
+                let rolling_window = match &self.window {
+                    Some(window) if window.seconds == 0 => return Err(PolicyError::EmptyWindow),
+                    Some(window) => Some(RollingWindowLimits {
+                        window_seconds: window.seconds,
+                        max_executions: window.max_executions.unwrap_or(u32::MAX),
+                        max_notional: window.max_notional.unwrap_or(u64::MAX),
+                    }),
+                    None => None,
+                };
+                let approval_policy = match &self.approvals {
+                    Some(approvals) => Some(ApprovalPolicy {
+                        allowed_spenders: canonical_allowlist(
+                            "approvals.spenders",
+                            approvals.spenders.clone(),
+                            MAX_ALLOWED_SPENDERS,
+                            |spender| hex_string(spender),
+                        )?,
+                        max_amount: approvals.max_amount.unwrap_or(u128::MAX),
+                        allow_unlimited: approvals.allow_unlimited,
+                    }),
+                    None => None,
+                };
+                let native_value = self.native_value.as_ref().map(|caps| NativeValueLimits {
+                    max_value_per_action: caps.max_per_action.unwrap_or(u128::MAX),
+                    max_value_per_output: caps.max_per_output.unwrap_or(u128::MAX),
+                });
+                let step_budget = match &self.steps {
+                    Some(steps) if steps.budget == 0 => {
+                        return Err(PolicyError::EmptyStepBudget)
+                    }
+                    Some(steps) => Some(steps.budget),
+                    None => None,
+                };
+                let (allowed_tokens, allowed_call_targets, allowed_calls) =
+                    self.canonical_allowlists()?;
+                Ok(ConstraintSet::V3(ConstraintSetV3 {
+                    version: 3,
+                    max_position_notional,
+                    max_leverage_bps,
+                    max_drawdown_bps,
+                    cooldown_seconds,
+                    max_actions_per_output,
+                    allowed_tokens,
+                    allowed_call_targets,
+                    allowed_calls,
+                    rolling_window,
+                    approval_policy,
+                    native_value,
+                    step_budget,
+                }))
+            }
+            other => Err(PolicyError::UnsupportedVersion(other)),
+        }
+    }
+
+    /// The token, call target and call allowlists in canonical order.
+    fn canonical_allowlists(&self) -> Result<CanonicalAllowlists, PolicyError> {
+        let allowlists = self.allowlists.clone().unwrap_or_default();
+        Ok((
+            canonical_allowlist(
+                "allowlists.tokens",
+                allowlists.tokens,
+                MAX_ALLOWED_TOKENS,
+                |token| hex_string(token),
+            )?,
+            canonical_allowlist(
+                "allowlists.call_targets",
+                allowlists.call_targets,
+                MAX_ALLOWED_CALL_TARGETS,
+                |target| hex_string(target),
+            )?,
+            canonical_allowlist(
+                "allowlists.calls",
+                allowlists.calls,
+                MAX_ALLOWED_CALLS,
+                |call| {
+                    format!(
+                        "{}:{}",
+                        hex_string(&call.target),
+                        hex_string(&call.selector)
+                    )
+                },
+            )?,
+        ))
+    }
+
+    /// Validate the policy and compile it to canonical bytes and hash.
+    pub fn compile(&self) -> Result<CompiledPolicy, PolicyError> {
+        let constraint_set = self.to_constraint_set()?;
+        let bytes = constraint_set.encode().map_err(PolicyError::Encoding)?;
+        let hash = compute_constraint_set_hash(&constraint_set).map_err(PolicyError::Encoding)?;
+        Ok(CompiledPolicy {
+            constraint_set,
+            bytes,
+            hash,
+        })
+    }
+}
+
+type CanonicalAllowlists = (Vec<[u8; 20]>, Vec<[u8; 20]>, Vec<AllowedCall>);
+
+/// Sort an allowlist into canonical order, rejecting oversized lists and
+/// repeated entries.
+fn canonical_allowlist<T: Ord>(
+    list: &'static str,
+    mut entries: Vec<T>,
+    limit: usize,
+    describe: impl Fn(&T) -> String,
+) -> Result<Vec<T>, PolicyError> {
+    if entries.len() > limit {
+        return Err(PolicyError::AllowlistTooLarge {
+            list,
+            count: entries.len(),
+            limit,
+        });
+    }
+    entries.sort();
+    if let Some(pair) = entries.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(PolicyError::DuplicateAllowlistEntry {
+            list,
+            entry: describe(&pair[0]),
+        });
+    }
+    Ok(entries)
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    let mut out = String::from("0x");
+    for byte in bytes {
+        out.push_str(&format!("{:02x}", byte));
+    }
+    out
+}
+
+/// An optional `0x`-hex `[u8; 32]`.
+fn bytes32_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<[u8; 32]>, D::Error> {
+    kernel_core::serde_hex::bytes32::deserialize(deserializer).map(Some)
+}
+
+/// A `u64` given as an integer or, for values TOML integers cannot hold, a
+/// decimal string.
+fn u64_int_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    struct U64Visitor;
+
+    impl Visitor<'_> for U64Visitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a non-negative integer or decimal string")
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<u64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<u64, E> {
+            u64::try_from(value).map_err(|_| E::custom("value must not be negative"))
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<u64, E> {
+            value.parse().map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_any(U64Visitor).map(Some)
+}
+
+/// A `u128` given as an integer or a decimal string.
+fn u128_int_or_string<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u128>, D::Error> {
+    struct U128Visitor;
+
+    impl Visitor<'_> for U128Visitor {
+        type Value = u128;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a non-negative integer or decimal string")
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<u128, E> {
+            Ok(value.into())
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<u128, E> {
+            u128::try_from(value).map_err(|_| E::custom("value must not be negative"))
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<u128, E> {
+            value.parse().map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_any(U128Visitor).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_CONSTRAINT_SET_HASH;
+    use alloc::vec;
+    use kernel_core::CanonicalDecode;
+
+    const USDC: [u8; 20] = [0xa0; 20];
+    const ADAPTER: [u8; 20] = [0x1d; 20];
+
+    const TOML_POLICY: &str = r#"
+        [limits]
+        max_position_notional = "1000000000"
+        max_leverage_bps = 30000
+        max_actions_per_output = 8
+
+        [cooldown]
+        seconds = 300
+
+        [drawdown]
+        max_bps = 2000
+
+        [allowlists]
+        tokens = ["0xa0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0"]
+        calls = [
+            { target = "0xa0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0", selector = "0x095ea7b3" },
+            { target = "0x1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d", selector = "0x51cff8d9" },
+        ]
+    "#;
+
+    #[test]
+    fn test_compile_toml_and_json_policies() {
+        let compiled = compile_policy(TOML_POLICY, PolicyFormat::Toml).unwrap();
+        let expected = ConstraintSet::V2(ConstraintSetV2 {
+            max_position_notional: 1_000_000_000,
+            max_leverage_bps: 30_000,
+            max_drawdown_bps: 2_000,
+            cooldown_seconds: 300,
+            max_actions_per_output: 8,
+            allowed_tokens: vec![USDC],
+            // Sorted into canonical order
+            allowed_calls: vec![
+                AllowedCall {
+                    target: ADAPTER,
+                    selector: [0x51, 0xcf, 0xf8, 0xd9],
+                },
+                AllowedCall {
+                    target: USDC,
+                    selector: [0x09, 0x5e, 0xa7, 0xb3],
+                },
+            ],
+            ..ConstraintSetV2::default()
+        });
+        assert_eq!(compiled.constraint_set, expected);
+        assert_eq!(compiled.bytes, expected.encode().unwrap());
+        assert_eq!(
+            compiled.hash,
+            compute_constraint_set_hash(&expected).unwrap()
+        );
+        assert_eq!(ConstraintSet::decode(&compiled.bytes).unwrap(), expected);
+
+        let json = r#"{
+            "limits": { "max_position_notional": 1000000000, "max_leverage_bps": 30000,
+                        "max_actions_per_output": 8 },
+            "cooldown": { "seconds": 300 },
+            "drawdown": { "max_bps": 2000 },
+            "allowlists": {
+                "tokens": ["0xa0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0"],
+                "calls": [
+                    { "target": "0x1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d", "selector": "0x51cff8d9" },
+                    { "target": "0xa0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0", "selector": "0x095ea7b3" }
+                ]
+            }
+        }"#;
+        assert_eq!(compile_policy(json, PolicyFormat::Json).unwrap(), compiled);
+
+        // An empty policy is the default constraint set
+        let empty = compile_policy("", PolicyFormat::Toml).unwrap();
+        assert_eq!(empty.hash, DEFAULT_CONSTRAINT_SET_HASH);
+    }
+
+    #[test]
+    fn test_compile_window_policy() {
+        let source = format!(
+            "{}\n[window]\nseconds = 86400\nmax_notional = \"18446744073709551615\"\n",
+            TOML_POLICY
+        );
+        let compiled = compile_policy(&source, PolicyFormat::Toml).unwrap();
+        let ConstraintSet::V3(set) = &compiled.constraint_set else {
+            panic!("expected a version 3 constraint set");
+        };
+        assert_eq!(
+            set.rolling_window,
+            Some(RollingWindowLimits {
+                window_seconds: 86_400,
+                max_executions: u32::MAX,
+                max_notional: u64::MAX,
+            })
+        );
+        assert_eq!(set.allowed_tokens, vec![USDC]);
+        assert_eq!(
+            ConstraintSet::decode(&compiled.bytes).unwrap(),
+            compiled.constraint_set
+        );
+
+        // Version 3 without a window still compiles, to a different hash
+        let no_window = compile_policy("version = 3", PolicyFormat::Toml).unwrap();
+        assert_eq!(
+            no_window.constraint_set,
+            ConstraintSet::V3(ConstraintSetV3::default())
+        );
+        assert_ne!(no_window.hash, DEFAULT_CONSTRAINT_SET_HASH);
+    }
+
+    #[test]
+    fn test_compile_approval_policy() {
+        let source = r#"
+            [approvals]
+            spenders = [
+                "0x1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d",
+                "0x0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e",
+            ]
+            max_amount = 5000
+        "#;
+        let compiled = compile_policy(source, PolicyFormat::Toml).unwrap();
+        let ConstraintSet::V3(set) = &compiled.constraint_set else {
+            panic!("expected a version 3 constraint set");
+        };
+        assert_eq!(
+            set.approval_policy,
+            Some(ApprovalPolicy {
+                allowed_spenders: vec![[0x0e; 20], ADAPTER],
+                max_amount: 5_000,
+                allow_unlimited: false,
+            })
+        );
+        assert_eq!(
+            ConstraintSet::decode(&compiled.bytes).unwrap(),
+            compiled.constraint_set
+        );
+    }
+
+    #[test]
+    fn test_compile_native_value_policy() {
+        let source = r#"
+            [native_value]
+            max_per_action = "340282366920938463463374607431768211455"
+            max_per_output = 2000000000000000000
+        "#;
+        let compiled = compile_policy(source, PolicyFormat::Toml).unwrap();
+        let ConstraintSet::V3(set) = &compiled.constraint_set else {
+            panic!("expected a version 3 constraint set");
+        };
+        assert_eq!(
+            set.native_value,
+            Some(NativeValueLimits {
+                max_value_per_action: u128::MAX,
+                max_value_per_output: 2_000_000_000_000_000_000,
+            })
+        );
+        assert_eq!(
+            ConstraintSet::decode(&compiled.bytes).unwrap(),
+            compiled.constraint_set
+        );
+
+        let json = r#"{ "native_value": { "max_per_output": "2000000000000000000" } }"#;
+        assert_eq!(compile_policy(json, PolicyFormat::Json).unwrap(), compiled);
+    }
+
+    #[test]
+    fn test_compile_step_budget_policy() {
+        let compiled = compile_policy("[steps]\nbudget = 50000000", PolicyFormat::Toml).unwrap();
+        assert_eq!(
+            compiled.constraint_set,
+            ConstraintSet::V3(ConstraintSetV3 {
+                step_budget: Some(50_000_000),
+                ..ConstraintSetV3::default()
+            })
+        );
+        assert_eq!(
+            ConstraintSet::decode(&compiled.bytes).unwrap(),
+            compiled.constraint_set
+        );
+    }
+
+    #[test]
+    fn test_invalid_policies_rejected() {
+        let compile = |source: &str| compile_policy(source, PolicyFormat::Toml).unwrap_err();
+
+        assert!(matches!(
+            compile("[limits]\nmax_leverage = 30000"),
+            PolicyError::Parse {
+                format: PolicyFormat::Toml,
+                ..
+            }
+        ));
+        assert!(matches!(
+            compile("[limits]\nmax_position_notional = -1"),
+            PolicyError::Parse { .. }
+        ));
+        assert_eq!(
+            compile("[limits]\nmax_actions_per_output = 65"),
+            PolicyError::TooManyActions {
+                count: 65,
+                limit: MAX_ACTIONS_PER_OUTPUT
+            }
+        );
+        assert_eq!(
+            compile("[drawdown]\nmax_bps = 10001"),
+            PolicyError::DrawdownOutOfRange(10_001)
+        );
+        assert_eq!(compile("version = 4"), PolicyError::UnsupportedVersion(4));
+        assert_eq!(
+            compile("version = 2\n[window]\nseconds = 60"),
+            PolicyError::WindowRequiresVersion3
+        );
+        assert_eq!(compile("[window]\nseconds = 0"), PolicyError::EmptyWindow);
+        assert_eq!(
+            compile("version = 2\n[approvals]"),
+            PolicyError::ApprovalsRequireVersion3
+        );
+        assert_eq!(
+            compile(
+                "[approvals]\nspenders = [\"0x1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d\", \
+                 \"0x1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d\"]"
+            ),
+            PolicyError::DuplicateAllowlistEntry {
+                list: "approvals.spenders",
+                entry: hex_string(&ADAPTER),
+            }
+        );
+        assert_eq!(
+            compile("version = 2\n[native_value]"),
+            PolicyError::NativeValueRequiresVersion3
+        );
+        assert_eq!(
+            compile("version = 2\n[steps]\nbudget = 1"),
+            PolicyError::StepBudgetRequiresVersion3
+        );
+        assert_eq!(
+            compile("[steps]\nbudget = 0"),
+            PolicyError::EmptyStepBudget
+        );
+        assert!(matches!(
+            compile("[native_value]\nmax_per_action = \"340282366920938463463374607431768211456\""),
+            PolicyError::Parse { .. }
+        ));
+        assert!(matches!(
+            compile("[window]\nmax_executions = 3"),
+            PolicyError::Parse { .. }
+        ));
+        assert_eq!(
+            compile("version = 1\n[allowlists]"),
+            PolicyError::AllowlistsRequireVersion2
+        );
+        assert_eq!(
+            compile(&format!(
+                "version = 2\nallowed_asset_id = \"0x{}\"",
+                "00".repeat(32)
+            )),
+            PolicyError::AssetIdRequiresVersion1
+        );
+        assert_eq!(
+            compile(
+                "[allowlists]\ncall_targets = [\"0x1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d\", \
+                 \"0x1D1D1D1D1D1D1D1D1D1D1D1D1D1D1D1D1D1D1D1D\"]"
+            ),
+            PolicyError::DuplicateAllowlistEntry {
+                list: "allowlists.call_targets",
+                entry: hex_string(&ADAPTER),
+            }
+        );
+        let tokens: Vec<String> = (0..=MAX_ALLOWED_TOKENS)
+            .map(|i| format!("\"0x{}{:02x}\"", "00".repeat(19), i))
+            .collect();
+        assert_eq!(
+            compile(&format!("[allowlists]\ntokens = [{}]", tokens.join(", "))),
+            PolicyError::AllowlistTooLarge {
+                list: "allowlists.tokens",
+                count: MAX_ALLOWED_TOKENS + 1,
+                limit: MAX_ALLOWED_TOKENS
+            }
+        );
+    }
+}
@@ -13,7 +13,10 @@
 //! to the position in the agent-provided order.
 
 use crate::types::*;
-use crate::{KERNEL_VERSION, MAX_AGENT_INPUT_BYTES, MAX_AGENT_OUTPUT_BYTES, PROTOCOL_VERSION};
+use crate::{
+    KERNEL_VERSION, KERNEL_VERSION_V2, KERNEL_VERSION_V3, KERNEL_VERSION_V4, KERNEL_VERSION_V5,
+    MAX_AGENT_INPUT_BYTES, MAX_AGENT_OUTPUT_BYTES, MAX_BATCH_STEPS, PROTOCOL_VERSION,
+};
 use alloc::vec::Vec;
 
 // ============================================================================
@@ -137,6 +140,17 @@ pub fn get_var_bytes(
     offset: &mut usize,
     max_len: usize,
 ) -> Result<Vec<u8>, CodecError> {
+    get_var_slice(bytes, offset, max_len).map(<[u8]>::to_vec)
+}
+
+/// Borrow variable-length bytes with u32 length prefix.
+/// Zero-copy counterpart of [`get_var_bytes`]; same checks and offset handling.
+#[inline]
+pub fn get_var_slice<'a>(
+    bytes: &'a [u8],
+    offset: &mut usize,
+    max_len: usize,
+) -> Result<&'a [u8], CodecError> {
     let len_u32 = get_u32_le(bytes, offset)?;
 
     if len_u32 > max_len as u32 {
@@ -155,7 +169,7 @@ pub fn get_var_bytes(
         return Err(CodecError::UnexpectedEndOfInput);
     }
 
-    let data = bytes[*offset..end].to_vec();
+    let data = &bytes[*offset..end];
     *offset = end;
     Ok(data)
 }
@@ -227,6 +241,20 @@ pub trait CanonicalDecode: Sized {
 // KernelInputV1 Codec
 // ============================================================================
 
+/// Check that an input declares a kernel version this codec can process.
+///
+/// KernelInputV1 has the same layout under kernel versions 1 and 2; the
+/// version only selects which journal the kernel produces.
+fn check_input_kernel_version(kernel_version: u32) -> Result<(), CodecError> {
+    if kernel_version != KERNEL_VERSION && kernel_version != KERNEL_VERSION_V2 {
+        return Err(CodecError::InvalidVersion {
+            expected: KERNEL_VERSION,
+            actual: kernel_version,
+        });
+    }
+    Ok(())
+}
+
 /// KernelInputV1 encoding layout (little-endian):
 /// - protocol_version: u32 (4 bytes)
 /// - kernel_version: u32 (4 bytes)
@@ -261,12 +289,7 @@ impl CanonicalEncode for KernelInputV1 {
                 actual: self.protocol_version,
             });
         }
-        if self.kernel_version != KERNEL_VERSION {
-            return Err(CodecError::InvalidVersion {
-                expected: KERNEL_VERSION,
-                actual: self.kernel_version,
-            });
-        }
+        check_input_kernel_version(self.kernel_version)?;
 
         let data_len = self.opaque_agent_inputs.len();
         if data_len > MAX_AGENT_INPUT_BYTES {
@@ -318,9 +341,118 @@ impl CanonicalDecode for KernelInputV1 {
         }
 
         let kernel_version = get_u32_le(bytes, &mut offset)?;
-        if kernel_version != KERNEL_VERSION {
+        check_input_kernel_version(kernel_version)?;
+
+        let agent_id = get_bytes32(bytes, &mut offset)?;
+        let agent_code_hash = get_bytes32(bytes, &mut offset)?;
+        let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
+        let input_root = get_bytes32(bytes, &mut offset)?;
+        let execution_nonce = get_u64_le(bytes, &mut offset)?;
+        let opaque_agent_inputs = get_var_bytes(bytes, &mut offset, MAX_AGENT_INPUT_BYTES)?;
+
+        ensure_no_trailing_bytes(bytes, offset)?;
+
+        Ok(KernelInputV1 {
+            protocol_version,
+            kernel_version,
+            agent_id,
+            agent_code_hash,
+            constraint_set_hash,
+            input_root,
+            execution_nonce,
+            opaque_agent_inputs,
+        })
+    }
+}
+
+// ============================================================================
+// KernelInputV3 Codec
+// ============================================================================
+
+/// KernelInputV3 encoding layout (little-endian):
+/// - protocol_version: u32 (4 bytes)
+/// - kernel_version: u32 (4 bytes, always 3)
+/// - agent_id: [u8; 32] (32 bytes)
+/// - agent_code_hash: [u8; 32] (32 bytes)
+/// - constraint_set_hash: [u8; 32] (32 bytes)
+/// - input_root: [u8; 32] (32 bytes)
+/// - execution_nonce: u64 (8 bytes)
+/// - prev_journal_hash: [u8; 32] (32 bytes)
+/// - opaque_agent_inputs_len: u32 (4 bytes)
+/// - opaque_agent_inputs: [u8; len] (variable)
+///
+/// Fixed header: 176 bytes + 4 byte length prefix + variable input data
+/// Minimum size with empty input: 180 bytes
+impl CanonicalEncode for KernelInputV3 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        let data_len = self.opaque_agent_inputs.len();
+        if data_len > MAX_AGENT_INPUT_BYTES {
+            return Err(CodecError::InputTooLarge {
+                size: data_len.min(u32::MAX as usize) as u32,
+                limit: MAX_AGENT_INPUT_BYTES,
+            });
+        }
+        // 176 (fixed) + 4 (length prefix) + data_len
+        Ok(180 + data_len)
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        // Validate versions match expected (prevents encoding invalid structures)
+        if self.protocol_version != PROTOCOL_VERSION {
             return Err(CodecError::InvalidVersion {
-                expected: KERNEL_VERSION,
+                expected: PROTOCOL_VERSION,
+                actual: self.protocol_version,
+            });
+        }
+        if self.kernel_version != KERNEL_VERSION_V3 {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V3,
+                actual: self.kernel_version,
+            });
+        }
+
+        let before = out.len();
+
+        put_u32_le(out, self.protocol_version);
+        put_u32_le(out, self.kernel_version);
+        put_bytes32(out, &self.agent_id);
+        put_bytes32(out, &self.agent_code_hash);
+        put_bytes32(out, &self.constraint_set_hash);
+        put_bytes32(out, &self.input_root);
+        put_u64_le(out, self.execution_nonce);
+        put_bytes32(out, &self.prev_journal_hash);
+        put_var_bytes(out, &self.opaque_agent_inputs, MAX_AGENT_INPUT_BYTES)?;
+
+        debug_assert_eq!(
+            out.len() - before,
+            self.encoded_len().unwrap(),
+            "KernelInputV3: encoded_len() / encode_into() mismatch"
+        );
+        Ok(())
+    }
+}
+
+impl CanonicalDecode for KernelInputV3 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        // Minimum size: fixed fields (176) + length prefix (4) = 180 bytes
+        if bytes.len() < 180 {
+            return Err(CodecError::UnexpectedEndOfInput);
+        }
+
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        let kernel_version = get_u32_le(bytes, &mut offset)?;
+        if kernel_version != KERNEL_VERSION_V3 {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V3,
                 actual: kernel_version,
             });
         }
@@ -330,11 +462,12 @@ impl CanonicalDecode for KernelInputV1 {
         let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
         let input_root = get_bytes32(bytes, &mut offset)?;
         let execution_nonce = get_u64_le(bytes, &mut offset)?;
+        let prev_journal_hash = get_bytes32(bytes, &mut offset)?;
         let opaque_agent_inputs = get_var_bytes(bytes, &mut offset, MAX_AGENT_INPUT_BYTES)?;
 
         ensure_no_trailing_bytes(bytes, offset)?;
 
-        Ok(KernelInputV1 {
+        Ok(KernelInputV3 {
             protocol_version,
             kernel_version,
             agent_id,
@@ -342,33 +475,42 @@ impl CanonicalDecode for KernelInputV1 {
             constraint_set_hash,
             input_root,
             execution_nonce,
+            prev_journal_hash,
             opaque_agent_inputs,
         })
     }
 }
 
 // ============================================================================
-// KernelJournalV1 Codec
+// KernelInputV4 Codec
 // ============================================================================
 
-/// KernelJournalV1 encoding layout (little-endian):
+/// KernelInputV4 encoding layout (little-endian):
 /// - protocol_version: u32 (4 bytes)
-/// - kernel_version: u32 (4 bytes)
+/// - kernel_version: u32 (4 bytes, always 4)
 /// - agent_id: [u8; 32] (32 bytes)
 /// - agent_code_hash: [u8; 32] (32 bytes)
 /// - constraint_set_hash: [u8; 32] (32 bytes)
 /// - input_root: [u8; 32] (32 bytes)
 /// - execution_nonce: u64 (8 bytes)
-/// - input_commitment: [u8; 32] (32 bytes)
-/// - action_commitment: [u8; 32] (32 bytes)
-/// - execution_status: u8 (1 byte)
+/// - prev_journal_hash: [u8; 32] (32 bytes)
+/// - valid_until: u64 (8 bytes)
+/// - opaque_agent_inputs_len: u32 (4 bytes)
+/// - opaque_agent_inputs: [u8; len] (variable)
 ///
-/// Total fixed size: 4+4+32+32+32+32+8+32+32+1 = 209 bytes
-const JOURNAL_SIZE: usize = 209;
-
-impl CanonicalEncode for KernelJournalV1 {
+/// Fixed header: 184 bytes + 4 byte length prefix + variable input data
+/// Minimum size with empty input: 188 bytes
+impl CanonicalEncode for KernelInputV4 {
     fn encoded_len(&self) -> Result<usize, CodecError> {
-        Ok(JOURNAL_SIZE)
+        let data_len = self.opaque_agent_inputs.len();
+        if data_len > MAX_AGENT_INPUT_BYTES {
+            return Err(CodecError::InputTooLarge {
+                size: data_len.min(u32::MAX as usize) as u32,
+                limit: MAX_AGENT_INPUT_BYTES,
+            });
+        }
+        // 184 (fixed) + 4 (length prefix) + data_len
+        Ok(188 + data_len)
     }
 
     fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
@@ -379,9 +521,9 @@ impl CanonicalEncode for KernelJournalV1 {
                 actual: self.protocol_version,
             });
         }
-        if self.kernel_version != KERNEL_VERSION {
+        if self.kernel_version != KERNEL_VERSION_V4 {
             return Err(CodecError::InvalidVersion {
-                expected: KERNEL_VERSION,
+                expected: KERNEL_VERSION_V4,
                 actual: self.kernel_version,
             });
         }
@@ -395,29 +537,24 @@ impl CanonicalEncode for KernelJournalV1 {
         put_bytes32(out, &self.constraint_set_hash);
         put_bytes32(out, &self.input_root);
         put_u64_le(out, self.execution_nonce);
-        put_bytes32(out, &self.input_commitment);
-        put_bytes32(out, &self.action_commitment);
-
-        // ExecutionStatus encoding: Success = 0x01, Failure = 0x02
-        // 0x00 is reserved to catch uninitialized memory
-        out.push(match self.execution_status {
-            ExecutionStatus::Success => 0x01,
-            ExecutionStatus::Failure => 0x02,
-        });
+        put_bytes32(out, &self.prev_journal_hash);
+        put_u64_le(out, self.valid_until);
+        put_var_bytes(out, &self.opaque_agent_inputs, MAX_AGENT_INPUT_BYTES)?;
 
         debug_assert_eq!(
             out.len() - before,
-            JOURNAL_SIZE,
-            "KernelJournalV1: encoded_len() / encode_into() mismatch"
+            self.encoded_len().unwrap(),
+            "KernelInputV4: encoded_len() / encode_into() mismatch"
         );
         Ok(())
     }
 }
 
-impl CanonicalDecode for KernelJournalV1 {
+impl CanonicalDecode for KernelInputV4 {
     fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
-        if bytes.len() != JOURNAL_SIZE {
-            return Err(CodecError::InvalidLength);
+        // Minimum size: fixed fields (184) + length prefix (4) = 188 bytes
+        if bytes.len() < 188 {
+            return Err(CodecError::UnexpectedEndOfInput);
         }
 
         let mut offset = 0;
@@ -431,9 +568,9 @@ impl CanonicalDecode for KernelJournalV1 {
         }
 
         let kernel_version = get_u32_le(bytes, &mut offset)?;
-        if kernel_version != KERNEL_VERSION {
+        if kernel_version != KERNEL_VERSION_V4 {
             return Err(CodecError::InvalidVersion {
-                expected: KERNEL_VERSION,
+                expected: KERNEL_VERSION_V4,
                 actual: kernel_version,
             });
         }
@@ -441,33 +578,1355 @@ impl CanonicalDecode for KernelJournalV1 {
         let agent_id = get_bytes32(bytes, &mut offset)?;
         let agent_code_hash = get_bytes32(bytes, &mut offset)?;
         let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
-        let input_root = get_bytes32(bytes, &mut offset)?;
-        let execution_nonce = get_u64_le(bytes, &mut offset)?;
-        let input_commitment = get_bytes32(bytes, &mut offset)?;
-        let action_commitment = get_bytes32(bytes, &mut offset)?;
+        let input_root = get_bytes32(bytes, &mut offset)?;
+        let execution_nonce = get_u64_le(bytes, &mut offset)?;
+        let prev_journal_hash = get_bytes32(bytes, &mut offset)?;
+        let valid_until = get_u64_le(bytes, &mut offset)?;
+        let opaque_agent_inputs = get_var_bytes(bytes, &mut offset, MAX_AGENT_INPUT_BYTES)?;
+
+        ensure_no_trailing_bytes(bytes, offset)?;
+
+        Ok(KernelInputV4 {
+            protocol_version,
+            kernel_version,
+            agent_id,
+            agent_code_hash,
+            constraint_set_hash,
+            input_root,
+            execution_nonce,
+            prev_journal_hash,
+            valid_until,
+            opaque_agent_inputs,
+        })
+    }
+}
+
+// ============================================================================
+// KernelInputV5 Codec
+// ============================================================================
+
+/// Presence flag of an absent state snapshot slot.
+const STATE_SNAPSHOT_ABSENT: u8 = 0x00;
+
+/// Presence flag of a state snapshot slot followed by the snapshot's bytes.
+const STATE_SNAPSHOT_PRESENT: u8 = 0x01;
+
+/// Append a state snapshot slot: the presence flag, then the snapshot if any.
+fn put_state_snapshot(out: &mut Vec<u8>, snapshot: Option<&StateSnapshot>) {
+    match snapshot {
+        Some(snapshot) => {
+            out.push(STATE_SNAPSHOT_PRESENT);
+            out.extend_from_slice(&snapshot.to_bytes());
+        }
+        None => out.push(STATE_SNAPSHOT_ABSENT),
+    }
+}
+
+/// Whether a snapshot's `snapshot_version` matches its layout, so that its
+/// encoding decodes back to it.
+fn state_snapshot_is_canonical(snapshot: &StateSnapshot) -> bool {
+    match snapshot {
+        StateSnapshot::V1(snapshot) => snapshot.snapshot_version == 1,
+        StateSnapshot::V2(snapshot) => snapshot.snapshot_version == 2,
+    }
+}
+
+/// Decode a state snapshot slot.
+///
+/// The snapshot's leading `snapshot_version` selects its size. Fails with
+/// `InvalidStateSnapshot` on an unknown presence flag or snapshot version.
+fn get_state_snapshot(
+    bytes: &[u8],
+    offset: &mut usize,
+) -> Result<Option<StateSnapshot>, CodecError> {
+    match get_u8(bytes, offset)? {
+        STATE_SNAPSHOT_ABSENT => Ok(None),
+        STATE_SNAPSHOT_PRESENT => {
+            let size = match get_u32_le(bytes, &mut offset.clone())? {
+                1 => StateSnapshotV1::ENCODED_SIZE,
+                2 => StateSnapshotV2::ENCODED_SIZE,
+                _ => return Err(CodecError::InvalidStateSnapshot),
+            };
+            let raw = get_slice(bytes, offset, size)?;
+            StateSnapshot::decode(raw)
+                .map(Some)
+                .ok_or(CodecError::InvalidStateSnapshot)
+        }
+        _ => Err(CodecError::InvalidStateSnapshot),
+    }
+}
+
+/// KernelInputV5 encoding layout (little-endian):
+/// - protocol_version: u32 (4 bytes)
+/// - kernel_version: u32 (4 bytes, always 5)
+/// - agent_id: [u8; 32] (32 bytes)
+/// - agent_code_hash: [u8; 32] (32 bytes)
+/// - constraint_set_hash: [u8; 32] (32 bytes)
+/// - input_root: [u8; 32] (32 bytes)
+/// - execution_nonce: u64 (8 bytes)
+/// - prev_journal_hash: [u8; 32] (32 bytes)
+/// - valid_until: u64 (8 bytes)
+/// - state_snapshot_flag: u8 (1 byte, 0x00 = absent, 0x01 = present)
+/// - state_snapshot: StateSnapshotV1 (36 bytes) or StateSnapshotV2
+///   (56 bytes), only if present
+/// - opaque_agent_inputs_len: u32 (4 bytes)
+/// - opaque_agent_inputs: [u8; len] (variable)
+///
+/// Fixed header: 185 bytes (221 or 241 with a snapshot) + 4 byte length
+/// prefix + variable input data
+/// Minimum size with empty input: 189 bytes
+impl CanonicalEncode for KernelInputV5 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        let data_len = self.opaque_agent_inputs.len();
+        if data_len > MAX_AGENT_INPUT_BYTES {
+            return Err(CodecError::InputTooLarge {
+                size: data_len.min(u32::MAX as usize) as u32,
+                limit: MAX_AGENT_INPUT_BYTES,
+            });
+        }
+        let snapshot_len = self
+            .state_snapshot
+            .map_or(0, |snapshot| snapshot.encoded_len());
+        // 185 (fixed) + snapshot + 4 (length prefix) + data_len
+        Ok(189 + snapshot_len + data_len)
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        // Validate versions match expected (prevents encoding invalid structures)
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: self.protocol_version,
+            });
+        }
+        if self.kernel_version != KERNEL_VERSION_V5 {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V5,
+                actual: self.kernel_version,
+            });
+        }
+        // A snapshot that would not decode back is rejected up front
+        if self
+            .state_snapshot
+            .is_some_and(|snapshot| !state_snapshot_is_canonical(&snapshot))
+        {
+            return Err(CodecError::InvalidStateSnapshot);
+        }
+
+        let before = out.len();
+
+        put_u32_le(out, self.protocol_version);
+        put_u32_le(out, self.kernel_version);
+        put_bytes32(out, &self.agent_id);
+        put_bytes32(out, &self.agent_code_hash);
+        put_bytes32(out, &self.constraint_set_hash);
+        put_bytes32(out, &self.input_root);
+        put_u64_le(out, self.execution_nonce);
+        put_bytes32(out, &self.prev_journal_hash);
+        put_u64_le(out, self.valid_until);
+        put_state_snapshot(out, self.state_snapshot.as_ref());
+        put_var_bytes(out, &self.opaque_agent_inputs, MAX_AGENT_INPUT_BYTES)?;
+
+        debug_assert_eq!(
+            out.len() - before,
+            self.encoded_len().unwrap(),
+            "KernelInputV5: encoded_len() / encode_into() mismatch"
+        );
+        Ok(())
+    }
+}
+
+impl CanonicalDecode for KernelInputV5 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        // Minimum size: fixed fields (185) + length prefix (4) = 189 bytes
+        if bytes.len() < 189 {
+            return Err(CodecError::UnexpectedEndOfInput);
+        }
+
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        let kernel_version = get_u32_le(bytes, &mut offset)?;
+        if kernel_version != KERNEL_VERSION_V5 {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V5,
+                actual: kernel_version,
+            });
+        }
+
+        let agent_id = get_bytes32(bytes, &mut offset)?;
+        let agent_code_hash = get_bytes32(bytes, &mut offset)?;
+        let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
+        let input_root = get_bytes32(bytes, &mut offset)?;
+        let execution_nonce = get_u64_le(bytes, &mut offset)?;
+        let prev_journal_hash = get_bytes32(bytes, &mut offset)?;
+        let valid_until = get_u64_le(bytes, &mut offset)?;
+        let state_snapshot = get_state_snapshot(bytes, &mut offset)?;
+        let opaque_agent_inputs = get_var_bytes(bytes, &mut offset, MAX_AGENT_INPUT_BYTES)?;
+
+        ensure_no_trailing_bytes(bytes, offset)?;
+
+        Ok(KernelInputV5 {
+            protocol_version,
+            kernel_version,
+            agent_id,
+            agent_code_hash,
+            constraint_set_hash,
+            input_root,
+            execution_nonce,
+            prev_journal_hash,
+            valid_until,
+            state_snapshot,
+            opaque_agent_inputs,
+        })
+    }
+}
+
+// ============================================================================
+// KernelInput (versioned) Codec
+// ============================================================================
+
+impl CanonicalEncode for KernelInput {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        match self {
+            KernelInput::V1(i) => i.encoded_len(),
+            KernelInput::V3(i) => i.encoded_len(),
+            KernelInput::V4(i) => i.encoded_len(),
+            KernelInput::V5(i) => i.encoded_len(),
+        }
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        match self {
+            KernelInput::V1(i) => i.encode_into(out),
+            KernelInput::V3(i) => i.encode_into(out),
+            KernelInput::V4(i) => i.encode_into(out),
+            KernelInput::V5(i) => i.encode_into(out),
+        }
+    }
+}
+
+/// Decodes an input of any supported kernel version.
+///
+/// Dispatch is on the `kernel_version` field (bytes 4..8); the selected
+/// layout is then checked exactly as by its own decoder. See
+/// [`KernelInputRef::decode`].
+impl CanonicalDecode for KernelInput {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        KernelInputRef::decode(bytes).map(|input| input.to_input())
+    }
+}
+
+// ============================================================================
+// KernelInputRef (borrowed) Decoding
+// ============================================================================
+
+/// Borrowed view of an encoded kernel input of any supported version.
+///
+/// Decoding validates the canonical encoding in place and borrows
+/// `opaque_agent_inputs` from the input bytes instead of copying up to
+/// `MAX_AGENT_INPUT_BYTES` into a `Vec`. The fixed-size fields are copied.
+///
+/// `prev_journal_hash` is present for kernel versions 3 to 5 (the
+/// [`KernelInputV3`], [`KernelInputV4`] and [`KernelInputV5`] layouts),
+/// `valid_until` for kernel versions 4 and 5, and `state_snapshot` only if a
+/// kernel version 5 input fills its snapshot slot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KernelInputRef<'a> {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Kernel version declaring which semantics are being proven
+    pub kernel_version: u32,
+    /// 32-byte agent identifier
+    pub agent_id: [u8; 32],
+    /// SHA-256 hash of the agent binary/code
+    pub agent_code_hash: [u8; 32],
+    /// SHA-256 hash of the constraint set being enforced
+    pub constraint_set_hash: [u8; 32],
+    /// External state root (market/vault snapshot) the agent observes
+    pub input_root: [u8; 32],
+    /// Monotonic nonce for replay protection
+    pub execution_nonce: u64,
+    /// SHA-256 of the previous execution's journal (kernel versions 3 to 5)
+    pub prev_journal_hash: Option<[u8; 32]>,
+    /// Unix timestamp after which the execution is stale (kernel versions 4 and 5)
+    pub valid_until: Option<u64>,
+    /// State snapshot from the typed slot (kernel version 5)
+    pub state_snapshot: Option<StateSnapshot>,
+    /// Opaque agent-specific input data, borrowed from the encoded input
+    pub opaque_agent_inputs: &'a [u8],
+}
+
+impl<'a> KernelInputRef<'a> {
+    /// Decode and validate an encoded input without copying its opaque data.
+    ///
+    /// Accepts exactly the encodings [`KernelInput::decode`] accepts and
+    /// fails with the same errors: dispatch is on `kernel_version`, then the
+    /// selected layout's minimum size, size limit and trailing-bytes rules
+    /// apply.
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, CodecError> {
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        // Minimum size per layout: fixed fields + length prefix
+        let kernel_version = get_u32_le(bytes, &mut offset)?;
+        let min_len = match kernel_version {
+            KERNEL_VERSION | KERNEL_VERSION_V2 => 148,
+            KERNEL_VERSION_V3 => 180,
+            KERNEL_VERSION_V4 => 188,
+            KERNEL_VERSION_V5 => 189,
+            actual => {
+                return Err(CodecError::InvalidVersion {
+                    expected: KERNEL_VERSION_V5,
+                    actual,
+                })
+            }
+        };
+        if bytes.len() < min_len {
+            return Err(CodecError::UnexpectedEndOfInput);
+        }
+
+        let agent_id = get_bytes32(bytes, &mut offset)?;
+        let agent_code_hash = get_bytes32(bytes, &mut offset)?;
+        let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
+        let input_root = get_bytes32(bytes, &mut offset)?;
+        let execution_nonce = get_u64_le(bytes, &mut offset)?;
+        let prev_journal_hash = if kernel_version >= KERNEL_VERSION_V3 {
+            Some(get_bytes32(bytes, &mut offset)?)
+        } else {
+            None
+        };
+        let valid_until = if kernel_version >= KERNEL_VERSION_V4 {
+            Some(get_u64_le(bytes, &mut offset)?)
+        } else {
+            None
+        };
+        let state_snapshot = if kernel_version == KERNEL_VERSION_V5 {
+            get_state_snapshot(bytes, &mut offset)?
+        } else {
+            None
+        };
+        let opaque_agent_inputs = get_var_slice(bytes, &mut offset, MAX_AGENT_INPUT_BYTES)?;
+
+        ensure_no_trailing_bytes(bytes, offset)?;
+
+        Ok(KernelInputRef {
+            protocol_version,
+            kernel_version,
+            agent_id,
+            agent_code_hash,
+            constraint_set_hash,
+            input_root,
+            execution_nonce,
+            prev_journal_hash,
+            valid_until,
+            state_snapshot,
+            opaque_agent_inputs,
+        })
+    }
+
+    /// Decode and validate a `KernelInputV1` encoding without copying its
+    /// opaque data.
+    ///
+    /// Accepts exactly the encodings [`KernelInputV1::decode`] accepts and
+    /// fails with the same errors.
+    pub fn decode_v1(bytes: &'a [u8]) -> Result<Self, CodecError> {
+        // Minimum size: fixed fields (144) + length prefix (4) = 148 bytes
+        if bytes.len() < 148 {
+            return Err(CodecError::UnexpectedEndOfInput);
+        }
+
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        check_input_kernel_version(get_u32_le(bytes, &mut offset)?)?;
+        Self::decode(bytes)
+    }
+
+    /// Copy into the owned input of the matching version.
+    pub fn to_input(&self) -> KernelInput {
+        let opaque_agent_inputs = self.opaque_agent_inputs.to_vec();
+        if let (KERNEL_VERSION_V5, Some(prev_journal_hash), Some(valid_until)) = (
+            self.kernel_version,
+            self.prev_journal_hash,
+            self.valid_until,
+        ) {
+            return KernelInput::V5(KernelInputV5 {
+                protocol_version: self.protocol_version,
+                kernel_version: self.kernel_version,
+                agent_id: self.agent_id,
+                agent_code_hash: self.agent_code_hash,
+                constraint_set_hash: self.constraint_set_hash,
+                input_root: self.input_root,
+                execution_nonce: self.execution_nonce,
+                prev_journal_hash,
+                valid_until,
+                state_snapshot: self.state_snapshot,
+                opaque_agent_inputs,
+            });
+        }
+        match (self.prev_journal_hash, self.valid_until) {
+            (Some(prev_journal_hash), Some(valid_until)) => KernelInput::V4(KernelInputV4 {
+                protocol_version: self.protocol_version,
+                kernel_version: self.kernel_version,
+                agent_id: self.agent_id,
+                agent_code_hash: self.agent_code_hash,
+                constraint_set_hash: self.constraint_set_hash,
+                input_root: self.input_root,
+                execution_nonce: self.execution_nonce,
+                prev_journal_hash,
+                valid_until,
+                opaque_agent_inputs,
+            }),
+            (Some(prev_journal_hash), None) => KernelInput::V3(KernelInputV3 {
+                protocol_version: self.protocol_version,
+                kernel_version: self.kernel_version,
+                agent_id: self.agent_id,
+                agent_code_hash: self.agent_code_hash,
+                constraint_set_hash: self.constraint_set_hash,
+                input_root: self.input_root,
+                execution_nonce: self.execution_nonce,
+                prev_journal_hash,
+                opaque_agent_inputs,
+            }),
+            (None, _) => KernelInput::V1(KernelInputV1 {
+                protocol_version: self.protocol_version,
+                kernel_version: self.kernel_version,
+                agent_id: self.agent_id,
+                agent_code_hash: self.agent_code_hash,
+                constraint_set_hash: self.constraint_set_hash,
+                input_root: self.input_root,
+                execution_nonce: self.execution_nonce,
+                opaque_agent_inputs,
+            }),
+        }
+    }
+}
+
+/// Borrows an owned v1-layout input (e.g. a decoded batch step).
+impl<'a> From<&'a KernelInputV1> for KernelInputRef<'a> {
+    fn from(input: &'a KernelInputV1) -> Self {
+        KernelInputRef {
+            protocol_version: input.protocol_version,
+            kernel_version: input.kernel_version,
+            agent_id: input.agent_id,
+            agent_code_hash: input.agent_code_hash,
+            constraint_set_hash: input.constraint_set_hash,
+            input_root: input.input_root,
+            execution_nonce: input.execution_nonce,
+            prev_journal_hash: None,
+            valid_until: None,
+            state_snapshot: None,
+            opaque_agent_inputs: &input.opaque_agent_inputs,
+        }
+    }
+}
+
+// ============================================================================
+// KernelJournalV1 Codec
+// ============================================================================
+
+/// KernelJournalV1 encoding layout (little-endian):
+/// - protocol_version: u32 (4 bytes)
+/// - kernel_version: u32 (4 bytes)
+/// - agent_id: [u8; 32] (32 bytes)
+/// - agent_code_hash: [u8; 32] (32 bytes)
+/// - constraint_set_hash: [u8; 32] (32 bytes)
+/// - input_root: [u8; 32] (32 bytes)
+/// - execution_nonce: u64 (8 bytes)
+/// - input_commitment: [u8; 32] (32 bytes)
+/// - action_commitment: [u8; 32] (32 bytes)
+/// - execution_status: u8 (1 byte)
+///
+/// Total fixed size: 4+4+32+32+32+32+8+32+32+1 = 209 bytes
+const JOURNAL_SIZE: usize = 209;
+
+impl CanonicalEncode for KernelJournalV1 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        Ok(JOURNAL_SIZE)
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        // Validate versions match expected (prevents encoding invalid structures)
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: self.protocol_version,
+            });
+        }
+        if self.kernel_version != KERNEL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION,
+                actual: self.kernel_version,
+            });
+        }
+
+        // ExecutionStatus encoding: Success = 0x01, Failure = 0x02
+        // 0x00 is reserved to catch uninitialized memory
+        let status_byte = frozen_status_byte(self.execution_status)?;
+
+        let before = out.len();
+
+        put_u32_le(out, self.protocol_version);
+        put_u32_le(out, self.kernel_version);
+        put_bytes32(out, &self.agent_id);
+        put_bytes32(out, &self.agent_code_hash);
+        put_bytes32(out, &self.constraint_set_hash);
+        put_bytes32(out, &self.input_root);
+        put_u64_le(out, self.execution_nonce);
+        put_bytes32(out, &self.input_commitment);
+        put_bytes32(out, &self.action_commitment);
+        out.push(status_byte);
+
+        debug_assert_eq!(
+            out.len() - before,
+            JOURNAL_SIZE,
+            "KernelJournalV1: encoded_len() / encode_into() mismatch"
+        );
+        Ok(())
+    }
+}
+
+impl CanonicalDecode for KernelJournalV1 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.len() != JOURNAL_SIZE {
+            return Err(CodecError::InvalidLength);
+        }
+
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        let kernel_version = get_u32_le(bytes, &mut offset)?;
+        if kernel_version != KERNEL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION,
+                actual: kernel_version,
+            });
+        }
+
+        let agent_id = get_bytes32(bytes, &mut offset)?;
+        let agent_code_hash = get_bytes32(bytes, &mut offset)?;
+        let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
+        let input_root = get_bytes32(bytes, &mut offset)?;
+        let execution_nonce = get_u64_le(bytes, &mut offset)?;
+        let input_commitment = get_bytes32(bytes, &mut offset)?;
+        let action_commitment = get_bytes32(bytes, &mut offset)?;
+
+        // ExecutionStatus decoding: 0x01 = Success, 0x02 = Failure
+        // 0x00 and anything else is invalid
+        let status_byte = get_u8(bytes, &mut offset)?;
+        let execution_status = match status_byte {
+            0x01 => ExecutionStatus::Success,
+            0x02 => ExecutionStatus::Failure,
+            _ => return Err(CodecError::InvalidExecutionStatus(status_byte)),
+        };
+
+        debug_assert_eq!(offset, JOURNAL_SIZE);
+
+        Ok(KernelJournalV1 {
+            protocol_version,
+            kernel_version,
+            agent_id,
+            agent_code_hash,
+            constraint_set_hash,
+            input_root,
+            execution_nonce,
+            input_commitment,
+            action_commitment,
+            execution_status,
+        })
+    }
+}
+
+// ============================================================================
+// KernelJournalV2 Codec
+// ============================================================================
+
+/// KernelJournalV2 encoding layout (little-endian):
+/// - bytes 0..209: KernelJournalV1 layout, with kernel_version = 2
+/// - violation_reason: u8 (1 byte, 0x00 = none, else ConstraintViolationReason code)
+/// - violation_action_index: u32 (4 bytes, 0xFFFFFFFF = none)
+///
+/// Total fixed size: 209+1+4 = 214 bytes
+const JOURNAL_V2_SIZE: usize = 214;
+
+/// Sentinel encoding of an absent `violation_action_index`.
+const NO_ACTION_INDEX: u32 = u32::MAX;
+
+/// Encode the status of a kernel v1-v3 journal.
+///
+/// These layouts are frozen with Success (0x01) and Failure (0x02) only;
+/// `AgentError` (0x03) is committed by kernel v4 and later journals.
+fn frozen_status_byte(status: ExecutionStatus) -> Result<u8, CodecError> {
+    match status {
+        ExecutionStatus::Success => Ok(0x01),
+        ExecutionStatus::Failure => Ok(0x02),
+        ExecutionStatus::AgentError => Err(CodecError::InvalidExecutionStatus(0x03)),
+    }
+}
+
+/// Check the status / violation detail invariants of a v2 journal.
+fn check_violation_details(
+    status: ExecutionStatus,
+    reason: Option<ConstraintViolationReason>,
+    action_index: Option<u32>,
+) -> Result<(), CodecError> {
+    let consistent = match status {
+        ExecutionStatus::Success => reason.is_none() && action_index.is_none(),
+        ExecutionStatus::Failure => reason.is_some(),
+        ExecutionStatus::AgentError => reason.is_none() && action_index.is_none(),
+    };
+    if !consistent || action_index == Some(NO_ACTION_INDEX) {
+        return Err(CodecError::InvalidViolationDetails);
+    }
+    Ok(())
+}
+
+impl CanonicalEncode for KernelJournalV2 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        Ok(JOURNAL_V2_SIZE)
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        // Validate versions match expected (prevents encoding invalid structures)
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: self.protocol_version,
+            });
+        }
+        if self.kernel_version != KERNEL_VERSION_V2 {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V2,
+                actual: self.kernel_version,
+            });
+        }
+        check_violation_details(
+            self.execution_status,
+            self.violation_reason,
+            self.violation_action_index,
+        )?;
+
+        let status_byte = frozen_status_byte(self.execution_status)?;
+
+        let before = out.len();
+
+        put_u32_le(out, self.protocol_version);
+        put_u32_le(out, self.kernel_version);
+        put_bytes32(out, &self.agent_id);
+        put_bytes32(out, &self.agent_code_hash);
+        put_bytes32(out, &self.constraint_set_hash);
+        put_bytes32(out, &self.input_root);
+        put_u64_le(out, self.execution_nonce);
+        put_bytes32(out, &self.input_commitment);
+        put_bytes32(out, &self.action_commitment);
+        out.push(status_byte);
+        out.push(self.violation_reason.map_or(0x00, |r| r.code()));
+        put_u32_le(out, self.violation_action_index.unwrap_or(NO_ACTION_INDEX));
+
+        debug_assert_eq!(
+            out.len() - before,
+            JOURNAL_V2_SIZE,
+            "KernelJournalV2: encoded_len() / encode_into() mismatch"
+        );
+        Ok(())
+    }
+}
+
+impl CanonicalDecode for KernelJournalV2 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.len() != JOURNAL_V2_SIZE {
+            return Err(CodecError::InvalidLength);
+        }
+
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        let kernel_version = get_u32_le(bytes, &mut offset)?;
+        if kernel_version != KERNEL_VERSION_V2 {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V2,
+                actual: kernel_version,
+            });
+        }
+
+        let agent_id = get_bytes32(bytes, &mut offset)?;
+        let agent_code_hash = get_bytes32(bytes, &mut offset)?;
+        let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
+        let input_root = get_bytes32(bytes, &mut offset)?;
+        let execution_nonce = get_u64_le(bytes, &mut offset)?;
+        let input_commitment = get_bytes32(bytes, &mut offset)?;
+        let action_commitment = get_bytes32(bytes, &mut offset)?;
+
+        let status_byte = get_u8(bytes, &mut offset)?;
+        let execution_status = match status_byte {
+            0x01 => ExecutionStatus::Success,
+            0x02 => ExecutionStatus::Failure,
+            _ => return Err(CodecError::InvalidExecutionStatus(status_byte)),
+        };
+
+        let reason_byte = get_u8(bytes, &mut offset)?;
+        let violation_reason = match reason_byte {
+            0x00 => None,
+            code => Some(
+                ConstraintViolationReason::from_code(code)
+                    .ok_or(CodecError::InvalidViolationReason(code))?,
+            ),
+        };
+
+        let index_raw = get_u32_le(bytes, &mut offset)?;
+        let violation_action_index = (index_raw != NO_ACTION_INDEX).then_some(index_raw);
+
+        check_violation_details(execution_status, violation_reason, violation_action_index)?;
+
+        debug_assert_eq!(offset, JOURNAL_V2_SIZE);
+
+        Ok(KernelJournalV2 {
+            protocol_version,
+            kernel_version,
+            agent_id,
+            agent_code_hash,
+            constraint_set_hash,
+            input_root,
+            execution_nonce,
+            input_commitment,
+            action_commitment,
+            execution_status,
+            violation_reason,
+            violation_action_index,
+        })
+    }
+}
+
+// ============================================================================
+// KernelJournalV3 Codec
+// ============================================================================
+
+/// KernelJournalV3 encoding layout (little-endian):
+/// - bytes 0..214: KernelJournalV2 layout, with kernel_version = 3
+/// - prev_journal_hash: [u8; 32] (32 bytes)
+///
+/// Total fixed size: 214+32 = 246 bytes
+const JOURNAL_V3_SIZE: usize = 246;
+
+impl CanonicalEncode for KernelJournalV3 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        Ok(JOURNAL_V3_SIZE)
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        // Validate versions match expected (prevents encoding invalid structures)
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: self.protocol_version,
+            });
+        }
+        if self.kernel_version != KERNEL_VERSION_V3 {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V3,
+                actual: self.kernel_version,
+            });
+        }
+        check_violation_details(
+            self.execution_status,
+            self.violation_reason,
+            self.violation_action_index,
+        )?;
+
+        let status_byte = frozen_status_byte(self.execution_status)?;
+
+        let before = out.len();
+
+        put_u32_le(out, self.protocol_version);
+        put_u32_le(out, self.kernel_version);
+        put_bytes32(out, &self.agent_id);
+        put_bytes32(out, &self.agent_code_hash);
+        put_bytes32(out, &self.constraint_set_hash);
+        put_bytes32(out, &self.input_root);
+        put_u64_le(out, self.execution_nonce);
+        put_bytes32(out, &self.input_commitment);
+        put_bytes32(out, &self.action_commitment);
+        out.push(status_byte);
+        out.push(self.violation_reason.map_or(0x00, |r| r.code()));
+        put_u32_le(out, self.violation_action_index.unwrap_or(NO_ACTION_INDEX));
+        put_bytes32(out, &self.prev_journal_hash);
+
+        debug_assert_eq!(
+            out.len() - before,
+            JOURNAL_V3_SIZE,
+            "KernelJournalV3: encoded_len() / encode_into() mismatch"
+        );
+        Ok(())
+    }
+}
+
+impl CanonicalDecode for KernelJournalV3 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.len() != JOURNAL_V3_SIZE {
+            return Err(CodecError::InvalidLength);
+        }
+
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        let kernel_version = get_u32_le(bytes, &mut offset)?;
+        if kernel_version != KERNEL_VERSION_V3 {
+            return Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V3,
+                actual: kernel_version,
+            });
+        }
+
+        let agent_id = get_bytes32(bytes, &mut offset)?;
+        let agent_code_hash = get_bytes32(bytes, &mut offset)?;
+        let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
+        let input_root = get_bytes32(bytes, &mut offset)?;
+        let execution_nonce = get_u64_le(bytes, &mut offset)?;
+        let input_commitment = get_bytes32(bytes, &mut offset)?;
+        let action_commitment = get_bytes32(bytes, &mut offset)?;
+
+        let status_byte = get_u8(bytes, &mut offset)?;
+        let execution_status = match status_byte {
+            0x01 => ExecutionStatus::Success,
+            0x02 => ExecutionStatus::Failure,
+            _ => return Err(CodecError::InvalidExecutionStatus(status_byte)),
+        };
+
+        let reason_byte = get_u8(bytes, &mut offset)?;
+        let violation_reason = match reason_byte {
+            0x00 => None,
+            code => Some(
+                ConstraintViolationReason::from_code(code)
+                    .ok_or(CodecError::InvalidViolationReason(code))?,
+            ),
+        };
+
+        let index_raw = get_u32_le(bytes, &mut offset)?;
+        let violation_action_index = (index_raw != NO_ACTION_INDEX).then_some(index_raw);
+
+        check_violation_details(execution_status, violation_reason, violation_action_index)?;
+
+        let prev_journal_hash = get_bytes32(bytes, &mut offset)?;
+
+        debug_assert_eq!(offset, JOURNAL_V3_SIZE);
+
+        Ok(KernelJournalV3 {
+            protocol_version,
+            kernel_version,
+            agent_id,
+            agent_code_hash,
+            constraint_set_hash,
+            input_root,
+            execution_nonce,
+            input_commitment,
+            action_commitment,
+            execution_status,
+            violation_reason,
+            violation_action_index,
+            prev_journal_hash,
+        })
+    }
+}
+
+// ============================================================================
+// KernelJournalV4 / KernelJournalV5 Codec
+// ============================================================================
+
+/// Check that a journal declares a kernel version using the v4 layout.
+///
+/// [`KernelJournalV5`] is an alias of KernelJournalV4: kernel versions 4 and
+/// 5 share the layout; v5 inputs differ only in carrying a typed state
+/// snapshot.
+fn check_journal_v4_kernel_version(kernel_version: u32) -> Result<(), CodecError> {
+    if kernel_version != KERNEL_VERSION_V4 && kernel_version != KERNEL_VERSION_V5 {
+        return Err(CodecError::InvalidVersion {
+            expected: KERNEL_VERSION_V4,
+            actual: kernel_version,
+        });
+    }
+    Ok(())
+}
+
+/// KernelJournalV4 encoding layout (little-endian):
+/// - bytes 0..246: KernelJournalV3 layout, with kernel_version = 4 or 5
+/// - valid_until: u64 (8 bytes)
+///
+/// Total fixed size: 246+8 = 254 bytes
+const JOURNAL_V4_SIZE: usize = 254;
+
+impl CanonicalEncode for KernelJournalV4 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        Ok(JOURNAL_V4_SIZE)
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        // Validate versions match expected (prevents encoding invalid structures)
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: self.protocol_version,
+            });
+        }
+        check_journal_v4_kernel_version(self.kernel_version)?;
+        check_violation_details(
+            self.execution_status,
+            self.violation_reason,
+            self.violation_action_index,
+        )?;
+
+        let before = out.len();
+
+        put_u32_le(out, self.protocol_version);
+        put_u32_le(out, self.kernel_version);
+        put_bytes32(out, &self.agent_id);
+        put_bytes32(out, &self.agent_code_hash);
+        put_bytes32(out, &self.constraint_set_hash);
+        put_bytes32(out, &self.input_root);
+        put_u64_le(out, self.execution_nonce);
+        put_bytes32(out, &self.input_commitment);
+        put_bytes32(out, &self.action_commitment);
+        out.push(match self.execution_status {
+            ExecutionStatus::Success => 0x01,
+            ExecutionStatus::Failure => 0x02,
+            ExecutionStatus::AgentError => 0x03,
+        });
+        out.push(self.violation_reason.map_or(0x00, |r| r.code()));
+        put_u32_le(out, self.violation_action_index.unwrap_or(NO_ACTION_INDEX));
+        put_bytes32(out, &self.prev_journal_hash);
+        put_u64_le(out, self.valid_until);
+
+        debug_assert_eq!(
+            out.len() - before,
+            JOURNAL_V4_SIZE,
+            "KernelJournalV4: encoded_len() / encode_into() mismatch"
+        );
+        Ok(())
+    }
+}
+
+impl CanonicalDecode for KernelJournalV4 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.len() != JOURNAL_V4_SIZE {
+            return Err(CodecError::InvalidLength);
+        }
+
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        let kernel_version = get_u32_le(bytes, &mut offset)?;
+        check_journal_v4_kernel_version(kernel_version)?;
+
+        let agent_id = get_bytes32(bytes, &mut offset)?;
+        let agent_code_hash = get_bytes32(bytes, &mut offset)?;
+        let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
+        let input_root = get_bytes32(bytes, &mut offset)?;
+        let execution_nonce = get_u64_le(bytes, &mut offset)?;
+        let input_commitment = get_bytes32(bytes, &mut offset)?;
+        let action_commitment = get_bytes32(bytes, &mut offset)?;
+
+        let status_byte = get_u8(bytes, &mut offset)?;
+        let execution_status = match status_byte {
+            0x01 => ExecutionStatus::Success,
+            0x02 => ExecutionStatus::Failure,
+            0x03 => ExecutionStatus::AgentError,
+            _ => return Err(CodecError::InvalidExecutionStatus(status_byte)),
+        };
+
+        let reason_byte = get_u8(bytes, &mut offset)?;
+        let violation_reason = match reason_byte {
+            0x00 => None,
+            code => Some(
+                ConstraintViolationReason::from_code(code)
+                    .ok_or(CodecError::InvalidViolationReason(code))?,
+            ),
+        };
+
+        let index_raw = get_u32_le(bytes, &mut offset)?;
+        let violation_action_index = (index_raw != NO_ACTION_INDEX).then_some(index_raw);
+
+        check_violation_details(execution_status, violation_reason, violation_action_index)?;
+
+        let prev_journal_hash = get_bytes32(bytes, &mut offset)?;
+        let valid_until = get_u64_le(bytes, &mut offset)?;
+
+        debug_assert_eq!(offset, JOURNAL_V4_SIZE);
+
+        Ok(KernelJournalV4 {
+            protocol_version,
+            kernel_version,
+            agent_id,
+            agent_code_hash,
+            constraint_set_hash,
+            input_root,
+            execution_nonce,
+            input_commitment,
+            action_commitment,
+            execution_status,
+            violation_reason,
+            violation_action_index,
+            prev_journal_hash,
+            valid_until,
+        })
+    }
+}
+
+// ============================================================================
+// KernelJournal (versioned) Codec
+// ============================================================================
+
+impl CanonicalEncode for KernelJournal {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        match self {
+            KernelJournal::V1(j) => j.encoded_len(),
+            KernelJournal::V2(j) => j.encoded_len(),
+            KernelJournal::V3(j) => j.encoded_len(),
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.encoded_len(),
+        }
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        match self {
+            KernelJournal::V1(j) => j.encode_into(out),
+            KernelJournal::V2(j) => j.encode_into(out),
+            KernelJournal::V3(j) => j.encode_into(out),
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.encode_into(out),
+        }
+    }
+}
+
+/// Decodes a journal of any supported kernel version.
+///
+/// Dispatch is on the `kernel_version` field (bytes 4..8); the selected
+/// version's decoder then applies its own strict length and field checks.
+impl CanonicalDecode for KernelJournal {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        match get_u32_le(bytes, &mut offset)? {
+            KERNEL_VERSION => KernelJournalV1::decode(bytes).map(KernelJournal::V1),
+            KERNEL_VERSION_V2 => KernelJournalV2::decode(bytes).map(KernelJournal::V2),
+            KERNEL_VERSION_V3 => KernelJournalV3::decode(bytes).map(KernelJournal::V3),
+            KERNEL_VERSION_V4 => KernelJournalV4::decode(bytes).map(KernelJournal::V4),
+            KERNEL_VERSION_V5 => KernelJournalV5::decode(bytes).map(KernelJournal::V5),
+            actual => Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V5,
+                actual,
+            }),
+        }
+    }
+}
+
+// ============================================================================
+// KernelBatchInputV1 Codec
+// ============================================================================
+
+/// Maximum encoded size of a single batch step (KernelInputV1).
+const MAX_BATCH_STEP_BYTES: usize = 148 + MAX_AGENT_INPUT_BYTES;
+
+/// Check that a batch has between 1 and `MAX_BATCH_STEPS` steps.
+fn check_batch_size(count: usize) -> Result<(), CodecError> {
+    if count == 0 || count > MAX_BATCH_STEPS {
+        return Err(CodecError::InvalidBatchSize {
+            count: count.min(u32::MAX as usize) as u32,
+            limit: MAX_BATCH_STEPS,
+        });
+    }
+    Ok(())
+}
+
+/// KernelBatchInputV1 encoding layout (little-endian):
+/// - protocol_version: u32 (4 bytes)
+/// - step_count: u32 (4 bytes, 1..=MAX_BATCH_STEPS)
+/// - For each step:
+///   - step_len: u32 (4 bytes)
+///   - step_bytes: KernelInputV1 encoding (step_len bytes)
+impl CanonicalEncode for KernelBatchInputV1 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        check_batch_size(self.steps.len())?;
+        let mut total: usize = 8;
+        for step in &self.steps {
+            total = total
+                .checked_add(4 + step.encoded_len()?)
+                .ok_or(CodecError::ArithmeticOverflow)?;
+        }
+        Ok(total)
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: self.protocol_version,
+            });
+        }
+        check_batch_size(self.steps.len())?;
+
+        let before = out.len();
+
+        put_u32_le(out, self.protocol_version);
+        put_u32_le(out, self.steps.len() as u32);
+        for step in &self.steps {
+            put_u32_le(out, step.encoded_len()? as u32);
+            step.encode_into(out)?;
+        }
+
+        debug_assert_eq!(
+            out.len() - before,
+            self.encoded_len().unwrap(),
+            "KernelBatchInputV1: encoded_len() / encode_into() mismatch"
+        );
+        Ok(())
+    }
+}
+
+impl CanonicalDecode for KernelBatchInputV1 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        KernelBatchInputRef::decode(bytes).map(|batch| batch.to_batch())
+    }
+}
+
+/// Borrowed view of an encoded [`KernelBatchInputV1`].
+///
+/// Decoding validates the batch exactly as [`KernelBatchInputV1::decode`]
+/// does, but decodes every step in place: each step keeps its canonical
+/// encoding and its opaque data borrowed from the batch bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KernelBatchInputRef<'a> {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Steps in execution order
+    pub steps: Vec<KernelBatchStepRef<'a>>,
+}
+
+/// A single step of a [`KernelBatchInputRef`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KernelBatchStepRef<'a> {
+    /// Canonical `KernelInputV1` encoding of the step
+    pub bytes: &'a [u8],
+    /// The step decoded from `bytes`
+    pub input: KernelInputRef<'a>,
+}
+
+impl<'a> KernelBatchInputRef<'a> {
+    /// Decode and validate an encoded batch without copying its steps.
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, CodecError> {
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        let step_count = get_u32_le(bytes, &mut offset)? as usize;
+        check_batch_size(step_count)?;
+
+        let mut steps = Vec::with_capacity(step_count);
+        for _ in 0..step_count {
+            let step_len = get_u32_le(bytes, &mut offset)?;
+            if step_len as usize > MAX_BATCH_STEP_BYTES {
+                return Err(CodecError::InputTooLarge {
+                    size: step_len,
+                    limit: MAX_BATCH_STEP_BYTES,
+                });
+            }
+            let step_bytes = get_slice(bytes, &mut offset, step_len as usize)?;
+            steps.push(KernelBatchStepRef {
+                bytes: step_bytes,
+                input: KernelInputRef::decode_v1(step_bytes)?,
+            });
+        }
+
+        ensure_no_trailing_bytes(bytes, offset)?;
+
+        Ok(KernelBatchInputRef {
+            protocol_version,
+            steps,
+        })
+    }
+
+    /// Copy into an owned batch.
+    pub fn to_batch(&self) -> KernelBatchInputV1 {
+        KernelBatchInputV1 {
+            protocol_version: self.protocol_version,
+            steps: self
+                .steps
+                .iter()
+                .map(|step| KernelInputV1 {
+                    protocol_version: step.input.protocol_version,
+                    kernel_version: step.input.kernel_version,
+                    agent_id: step.input.agent_id,
+                    agent_code_hash: step.input.agent_code_hash,
+                    constraint_set_hash: step.input.constraint_set_hash,
+                    input_root: step.input.input_root,
+                    execution_nonce: step.input.execution_nonce,
+                    opaque_agent_inputs: step.input.opaque_agent_inputs.to_vec(),
+                })
+                .collect(),
+        }
+    }
+}
+
+// ============================================================================
+// KernelBatchJournalV1 Codec
+// ============================================================================
+
+/// KernelBatchJournalV1 encoding layout (little-endian):
+/// - protocol_version: u32 (4 bytes)
+/// - kernel_version: u32 (4 bytes)
+/// - agent_id: [u8; 32] (32 bytes)
+/// - agent_code_hash: [u8; 32] (32 bytes)
+/// - constraint_set_hash: [u8; 32] (32 bytes)
+/// - first_execution_nonce: u64 (8 bytes)
+/// - step_count: u32 (4 bytes, 1..=MAX_BATCH_STEPS)
+/// - step_journal_hashes: [u8; 32] * step_count
+///
+/// Total size: 116 + 32 * step_count bytes
+const BATCH_JOURNAL_HEADER_SIZE: usize = 116;
+
+impl CanonicalEncode for KernelBatchJournalV1 {
+    fn encoded_len(&self) -> Result<usize, CodecError> {
+        check_batch_size(self.step_journal_hashes.len())?;
+        Ok(BATCH_JOURNAL_HEADER_SIZE + 32 * self.step_journal_hashes.len())
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), CodecError> {
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: self.protocol_version,
+            });
+        }
+        check_input_kernel_version(self.kernel_version)?;
+        check_batch_size(self.step_journal_hashes.len())?;
+
+        let before = out.len();
+
+        put_u32_le(out, self.protocol_version);
+        put_u32_le(out, self.kernel_version);
+        put_bytes32(out, &self.agent_id);
+        put_bytes32(out, &self.agent_code_hash);
+        put_bytes32(out, &self.constraint_set_hash);
+        put_u64_le(out, self.first_execution_nonce);
+        put_u32_le(out, self.step_journal_hashes.len() as u32);
+        for hash in &self.step_journal_hashes {
+            put_bytes32(out, hash);
+        }
+
+        debug_assert_eq!(
+            out.len() - before,
+            self.encoded_len().unwrap(),
+            "KernelBatchJournalV1: encoded_len() / encode_into() mismatch"
+        );
+        Ok(())
+    }
+}
+
+impl CanonicalDecode for KernelBatchJournalV1 {
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.len() < BATCH_JOURNAL_HEADER_SIZE {
+            return Err(CodecError::UnexpectedEndOfInput);
+        }
+
+        let mut offset = 0;
+
+        let protocol_version = get_u32_le(bytes, &mut offset)?;
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(CodecError::InvalidVersion {
+                expected: PROTOCOL_VERSION,
+                actual: protocol_version,
+            });
+        }
+
+        let kernel_version = get_u32_le(bytes, &mut offset)?;
+        check_input_kernel_version(kernel_version)?;
+
+        let agent_id = get_bytes32(bytes, &mut offset)?;
+        let agent_code_hash = get_bytes32(bytes, &mut offset)?;
+        let constraint_set_hash = get_bytes32(bytes, &mut offset)?;
+        let first_execution_nonce = get_u64_le(bytes, &mut offset)?;
 
-        // ExecutionStatus decoding: 0x01 = Success, 0x02 = Failure
-        // 0x00 and anything else is invalid
-        let status_byte = get_u8(bytes, &mut offset)?;
-        let execution_status = match status_byte {
-            0x01 => ExecutionStatus::Success,
-            0x02 => ExecutionStatus::Failure,
-            _ => return Err(CodecError::InvalidExecutionStatus(status_byte)),
-        };
+        let step_count = get_u32_le(bytes, &mut offset)? as usize;
+        check_batch_size(step_count)?;
+        if bytes.len() != BATCH_JOURNAL_HEADER_SIZE + 32 * step_count {
+            return Err(CodecError::InvalidLength);
+        }
 
-        debug_assert_eq!(offset, JOURNAL_SIZE);
+        let mut step_journal_hashes = Vec::with_capacity(step_count);
+        for _ in 0..step_count {
+            step_journal_hashes.push(get_bytes32(bytes, &mut offset)?);
+        }
 
-        Ok(KernelJournalV1 {
+        debug_assert_eq!(offset, bytes.len());
+
+        Ok(KernelBatchJournalV1 {
             protocol_version,
             kernel_version,
             agent_id,
             agent_code_hash,
             constraint_set_hash,
-            input_root,
-            execution_nonce,
-            input_commitment,
-            action_commitment,
-            execution_status,
+            first_execution_nonce,
+            step_journal_hashes,
         })
     }
 }
@@ -526,6 +1985,28 @@ impl CanonicalEncode for ActionV1 {
 
 impl CanonicalDecode for ActionV1 {
     fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        ActionRef::decode(bytes).map(|action| action.to_action())
+    }
+}
+
+/// Borrowed view of an encoded [`ActionV1`].
+///
+/// Validates the encoding in place and borrows `payload` from it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActionRef<'a> {
+    /// Action type identifier
+    pub action_type: u32,
+    /// Target address or identifier (32 bytes)
+    pub target: [u8; 32],
+    /// Action payload, borrowed from the encoded action
+    pub payload: &'a [u8],
+}
+
+impl<'a> ActionRef<'a> {
+    /// Decode and validate an encoded action without copying its payload.
+    ///
+    /// Applies the same checks as [`ActionV1::decode`].
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, CodecError> {
         // Minimum: action_type (4) + target (32) + payload_len (4) = 40 bytes
         if bytes.len() < 40 {
             return Err(CodecError::UnexpectedEndOfInput);
@@ -553,17 +2034,26 @@ impl CanonicalDecode for ActionV1 {
             return Err(CodecError::UnexpectedEndOfInput);
         }
 
-        let payload = bytes[offset..end].to_vec();
+        let payload = &bytes[offset..end];
         offset = end;
 
         ensure_no_trailing_bytes(bytes, offset)?;
 
-        Ok(ActionV1 {
+        Ok(ActionRef {
             action_type,
             target,
             payload,
         })
     }
+
+    /// Copy into an owned [`ActionV1`].
+    pub fn to_action(&self) -> ActionV1 {
+        ActionV1 {
+            action_type: self.action_type,
+            target: self.target,
+            payload: self.payload.to_vec(),
+        }
+    }
 }
 
 // ============================================================================
@@ -663,6 +2153,24 @@ impl CanonicalEncode for AgentOutput {
 
 impl CanonicalDecode for AgentOutput {
     fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        AgentOutputRef::decode(bytes).map(|output| output.to_output())
+    }
+}
+
+/// Borrowed view of an encoded [`AgentOutput`].
+///
+/// Actions are held as [`ActionRef`]s in wire order; no payload is copied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AgentOutputRef<'a> {
+    /// Ordered list of borrowed actions
+    pub actions: Vec<ActionRef<'a>>,
+}
+
+impl<'a> AgentOutputRef<'a> {
+    /// Decode and validate an encoded output without copying action payloads.
+    ///
+    /// Applies the same checks as [`AgentOutput::decode`].
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, CodecError> {
         // Enforce total size limit on input
         if bytes.len() > MAX_AGENT_OUTPUT_BYTES {
             return Err(CodecError::OutputTooLarge {
@@ -709,14 +2217,21 @@ impl CanonicalDecode for AgentOutput {
                 return Err(CodecError::UnexpectedEndOfInput);
             }
 
-            let action = ActionV1::decode(&bytes[offset..action_end])?;
+            let action = ActionRef::decode(&bytes[offset..action_end])?;
             actions.push(action);
             offset = action_end;
         }
 
         ensure_no_trailing_bytes(bytes, offset)?;
 
-        Ok(AgentOutput { actions })
+        Ok(AgentOutputRef { actions })
+    }
+
+    /// Copy into an owned [`AgentOutput`].
+    pub fn to_output(&self) -> AgentOutput {
+        AgentOutput {
+            actions: self.actions.iter().map(ActionRef::to_action).collect(),
+        }
     }
 }
 
@@ -864,4 +2379,702 @@ mod tests {
         assert_eq!(decoded.actions[0].action_type, 0x02); // first stays first
         assert_eq!(decoded.actions[1].action_type, 0x01); // second stays second
     }
+
+    fn sample_journal_v2() -> KernelJournalV2 {
+        KernelJournalV2 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V2,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            input_root: [0xCC; 32],
+            execution_nonce: 7,
+            input_commitment: [0x11; 32],
+            action_commitment: [0x22; 32],
+            execution_status: ExecutionStatus::Failure,
+            violation_reason: Some(ConstraintViolationReason::InvalidActionPayload),
+            violation_action_index: Some(3),
+        }
+    }
+
+    #[test]
+    fn test_journal_v2_roundtrip() {
+        let journal = sample_journal_v2();
+        let encoded = journal.encode().unwrap();
+        assert_eq!(encoded.len(), JOURNAL_V2_SIZE);
+        assert_eq!(encoded[209], 0x0A);
+        assert_eq!(&encoded[210..214], &3u32.to_le_bytes());
+        assert_eq!(KernelJournalV2::decode(&encoded).unwrap(), journal);
+
+        let global = KernelJournalV2 {
+            violation_reason: Some(ConstraintViolationReason::CooldownNotElapsed),
+            violation_action_index: None,
+            ..journal
+        };
+        let encoded = global.encode().unwrap();
+        assert_eq!(&encoded[210..214], &[0xFF; 4]);
+        assert_eq!(KernelJournalV2::decode(&encoded).unwrap(), global);
+    }
+
+    #[test]
+    fn test_journal_v2_rejects_inconsistent_details() {
+        let success_with_reason = KernelJournalV2 {
+            execution_status: ExecutionStatus::Success,
+            ..sample_journal_v2()
+        };
+        assert_eq!(
+            success_with_reason.encode(),
+            Err(CodecError::InvalidViolationDetails)
+        );
+
+        let failure_without_reason = KernelJournalV2 {
+            violation_reason: None,
+            violation_action_index: None,
+            ..sample_journal_v2()
+        };
+        assert_eq!(
+            failure_without_reason.encode(),
+            Err(CodecError::InvalidViolationDetails)
+        );
+
+        // Index without reason is rejected on decode as well
+        let mut encoded = sample_journal_v2().encode().unwrap();
+        encoded[208] = 0x01;
+        encoded[209] = 0x00;
+        assert_eq!(
+            KernelJournalV2::decode(&encoded),
+            Err(CodecError::InvalidViolationDetails)
+        );
+    }
+
+    #[test]
+    fn test_journal_v2_rejects_agent_error() {
+        // The v1-v3 layouts are frozen at Success and Failure
+        let journal = KernelJournalV2 {
+            execution_status: ExecutionStatus::AgentError,
+            violation_reason: None,
+            violation_action_index: None,
+            ..sample_journal_v2()
+        };
+        assert_eq!(
+            journal.encode(),
+            Err(CodecError::InvalidExecutionStatus(0x03))
+        );
+
+        let mut encoded = sample_journal_v2().encode().unwrap();
+        encoded[208] = 0x03;
+        encoded[209] = 0x00;
+        encoded[210..214].copy_from_slice(&NO_ACTION_INDEX.to_le_bytes());
+        assert_eq!(
+            KernelJournalV2::decode(&encoded),
+            Err(CodecError::InvalidExecutionStatus(0x03))
+        );
+    }
+
+    #[test]
+    fn test_journal_v2_rejects_unknown_reason_code() {
+        let mut encoded = sample_journal_v2().encode().unwrap();
+        encoded[209] = 0xEE;
+        assert_eq!(
+            KernelJournalV2::decode(&encoded),
+            Err(CodecError::InvalidViolationReason(0xEE))
+        );
+    }
+
+    #[test]
+    fn test_kernel_journal_dispatches_on_kernel_version() {
+        let v2 = sample_journal_v2();
+        let v1 = KernelJournalV1 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION,
+            agent_id: v2.agent_id,
+            agent_code_hash: v2.agent_code_hash,
+            constraint_set_hash: v2.constraint_set_hash,
+            input_root: v2.input_root,
+            execution_nonce: v2.execution_nonce,
+            input_commitment: v2.input_commitment,
+            action_commitment: v2.action_commitment,
+            execution_status: ExecutionStatus::Failure,
+        };
+
+        let decoded = KernelJournal::decode(&v1.encode().unwrap()).unwrap();
+        assert_eq!(decoded, KernelJournal::V1(v1.clone()));
+        assert_eq!(decoded.violation_reason(), None);
+
+        let decoded = KernelJournal::decode(&v2.encode().unwrap()).unwrap();
+        assert_eq!(decoded.kernel_version(), KERNEL_VERSION_V2);
+        assert_eq!(
+            decoded.violation_reason(),
+            Some(ConstraintViolationReason::InvalidActionPayload)
+        );
+        assert_eq!(decoded.violation_action_index(), Some(3));
+        assert_eq!(decoded.encode().unwrap(), v2.encode().unwrap());
+
+        // A v1-sized journal claiming kernel_version 2 is rejected by length
+        let mut mislabeled = v1.encode().unwrap();
+        mislabeled[4..8].copy_from_slice(&KERNEL_VERSION_V2.to_le_bytes());
+        assert_eq!(
+            KernelJournal::decode(&mislabeled),
+            Err(CodecError::InvalidLength)
+        );
+
+        let mut unknown = v1.encode().unwrap();
+        unknown[4..8].copy_from_slice(&99u32.to_le_bytes());
+        assert_eq!(
+            KernelJournal::decode(&unknown),
+            Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V5,
+                actual: 99
+            })
+        );
+    }
+
+    #[test]
+    fn test_input_v3_roundtrip_and_dispatch() {
+        let input = KernelInputV3 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V3,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            input_root: [0xCC; 32],
+            execution_nonce: 7,
+            prev_journal_hash: [0xDD; 32],
+            opaque_agent_inputs: vec![1, 2, 3],
+        };
+        let encoded = input.encode().unwrap();
+        assert_eq!(encoded.len(), 183);
+        assert_eq!(&encoded[144..176], &[0xDD; 32]);
+        assert_eq!(KernelInputV3::decode(&encoded).unwrap(), input);
+        assert_eq!(
+            KernelInput::decode(&encoded).unwrap(),
+            KernelInput::V3(input.clone())
+        );
+
+        // Version 1 and 2 inputs still decode through the v1 layout
+        let v1 = KernelInputV1 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V2,
+            agent_id: input.agent_id,
+            agent_code_hash: input.agent_code_hash,
+            constraint_set_hash: input.constraint_set_hash,
+            input_root: input.input_root,
+            execution_nonce: input.execution_nonce,
+            opaque_agent_inputs: input.opaque_agent_inputs.clone(),
+        };
+        assert_eq!(
+            KernelInput::decode(&v1.encode().unwrap()).unwrap(),
+            KernelInput::V1(v1)
+        );
+
+        let wrong_version = KernelInputV3 {
+            kernel_version: KERNEL_VERSION_V2,
+            ..input
+        };
+        assert_eq!(
+            wrong_version.encode(),
+            Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V3,
+                actual: KERNEL_VERSION_V2
+            })
+        );
+    }
+
+    #[test]
+    fn test_journal_v3_roundtrip_and_dispatch() {
+        let v2 = sample_journal_v2();
+        let journal = KernelJournalV3 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V3,
+            agent_id: v2.agent_id,
+            agent_code_hash: v2.agent_code_hash,
+            constraint_set_hash: v2.constraint_set_hash,
+            input_root: v2.input_root,
+            execution_nonce: v2.execution_nonce,
+            input_commitment: v2.input_commitment,
+            action_commitment: v2.action_commitment,
+            execution_status: v2.execution_status,
+            violation_reason: v2.violation_reason,
+            violation_action_index: v2.violation_action_index,
+            prev_journal_hash: [0xDD; 32],
+        };
+        let encoded = journal.encode().unwrap();
+        assert_eq!(encoded.len(), JOURNAL_V3_SIZE);
+        assert_eq!(&encoded[8..214], &v2.encode().unwrap()[8..]);
+        assert_eq!(&encoded[214..], &[0xDD; 32]);
+        assert_eq!(KernelJournalV3::decode(&encoded).unwrap(), journal);
+
+        let decoded = KernelJournal::decode(&encoded).unwrap();
+        assert_eq!(decoded.prev_journal_hash(), Some([0xDD; 32]));
+        assert_eq!(decoded.violation_action_index(), Some(3));
+        assert_eq!(
+            KernelJournal::decode(&v2.encode().unwrap())
+                .unwrap()
+                .prev_journal_hash(),
+            None
+        );
+
+        assert_eq!(
+            KernelJournalV3::decode(&encoded[..JOURNAL_V2_SIZE]),
+            Err(CodecError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_input_v4_roundtrip_and_dispatch() {
+        let input = KernelInputV4 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V4,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            input_root: [0xCC; 32],
+            execution_nonce: 7,
+            prev_journal_hash: [0xDD; 32],
+            valid_until: 1_700_000_000,
+            opaque_agent_inputs: vec![1, 2, 3],
+        };
+        let encoded = input.encode().unwrap();
+        assert_eq!(encoded.len(), 191);
+        assert_eq!(&encoded[176..184], &1_700_000_000u64.to_le_bytes());
+        assert_eq!(KernelInputV4::decode(&encoded).unwrap(), input);
+        assert_eq!(
+            KernelInput::decode(&encoded).unwrap(),
+            KernelInput::V4(input.clone())
+        );
+
+        // A v3-labelled input must use the chained layout without valid_until
+        let mut mislabeled = encoded.clone();
+        mislabeled[4..8].copy_from_slice(&KERNEL_VERSION_V3.to_le_bytes());
+        assert!(KernelInput::decode(&mislabeled).is_err());
+    }
+
+    #[test]
+    fn test_journal_v4_roundtrip_and_dispatch() {
+        let v2 = sample_journal_v2();
+        let journal = KernelJournalV4 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V4,
+            agent_id: v2.agent_id,
+            agent_code_hash: v2.agent_code_hash,
+            constraint_set_hash: v2.constraint_set_hash,
+            input_root: v2.input_root,
+            execution_nonce: v2.execution_nonce,
+            input_commitment: v2.input_commitment,
+            action_commitment: v2.action_commitment,
+            execution_status: v2.execution_status,
+            violation_reason: Some(ConstraintViolationReason::InputExpired),
+            violation_action_index: None,
+            prev_journal_hash: [0xDD; 32],
+            valid_until: 1_700_000_000,
+        };
+        let encoded = journal.encode().unwrap();
+        assert_eq!(encoded.len(), JOURNAL_V4_SIZE);
+        assert_eq!(&encoded[214..246], &[0xDD; 32]);
+        assert_eq!(&encoded[246..], &1_700_000_000u64.to_le_bytes());
+        assert_eq!(KernelJournalV4::decode(&encoded).unwrap(), journal);
+
+        let decoded = KernelJournal::decode(&encoded).unwrap();
+        assert_eq!(decoded.valid_until(), Some(1_700_000_000));
+        assert_eq!(decoded.prev_journal_hash(), Some([0xDD; 32]));
+        assert_eq!(
+            decoded.violation_reason(),
+            Some(ConstraintViolationReason::InputExpired)
+        );
+        assert_eq!(
+            KernelJournal::decode(&v2.encode().unwrap())
+                .unwrap()
+                .valid_until(),
+            None
+        );
+
+        assert_eq!(
+            KernelJournalV4::decode(&encoded[..JOURNAL_V3_SIZE]),
+            Err(CodecError::InvalidLength)
+        );
+
+        // Kernel v4 journals are the first to commit AgentError
+        let agent_error = KernelJournalV4 {
+            execution_status: ExecutionStatus::AgentError,
+            violation_reason: None,
+            ..journal
+        };
+        let encoded = agent_error.encode().unwrap();
+        assert_eq!(encoded[208], 0x03);
+        assert_eq!(KernelJournalV4::decode(&encoded).unwrap(), agent_error);
+
+        // An agent error never carries violation details
+        let with_reason = KernelJournalV4 {
+            violation_reason: Some(ConstraintViolationReason::InputExpired),
+            ..agent_error
+        };
+        assert_eq!(
+            with_reason.encode(),
+            Err(CodecError::InvalidViolationDetails)
+        );
+    }
+
+    #[test]
+    fn test_input_v5_roundtrip_and_dispatch() {
+        let snapshot = StateSnapshotV1 {
+            snapshot_version: 1,
+            last_execution_ts: 1_000,
+            current_ts: 2_000,
+            current_equity: 90_000,
+            peak_equity: 100_000,
+        };
+        let input = KernelInputV5 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V5,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            input_root: [0xCC; 32],
+            execution_nonce: 7,
+            prev_journal_hash: [0xDD; 32],
+            valid_until: 1_700_000_000,
+            state_snapshot: Some(snapshot.into()),
+            opaque_agent_inputs: vec![1, 2, 3],
+        };
+        let encoded = input.encode().unwrap();
+        assert_eq!(encoded.len(), 228);
+        assert_eq!(encoded[184], 0x01);
+        assert_eq!(&encoded[185..221], &snapshot.to_bytes());
+        assert_eq!(KernelInputV5::decode(&encoded).unwrap(), input);
+        assert_eq!(
+            KernelInput::decode(&encoded).unwrap(),
+            KernelInput::V5(input.clone())
+        );
+
+        let view = KernelInputRef::decode(&encoded).unwrap();
+        assert_eq!(view.state_snapshot, Some(StateSnapshot::V1(snapshot)));
+        assert_eq!(view.valid_until, Some(1_700_000_000));
+        assert!(core::ptr::eq(view.opaque_agent_inputs, &encoded[225..]));
+        assert_eq!(view.to_input(), KernelInput::V5(input.clone()));
+
+        // An empty slot is a single flag byte; opaque inputs are never sniffed
+        let without = KernelInputV5 {
+            state_snapshot: None,
+            opaque_agent_inputs: snapshot.to_bytes().to_vec(),
+            ..input.clone()
+        };
+        let encoded_without = without.encode().unwrap();
+        assert_eq!(encoded_without.len(), 189 + 36);
+        let view = KernelInputRef::decode(&encoded_without).unwrap();
+        assert_eq!(view.state_snapshot, None);
+        assert_eq!(view.to_input(), KernelInput::V5(without));
+
+        let mut bad_flag = encoded.clone();
+        bad_flag[184] = 0x02;
+        assert_eq!(
+            KernelInput::decode(&bad_flag),
+            Err(CodecError::InvalidStateSnapshot)
+        );
+        let mut bad_snapshot = encoded.clone();
+        bad_snapshot[185..189].copy_from_slice(&3u32.to_le_bytes());
+        assert_eq!(
+            KernelInputV5::decode(&bad_snapshot),
+            Err(CodecError::InvalidStateSnapshot)
+        );
+        let unencodable = KernelInputV5 {
+            state_snapshot: Some(StateSnapshot::V1(StateSnapshotV1 {
+                snapshot_version: 2,
+                ..snapshot
+            })),
+            ..input.clone()
+        };
+        assert_eq!(unencodable.encode(), Err(CodecError::InvalidStateSnapshot));
+
+        // A v4-labelled input must not carry the snapshot slot
+        let mut mislabeled = encoded.clone();
+        mislabeled[4..8].copy_from_slice(&KERNEL_VERSION_V4.to_le_bytes());
+        assert!(KernelInput::decode(&mislabeled).is_err());
+    }
+
+    #[test]
+    fn test_input_v5_carries_windowed_snapshot() {
+        let snapshot = StateSnapshot::V2(StateSnapshotV2 {
+            snapshot_version: 2,
+            last_execution_ts: 1_000,
+            current_ts: 2_000,
+            current_equity: 90_000,
+            peak_equity: 100_000,
+            window_start_ts: 500,
+            window_executions: 4,
+            window_notional: 12_345,
+        });
+        let input = KernelInputV5 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V5,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            input_root: [0xCC; 32],
+            execution_nonce: 7,
+            prev_journal_hash: [0xDD; 32],
+            valid_until: u64::MAX,
+            state_snapshot: Some(snapshot),
+            opaque_agent_inputs: vec![1, 2, 3],
+        };
+        let encoded = input.encode().unwrap();
+        assert_eq!(encoded.len(), 189 + 56 + 3);
+        assert_eq!(&encoded[185..241], &snapshot.to_bytes()[..]);
+        assert_eq!(KernelInputV5::decode(&encoded).unwrap(), input);
+        assert_eq!(
+            KernelInputRef::decode(&encoded).unwrap().state_snapshot,
+            Some(snapshot)
+        );
+    }
+
+    #[test]
+    fn test_journal_v5_roundtrip_and_dispatch() {
+        let journal = KernelJournalV5 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V5,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            input_root: [0xCC; 32],
+            execution_nonce: 7,
+            input_commitment: [0x11; 32],
+            action_commitment: [0x22; 32],
+            execution_status: ExecutionStatus::Success,
+            violation_reason: None,
+            violation_action_index: None,
+            prev_journal_hash: [0xDD; 32],
+            valid_until: u64::MAX,
+        };
+        let encoded = journal.encode().unwrap();
+        assert_eq!(encoded.len(), JOURNAL_V4_SIZE);
+        assert_eq!(
+            KernelJournal::decode(&encoded).unwrap(),
+            KernelJournal::V5(journal.clone())
+        );
+
+        let mismatched = KernelJournalV5 {
+            kernel_version: KERNEL_VERSION_V3,
+            ..journal
+        };
+        assert_eq!(
+            mismatched.encode(),
+            Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION_V4,
+                actual: KERNEL_VERSION_V3
+            })
+        );
+    }
+
+    #[test]
+    fn test_kernel_input_ref_borrows_opaque_inputs() {
+        let v1 = KernelInputV1 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            input_root: [0xCC; 32],
+            execution_nonce: 7,
+            opaque_agent_inputs: vec![1, 2, 3],
+        };
+        let v3 = KernelInputV4 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V4,
+            agent_id: v1.agent_id,
+            agent_code_hash: v1.agent_code_hash,
+            constraint_set_hash: v1.constraint_set_hash,
+            input_root: v1.input_root,
+            execution_nonce: v1.execution_nonce,
+            prev_journal_hash: [0xDD; 32],
+            valid_until: 1_700_000_000,
+            opaque_agent_inputs: v1.opaque_agent_inputs.clone(),
+        };
+
+        let encoded = v1.encode().unwrap();
+        let view = KernelInputRef::decode(&encoded).unwrap();
+        assert_eq!(view, KernelInputRef::from(&v1));
+        assert_eq!(view.to_input(), KernelInput::V1(v1.clone()));
+        // Opaque inputs point into the encoded bytes
+        assert!(core::ptr::eq(view.opaque_agent_inputs, &encoded[148..]));
+
+        let encoded = v3.encode().unwrap();
+        let view = KernelInputRef::decode(&encoded).unwrap();
+        assert_eq!(view.prev_journal_hash, Some([0xDD; 32]));
+        assert_eq!(view.valid_until, Some(1_700_000_000));
+        assert_eq!(view.to_input(), KernelInput::V4(v3));
+        assert!(core::ptr::eq(view.opaque_agent_inputs, &encoded[188..]));
+    }
+
+    #[test]
+    fn test_kernel_input_ref_rejects_like_owned_decoders() {
+        let v1 = KernelInputV1 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V2,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            input_root: [0xCC; 32],
+            execution_nonce: 7,
+            opaque_agent_inputs: vec![1, 2, 3],
+        };
+        let encoded = v1.encode().unwrap();
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        let mut oversized = encoded.clone();
+        oversized[144..148].copy_from_slice(&(MAX_AGENT_INPUT_BYTES as u32 + 1).to_le_bytes());
+        let mut unknown = encoded.clone();
+        unknown[4..8].copy_from_slice(&99u32.to_le_bytes());
+        let mut chained = encoded.clone();
+        chained[4..8].copy_from_slice(&KERNEL_VERSION_V3.to_le_bytes());
+
+        for bytes in [
+            &encoded[..147],
+            &encoded[..150],
+            &trailing[..],
+            &oversized[..],
+            &unknown[..],
+            &chained[..],
+        ] {
+            let err = KernelInputRef::decode(bytes).unwrap_err();
+            assert_eq!(KernelInput::decode(bytes).unwrap_err(), err);
+            if bytes[4..8] == KERNEL_VERSION_V2.to_le_bytes() {
+                assert_eq!(KernelInputV1::decode(bytes).unwrap_err(), err);
+            }
+        }
+    }
+
+    #[test]
+    fn test_agent_output_ref_borrows_payloads() {
+        let output = AgentOutput {
+            actions: vec![
+                ActionV1 {
+                    action_type: ACTION_TYPE_CALL,
+                    target: [0x11; 32],
+                    payload: vec![0xAB; 96],
+                },
+                ActionV1 {
+                    action_type: ACTION_TYPE_NO_OP,
+                    target: [0x22; 32],
+                    payload: vec![],
+                },
+            ],
+        };
+        let encoded = output.encode().unwrap();
+
+        let view = AgentOutputRef::decode(&encoded).unwrap();
+        assert_eq!(view.actions.len(), 2);
+        assert_eq!(view.actions[0].payload, &[0xAB; 96][..]);
+        // First payload starts after count (4), action_len (4) and the action header (40)
+        assert!(core::ptr::eq(view.actions[0].payload, &encoded[48..144]));
+        assert_eq!(view.to_output(), output);
+
+        let action_bytes = output.actions[1].encode().unwrap();
+        let action = ActionRef::decode(&action_bytes).unwrap();
+        assert_eq!(action.to_action(), output.actions[1]);
+
+        let mut truncated = encoded.clone();
+        truncated.pop();
+        assert_eq!(
+            AgentOutputRef::decode(&truncated),
+            Err(CodecError::UnexpectedEndOfInput)
+        );
+    }
+
+    fn sample_batch(step_count: u64) -> KernelBatchInputV1 {
+        KernelBatchInputV1 {
+            protocol_version: PROTOCOL_VERSION,
+            steps: (0..step_count)
+                .map(|i| KernelInputV1 {
+                    protocol_version: PROTOCOL_VERSION,
+                    kernel_version: KERNEL_VERSION,
+                    agent_id: [0x42; 32],
+                    agent_code_hash: [0xAA; 32],
+                    constraint_set_hash: [0xBB; 32],
+                    input_root: [0xCC; 32],
+                    execution_nonce: 10 + i,
+                    opaque_agent_inputs: vec![i as u8; i as usize],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_batch_input_roundtrip() {
+        let batch = sample_batch(3);
+        let encoded = batch.encode().unwrap();
+        assert_eq!(encoded.len(), batch.encoded_len().unwrap());
+        assert_eq!(KernelBatchInputV1::decode(&encoded).unwrap(), batch);
+
+        // Each step is embedded as its canonical KernelInputV1 encoding
+        let first = batch.steps[0].encode().unwrap();
+        assert_eq!(&encoded[8..12], &(first.len() as u32).to_le_bytes());
+        assert_eq!(&encoded[12..12 + first.len()], first.as_slice());
+    }
+
+    #[test]
+    fn test_batch_input_ref_borrows_steps() {
+        let batch = sample_batch(3);
+        let encoded = batch.encode().unwrap();
+        let view = KernelBatchInputRef::decode(&encoded).unwrap();
+        assert_eq!(view.to_batch(), batch);
+
+        for (step, owned) in view.steps.iter().zip(&batch.steps) {
+            assert_eq!(step.bytes, owned.encode().unwrap().as_slice());
+            assert_eq!(step.input, KernelInputRef::from(owned));
+        }
+
+        // Steps must use the v1 layout, as in KernelBatchInputV1::decode
+        let mut v3_step = batch.steps[0].encode().unwrap();
+        v3_step[4..8].copy_from_slice(&KERNEL_VERSION_V3.to_le_bytes());
+        assert_eq!(
+            KernelInputRef::decode_v1(&v3_step),
+            Err(CodecError::InvalidVersion {
+                expected: KERNEL_VERSION,
+                actual: KERNEL_VERSION_V3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_batch_input_size_limits() {
+        assert_eq!(
+            sample_batch(0).encode(),
+            Err(CodecError::InvalidBatchSize {
+                count: 0,
+                limit: MAX_BATCH_STEPS
+            })
+        );
+        assert!(matches!(
+            sample_batch(MAX_BATCH_STEPS as u64 + 1).encode(),
+            Err(CodecError::InvalidBatchSize { .. })
+        ));
+
+        let mut encoded = sample_batch(2).encode().unwrap();
+        encoded.push(0);
+        assert_eq!(
+            KernelBatchInputV1::decode(&encoded),
+            Err(CodecError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_batch_journal_roundtrip() {
+        let journal = KernelBatchJournalV1 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            first_execution_nonce: 10,
+            step_journal_hashes: vec![[0x01; 32], [0x02; 32]],
+        };
+        let encoded = journal.encode().unwrap();
+        assert_eq!(encoded.len(), BATCH_JOURNAL_HEADER_SIZE + 64);
+        assert_eq!(KernelBatchJournalV1::decode(&encoded).unwrap(), journal);
+
+        assert_eq!(
+            KernelBatchJournalV1::decode(&encoded[..encoded.len() - 1]),
+            Err(CodecError::InvalidLength)
+        );
+    }
 }
@@ -2,11 +2,35 @@
 //!
 //! This module provides the canonical hashing functions used for computing
 //! input and action commitments in the kernel protocol.
+//!
+//! # Action Merkle Commitment
+//!
+//! In addition to the flat `action_commitment`, an [`AgentOutput`] can be
+//! committed to as a binary Merkle tree over its actions, which allows a
+//! single action to be proven without revealing the rest of the output:
+//!
+//! - leaf = SHA-256(0x00 || encoded ActionV1)
+//! - node = SHA-256(0x01 || left || right); an unpaired last node is promoted
+//!   to the next level unchanged
+//! - root = SHA-256(0x02 || action_count as u32 LE || top), where `top` is
+//!   the single remaining node, or 32 zero bytes for an empty output
+//!
+//! Actions are taken in the agent-provided order. The domain tags keep leaf,
+//! node and root preimages distinct, and binding `action_count` into the root
+//! pins each action to its index.
 
 use sha2::{Digest, Sha256};
 
 use crate::codec::CanonicalEncode;
-use crate::types::{CodecError, KernelInputV1};
+use crate::types::{ActionV1, AgentOutput, CodecError, KernelInputV1, KernelJournalV2};
+use alloc::vec::Vec;
+
+/// Domain tag prefixed to action Merkle leaf preimages.
+const ACTION_MERKLE_LEAF_TAG: u8 = 0x00;
+/// Domain tag prefixed to action Merkle internal node preimages.
+const ACTION_MERKLE_NODE_TAG: u8 = 0x01;
+/// Domain tag prefixed to the action Merkle root preimage.
+const ACTION_MERKLE_ROOT_TAG: u8 = 0x02;
 
 /// Compute SHA-256 hash of arbitrary bytes.
 ///
@@ -55,6 +79,93 @@ pub fn kernel_input_v1_commitment(input: &KernelInputV1) -> Result<[u8; 32], Cod
     Ok(sha256(&bytes))
 }
 
+/// Compute SHA-256 hash over encoded journal bytes (any journal version).
+///
+/// This is the `journal_hash` used on-chain to identify a submitted journal.
+/// This function does not validate the encoding; it simply hashes the bytes.
+#[inline]
+#[must_use]
+pub fn compute_journal_hash(journal_bytes: &[u8]) -> [u8; 32] {
+    sha256(journal_bytes)
+}
+
+/// Convenience: encode KernelJournalV2 canonically, then hash it.
+///
+/// Fails if the journal violates the v2 status / violation detail invariants.
+pub fn kernel_journal_v2_hash(journal: &KernelJournalV2) -> Result<[u8; 32], CodecError> {
+    let bytes = journal.encode()?;
+    Ok(compute_journal_hash(&bytes))
+}
+
+/// Compute the action Merkle leaf for a single action.
+///
+/// leaf = SHA-256(0x00 || ActionV1 canonical encoding)
+pub fn action_merkle_leaf(action: &ActionV1) -> Result<[u8; 32], CodecError> {
+    let mut preimage = Vec::with_capacity(1 + action.encoded_len()?);
+    preimage.push(ACTION_MERKLE_LEAF_TAG);
+    action.encode_into(&mut preimage)?;
+    Ok(sha256(&preimage))
+}
+
+/// Combine two child nodes of the action Merkle tree.
+///
+/// node = SHA-256(0x01 || left || right)
+#[must_use]
+pub fn action_merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([ACTION_MERKLE_NODE_TAG]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Compute the action Merkle root from the tree's top node.
+///
+/// root = SHA-256(0x02 || action_count as u32 LE || top)
+#[must_use]
+pub fn action_merkle_root_from_top(action_count: u32, top: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([ACTION_MERKLE_ROOT_TAG]);
+    hasher.update(action_count.to_le_bytes());
+    hasher.update(top);
+    hasher.finalize().into()
+}
+
+/// Compute the action Merkle root over precomputed leaves.
+///
+/// Fails with `ArithmeticOverflow` if the leaf count does not fit in a u32.
+pub fn action_merkle_root_from_leaves(leaves: &[[u8; 32]]) -> Result<[u8; 32], CodecError> {
+    let action_count = u32::try_from(leaves.len()).map_err(|_| CodecError::ArithmeticOverflow)?;
+
+    let mut level: Vec<[u8; 32]> = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => action_merkle_node(left, right),
+                [single] => *single,
+                _ => unreachable!("chunks(2) yields one or two nodes"),
+            })
+            .collect();
+    }
+    let top = level.first().copied().unwrap_or([0u8; 32]);
+
+    Ok(action_merkle_root_from_top(action_count, &top))
+}
+
+/// Compute the Merkle action commitment of an [`AgentOutput`].
+///
+/// This is an optional alternative to [`compute_action_commitment`]; it is
+/// not part of the journal. See the module docs for the tree construction.
+pub fn compute_action_merkle_root(output: &AgentOutput) -> Result<[u8; 32], CodecError> {
+    let leaves = output
+        .actions
+        .iter()
+        .map(action_merkle_leaf)
+        .collect::<Result<Vec<_>, _>>()?;
+    action_merkle_root_from_leaves(&leaves)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,9 +194,69 @@ mod tests {
         let direct = sha256(data);
         let input_commit = compute_input_commitment(data);
         let action_commit = compute_action_commitment(data);
+        let journal_hash = compute_journal_hash(data);
 
         // All should produce the same hash for the same input
         assert_eq!(direct, input_commit);
         assert_eq!(direct, action_commit);
+        assert_eq!(direct, journal_hash);
+    }
+
+    fn no_op_action(tag: u8) -> ActionV1 {
+        ActionV1 {
+            action_type: crate::types::ACTION_TYPE_NO_OP,
+            target: [tag; 32],
+            payload: alloc::vec![],
+        }
+    }
+
+    #[test]
+    fn test_action_merkle_root_structure() {
+        let actions: Vec<ActionV1> = (1..=3).map(no_op_action).collect();
+        let leaves: Vec<[u8; 32]> = actions
+            .iter()
+            .map(|a| action_merkle_leaf(a).unwrap())
+            .collect();
+
+        // Three leaves: the third is promoted past the first level
+        let top = action_merkle_node(&action_merkle_node(&leaves[0], &leaves[1]), &leaves[2]);
+        let expected = action_merkle_root_from_top(3, &top);
+
+        let output = AgentOutput { actions };
+        assert_eq!(compute_action_merkle_root(&output).unwrap(), expected);
+        assert_eq!(action_merkle_root_from_leaves(&leaves).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_action_merkle_root_binds_count_and_order() {
+        let a = no_op_action(1);
+        let b = no_op_action(2);
+
+        let empty = compute_action_merkle_root(&AgentOutput {
+            actions: alloc::vec![],
+        })
+        .unwrap();
+        assert_eq!(empty, action_merkle_root_from_top(0, &[0u8; 32]));
+
+        let single = AgentOutput {
+            actions: alloc::vec![a.clone()],
+        };
+        let leaf = action_merkle_leaf(&a).unwrap();
+        assert_eq!(
+            compute_action_merkle_root(&single).unwrap(),
+            action_merkle_root_from_top(1, &leaf)
+        );
+
+        let ab = AgentOutput {
+            actions: alloc::vec![a.clone(), b.clone()],
+        };
+        let ba = AgentOutput {
+            actions: alloc::vec![b, a],
+        };
+        assert_ne!(
+            compute_action_merkle_root(&ab).unwrap(),
+            compute_action_merkle_root(&ba).unwrap()
+        );
+        assert_ne!(empty, compute_action_merkle_root(&single).unwrap());
     }
 }
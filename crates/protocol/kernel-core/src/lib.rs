@@ -7,6 +7,12 @@
 //!
 //! This crate is `#![no_std]` by default and uses `alloc` for heap allocations.
 //! Enable the `std` feature for host-side tooling that needs std functionality.
+//!
+//! # Serde
+//!
+//! The `serde` feature derives `Serialize`/`Deserialize` for the wire types
+//! (inputs, journals, actions and outputs). Byte fields use `0x`-hex strings;
+//! see [`serde_hex`].
 
 #![no_std]
 #![forbid(unsafe_code)]
@@ -19,6 +25,9 @@ pub mod codec;
 pub mod hash;
 pub mod types;
 
+#[cfg(feature = "serde")]
+pub mod serde_hex;
+
 pub use codec::*;
 pub use hash::*;
 pub use types::*;
@@ -35,11 +44,38 @@ pub const PROTOCOL_VERSION: u32 = 1;
 /// Kernel version declaring execution semantics
 pub const KERNEL_VERSION: u32 = 1;
 
+/// Kernel version whose journal commits to constraint violation details
+/// (see [`KernelJournalV2`]). Inputs use the same layout as v1.
+pub const KERNEL_VERSION_V2: u32 = 2;
+
+/// Kernel version that hash-chains executions: inputs carry the hash of the
+/// previous journal (see [`KernelInputV3`]) and the kernel commits it to a
+/// [`KernelJournalV3`].
+pub const KERNEL_VERSION_V3: u32 = 3;
+
+/// `prev_journal_hash` of the first execution in an agent's chain.
+pub const GENESIS_PREV_JOURNAL_HASH: [u8; 32] = [0u8; 32];
+
+/// Kernel version whose inputs expire: [`KernelInputV4`] adds `valid_until`,
+/// which the kernel commits to a [`KernelJournalV4`].
+pub const KERNEL_VERSION_V4: u32 = 4;
+
+/// `valid_until` value for an input that never expires.
+pub const NO_EXPIRY: u64 = u64::MAX;
+
+/// Kernel version whose inputs carry the state snapshot in a typed slot
+/// (see [`KernelInputV5`]) rather than in `opaque_agent_inputs`. Journals
+/// are [`KernelJournalV5`], an alias of the [`KernelJournalV4`] layout.
+pub const KERNEL_VERSION_V5: u32 = 5;
+
 /// Maximum size of opaque agent inputs (64KB)
 pub const MAX_AGENT_INPUT_BYTES: usize = 64_000;
 
 /// Maximum total size of agent output when encoded
 pub const MAX_AGENT_OUTPUT_BYTES: usize = 64_000;
 
+/// Maximum number of steps in a batch input (see [`KernelBatchInputV1`])
+pub const MAX_BATCH_STEPS: usize = 16;
+
 /// Maximum memory allocation for bounded execution
 pub const MAX_ALLOCATION_BYTES: usize = 1_000_000;
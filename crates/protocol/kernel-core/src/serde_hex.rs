@@ -0,0 +1,200 @@
+//! `0x`-hex serde adapters for byte fields (`serde` feature).
+//!
+//! Wire types serialize their byte fields as lowercase, `0x`-prefixed hex
+//! strings so that JSON stays readable and diffable against the canonical
+//! encoding. Integers and enums use serde's default representation:
+//! enums by variant name (`"Success"`), `Option`s as `null` when absent.
+//! The exception is [`ConstraintViolationReason`], which serializes as its
+//! one-byte journal code (`"0x0a"`) so journal JSON mirrors the wire format.
+//!
+//! Deserialization accepts hex with or without the `0x` prefix, in either
+//! case, so the unprefixed test vectors under `tests/vectors/` parse as-is.
+//!
+//! Use with `#[serde(with = "...")]`:
+//!
+//! - [`bytes32`] for `[u8; 32]`, [`array`] for any other `[u8; N]`
+//! - [`bytes`] for `Vec<u8>`
+//! - [`bytes32_vec`] for `Vec<[u8; 32]>`, [`array_vec`] for any other `Vec<[u8; N]>`
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::de::{Error as _, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::types::ConstraintViolationReason;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Encode bytes as a lowercase `0x`-prefixed hex string.
+fn encode_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + bytes.len() * 2);
+    out.push_str("0x");
+    for byte in bytes {
+        out.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        out.push(HEX_DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    out
+}
+
+fn hex_nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decode a hex string with an optional `0x` prefix.
+fn decode_hex(s: &str) -> Result<Vec<u8>, &'static str> {
+    let digits = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+    if digits.len() % 2 != 0 {
+        return Err("hex string has an odd number of digits");
+    }
+    digits
+        .chunks_exact(2)
+        .map(|pair| match (hex_nibble(pair[0]), hex_nibble(pair[1])) {
+            (Some(hi), Some(lo)) => Ok((hi << 4) | lo),
+            _ => Err("invalid hex digit"),
+        })
+        .collect()
+}
+
+fn decode_array<const N: usize>(s: &str) -> Result<[u8; N], String> {
+    let bytes = decode_hex(s).map_err(String::from)?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| alloc::format!("expected {} bytes, got {}", N, bytes.len()))
+}
+
+/// `[u8; N]` as a hex string of exactly `2 * N` digits.
+pub mod array {
+    use super::*;
+
+    pub fn serialize<const N: usize, S: Serializer>(
+        value: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode_hex(value))
+    }
+
+    pub fn deserialize<'de, const N: usize, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        decode_array(&s).map_err(D::Error::custom)
+    }
+}
+
+/// `[u8; 32]` as a 64-digit hex string.
+pub mod bytes32 {
+    pub use super::array::{deserialize, serialize};
+}
+
+/// `Vec<u8>` as a hex string of any even length (`"0x"` when empty).
+pub mod bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode_hex(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        decode_hex(&s).map_err(D::Error::custom)
+    }
+}
+
+/// `Vec<[u8; N]>` as an array of `2 * N`-digit hex strings.
+pub mod array_vec {
+    use super::*;
+
+    pub fn serialize<const N: usize, S: Serializer>(
+        value: &[[u8; N]],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(value.len()))?;
+        for item in value {
+            seq.serialize_element(&encode_hex(item))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, const N: usize, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<[u8; N]>, D::Error> {
+        struct ArraysVisitor<const N: usize>;
+
+        impl<'de, const N: usize> Visitor<'de> for ArraysVisitor<N> {
+            type Value = Vec<[u8; N]>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an array of {}-byte hex strings", N)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(256));
+                while let Some(s) = seq.next_element::<String>()? {
+                    out.push(decode_array(&s).map_err(A::Error::custom)?);
+                }
+                Ok(out)
+            }
+        }
+
+        deserializer.deserialize_seq(ArraysVisitor::<N>)
+    }
+}
+
+/// `Vec<[u8; 32]>` as an array of 64-digit hex strings.
+pub mod bytes32_vec {
+    pub use super::array_vec::{deserialize, serialize};
+}
+
+impl Serialize for ConstraintViolationReason {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode_hex(&[self.code()]))
+    }
+}
+
+impl<'de> Deserialize<'de> for ConstraintViolationReason {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        match decode_hex(&s).map_err(D::Error::custom)?.as_slice() {
+            [code] => ConstraintViolationReason::from_code(*code).ok_or_else(|| {
+                D::Error::custom(alloc::format!(
+                    "unknown violation reason code 0x{:02x}",
+                    code
+                ))
+            }),
+            _ => Err(D::Error::custom("violation reason must be a one-byte code")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_roundtrip_and_prefix() {
+        assert_eq!(encode_hex(&[]), "0x");
+        assert_eq!(encode_hex(&[0x00, 0xab, 0xff]), "0x00abff");
+        assert_eq!(decode_hex("0x00abff").unwrap(), [0x00, 0xab, 0xff]);
+        assert_eq!(decode_hex("00ABFF").unwrap(), [0x00, 0xab, 0xff]);
+        assert_eq!(decode_hex("").unwrap(), Vec::<u8>::new());
+        assert!(decode_hex("0xabc").is_err());
+        assert!(decode_hex("0xzz").is_err());
+        assert!(decode_array::<32>("0x00").is_err());
+        assert_eq!(
+            decode_array::<32>(&encode_hex(&[7u8; 32])).unwrap(),
+            [7u8; 32]
+        );
+        assert_eq!(
+            decode_array::<4>("0xa9059cbb").unwrap(),
+            [0xa9, 0x05, 0x9c, 0xbb]
+        );
+    }
+}
@@ -64,26 +64,410 @@ pub const ACTION_TYPE_ECHO: u32 = 0x00000001;
 /// - The constraint policy enforced (constraint_set_hash)
 /// - The external state observed (input_root)
 /// - Replay protection (execution_nonce)
+///
+/// Used by kernel versions 1 and 2, which share this layout; there is no
+/// `KernelInputV2`. Later input types are named after their kernel version.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KernelInputV1 {
     /// Protocol version for wire format compatibility
     pub protocol_version: u32,
     /// Kernel version declaring which semantics are being proven
     pub kernel_version: u32,
     /// 32-byte agent identifier
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_id: [u8; 32],
+    /// SHA-256 hash of the agent binary/code
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_code_hash: [u8; 32],
+    /// SHA-256 hash of the constraint set being enforced
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub constraint_set_hash: [u8; 32],
+    /// External state root (market/vault snapshot) the agent observes
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub input_root: [u8; 32],
+    /// Monotonic nonce for replay protection
+    pub execution_nonce: u64,
+    /// Opaque agent-specific input data (max 64KB)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+    pub opaque_agent_inputs: Vec<u8>,
+}
+
+/// Kernel version 3 input: [`KernelInputV1`] plus the hash of the previous journal.
+///
+/// Declares `kernel_version = 3`. `prev_journal_hash` links this execution to
+/// the one before it, so an agent's executions form a hash chain:
+/// - First execution: [`GENESIS_PREV_JOURNAL_HASH`](crate::GENESIS_PREV_JOURNAL_HASH)
+/// - Otherwise: SHA-256 of the previous execution's encoded journal
+///
+/// The kernel does not check the link (it cannot see the previous journal);
+/// it copies the value into the [`KernelJournalV3`] so verifiers can.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelInputV3 {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Kernel version declaring which semantics are being proven (always 3)
+    pub kernel_version: u32,
+    /// 32-byte agent identifier
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub agent_id: [u8; 32],
     /// SHA-256 hash of the agent binary/code
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub agent_code_hash: [u8; 32],
     /// SHA-256 hash of the constraint set being enforced
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub constraint_set_hash: [u8; 32],
     /// External state root (market/vault snapshot) the agent observes
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub input_root: [u8; 32],
     /// Monotonic nonce for replay protection
     pub execution_nonce: u64,
+    /// SHA-256 of the previous execution's journal (zero at genesis)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub prev_journal_hash: [u8; 32],
     /// Opaque agent-specific input data (max 64KB)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
     pub opaque_agent_inputs: Vec<u8>,
 }
 
+/// Kernel version 4 input: [`KernelInputV3`] plus an expiry timestamp.
+///
+/// Declares `kernel_version = 4`. `valid_until` is a unix timestamp (seconds)
+/// after which the execution must not be acted on; [`NO_EXPIRY`](crate::NO_EXPIRY)
+/// disables it. The kernel has no clock, so it:
+/// - commits `valid_until` to the [`KernelJournalV4`] for contracts to
+///   compare against `block.timestamp`
+/// - fails the execution with `InputExpired` when the input's state snapshot
+///   reports a `current_ts` later than `valid_until`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelInputV4 {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Kernel version declaring which semantics are being proven (always 4)
+    pub kernel_version: u32,
+    /// 32-byte agent identifier
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_id: [u8; 32],
+    /// SHA-256 hash of the agent binary/code
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_code_hash: [u8; 32],
+    /// SHA-256 hash of the constraint set being enforced
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub constraint_set_hash: [u8; 32],
+    /// External state root (market/vault snapshot) the agent observes
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub input_root: [u8; 32],
+    /// Monotonic nonce for replay protection
+    pub execution_nonce: u64,
+    /// SHA-256 of the previous execution's journal (zero at genesis)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub prev_journal_hash: [u8; 32],
+    /// Unix timestamp after which the execution is stale (u64::MAX = never)
+    pub valid_until: u64,
+    /// Opaque agent-specific input data (max 64KB)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+    pub opaque_agent_inputs: Vec<u8>,
+}
+
+/// State snapshot for cooldown and drawdown checks.
+///
+/// Carried in the typed `state_snapshot` slot of a [`KernelInputV5`]. Older
+/// input versions have no slot; for them the constraint engine reads a
+/// snapshot from the front of `opaque_agent_inputs` if it decodes (see
+/// [`StateSnapshot::decode`]).
+///
+/// Size: 36 bytes when encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateSnapshotV1 {
+    /// Version (must be 1)
+    pub snapshot_version: u32,
+    /// Timestamp of last execution
+    pub last_execution_ts: u64,
+    /// Current timestamp (from input)
+    pub current_ts: u64,
+    /// Current portfolio equity
+    pub current_equity: u64,
+    /// Peak portfolio equity
+    pub peak_equity: u64,
+}
+
+impl StateSnapshotV1 {
+    /// Size of an encoded snapshot
+    pub const ENCODED_SIZE: usize = 36;
+
+    /// Decode a state snapshot from the first 36 bytes of `bytes`.
+    ///
+    /// Returns None if bytes are too short or version is wrong. Bytes after
+    /// the first 36 are ignored.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < Self::ENCODED_SIZE {
+            return None;
+        }
+
+        let snapshot_version = u32::from_le_bytes(bytes[0..4].try_into().ok()?);
+        if snapshot_version != 1 {
+            return None;
+        }
+
+        Some(Self {
+            snapshot_version,
+            last_execution_ts: u64::from_le_bytes(bytes[4..12].try_into().ok()?),
+            current_ts: u64::from_le_bytes(bytes[12..20].try_into().ok()?),
+            current_equity: u64::from_le_bytes(bytes[20..28].try_into().ok()?),
+            peak_equity: u64::from_le_bytes(bytes[28..36].try_into().ok()?),
+        })
+    }
+
+    /// Encode the snapshot as its 36-byte little-endian layout.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_SIZE] {
+        let mut out = [0u8; Self::ENCODED_SIZE];
+        out[0..4].copy_from_slice(&self.snapshot_version.to_le_bytes());
+        out[4..12].copy_from_slice(&self.last_execution_ts.to_le_bytes());
+        out[12..20].copy_from_slice(&self.current_ts.to_le_bytes());
+        out[20..28].copy_from_slice(&self.current_equity.to_le_bytes());
+        out[28..36].copy_from_slice(&self.peak_equity.to_le_bytes());
+        out
+    }
+}
+
+/// State snapshot with rolling-window accumulators.
+///
+/// Extends [`StateSnapshotV1`] with the usage of the current rolling window,
+/// which the constraint engine needs to enforce per-window execution and
+/// notional limits. The host keeps the accumulators up to date between
+/// executions, exactly like `last_execution_ts`.
+///
+/// Size: 56 bytes when encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateSnapshotV2 {
+    /// Version (must be 2)
+    pub snapshot_version: u32,
+    /// Timestamp of last execution
+    pub last_execution_ts: u64,
+    /// Current timestamp (from input)
+    pub current_ts: u64,
+    /// Current portfolio equity
+    pub current_equity: u64,
+    /// Peak portfolio equity
+    pub peak_equity: u64,
+    /// Timestamp at which the current window started
+    pub window_start_ts: u64,
+    /// Executions already committed in the current window
+    pub window_executions: u32,
+    /// Notional already committed in the current window, in token base units
+    pub window_notional: u64,
+}
+
+impl StateSnapshotV2 {
+    /// Size of an encoded snapshot
+    pub const ENCODED_SIZE: usize = 56;
+
+    /// Decode a state snapshot from the first 56 bytes of `bytes`.
+    ///
+    /// Returns None if bytes are too short or version is wrong. Bytes after
+    /// the first 56 are ignored.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < Self::ENCODED_SIZE {
+            return None;
+        }
+
+        let snapshot_version = u32::from_le_bytes(bytes[0..4].try_into().ok()?);
+        if snapshot_version != 2 {
+            return None;
+        }
+
+        Some(Self {
+            snapshot_version,
+            last_execution_ts: u64::from_le_bytes(bytes[4..12].try_into().ok()?),
+            current_ts: u64::from_le_bytes(bytes[12..20].try_into().ok()?),
+            current_equity: u64::from_le_bytes(bytes[20..28].try_into().ok()?),
+            peak_equity: u64::from_le_bytes(bytes[28..36].try_into().ok()?),
+            window_start_ts: u64::from_le_bytes(bytes[36..44].try_into().ok()?),
+            window_executions: u32::from_le_bytes(bytes[44..48].try_into().ok()?),
+            window_notional: u64::from_le_bytes(bytes[48..56].try_into().ok()?),
+        })
+    }
+
+    /// Encode the snapshot as its 56-byte little-endian layout.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_SIZE] {
+        let mut out = [0u8; Self::ENCODED_SIZE];
+        out[0..4].copy_from_slice(&self.snapshot_version.to_le_bytes());
+        out[4..12].copy_from_slice(&self.last_execution_ts.to_le_bytes());
+        out[12..20].copy_from_slice(&self.current_ts.to_le_bytes());
+        out[20..28].copy_from_slice(&self.current_equity.to_le_bytes());
+        out[28..36].copy_from_slice(&self.peak_equity.to_le_bytes());
+        out[36..44].copy_from_slice(&self.window_start_ts.to_le_bytes());
+        out[44..48].copy_from_slice(&self.window_executions.to_le_bytes());
+        out[48..56].copy_from_slice(&self.window_notional.to_le_bytes());
+        out
+    }
+}
+
+/// A state snapshot of any supported version.
+///
+/// Decoding dispatches on the leading `snapshot_version` field. The fields
+/// shared by every version are available through accessors, so cooldown,
+/// drawdown, leverage and expiry checks work on either version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StateSnapshot {
+    /// Equity and timestamps (36 bytes)
+    V1(StateSnapshotV1),
+    /// Equity, timestamps and rolling-window usage (56 bytes)
+    V2(StateSnapshotV2),
+}
+
+impl From<StateSnapshotV1> for StateSnapshot {
+    fn from(snapshot: StateSnapshotV1) -> Self {
+        StateSnapshot::V1(snapshot)
+    }
+}
+
+impl From<StateSnapshotV2> for StateSnapshot {
+    fn from(snapshot: StateSnapshotV2) -> Self {
+        StateSnapshot::V2(snapshot)
+    }
+}
+
+impl StateSnapshot {
+    /// Decode a state snapshot of any version from the front of `bytes`.
+    ///
+    /// Returns None if the version is unknown or `bytes` is too short for
+    /// it. Bytes after the snapshot are ignored.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        match u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?) {
+            1 => StateSnapshotV1::decode(bytes).map(StateSnapshot::V1),
+            2 => StateSnapshotV2::decode(bytes).map(StateSnapshot::V2),
+            _ => None,
+        }
+    }
+
+    /// Size of the encoded snapshot in bytes.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            StateSnapshot::V1(_) => StateSnapshotV1::ENCODED_SIZE,
+            StateSnapshot::V2(_) => StateSnapshotV2::ENCODED_SIZE,
+        }
+    }
+
+    /// Encode the snapshot in its version's layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            StateSnapshot::V1(snapshot) => snapshot.to_bytes().to_vec(),
+            StateSnapshot::V2(snapshot) => snapshot.to_bytes().to_vec(),
+        }
+    }
+
+    /// Snapshot version.
+    pub fn version(&self) -> u32 {
+        match self {
+            StateSnapshot::V1(snapshot) => snapshot.snapshot_version,
+            StateSnapshot::V2(snapshot) => snapshot.snapshot_version,
+        }
+    }
+
+    /// Timestamp of last execution.
+    pub fn last_execution_ts(&self) -> u64 {
+        match self {
+            StateSnapshot::V1(snapshot) => snapshot.last_execution_ts,
+            StateSnapshot::V2(snapshot) => snapshot.last_execution_ts,
+        }
+    }
+
+    /// Current timestamp (from input).
+    pub fn current_ts(&self) -> u64 {
+        match self {
+            StateSnapshot::V1(snapshot) => snapshot.current_ts,
+            StateSnapshot::V2(snapshot) => snapshot.current_ts,
+        }
+    }
+
+    /// Current portfolio equity.
+    pub fn current_equity(&self) -> u64 {
+        match self {
+            StateSnapshot::V1(snapshot) => snapshot.current_equity,
+            StateSnapshot::V2(snapshot) => snapshot.current_equity,
+        }
+    }
+
+    /// Peak portfolio equity.
+    pub fn peak_equity(&self) -> u64 {
+        match self {
+            StateSnapshot::V1(snapshot) => snapshot.peak_equity,
+            StateSnapshot::V2(snapshot) => snapshot.peak_equity,
+        }
+    }
+
+    /// Rolling-window usage (`None` for version 1, which carries none).
+    pub fn window(&self) -> Option<&StateSnapshotV2> {
+        match self {
+            StateSnapshot::V1(_) => None,
+            StateSnapshot::V2(snapshot) => Some(snapshot),
+        }
+    }
+}
+
+/// Kernel version 5 input: [`KernelInputV4`] plus a typed state snapshot slot.
+///
+/// Declares `kernel_version = 5`. The [`StateSnapshot`] used for cooldown,
+/// drawdown, rolling-window and expiry checks travels in `state_snapshot` instead of being
+/// sniffed from the front of `opaque_agent_inputs`, which is entirely
+/// agent-defined. The kernel passes the snapshot to both the constraint
+/// engine and the agent's context, and commits to a [`KernelJournalV5`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelInputV5 {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Kernel version declaring which semantics are being proven (always 5)
+    pub kernel_version: u32,
+    /// 32-byte agent identifier
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_id: [u8; 32],
+    /// SHA-256 hash of the agent binary/code
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_code_hash: [u8; 32],
+    /// SHA-256 hash of the constraint set being enforced
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub constraint_set_hash: [u8; 32],
+    /// External state root (market/vault snapshot) the agent observes
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub input_root: [u8; 32],
+    /// Monotonic nonce for replay protection
+    pub execution_nonce: u64,
+    /// SHA-256 of the previous execution's journal (zero at genesis)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub prev_journal_hash: [u8; 32],
+    /// Unix timestamp after which the execution is stale (u64::MAX = never)
+    pub valid_until: u64,
+    /// State snapshot for cooldown, drawdown, rolling-window and expiry checks
+    pub state_snapshot: Option<StateSnapshot>,
+    /// Opaque agent-specific input data (max 64KB)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+    pub opaque_agent_inputs: Vec<u8>,
+}
+
+/// Any supported kernel input version.
+///
+/// The kernel decodes into this type; dispatch is on the `kernel_version`
+/// field (1 or 2 select [`KernelInputV1`], 3 selects [`KernelInputV3`],
+/// 4 selects [`KernelInputV4`], 5 selects [`KernelInputV5`]).
+#[derive(Clone, Debug, PartialEq)]
+pub enum KernelInput {
+    /// Input for kernel versions 1 and 2
+    V1(KernelInputV1),
+    /// Input for kernel version 3
+    V3(KernelInputV3),
+    /// Input for kernel version 4
+    V4(KernelInputV4),
+    /// Input for kernel version 5
+    V5(KernelInputV5),
+}
+
 /// Kernel journal (output) structure for P0.1 protocol.
 ///
 /// Contains all fields needed for on-chain verification:
@@ -94,49 +478,388 @@ pub struct KernelInputV1 {
 ///
 /// Journal size: 209 bytes fixed (4+4+32+32+32+32+8+32+32+1)
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KernelJournalV1 {
     /// Protocol version for wire format compatibility
     pub protocol_version: u32,
     /// Kernel version that produced this journal
     pub kernel_version: u32,
     /// Agent identifier (copied from input for verifier convenience)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub agent_id: [u8; 32],
     /// Agent code hash (proof binds to this specific agent)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub agent_code_hash: [u8; 32],
     /// Constraint set hash (proof binds to this policy)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub constraint_set_hash: [u8; 32],
     /// Input root (external state that was observed)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub input_root: [u8; 32],
     /// Execution nonce for replay protection
     pub execution_nonce: u64,
     /// SHA-256(full_input_bytes) - commits to entire input
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub input_commitment: [u8; 32],
     /// SHA-256(agent_output_bytes) - commits to actions
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub action_commitment: [u8; 32],
     /// Execution result status
     pub execution_status: ExecutionStatus,
 }
 
+/// Kernel journal v2: [`KernelJournalV1`] plus committed failure details.
+///
+/// Produced when the input declares `kernel_version = 2`. On Failure the
+/// journal additionally commits to the [`ConstraintViolationReason`] code and,
+/// for per-action violations, the index of the offending action, so verifiers
+/// can tell *why* an execution was rejected without re-running it.
+///
+/// Invariants (enforced by the codec):
+/// - Success: `violation_reason` and `violation_action_index` are both `None`
+/// - Failure: `violation_reason` is `Some`
+/// - `violation_action_index` is only present alongside a reason
+///
+/// Journal size: 214 bytes fixed (209 + 1 + 4)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelJournalV2 {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Kernel version that produced this journal (always 2)
+    pub kernel_version: u32,
+    /// Agent identifier (copied from input for verifier convenience)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_id: [u8; 32],
+    /// Agent code hash (proof binds to this specific agent)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_code_hash: [u8; 32],
+    /// Constraint set hash (proof binds to this policy)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub constraint_set_hash: [u8; 32],
+    /// Input root (external state that was observed)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub input_root: [u8; 32],
+    /// Execution nonce for replay protection
+    pub execution_nonce: u64,
+    /// SHA-256(full_input_bytes) - commits to entire input
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub input_commitment: [u8; 32],
+    /// SHA-256(agent_output_bytes) - commits to actions
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub action_commitment: [u8; 32],
+    /// Execution result status
+    pub execution_status: ExecutionStatus,
+    /// Violated constraint (encoded as its code, 0x00 = none)
+    pub violation_reason: Option<ConstraintViolationReason>,
+    /// Index of the violating action (encoded as u32 LE, 0xFFFFFFFF = none)
+    pub violation_action_index: Option<u32>,
+}
+
+/// Kernel journal v3: [`KernelJournalV2`] plus the chained previous-journal hash.
+///
+/// Produced for [`KernelInputV3`] inputs (`kernel_version = 3`).
+/// `prev_journal_hash` is copied from the input; a verifier holding the
+/// previous journal checks that it hashes to this value.
+///
+/// Journal size: 246 bytes fixed (214 + 32)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelJournalV3 {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Kernel version that produced this journal (always 3)
+    pub kernel_version: u32,
+    /// Agent identifier (copied from input for verifier convenience)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_id: [u8; 32],
+    /// Agent code hash (proof binds to this specific agent)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_code_hash: [u8; 32],
+    /// Constraint set hash (proof binds to this policy)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub constraint_set_hash: [u8; 32],
+    /// Input root (external state that was observed)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub input_root: [u8; 32],
+    /// Execution nonce for replay protection
+    pub execution_nonce: u64,
+    /// SHA-256(full_input_bytes) - commits to entire input
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub input_commitment: [u8; 32],
+    /// SHA-256(agent_output_bytes) - commits to actions
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub action_commitment: [u8; 32],
+    /// Execution result status
+    pub execution_status: ExecutionStatus,
+    /// Violated constraint (encoded as its code, 0x00 = none)
+    pub violation_reason: Option<ConstraintViolationReason>,
+    /// Index of the violating action (encoded as u32 LE, 0xFFFFFFFF = none)
+    pub violation_action_index: Option<u32>,
+    /// SHA-256 of the previous execution's journal (zero at genesis)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub prev_journal_hash: [u8; 32],
+}
+
+/// Kernel journal v4: [`KernelJournalV3`] plus the input's expiry timestamp.
+///
+/// Produced for [`KernelInputV4`] inputs (`kernel_version = 4`). Contracts
+/// reject the journal once `block.timestamp > valid_until`. Kernel version 5
+/// reuses this layout as [`KernelJournalV5`].
+///
+/// Journal size: 254 bytes fixed (246 + 8)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelJournalV4 {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Kernel version that produced this journal (4, or 5 for a [`KernelJournalV5`])
+    pub kernel_version: u32,
+    /// Agent identifier (copied from input for verifier convenience)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_id: [u8; 32],
+    /// Agent code hash (proof binds to this specific agent)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_code_hash: [u8; 32],
+    /// Constraint set hash (proof binds to this policy)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub constraint_set_hash: [u8; 32],
+    /// Input root (external state that was observed)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub input_root: [u8; 32],
+    /// Execution nonce for replay protection
+    pub execution_nonce: u64,
+    /// SHA-256(full_input_bytes) - commits to entire input
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub input_commitment: [u8; 32],
+    /// SHA-256(agent_output_bytes) - commits to actions
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub action_commitment: [u8; 32],
+    /// Execution result status
+    pub execution_status: ExecutionStatus,
+    /// Violated constraint (encoded as its code, 0x00 = none)
+    pub violation_reason: Option<ConstraintViolationReason>,
+    /// Index of the violating action (encoded as u32 LE, 0xFFFFFFFF = none)
+    pub violation_action_index: Option<u32>,
+    /// SHA-256 of the previous execution's journal (zero at genesis)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub prev_journal_hash: [u8; 32],
+    /// Unix timestamp after which the execution is stale (u64::MAX = never)
+    pub valid_until: u64,
+}
+
+/// Kernel version 5 journal: the [`KernelJournalV4`] layout with
+/// `kernel_version = 5`.
+///
+/// Produced for [`KernelInputV5`] inputs. The typed state snapshot is not
+/// repeated in the journal; it is committed through `input_commitment`.
+///
+/// Journal size: 254 bytes fixed
+pub type KernelJournalV5 = KernelJournalV4;
+
+/// Any supported kernel journal version.
+///
+/// Host tooling that must accept journals from multiple kernel versions
+/// should decode into this type; dispatch is on the `kernel_version` field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KernelJournal {
+    /// Journal produced by kernel version 1
+    V1(KernelJournalV1),
+    /// Journal produced by kernel version 2
+    V2(KernelJournalV2),
+    /// Journal produced by kernel version 3
+    V3(KernelJournalV3),
+    /// Journal produced by kernel version 4
+    V4(KernelJournalV4),
+    /// Journal produced by kernel version 5
+    V5(KernelJournalV5),
+}
+
+impl KernelJournal {
+    /// Protocol version of this journal.
+    pub fn protocol_version(&self) -> u32 {
+        match self {
+            KernelJournal::V1(j) => j.protocol_version,
+            KernelJournal::V2(j) => j.protocol_version,
+            KernelJournal::V3(j) => j.protocol_version,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.protocol_version,
+        }
+    }
+
+    /// Kernel version that produced this journal.
+    pub fn kernel_version(&self) -> u32 {
+        match self {
+            KernelJournal::V1(j) => j.kernel_version,
+            KernelJournal::V2(j) => j.kernel_version,
+            KernelJournal::V3(j) => j.kernel_version,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.kernel_version,
+        }
+    }
+
+    /// Agent identifier.
+    pub fn agent_id(&self) -> [u8; 32] {
+        match self {
+            KernelJournal::V1(j) => j.agent_id,
+            KernelJournal::V2(j) => j.agent_id,
+            KernelJournal::V3(j) => j.agent_id,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.agent_id,
+        }
+    }
+
+    /// Execution nonce.
+    pub fn execution_nonce(&self) -> u64 {
+        match self {
+            KernelJournal::V1(j) => j.execution_nonce,
+            KernelJournal::V2(j) => j.execution_nonce,
+            KernelJournal::V3(j) => j.execution_nonce,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.execution_nonce,
+        }
+    }
+
+    /// Input commitment.
+    pub fn input_commitment(&self) -> [u8; 32] {
+        match self {
+            KernelJournal::V1(j) => j.input_commitment,
+            KernelJournal::V2(j) => j.input_commitment,
+            KernelJournal::V3(j) => j.input_commitment,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.input_commitment,
+        }
+    }
+
+    /// Action commitment.
+    pub fn action_commitment(&self) -> [u8; 32] {
+        match self {
+            KernelJournal::V1(j) => j.action_commitment,
+            KernelJournal::V2(j) => j.action_commitment,
+            KernelJournal::V3(j) => j.action_commitment,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.action_commitment,
+        }
+    }
+
+    /// Execution status.
+    pub fn execution_status(&self) -> ExecutionStatus {
+        match self {
+            KernelJournal::V1(j) => j.execution_status,
+            KernelJournal::V2(j) => j.execution_status,
+            KernelJournal::V3(j) => j.execution_status,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.execution_status,
+        }
+    }
+
+    /// Committed violation reason (always `None` for v1 journals).
+    pub fn violation_reason(&self) -> Option<ConstraintViolationReason> {
+        match self {
+            KernelJournal::V1(_) => None,
+            KernelJournal::V2(j) => j.violation_reason,
+            KernelJournal::V3(j) => j.violation_reason,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.violation_reason,
+        }
+    }
+
+    /// Committed violating action index (always `None` for v1 journals).
+    pub fn violation_action_index(&self) -> Option<u32> {
+        match self {
+            KernelJournal::V1(_) => None,
+            KernelJournal::V2(j) => j.violation_action_index,
+            KernelJournal::V3(j) => j.violation_action_index,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => j.violation_action_index,
+        }
+    }
+
+    /// Committed previous-journal hash (`None` for v1 and v2 journals).
+    pub fn prev_journal_hash(&self) -> Option<[u8; 32]> {
+        match self {
+            KernelJournal::V1(_) | KernelJournal::V2(_) => None,
+            KernelJournal::V3(j) => Some(j.prev_journal_hash),
+            KernelJournal::V4(j) | KernelJournal::V5(j) => Some(j.prev_journal_hash),
+        }
+    }
+
+    /// Committed expiry timestamp (`None` before v4 journals).
+    pub fn valid_until(&self) -> Option<u64> {
+        match self {
+            KernelJournal::V1(_) | KernelJournal::V2(_) | KernelJournal::V3(_) => None,
+            KernelJournal::V4(j) | KernelJournal::V5(j) => Some(j.valid_until),
+        }
+    }
+}
+
+/// Batch of kernel inputs executed sequentially in a single proof.
+///
+/// Each step is a complete [`KernelInputV1`] executed with the same
+/// semantics (and constraint enforcement) as a standalone execution. The
+/// kernel additionally requires:
+/// - 1 to `MAX_BATCH_STEPS` steps
+/// - all steps share `kernel_version`, `agent_id`, `agent_code_hash` and
+///   `constraint_set_hash`
+/// - consecutive nonces: `steps[i + 1].execution_nonce == steps[i].execution_nonce + 1`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelBatchInputV1 {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Inputs executed in order
+    pub steps: Vec<KernelInputV1>,
+}
+
+/// Journal committed by a batch execution.
+///
+/// Commits to the shared identity of the batch and to the SHA-256 hash of
+/// every step journal, in step order. Consumers verify a step journal by
+/// hashing it and comparing against `step_journal_hashes[i]`.
+///
+/// Journal size: 116 + 32 * step_count bytes (4+4+32+32+32+8+4 + 32n)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelBatchJournalV1 {
+    /// Protocol version for wire format compatibility
+    pub protocol_version: u32,
+    /// Kernel version shared by all steps (selects the step journal format)
+    pub kernel_version: u32,
+    /// Agent identifier shared by all steps
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_id: [u8; 32],
+    /// Agent code hash shared by all steps
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub agent_code_hash: [u8; 32],
+    /// Constraint set hash shared by all steps
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
+    pub constraint_set_hash: [u8; 32],
+    /// Execution nonce of the first step
+    pub first_execution_nonce: u64,
+    /// SHA-256 of each step's encoded journal, in step order
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32_vec"))]
+    pub step_journal_hashes: Vec<[u8; 32]>,
+}
+
 /// Execution status enum.
 ///
 /// Encoding:
 /// - Success = 0x01: Execution completed and constraints passed
 /// - Failure = 0x02: Execution completed but constraints violated
+/// - AgentError = 0x03: The agent rejected its input (e.g. malformed opaque inputs).
+///   Only `KernelJournalV4`/`KernelJournalV5` can carry it; the v1-v3 journal
+///   layouts are frozen at Success and Failure.
 /// - 0x00 is reserved/invalid (prevents uninitialized memory from being interpreted as success)
-/// - 0x03-0xFF are reserved for future expansion
+/// - 0x04-0xFF are reserved for future expansion
 ///
-/// On Failure, the journal is still produced with:
+/// On Failure or AgentError, the journal is still produced with:
 /// - action_commitment = SHA256(empty AgentOutput encoding)
-/// - execution_status = Failure (0x02)
+/// - execution_status = Failure (0x02) or AgentError (0x03)
 ///
-/// Verifiers/contracts should reject state transitions for Failure journals.
+/// Verifiers/contracts should reject state transitions for any non-Success journal.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExecutionStatus {
     /// Execution completed successfully and all constraints passed. Encoded as 0x01.
     Success,
     /// Execution completed but constraints were violated. Encoded as 0x02.
     /// The action_commitment will be the commitment to an empty AgentOutput.
     Failure,
+    /// The agent returned an error instead of an output. Encoded as 0x03.
+    /// Constraints are not evaluated, no violation details are recorded, and
+    /// the action_commitment will be the commitment to an empty AgentOutput.
+    AgentError,
 }
 
 /// Structured action format for agent output.
@@ -156,12 +879,15 @@ pub enum ExecutionStatus {
 /// This kernel-side canonicalization ensures determinism regardless
 /// of the order in which agents produce actions.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ActionV1 {
     /// 4-byte action type identifier
     pub action_type: u32,
     /// 32-byte target address/identifier
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes32"))]
     pub target: [u8; 32],
     /// Action-specific payload (max 16KB)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
     pub payload: Vec<u8>,
 }
 
@@ -183,6 +909,7 @@ pub const MAX_SINGLE_ACTION_BYTES: usize = 40 + MAX_ACTION_PAYLOAD_BYTES;
 /// The action_commitment is computed over the encoded AgentOutput
 /// after canonicalization.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AgentOutput {
     /// Ordered list of actions (max 64 actions)
     pub actions: Vec<ActionV1>,
@@ -243,6 +970,14 @@ pub enum CodecError {
     TooManyActions { count: u32, limit: usize },
     ActionPayloadTooLarge { size: u32, limit: usize },
     ActionTooLarge { size: u32, limit: usize },
+    InvalidViolationReason(u8),
+    InvalidViolationDetails,
+    InvalidBatchSize { count: u32, limit: usize },
+    AllowlistTooLarge { count: u32, limit: usize },
+    NonCanonicalAllowlist,
+    InvalidStateSnapshot,
+    UnknownConstraintExtension(u32),
+    InvalidApprovalPolicy,
 }
 
 /// Kernel-level execution errors.
@@ -266,6 +1001,12 @@ pub enum KernelError {
     InvalidAgentId,
     /// Agent code hash mismatch
     AgentCodeHashMismatch,
+    /// Enforced constraint set does not hash to `constraint_set_hash`
+    ConstraintSetHashMismatch,
+    /// Batch step does not share the first step's version or identity fields
+    BatchStepMismatch { step: u32 },
+    /// Batch step nonce is not the previous step's nonce plus one
+    NonConsecutiveBatchNonce { step: u32 },
     /// Output encoding failed
     EncodingFailed(CodecError),
 }
@@ -310,6 +1051,20 @@ pub enum ConstraintViolationReason {
     InvalidConstraintSet = 0x09,
     /// Action payload is malformed or invalid
     InvalidActionPayload = 0x0A,
+    /// State snapshot timestamp is past the input's `valid_until`
+    InputExpired = 0x0B,
+    /// CALL target or (target, selector) is not on the constraint set's allowlist
+    CallNotAllowed = 0x0C,
+    /// Execution would exceed the rolling window's execution limit
+    WindowExecutionsExceeded = 0x0D,
+    /// Execution would exceed the rolling window's notional limit
+    WindowNotionalExceeded = 0x0E,
+    /// ERC20 approve spender not allowed or allowance above the limit
+    ApprovalNotAllowed = 0x0F,
+    /// Native value sent by CALL actions exceeds the per-action or per-output cap
+    NativeValueExceeded = 0x10,
+    /// The agent charged more steps than the constraint set's budget
+    StepBudgetExceeded = 0x11,
 }
 
 impl ConstraintViolationReason {
@@ -317,6 +1072,32 @@ impl ConstraintViolationReason {
     pub fn code(self) -> u8 {
         self as u8
     }
+
+    /// Parse a numeric code back into a violation reason.
+    ///
+    /// Returns `None` for unassigned codes (including the reserved 0x00).
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0x01 => Some(Self::InvalidOutputStructure),
+            0x02 => Some(Self::UnknownActionType),
+            0x03 => Some(Self::AssetNotWhitelisted),
+            0x04 => Some(Self::PositionTooLarge),
+            0x05 => Some(Self::LeverageTooHigh),
+            0x06 => Some(Self::DrawdownExceeded),
+            0x07 => Some(Self::CooldownNotElapsed),
+            0x08 => Some(Self::InvalidStateSnapshot),
+            0x09 => Some(Self::InvalidConstraintSet),
+            0x0A => Some(Self::InvalidActionPayload),
+            0x0B => Some(Self::InputExpired),
+            0x0C => Some(Self::CallNotAllowed),
+            0x0D => Some(Self::WindowExecutionsExceeded),
+            0x0E => Some(Self::WindowNotionalExceeded),
+            0x0F => Some(Self::ApprovalNotAllowed),
+            0x10 => Some(Self::NativeValueExceeded),
+            0x11 => Some(Self::StepBudgetExceeded),
+            _ => None,
+        }
+    }
 }
 
 /// Detailed constraint violation information.
@@ -444,4 +1225,60 @@ mod tests {
         let _: u32 = ACTION_TYPE_TRANSFER_ERC20;
         let _: u32 = ACTION_TYPE_NO_OP;
     }
+
+    /// Verify violation reason codes roundtrip and unassigned codes are rejected.
+    #[test]
+    fn test_violation_reason_from_code() {
+        for code in 0x01..=0x11u8 {
+            let reason = ConstraintViolationReason::from_code(code).unwrap();
+            assert_eq!(reason.code(), code);
+        }
+        assert_eq!(ConstraintViolationReason::from_code(0x00), None);
+        assert_eq!(ConstraintViolationReason::from_code(0x12), None);
+        assert_eq!(ConstraintViolationReason::from_code(0xFF), None);
+    }
+
+    /// Verify snapshots decode by version and keep their shared fields.
+    #[test]
+    fn test_state_snapshot_version_dispatch() {
+        let v1 = StateSnapshotV1 {
+            snapshot_version: 1,
+            last_execution_ts: 10,
+            current_ts: 20,
+            current_equity: 30,
+            peak_equity: 40,
+        };
+        let v2 = StateSnapshotV2 {
+            snapshot_version: 2,
+            last_execution_ts: 10,
+            current_ts: 20,
+            current_equity: 30,
+            peak_equity: 40,
+            window_start_ts: 5,
+            window_executions: 3,
+            window_notional: 1_000,
+        };
+
+        let mut bytes = v2.to_bytes().to_vec();
+        bytes.extend_from_slice(&[0xAA; 8]);
+        let decoded = StateSnapshot::decode(&bytes).unwrap();
+        assert_eq!(decoded, StateSnapshot::V2(v2));
+        assert_eq!(decoded.encoded_len(), StateSnapshotV2::ENCODED_SIZE);
+        assert_eq!(decoded.to_bytes(), v2.to_bytes().to_vec());
+        assert_eq!(decoded.window(), Some(&v2));
+
+        let decoded = StateSnapshot::decode(&v1.to_bytes()).unwrap();
+        assert_eq!(decoded, StateSnapshot::V1(v1));
+        assert_eq!(decoded.window(), None);
+        assert_eq!(
+            (decoded.last_execution_ts(), decoded.current_ts()),
+            (10, 20)
+        );
+        assert_eq!((decoded.current_equity(), decoded.peak_equity()), (30, 40));
+
+        // A v2 header with only v1's length, and an unknown version
+        assert_eq!(StateSnapshot::decode(&bytes[..36]), None);
+        bytes[0] = 3;
+        assert_eq!(StateSnapshot::decode(&bytes), None);
+    }
 }
@@ -1,16 +1,20 @@
-//! Cross-language conformance tests for ActionV1 and AgentOutput encoding.
+//! Cross-language conformance tests for ActionV1, AgentOutput and
+//! KernelJournalV2 encoding, and the Merkle action commitment.
 //!
 //! These tests validate that Rust encoding matches the expected golden vectors,
 //! ensuring consistency with the Solidity KernelOutputParser implementation.
 //!
-//! The fixtures are located at `tests/fixtures/action_vectors.json`.
+//! The fixtures are located at `tests/fixtures/action_vectors.json`; the
+//! journal vectors mirror `tests/vectors/kernel_journal_v2.json`.
 //!
 //! To regenerate fixture values after intentional changes, set GENERATE_VECTORS=1:
 //!   GENERATE_VECTORS=1 cargo test -p kernel-core --test conformance_tests
 
 use kernel_core::{
-    compute_action_commitment, ActionV1, AgentOutput, CanonicalEncode, ACTION_TYPE_CALL,
-    ACTION_TYPE_NO_OP, ACTION_TYPE_TRANSFER_ERC20,
+    compute_action_commitment, compute_action_merkle_root, compute_journal_hash,
+    kernel_journal_v2_hash, ActionV1, AgentOutput, CanonicalDecode, CanonicalEncode,
+    ConstraintViolationReason, ExecutionStatus, KernelJournal, KernelJournalV2, ACTION_TYPE_CALL,
+    ACTION_TYPE_NO_OP, ACTION_TYPE_TRANSFER_ERC20, KERNEL_VERSION_V2, PROTOCOL_VERSION,
 };
 
 /// Helper to convert hex string (without 0x prefix) to bytes
@@ -476,3 +480,131 @@ fn test_transfer_erc20_payload_structure() {
         "amount must be big-endian"
     );
 }
+
+// ============================================================================
+// KernelJournalV2 Vectors
+// ============================================================================
+
+/// Failure journal matching the `failure_action_violation` vector.
+fn failure_journal_v2(
+    reason: ConstraintViolationReason,
+    action_index: Option<u32>,
+) -> KernelJournalV2 {
+    let empty_output = AgentOutput { actions: vec![] }.encode().unwrap();
+    KernelJournalV2 {
+        protocol_version: PROTOCOL_VERSION,
+        kernel_version: KERNEL_VERSION_V2,
+        agent_id: [0x42; 32],
+        agent_code_hash: [0xaa; 32],
+        constraint_set_hash: [0xbb; 32],
+        input_root: [0xcc; 32],
+        execution_nonce: 12345,
+        input_commitment: [0xdd; 32],
+        action_commitment: compute_action_commitment(&empty_output),
+        execution_status: ExecutionStatus::Failure,
+        violation_reason: Some(reason),
+        violation_action_index: action_index,
+    }
+}
+
+#[test]
+fn test_journal_v2_action_violation() {
+    let journal = failure_journal_v2(ConstraintViolationReason::InvalidActionPayload, Some(2));
+
+    let encoded = journal.encode().expect("encoding should succeed");
+    let encoded_hex = bytes_to_hex(&encoded);
+    let hash_hex = bytes_to_hex(&compute_journal_hash(&encoded));
+
+    let expected_encoded = "01000000020000004242424242424242424242424242424242424242424242424242424242424242aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbcccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc3930000000000000dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddf3f619804a92fdb4057192dc43dd748ea778adc52bc498ce80524c014b81119020a02000000";
+    let expected_hash = "898ee44c57eb756cb696d84b6fcf556a18048b89c171eb2143de8a648fcab155";
+
+    if std::env::var("GENERATE_VECTORS").is_ok() {
+        println!("\n=== journal_v2_failure_action_violation ===");
+        println!("encoded_hex: {}", encoded_hex);
+        println!("journal_hash: {}", hash_hex);
+    }
+
+    assert_eq!(
+        encoded_hex, expected_encoded,
+        "journal_v2: encoding mismatch"
+    );
+    assert_eq!(hash_hex, expected_hash, "journal_v2: hash mismatch");
+
+    assert_eq!(
+        kernel_journal_v2_hash(&journal).unwrap(),
+        compute_journal_hash(&encoded)
+    );
+
+    let decoded = KernelJournalV2::decode(&hex_to_bytes(expected_encoded)).unwrap();
+    assert_eq!(decoded, journal);
+}
+
+#[test]
+fn test_journal_v2_global_violation() {
+    let journal = failure_journal_v2(ConstraintViolationReason::CooldownNotElapsed, None);
+
+    let encoded = journal.encode().expect("encoding should succeed");
+    let encoded_hex = bytes_to_hex(&encoded);
+    let hash_hex = bytes_to_hex(&compute_journal_hash(&encoded));
+
+    let expected_encoded = "01000000020000004242424242424242424242424242424242424242424242424242424242424242aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbcccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc3930000000000000dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddf3f619804a92fdb4057192dc43dd748ea778adc52bc498ce80524c014b811190207ffffffff";
+    let expected_hash = "b611f285e6bcae2c7acfeb2dec964f9c9bfc26ab4916233cc6665b8d2d9faa66";
+
+    if std::env::var("GENERATE_VECTORS").is_ok() {
+        println!("\n=== journal_v2_failure_global_violation ===");
+        println!("encoded_hex: {}", encoded_hex);
+        println!("journal_hash: {}", hash_hex);
+    }
+
+    assert_eq!(
+        encoded_hex, expected_encoded,
+        "journal_v2: encoding mismatch"
+    );
+    assert_eq!(hash_hex, expected_hash, "journal_v2: hash mismatch");
+
+    // Versioned dispatch picks v2 and exposes the committed reason
+    let decoded = KernelJournal::decode(&encoded).unwrap();
+    assert_eq!(
+        decoded.violation_reason(),
+        Some(ConstraintViolationReason::CooldownNotElapsed)
+    );
+    assert_eq!(decoded.violation_action_index(), None);
+}
+
+// ============================================================================
+// Merkle Action Commitment Vectors
+// ============================================================================
+
+#[test]
+fn test_action_merkle_root_three_actions() {
+    // Three NO_OP actions with targets 0x01.., 0x02.., 0x03.. (odd count
+    // exercises promotion of the unpaired last node)
+    let output = AgentOutput {
+        actions: (1..=3u8)
+            .map(|i| ActionV1 {
+                action_type: ACTION_TYPE_NO_OP,
+                target: [i; 32],
+                payload: vec![],
+            })
+            .collect(),
+    };
+
+    let root_hex = bytes_to_hex(&compute_action_merkle_root(&output).unwrap());
+    let empty_root_hex =
+        bytes_to_hex(&compute_action_merkle_root(&AgentOutput { actions: vec![] }).unwrap());
+
+    let expected_root = "5115ba5046e374040ce99ee1d791203486154c04c4002d35bccf829bd378ab2a";
+    let expected_empty_root = "22689034dd7977d019ec4fc2606eddaad978a0e5e7cba9a2712a549341c46ca9";
+
+    if std::env::var("GENERATE_VECTORS").is_ok() {
+        println!("\n=== action_merkle_root ===");
+        println!("three_actions_root: {}", root_hex);
+        println!("empty_root: {}", empty_root_hex);
+    }
+
+    assert_eq!(root_hex, expected_root, "merkle root mismatch");
+    assert_eq!(
+        empty_root_hex, expected_empty_root,
+        "empty merkle root mismatch"
+    );
+}
@@ -4,6 +4,8 @@
 //! - Verify Agent Pack bundles (offline and on-chain)
 //! - Generate proofs from bundles
 //! - Execute proven results on-chain
+//! - Verify an agent's hash-chained execution history
+//! - Check an agent output against a constraint set, listing every violation
 //!
 //! Exit codes:
 //!   0 - Success
@@ -18,8 +20,12 @@ use reference_integrator::{feature_status, verify_offline, verify_structure, Loa
 use serde::Serialize;
 
 #[cfg(feature = "prove")]
-use reference_integrator::{build_and_encode_input, parse_hex, InputParams};
-use std::path::PathBuf;
+use reference_integrator::{
+    build_and_encode_chained_input, build_and_encode_expiring_input, build_and_encode_input,
+    build_and_encode_snapshot_input, parse_hex, InputParams, StateSnapshot, StateSnapshotV1,
+    StateSnapshotV2, GENESIS_PREV_JOURNAL_HASH, NO_EXPIRY,
+};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
 /// Exit codes for the CLI
@@ -125,14 +131,35 @@ enum Commands {
         #[arg(long, default_value = "1")]
         nonce: u64,
 
-        /// Constraint set hash as hex (0x prefixed)
+        /// Encoded ConstraintSetV1, V2 or V3 as hex (0x prefixed) or @filepath.
+        /// Defaults to the default constraint set; the input's
+        /// constraint_set_hash is derived from it.
         #[arg(long)]
-        constraint_set_hash: Option<String>,
+        constraint_set: Option<String>,
 
         /// Input root as hex (0x prefixed)
         #[arg(long)]
         input_root: Option<String>,
 
+        /// Hash of the agent's previous journal as hex (0x prefixed).
+        /// Builds a hash-chained (kernel version 3) input; use 32 zero bytes
+        /// for the agent's first execution.
+        #[arg(long)]
+        prev_journal_hash: Option<String>,
+
+        /// Unix timestamp after which the proof must not be acted on.
+        /// Builds an expiring (kernel version 4) input, chained to
+        /// --prev-journal-hash or to genesis if it is omitted.
+        #[arg(long)]
+        valid_until: Option<u64>,
+
+        /// 36-byte StateSnapshotV1 or 56-byte StateSnapshotV2 as hex (0x prefixed).
+        /// Builds a kernel version 5 input carrying the snapshot in its typed
+        /// slot, so --opaque-inputs holds only agent data. Chained and
+        /// expiring as above; never expires if --valid-until is omitted.
+        #[arg(long)]
+        state_snapshot: Option<String>,
+
         /// Output directory for proof artifacts
         #[arg(short, long)]
         out: PathBuf,
@@ -183,6 +210,63 @@ enum Commands {
         json: bool,
     },
 
+    /// Verify an agent's hash-chained execution history
+    ///
+    /// Checks that the journals, given in execution order, form an unbroken
+    /// hash chain from genesis with increasing nonces.
+    VerifyChain {
+        /// Paths to journal bytes files, in execution order
+        #[arg(long = "journal", required = true)]
+        journals: Vec<PathBuf>,
+
+        /// Agent ID as hex (0x prefixed). Defaults to the first journal's agent.
+        #[arg(long)]
+        agent_id: Option<String>,
+
+        /// Output JSON instead of human-readable text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check an agent output against a constraint set
+    ///
+    /// Evaluates every constraint rule and lists all violations, instead of
+    /// only the first one the kernel commits to the journal.
+    CheckConstraints {
+        /// Path to the encoded kernel input (any kernel version)
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Path to the encoded AgentOutput
+        #[arg(long)]
+        output: PathBuf,
+
+        /// Path to the encoded constraint set. Defaults to the default constraint set.
+        #[arg(long)]
+        constraint_set: Option<PathBuf>,
+
+        /// Output JSON instead of human-readable text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compile a TOML or JSON policy file into a constraint set
+    ///
+    /// Validates the policy and prints the canonical constraint set bytes
+    /// and the constraint_set_hash inputs must carry.
+    CompilePolicy {
+        /// Path to the policy file (.toml or .json)
+        policy: PathBuf,
+
+        /// Write the canonical constraint set bytes to this file
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        /// Output JSON instead of human-readable text
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Show feature status or inspect proof artifacts
     ///
     /// Without arguments, shows feature availability.
@@ -225,6 +309,10 @@ struct ProveOutput {
     journal_size: usize,
     seal_size: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
+    user_cycles: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_cycles: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
 }
 
@@ -239,6 +327,48 @@ struct ExecuteOutput {
     error: Option<String>,
 }
 
+#[derive(Serialize)]
+struct VerifyChainOutput {
+    success: bool,
+    journal_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    head: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_nonce: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CheckConstraintsOutput {
+    success: bool,
+    violations: Vec<ViolationInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ViolationInfo {
+    reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action_index: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct CompilePolicyOutput {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    constraint_set_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    constraint_set_bytes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 #[derive(Serialize)]
 struct StatusOutput {
     version: String,
@@ -270,6 +400,14 @@ struct ArtifactsInfo {
     action_commitment: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     execution_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    violation_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    violation_action_index: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prev_journal_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    valid_until: Option<u64>,
 }
 
 fn main() -> ExitCode {
@@ -287,8 +425,11 @@ fn main() -> ExitCode {
             bundle,
             opaque_inputs,
             nonce,
-            constraint_set_hash,
+            constraint_set,
             input_root,
+            prev_journal_hash,
+            valid_until,
+            state_snapshot,
             out,
             dev,
             json,
@@ -296,8 +437,11 @@ fn main() -> ExitCode {
             bundle,
             opaque_inputs,
             nonce,
-            constraint_set_hash,
+            constraint_set,
             input_root,
+            prev_journal_hash,
+            valid_until,
+            state_snapshot,
             out,
             dev,
             json,
@@ -312,6 +456,18 @@ fn main() -> ExitCode {
             agent_output,
             json,
         } => cmd_execute(bundle, vault, rpc, pk, journal, seal, agent_output, json),
+        Commands::VerifyChain {
+            journals,
+            agent_id,
+            json,
+        } => cmd_verify_chain(journals, agent_id, json),
+        Commands::CheckConstraints {
+            input,
+            output,
+            constraint_set,
+            json,
+        } => cmd_check_constraints(input, output, constraint_set, json),
+        Commands::CompilePolicy { policy, out, json } => cmd_compile_policy(policy, out, json),
         Commands::Status {
             artifacts_dir,
             json,
@@ -533,8 +689,11 @@ fn cmd_prove(
     bundle_path: PathBuf,
     opaque_inputs: Option<String>,
     nonce: u64,
-    constraint_set_hash: Option<String>,
+    constraint_set: Option<String>,
     input_root: Option<String>,
+    prev_journal_hash: Option<String>,
+    valid_until: Option<u64>,
+    state_snapshot: Option<String>,
     out_dir: PathBuf,
     dev_mode: bool,
     json_output: bool,
@@ -550,6 +709,8 @@ fn cmd_prove(
                 seal_path: String::new(),
                 journal_size: 0,
                 seal_size: 0,
+                user_cycles: None,
+                total_cycles: None,
                 error: Some("Proving requires --features prove".to_string()),
             };
             println!("{}", serde_json::to_string_pretty(&output).unwrap());
@@ -572,6 +733,8 @@ fn cmd_prove(
             seal_path: String::new(),
             journal_size: 0,
             seal_size: 0,
+            user_cycles: None,
+            total_cycles: None,
             error: None,
         };
 
@@ -620,11 +783,27 @@ fn cmd_prove(
             }
         };
 
-        // Parse constraint set hash
-        let constraint_set_hash_bytes = match parse_optional_hex_32(constraint_set_hash) {
-            Ok(hash) => hash.unwrap_or([0u8; 32]),
+        // Parse constraint set and derive its hash
+        let (constraint_set_bytes, constraint_set_hash_bytes) =
+            match parse_constraint_set(constraint_set) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    let error_msg = format!("Invalid constraint set: {}", e);
+                    if json_output {
+                        output.error = Some(error_msg);
+                        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                    } else {
+                        eprintln!("Error: {}", error_msg);
+                    }
+                    return exit_codes::invalid_usage();
+                }
+            };
+
+        // Parse input root
+        let input_root_bytes = match parse_optional_hex_32(input_root) {
+            Ok(root) => root.unwrap_or([0u8; 32]),
             Err(e) => {
-                let error_msg = format!("Invalid constraint_set_hash: {}", e);
+                let error_msg = format!("Invalid input_root: {}", e);
                 if json_output {
                     output.error = Some(error_msg);
                     println!("{}", serde_json::to_string_pretty(&output).unwrap());
@@ -635,11 +814,26 @@ fn cmd_prove(
             }
         };
 
-        // Parse input root
-        let input_root_bytes = match parse_optional_hex_32(input_root) {
-            Ok(root) => root.unwrap_or([0u8; 32]),
+        // Parse previous journal hash (selects a hash-chained input)
+        let prev_journal_hash_bytes = match parse_optional_hex_32(prev_journal_hash) {
+            Ok(hash) => hash,
             Err(e) => {
-                let error_msg = format!("Invalid input_root: {}", e);
+                let error_msg = format!("Invalid prev_journal_hash: {}", e);
+                if json_output {
+                    output.error = Some(error_msg);
+                    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                } else {
+                    eprintln!("Error: {}", error_msg);
+                }
+                return exit_codes::invalid_usage();
+            }
+        };
+
+        // Parse state snapshot (selects a kernel version 5 input)
+        let state_snapshot = match parse_state_snapshot(state_snapshot) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                let error_msg = format!("Invalid state_snapshot: {}", e);
                 if json_output {
                     output.error = Some(error_msg);
                     println!("{}", serde_json::to_string_pretty(&output).unwrap());
@@ -663,7 +857,24 @@ fn cmd_prove(
             println!("Building kernel input...");
         }
 
-        let input_bytes = match build_and_encode_input(&bundle, &params) {
+        let encoded_input = match (prev_journal_hash_bytes, valid_until, state_snapshot) {
+            (prev, valid_until, Some(snapshot)) => build_and_encode_snapshot_input(
+                &bundle,
+                &params,
+                prev.unwrap_or(GENESIS_PREV_JOURNAL_HASH),
+                valid_until.unwrap_or(NO_EXPIRY),
+                Some(snapshot),
+            ),
+            (prev, Some(valid_until), None) => build_and_encode_expiring_input(
+                &bundle,
+                &params,
+                prev.unwrap_or(GENESIS_PREV_JOURNAL_HASH),
+                valid_until,
+            ),
+            (Some(prev), None, None) => build_and_encode_chained_input(&bundle, &params, prev),
+            (None, None, None) => build_and_encode_input(&bundle, &params),
+        };
+        let input_bytes = match encoded_input {
             Ok(bytes) => bytes,
             Err(e) => {
                 let error_msg = format!("Failed to build input: {}", e);
@@ -724,7 +935,7 @@ fn cmd_prove(
             println!("Generating proof (this may take a while)...");
         }
 
-        let proof_result = match prove(&elf_bytes, &input_bytes, mode) {
+        let proof_result = match prove(&elf_bytes, &input_bytes, &constraint_set_bytes, mode) {
             Ok(result) => result,
             Err(e) => {
                 let error_msg = format!("Proof generation failed: {}", e);
@@ -802,6 +1013,8 @@ fn cmd_prove(
         output.seal_path = seal_path.display().to_string();
         output.journal_size = proof_result.journal_bytes.len();
         output.seal_size = proof_result.seal_bytes.len();
+        output.user_cycles = Some(proof_result.user_cycles);
+        output.total_cycles = Some(proof_result.total_cycles);
 
         if json_output {
             println!("{}", serde_json::to_string_pretty(&output).unwrap());
@@ -810,9 +1023,13 @@ fn cmd_prove(
             println!("Proof generated successfully!");
             println!("  Journal size: {} bytes", proof_result.journal_bytes.len());
             println!("  Seal size: {} bytes", proof_result.seal_bytes.len());
+            println!(
+                "  Cycles: {} user, {} total",
+                proof_result.user_cycles, proof_result.total_cycles
+            );
             println!(
                 "  Execution status: {:?}",
-                proof_result.journal.execution_status
+                proof_result.journal.execution_status()
             );
             println!();
             println!("Output files:");
@@ -1038,6 +1255,253 @@ fn cmd_execute(
     }
 }
 
+fn cmd_verify_chain(
+    journal_paths: Vec<PathBuf>,
+    agent_id: Option<String>,
+    json_output: bool,
+) -> ExitCode {
+    use reference_integrator::{parse_hex_32, verify_execution_chain, KernelJournal};
+
+    let mut output = VerifyChainOutput {
+        success: false,
+        journal_count: journal_paths.len(),
+        agent_id: None,
+        head: None,
+        last_nonce: None,
+        error: None,
+    };
+
+    let report_error = |output: &mut VerifyChainOutput, error_msg: String| {
+        if json_output {
+            output.error = Some(error_msg);
+            println!("{}", serde_json::to_string_pretty(output).unwrap());
+        } else {
+            eprintln!("Error: {}", error_msg);
+        }
+    };
+
+    let mut journals = Vec::with_capacity(journal_paths.len());
+    for path in &journal_paths {
+        match std::fs::read(path) {
+            Ok(bytes) => journals.push(bytes),
+            Err(e) => {
+                report_error(
+                    &mut output,
+                    format!("Failed to read {}: {}", path.display(), e),
+                );
+                return exit_codes::invalid_usage();
+            }
+        }
+    }
+
+    // Agent ID: explicit, or taken from the first journal
+    let agent_id = match agent_id {
+        Some(s) => parse_hex_32(&s).map_err(|e| format!("Invalid agent_id: {}", e)),
+        None => KernelJournal::decode(&journals[0])
+            .map(|j| j.agent_id())
+            .map_err(|e| format!("Failed to decode first journal: {:?}", e)),
+    };
+    let agent_id = match agent_id {
+        Ok(id) => id,
+        Err(error_msg) => {
+            report_error(&mut output, error_msg);
+            return exit_codes::invalid_usage();
+        }
+    };
+    output.agent_id = Some(format!("0x{}", hex::encode(agent_id)));
+
+    let chain = match verify_execution_chain(agent_id, &journals) {
+        Ok(chain) => chain,
+        Err(e) => {
+            report_error(&mut output, format!("Chain verification failed: {}", e));
+            return exit_codes::verification_mismatch();
+        }
+    };
+
+    output.success = true;
+    output.head = Some(format!("0x{}", hex::encode(chain.head())));
+    output.last_nonce = chain.last_nonce();
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        println!("Execution chain verified");
+        println!("  Agent ID: {}", output.agent_id.as_deref().unwrap_or(""));
+        println!("  Journals: {}", chain.len());
+        if let Some(nonce) = output.last_nonce {
+            println!("  Last nonce: {}", nonce);
+        }
+        println!("  Head: {}", output.head.as_deref().unwrap_or(""));
+    }
+
+    exit_codes::success()
+}
+
+fn cmd_check_constraints(
+    input_path: PathBuf,
+    output_path: PathBuf,
+    constraint_set_path: Option<PathBuf>,
+    json_output: bool,
+) -> ExitCode {
+    let mut output = CheckConstraintsOutput {
+        success: false,
+        violations: Vec::new(),
+        error: None,
+    };
+
+    let decoded = diagnose_files(&input_path, &output_path, constraint_set_path.as_deref());
+
+    let violations = match decoded {
+        Ok(violations) => violations,
+        Err(error_msg) => {
+            if json_output {
+                output.error = Some(error_msg);
+                println!("{}", serde_json::to_string_pretty(&output).unwrap());
+            } else {
+                eprintln!("Error: {}", error_msg);
+            }
+            return exit_codes::invalid_usage();
+        }
+    };
+
+    output.success = violations.is_empty();
+    output.violations = violations
+        .iter()
+        .map(|v| ViolationInfo {
+            reason: format!("{:?} (0x{:02x})", v.reason, v.reason.code()),
+            action_index: v.action_index,
+        })
+        .collect();
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else if output.success {
+        println!("All constraints satisfied");
+    } else {
+        println!("{} constraint violation(s):", output.violations.len());
+        for violation in &output.violations {
+            match violation.action_index {
+                Some(index) => println!("  action {}: {}", index, violation.reason),
+                None => println!("  global: {}", violation.reason),
+            }
+        }
+    }
+
+    if output.success {
+        exit_codes::success()
+    } else {
+        exit_codes::verification_mismatch()
+    }
+}
+
+fn cmd_compile_policy(
+    policy_path: PathBuf,
+    out_path: Option<PathBuf>,
+    json_output: bool,
+) -> ExitCode {
+    let mut output = CompilePolicyOutput {
+        success: false,
+        version: None,
+        constraint_set_hash: None,
+        constraint_set_bytes: None,
+        error: None,
+    };
+
+    let compiled = compile_policy_file(&policy_path).and_then(|compiled| {
+        if let Some(path) = &out_path {
+            std::fs::write(path, &compiled.bytes)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        Ok(compiled)
+    });
+
+    let compiled = match compiled {
+        Ok(compiled) => compiled,
+        Err(error_msg) => {
+            if json_output {
+                output.error = Some(error_msg);
+                println!("{}", serde_json::to_string_pretty(&output).unwrap());
+            } else {
+                eprintln!("Error: {}", error_msg);
+            }
+            return exit_codes::invalid_usage();
+        }
+    };
+
+    output.success = true;
+    output.version = Some(compiled.constraint_set.version());
+    output.constraint_set_hash = Some(format!("0x{}", hex::encode(compiled.hash)));
+    output.constraint_set_bytes = Some(format!("0x{}", hex::encode(&compiled.bytes)));
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        println!(
+            "Constraint set version: {}",
+            compiled.constraint_set.version()
+        );
+        println!("constraint_set_hash:    0x{}", hex::encode(compiled.hash));
+        println!("Constraint set bytes:   0x{}", hex::encode(&compiled.bytes));
+        if let Some(path) = &out_path {
+            println!("Wrote {} bytes to {}", compiled.bytes.len(), path.display());
+        }
+    }
+
+    exit_codes::success()
+}
+
+/// Read a policy file and compile it, inferring the format from its extension.
+fn compile_policy_file(path: &Path) -> Result<reference_integrator::CompiledPolicy, String> {
+    use reference_integrator::{compile_policy, PolicyFormat};
+
+    let format = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(PolicyFormat::from_extension)
+        .ok_or_else(|| {
+            format!(
+                "Cannot infer policy format of {} (expected .toml or .json)",
+                path.display()
+            )
+        })?;
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    compile_policy(&source, format).map_err(|e| e.to_string())
+}
+
+/// Decode the input, agent output and constraint set files and run the
+/// constraint engine in diagnostic mode.
+fn diagnose_files(
+    input_path: &Path,
+    output_path: &Path,
+    constraint_set_path: Option<&Path>,
+) -> Result<Vec<reference_integrator::ConstraintViolation>, String> {
+    use reference_integrator::{
+        diagnose_constraints_ref, AgentOutput, CanonicalDecode, ConstraintSet, KernelInputRef,
+    };
+
+    let read = |path: &Path| {
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+
+    let input_bytes = read(input_path)?;
+    let input = KernelInputRef::decode(&input_bytes)
+        .map_err(|e| format!("Failed to decode kernel input: {:?}", e))?;
+    let agent_output = AgentOutput::decode(&read(output_path)?)
+        .map_err(|e| format!("Failed to decode agent output: {:?}", e))?;
+    let constraint_set = match constraint_set_path {
+        None => ConstraintSet::default(),
+        Some(path) => ConstraintSet::decode(&read(path)?)
+            .map_err(|e| format!("Failed to decode constraint set: {:?}", e))?,
+    };
+
+    Ok(diagnose_constraints_ref(
+        &input,
+        &agent_output,
+        &constraint_set,
+    ))
+}
+
 fn cmd_status(artifacts_dir: Option<PathBuf>, json_output: bool) -> ExitCode {
     let mut output = StatusOutput {
         version: reference_integrator::VERSION.to_string(),
@@ -1093,18 +1557,30 @@ fn cmd_status(artifacts_dir: Option<PathBuf>, json_output: bool) -> ExitCode {
             input_commitment: None,
             action_commitment: None,
             execution_status: None,
+            violation_reason: None,
+            violation_action_index: None,
+            prev_journal_hash: None,
+            valid_until: None,
         };
 
-        // Try to decode journal
-        if let Ok(journal) = kernel_core::KernelJournalV1::decode(&journal_bytes) {
-            artifacts_info.protocol_version = Some(journal.protocol_version);
-            artifacts_info.kernel_version = Some(journal.kernel_version);
-            artifacts_info.agent_id = Some(format!("0x{}", hex::encode(journal.agent_id)));
+        // Try to decode journal (any version, dispatched on kernel_version)
+        if let Ok(journal) = kernel_core::KernelJournal::decode(&journal_bytes) {
+            artifacts_info.protocol_version = Some(journal.protocol_version());
+            artifacts_info.kernel_version = Some(journal.kernel_version());
+            artifacts_info.agent_id = Some(format!("0x{}", hex::encode(journal.agent_id())));
             artifacts_info.input_commitment =
-                Some(format!("0x{}", hex::encode(journal.input_commitment)));
+                Some(format!("0x{}", hex::encode(journal.input_commitment())));
             artifacts_info.action_commitment =
-                Some(format!("0x{}", hex::encode(journal.action_commitment)));
-            artifacts_info.execution_status = Some(format!("{:?}", journal.execution_status));
+                Some(format!("0x{}", hex::encode(journal.action_commitment())));
+            artifacts_info.execution_status = Some(format!("{:?}", journal.execution_status()));
+            artifacts_info.violation_reason = journal
+                .violation_reason()
+                .map(|r| format!("{:?} (0x{:02x})", r, r.code()));
+            artifacts_info.violation_action_index = journal.violation_action_index();
+            artifacts_info.prev_journal_hash = journal
+                .prev_journal_hash()
+                .map(|h| format!("0x{}", hex::encode(h)));
+            artifacts_info.valid_until = journal.valid_until();
         }
 
         output.artifacts = Some(artifacts_info);
@@ -1143,6 +1619,18 @@ fn cmd_status(artifacts_dir: Option<PathBuf>, json_output: bool) -> ExitCode {
             if let Some(ref es) = artifacts.execution_status {
                 println!("  Execution status: {}", es);
             }
+            if let Some(ref vr) = artifacts.violation_reason {
+                println!("  Violation reason: {}", vr);
+            }
+            if let Some(index) = artifacts.violation_action_index {
+                println!("  Violating action index: {}", index);
+            }
+            if let Some(ref prev) = artifacts.prev_journal_hash {
+                println!("  Previous journal hash: {}", prev);
+            }
+            if let Some(valid_until) = artifacts.valid_until {
+                println!("  Valid until: {}", valid_until);
+            }
         }
 
         if !reference_integrator::is_proving_available() {
@@ -1179,6 +1667,24 @@ fn parse_opaque_inputs(input: Option<String>) -> Result<Vec<u8>, String> {
     }
 }
 
+/// Parse an encoded constraint set (hex or @filepath), returning the
+/// canonical bytes and their constraint_set_hash.
+#[cfg(feature = "prove")]
+fn parse_constraint_set(input: Option<String>) -> Result<(Vec<u8>, [u8; 32]), String> {
+    use reference_integrator::{compute_constraint_set_hash, CanonicalEncode, ConstraintSet};
+
+    let constraint_set = match input {
+        None => ConstraintSet::default(),
+        Some(s) => {
+            let bytes = parse_opaque_inputs(Some(s))?;
+            ConstraintSet::decode(&bytes).map_err(|e| format!("{:?}", e))?
+        }
+    };
+    let bytes = constraint_set.encode().map_err(|e| format!("{:?}", e))?;
+    let hash = compute_constraint_set_hash(&constraint_set).map_err(|e| format!("{:?}", e))?;
+    Ok((bytes, hash))
+}
+
 #[cfg(feature = "prove")]
 fn parse_optional_hex_32(input: Option<String>) -> Result<Option<[u8; 32]>, String> {
     match input {
@@ -1195,5 +1701,36 @@ fn parse_optional_hex_32(input: Option<String>) -> Result<Option<[u8; 32]>, Stri
     }
 }
 
+#[cfg(feature = "prove")]
+fn parse_state_snapshot(input: Option<String>) -> Result<Option<StateSnapshot>, String> {
+    match input {
+        None => Ok(None),
+        Some(s) => {
+            let bytes = parse_hex(&s)?;
+            if bytes.len() != StateSnapshotV1::ENCODED_SIZE
+                && bytes.len() != StateSnapshotV2::ENCODED_SIZE
+            {
+                return Err(format!(
+                    "Expected {} or {} bytes, got {}",
+                    StateSnapshotV1::ENCODED_SIZE,
+                    StateSnapshotV2::ENCODED_SIZE,
+                    bytes.len()
+                ));
+            }
+            let snapshot = StateSnapshot::decode(&bytes)
+                .ok_or_else(|| "Unsupported snapshot version (expected 1 or 2)".to_string())?;
+            if snapshot.encoded_len() != bytes.len() {
+                return Err(format!(
+                    "Version {} snapshot is {} bytes, got {}",
+                    snapshot.version(),
+                    snapshot.encoded_len(),
+                    bytes.len()
+                ));
+            }
+            Ok(Some(snapshot))
+        }
+    }
+}
+
 // Re-export for use in status command
 use kernel_core::CanonicalDecode;
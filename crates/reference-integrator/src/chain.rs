@@ -0,0 +1,288 @@
+//! Execution hash-chain tracking.
+//!
+//! Kernel version 3 and 4 journals ([`KernelJournalV3`](kernel_core::KernelJournalV3),
+//! [`KernelJournalV4`](kernel_core::KernelJournalV4)) commit to
+//! `prev_journal_hash`, the SHA-256 of the agent's previous journal. An agent's executions therefore form a hash chain that starts at
+//! [`GENESIS_PREV_JOURNAL_HASH`] and can be checked link by link without
+//! trusting the order a host presents them in.
+//!
+//! [`ExecutionChain`] is used on both sides:
+//! - A host appends each journal it proves and feeds [`ExecutionChain::head`]
+//!   into the next input as `prev_journal_hash`.
+//! - An auditor replays an agent's full history with
+//!   [`verify_execution_chain`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use reference_integrator::verify_execution_chain;
+//!
+//! # let agent_id = [0u8; 32];
+//! # let journals: Vec<Vec<u8>> = vec![];
+//! let chain = verify_execution_chain(agent_id, &journals).unwrap();
+//! println!("{} executions, head 0x{}", chain.len(), hex::encode(chain.head()));
+//! ```
+
+use kernel_core::{
+    compute_journal_hash, CanonicalDecode, KernelJournal, GENESIS_PREV_JOURNAL_HASH,
+};
+
+/// Error type for execution chain verification.
+///
+/// `index` is the position of the offending journal in the chain.
+#[derive(Debug, thiserror::Error)]
+pub enum ChainError {
+    /// Journal bytes could not be decoded.
+    #[error("Failed to decode journal {index}: {message}")]
+    DecodeError { index: u64, message: String },
+
+    /// Journal version does not commit a previous-journal hash.
+    #[error("Journal {index} has kernel version {kernel_version}, which is not hash-chained")]
+    NotChained { index: u64, kernel_version: u32 },
+
+    /// Journal belongs to a different agent than the chain.
+    #[error("Journal {index} belongs to agent 0x{actual}, expected 0x{expected}")]
+    AgentMismatch {
+        index: u64,
+        expected: String,
+        actual: String,
+    },
+
+    /// Journal does not link to the previous journal (or to genesis).
+    #[error("Journal {index} links to 0x{actual}, expected 0x{expected}")]
+    BrokenLink {
+        index: u64,
+        expected: String,
+        actual: String,
+    },
+
+    /// Journal nonce is not greater than the previous journal's nonce.
+    #[error("Journal {index} has nonce {actual}, not greater than previous nonce {previous}")]
+    NonceNotIncreasing {
+        index: u64,
+        previous: u64,
+        actual: u64,
+    },
+}
+
+/// Verified prefix of an agent's execution chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionChain {
+    agent_id: [u8; 32],
+    head: [u8; 32],
+    last_nonce: Option<u64>,
+    len: u64,
+}
+
+impl ExecutionChain {
+    /// Start an empty chain for `agent_id`, anchored at genesis.
+    pub fn new(agent_id: [u8; 32]) -> Self {
+        Self {
+            agent_id,
+            head: GENESIS_PREV_JOURNAL_HASH,
+            last_nonce: None,
+            len: 0,
+        }
+    }
+
+    /// Agent this chain belongs to.
+    pub fn agent_id(&self) -> [u8; 32] {
+        self.agent_id
+    }
+
+    /// Hash of the last appended journal ([`GENESIS_PREV_JOURNAL_HASH`] when
+    /// empty). This is the `prev_journal_hash` for the next execution.
+    pub fn head(&self) -> [u8; 32] {
+        self.head
+    }
+
+    /// Nonce of the last appended journal.
+    pub fn last_nonce(&self) -> Option<u64> {
+        self.last_nonce
+    }
+
+    /// Number of journals in the chain.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether no journal has been appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Check that `journal_bytes` extends the chain and append it.
+    ///
+    /// The journal must be a hash-chained (kernel version 3+) journal for this
+    /// agent, link to the current head, and carry a greater nonce than the
+    /// previous journal. On error the chain is left unchanged.
+    ///
+    /// Returns the new head.
+    pub fn append(&mut self, journal_bytes: &[u8]) -> Result<[u8; 32], ChainError> {
+        let index = self.len;
+        let journal =
+            KernelJournal::decode(journal_bytes).map_err(|e| ChainError::DecodeError {
+                index,
+                message: format!("{:?}", e),
+            })?;
+
+        let prev_journal_hash = journal.prev_journal_hash().ok_or(ChainError::NotChained {
+            index,
+            kernel_version: journal.kernel_version(),
+        })?;
+
+        if journal.agent_id() != self.agent_id {
+            return Err(ChainError::AgentMismatch {
+                index,
+                expected: hex::encode(self.agent_id),
+                actual: hex::encode(journal.agent_id()),
+            });
+        }
+
+        if prev_journal_hash != self.head {
+            return Err(ChainError::BrokenLink {
+                index,
+                expected: hex::encode(self.head),
+                actual: hex::encode(prev_journal_hash),
+            });
+        }
+
+        let nonce = journal.execution_nonce();
+        if let Some(previous) = self.last_nonce {
+            if nonce <= previous {
+                return Err(ChainError::NonceNotIncreasing {
+                    index,
+                    previous,
+                    actual: nonce,
+                });
+            }
+        }
+
+        self.head = compute_journal_hash(journal_bytes);
+        self.last_nonce = Some(nonce);
+        self.len += 1;
+        Ok(self.head)
+    }
+}
+
+/// Verify an agent's full execution history from genesis.
+///
+/// `journals` are the encoded journals in execution order. Returns the
+/// verified chain, whose [`head`](ExecutionChain::head) commits to the whole
+/// history.
+pub fn verify_execution_chain<J: AsRef<[u8]>>(
+    agent_id: [u8; 32],
+    journals: &[J],
+) -> Result<ExecutionChain, ChainError> {
+    let mut chain = ExecutionChain::new(agent_id);
+    for journal in journals {
+        chain.append(journal.as_ref())?;
+    }
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel_core::{
+        CanonicalEncode, ExecutionStatus, KernelJournalV3, KERNEL_VERSION_V3, PROTOCOL_VERSION,
+    };
+
+    const AGENT_ID: [u8; 32] = [0x42; 32];
+
+    fn make_journal(nonce: u64, prev_journal_hash: [u8; 32]) -> Vec<u8> {
+        KernelJournalV3 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V3,
+            agent_id: AGENT_ID,
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: [0xBB; 32],
+            input_root: [0xCC; 32],
+            execution_nonce: nonce,
+            input_commitment: [nonce as u8; 32],
+            action_commitment: [0x22; 32],
+            execution_status: ExecutionStatus::Success,
+            violation_reason: None,
+            violation_action_index: None,
+            prev_journal_hash,
+        }
+        .encode()
+        .unwrap()
+    }
+
+    fn make_history(len: u64) -> Vec<Vec<u8>> {
+        let mut journals = Vec::new();
+        let mut prev = GENESIS_PREV_JOURNAL_HASH;
+        for nonce in 1..=len {
+            let journal = make_journal(nonce, prev);
+            prev = compute_journal_hash(&journal);
+            journals.push(journal);
+        }
+        journals
+    }
+
+    #[test]
+    fn test_verify_history_from_genesis() {
+        let journals = make_history(4);
+        let chain = verify_execution_chain(AGENT_ID, &journals).unwrap();
+        assert_eq!(chain.len(), 4);
+        assert_eq!(chain.last_nonce(), Some(4));
+        assert_eq!(chain.head(), compute_journal_hash(&journals[3]));
+
+        let empty = verify_execution_chain::<Vec<u8>>(AGENT_ID, &[]).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.head(), GENESIS_PREV_JOURNAL_HASH);
+    }
+
+    #[test]
+    fn test_broken_chain_rejected() {
+        let journals = make_history(3);
+
+        // Missing journal
+        let gapped = [journals[0].clone(), journals[2].clone()];
+        assert!(matches!(
+            verify_execution_chain(AGENT_ID, &gapped),
+            Err(ChainError::BrokenLink { index: 1, .. })
+        ));
+
+        // History not starting at genesis
+        assert!(matches!(
+            verify_execution_chain(AGENT_ID, &journals[1..]),
+            Err(ChainError::BrokenLink { index: 0, .. })
+        ));
+
+        // Wrong agent
+        assert!(matches!(
+            verify_execution_chain([0x43; 32], &journals),
+            Err(ChainError::AgentMismatch { index: 0, .. })
+        ));
+
+        // Correctly linked but replaying an old nonce
+        let mut chain = verify_execution_chain(AGENT_ID, &journals).unwrap();
+        let replay = make_journal(3, chain.head());
+        assert!(matches!(
+            chain.append(&replay),
+            Err(ChainError::NonceNotIncreasing {
+                index: 3,
+                previous: 3,
+                actual: 3
+            })
+        ));
+        assert_eq!(chain.len(), 3);
+    }
+
+    #[test]
+    fn test_unchained_journal_rejected() {
+        // A v2 journal is the first 214 bytes of a v3 journal with kernel_version 2
+        let mut v2 = make_history(1).remove(0);
+        v2.truncate(214);
+        v2[4..8].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(
+            verify_execution_chain(AGENT_ID, &[v2]),
+            Err(ChainError::NotChained {
+                index: 0,
+                kernel_version: 2
+            })
+        ));
+    }
+}
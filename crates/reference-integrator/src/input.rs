@@ -1,15 +1,27 @@
 //! Helpers for constructing KernelInputV1 structures.
 //!
 //! This module provides utilities to build kernel inputs from bundle metadata
-//! and user-provided execution parameters.
+//! and user-provided execution parameters. The `*_chained_*` variants build a
+//! hash-chained `KernelInputV3` instead (see [`crate::chain`]), the
+//! `*_expiring_*` variants a `KernelInputV4` that also carries `valid_until`,
+//! and the `*_snapshot_*` variants a `KernelInputV5` that carries the state
+//! snapshot in its typed slot instead of in the opaque inputs.
 
 use crate::bundle::{BundleError, LoadedBundle};
-use kernel_core::{CanonicalEncode, CodecError, KernelInputV1, KERNEL_VERSION, PROTOCOL_VERSION};
+use constraints::DEFAULT_CONSTRAINT_SET_HASH;
+use kernel_core::{
+    CanonicalEncode, CodecError, KernelInputV1, KernelInputV3, KernelInputV4, KernelInputV5,
+    StateSnapshot, KERNEL_VERSION, KERNEL_VERSION_V3, KERNEL_VERSION_V4, KERNEL_VERSION_V5,
+    PROTOCOL_VERSION,
+};
 
 /// Parameters for building a kernel input.
 #[derive(Debug, Clone)]
 pub struct InputParams {
     /// Constraint set hash (32 bytes).
+    ///
+    /// Must be the hash of the constraint set the guest enforces; defaults
+    /// to the hash of `ConstraintSetV1::default()`.
     pub constraint_set_hash: [u8; 32],
     /// External state root / input root (32 bytes).
     pub input_root: [u8; 32],
@@ -22,7 +34,7 @@ pub struct InputParams {
 impl Default for InputParams {
     fn default() -> Self {
         Self {
-            constraint_set_hash: [0u8; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0u8; 32],
             execution_nonce: 1,
             opaque_agent_inputs: Vec::new(),
@@ -108,6 +120,151 @@ pub fn build_and_encode_input(
     input.encode().map_err(InputError::from)
 }
 
+/// Build a hash-chained KernelInputV3 from a loaded bundle and execution parameters.
+///
+/// Same as [`build_kernel_input`], but declares kernel version 3 and links the
+/// execution to the previous one.
+///
+/// # Arguments
+///
+/// * `bundle` - The loaded Agent Pack bundle
+/// * `params` - Execution parameters (constraint_set_hash, input_root, nonce, opaque_inputs)
+/// * `prev_journal_hash` - Hash of the agent's previous journal
+///   (`GENESIS_PREV_JOURNAL_HASH` for its first execution, else
+///   [`ExecutionChain::head`](crate::ExecutionChain::head))
+///
+/// # Returns
+///
+/// A `KernelInputV3` ready for encoding and proving.
+pub fn build_chained_kernel_input(
+    bundle: &LoadedBundle,
+    params: &InputParams,
+    prev_journal_hash: [u8; 32],
+) -> Result<KernelInputV3, InputError> {
+    let input = build_kernel_input(bundle, params)?;
+
+    Ok(KernelInputV3 {
+        protocol_version: input.protocol_version,
+        kernel_version: KERNEL_VERSION_V3,
+        agent_id: input.agent_id,
+        agent_code_hash: input.agent_code_hash,
+        constraint_set_hash: input.constraint_set_hash,
+        input_root: input.input_root,
+        execution_nonce: input.execution_nonce,
+        prev_journal_hash,
+        opaque_agent_inputs: input.opaque_agent_inputs,
+    })
+}
+
+/// Build and encode a hash-chained KernelInputV3 to bytes.
+///
+/// Chained counterpart of [`build_and_encode_input`].
+pub fn build_and_encode_chained_input(
+    bundle: &LoadedBundle,
+    params: &InputParams,
+    prev_journal_hash: [u8; 32],
+) -> Result<Vec<u8>, InputError> {
+    let input = build_chained_kernel_input(bundle, params, prev_journal_hash)?;
+    input.encode().map_err(InputError::from)
+}
+
+/// Build an expiring KernelInputV4 from a loaded bundle and execution parameters.
+///
+/// Same as [`build_chained_kernel_input`], but declares kernel version 4 and
+/// commits `valid_until`, the unix timestamp after which contracts reject the
+/// resulting proof. Pass `NO_EXPIRY` for an input that never expires.
+///
+/// # Returns
+///
+/// A `KernelInputV4` ready for encoding and proving.
+pub fn build_expiring_kernel_input(
+    bundle: &LoadedBundle,
+    params: &InputParams,
+    prev_journal_hash: [u8; 32],
+    valid_until: u64,
+) -> Result<KernelInputV4, InputError> {
+    let input = build_kernel_input(bundle, params)?;
+
+    Ok(KernelInputV4 {
+        protocol_version: input.protocol_version,
+        kernel_version: KERNEL_VERSION_V4,
+        agent_id: input.agent_id,
+        agent_code_hash: input.agent_code_hash,
+        constraint_set_hash: input.constraint_set_hash,
+        input_root: input.input_root,
+        execution_nonce: input.execution_nonce,
+        prev_journal_hash,
+        valid_until,
+        opaque_agent_inputs: input.opaque_agent_inputs,
+    })
+}
+
+/// Build and encode an expiring KernelInputV4 to bytes.
+///
+/// Expiring counterpart of [`build_and_encode_input`].
+pub fn build_and_encode_expiring_input(
+    bundle: &LoadedBundle,
+    params: &InputParams,
+    prev_journal_hash: [u8; 32],
+    valid_until: u64,
+) -> Result<Vec<u8>, InputError> {
+    let input = build_expiring_kernel_input(bundle, params, prev_journal_hash, valid_until)?;
+    input.encode().map_err(InputError::from)
+}
+
+/// Build a KernelInputV5 from a loaded bundle and execution parameters.
+///
+/// Same as [`build_expiring_kernel_input`], but declares kernel version 5
+/// and passes `state_snapshot` in the input's typed snapshot slot, so
+/// `params.opaque_agent_inputs` holds only agent-defined data.
+///
+/// # Returns
+///
+/// A `KernelInputV5` ready for encoding and proving.
+pub fn build_snapshot_kernel_input(
+    bundle: &LoadedBundle,
+    params: &InputParams,
+    prev_journal_hash: [u8; 32],
+    valid_until: u64,
+    state_snapshot: Option<StateSnapshot>,
+) -> Result<KernelInputV5, InputError> {
+    let input = build_kernel_input(bundle, params)?;
+
+    Ok(KernelInputV5 {
+        protocol_version: input.protocol_version,
+        kernel_version: KERNEL_VERSION_V5,
+        agent_id: input.agent_id,
+        agent_code_hash: input.agent_code_hash,
+        constraint_set_hash: input.constraint_set_hash,
+        input_root: input.input_root,
+        execution_nonce: input.execution_nonce,
+        prev_journal_hash,
+        valid_until,
+        state_snapshot,
+        opaque_agent_inputs: input.opaque_agent_inputs,
+    })
+}
+
+/// Build and encode a KernelInputV5 to bytes.
+///
+/// Snapshot counterpart of [`build_and_encode_input`].
+pub fn build_and_encode_snapshot_input(
+    bundle: &LoadedBundle,
+    params: &InputParams,
+    prev_journal_hash: [u8; 32],
+    valid_until: u64,
+    state_snapshot: Option<StateSnapshot>,
+) -> Result<Vec<u8>, InputError> {
+    let input = build_snapshot_kernel_input(
+        bundle,
+        params,
+        prev_journal_hash,
+        valid_until,
+        state_snapshot,
+    )?;
+    input.encode().map_err(InputError::from)
+}
+
 /// Build a KernelInputV1 from raw parameters (without a bundle).
 ///
 /// Use this when you have the raw values rather than a bundle.
@@ -175,7 +332,7 @@ mod tests {
     #[test]
     fn test_default_input_params() {
         let params = InputParams::default();
-        assert_eq!(params.constraint_set_hash, [0u8; 32]);
+        assert_eq!(params.constraint_set_hash, DEFAULT_CONSTRAINT_SET_HASH);
         assert_eq!(params.input_root, [0u8; 32]);
         assert_eq!(params.execution_nonce, 1);
         assert!(params.opaque_agent_inputs.is_empty());
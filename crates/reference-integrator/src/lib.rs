@@ -38,7 +38,7 @@
 //! ```rust,ignore
 //! use reference_integrator::{
 //!     LoadedBundle, verify_offline, verify_onchain, build_and_encode_input,
-//!     prove, execute_onchain, InputParams, ProvingMode,
+//!     prove, execute_onchain, CanonicalEncode, ConstraintSetV1, InputParams, ProvingMode,
 //! };
 //!
 //! // 1. Load and verify bundle
@@ -60,9 +60,10 @@
 //! };
 //! let input_bytes = build_and_encode_input(&bundle, &params)?;
 //!
-//! // 5. Generate proof
+//! // 5. Generate proof (default params bind the default constraint set)
 //! let elf = bundle.read_elf()?;
-//! let proof = prove(&elf, &input_bytes, ProvingMode::Groth16)?;
+//! let constraint_set_bytes = ConstraintSetV1::default().encode()?;
+//! let proof = prove(&elf, &input_bytes, &constraint_set_bytes, ProvingMode::Groth16)?;
 //!
 //! // 6. Execute on-chain
 //! let result = execute_onchain(
@@ -75,8 +76,10 @@
 
 pub mod agent_output;
 pub mod bundle;
+pub mod chain;
 pub mod execute;
 pub mod input;
+pub mod merkle;
 pub mod optimistic;
 pub mod predict;
 pub mod prove;
@@ -85,13 +88,21 @@ pub mod verify;
 // Re-export main types at crate root for convenience
 pub use agent_output::{reconstruct_yield_agent_output, AgentOutputError};
 pub use bundle::{BundleError, LoadedBundle};
+pub use chain::{verify_execution_chain, ChainError, ExecutionChain};
 pub use execute::{is_onchain_available, ExecuteError};
 pub use input::{
-    build_and_encode_input, build_kernel_input, build_kernel_input_raw, parse_hex, parse_hex_32,
-    InputError, InputParams,
+    build_and_encode_chained_input, build_and_encode_expiring_input, build_and_encode_input,
+    build_and_encode_snapshot_input, build_chained_kernel_input, build_expiring_kernel_input,
+    build_kernel_input, build_kernel_input_raw, build_snapshot_kernel_input, parse_hex,
+    parse_hex_32, InputError, InputParams,
+};
+pub use merkle::{
+    build_action_inclusion_proof, verify_action_inclusion, ActionInclusionProof, MerkleError,
 };
 pub use predict::{build_predicted_journal, build_predicted_journal_struct, PredictError};
-pub use prove::{is_proving_available, ProveError, ProveResult, ProvingMode};
+pub use prove::{
+    execute_guest, is_proving_available, ExecutionReport, ProveError, ProveResult, ProvingMode,
+};
 pub use verify::{verify_offline, verify_structure, OfflineVerificationResult, VerifyError};
 
 // Conditional re-exports based on features
@@ -111,10 +122,23 @@ pub use prove::prove;
 
 // Re-export useful types from dependencies
 pub use kernel_core::{
-    AgentOutput, CanonicalDecode, CanonicalEncode, ExecutionStatus, KernelInputV1, KernelJournalV1,
-    KERNEL_VERSION, PROTOCOL_VERSION,
+    compute_action_merkle_root, compute_journal_hash, AgentOutput, CanonicalDecode,
+    CanonicalEncode, ConstraintViolation, ConstraintViolationReason, ExecutionStatus,
+    KernelInputRef, KernelInputV1, KernelInputV3, KernelInputV4, KernelInputV5, KernelJournal,
+    KernelJournalV1, StateSnapshot, StateSnapshotV1, StateSnapshotV2, GENESIS_PREV_JOURNAL_HASH,
+    KERNEL_VERSION, KERNEL_VERSION_V3, KERNEL_VERSION_V4, KERNEL_VERSION_V5, NO_EXPIRY,
+    PROTOCOL_VERSION,
 };
 
+pub use constraints::{
+    compute_constraint_set_hash, diagnose_constraints, diagnose_constraints_ref, AllowedCall,
+    ApprovalPolicy, ConstraintSet, ConstraintSetV1, ConstraintSetV2, ConstraintSetV3,
+    Erc20Approval, NativeValueLimits, RollingWindowLimits, WindowUsage,
+    DEFAULT_CONSTRAINT_SET_HASH,
+};
+
+pub use constraints::policy::{compile_policy, CompiledPolicy, PolicyError, PolicyFormat};
+
 /// Crate version.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -0,0 +1,213 @@
+//! Action inclusion proofs over the Merkle action commitment.
+//!
+//! The kernel's `action_commitment` is a flat SHA-256 over the whole encoded
+//! [`AgentOutput`], so checking a single action requires every action. The
+//! Merkle action commitment ([`kernel_core::compute_action_merkle_root`])
+//! lets a settlement layer or auditor verify one action against the root
+//! with only `O(log n)` sibling hashes.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use reference_integrator::{
+//!     build_action_inclusion_proof, compute_action_merkle_root, verify_action_inclusion,
+//!     AgentOutput,
+//! };
+//!
+//! # let output = AgentOutput { actions: vec![] };
+//! let root = compute_action_merkle_root(&output).unwrap();
+//! let proof = build_action_inclusion_proof(&output, 1).unwrap();
+//!
+//! // Ship only `output.actions[1]` and `proof` to the verifier
+//! assert!(verify_action_inclusion(&root, &output.actions[1], &proof).unwrap());
+//! ```
+
+use kernel_core::{
+    action_merkle_leaf, action_merkle_node, action_merkle_root_from_top, ActionV1, AgentOutput,
+};
+
+/// Error type for action inclusion proofs.
+#[derive(Debug, thiserror::Error)]
+pub enum MerkleError {
+    /// Requested action index is outside the output.
+    #[error("Action index {index} out of range for {count} actions")]
+    IndexOutOfRange { index: usize, count: usize },
+
+    /// Failed to encode an action for hashing.
+    #[error("Failed to encode action: {0}")]
+    EncodeError(String),
+}
+
+/// Proof that an action is included at a given index of an [`AgentOutput`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionInclusionProof {
+    /// Index of the action in the agent-provided order.
+    pub action_index: u32,
+    /// Total number of actions in the output.
+    pub action_count: u32,
+    /// Sibling hashes from the leaf level upwards. Levels where the node is
+    /// promoted without a sibling contribute no entry.
+    pub siblings: Vec<[u8; 32]>,
+}
+
+/// Build an inclusion proof for the action at `index`.
+pub fn build_action_inclusion_proof(
+    output: &AgentOutput,
+    index: usize,
+) -> Result<ActionInclusionProof, MerkleError> {
+    let count = output.actions.len();
+    if index >= count {
+        return Err(MerkleError::IndexOutOfRange { index, count });
+    }
+
+    let mut level = output
+        .actions
+        .iter()
+        .map(|action| {
+            action_merkle_leaf(action).map_err(|e| MerkleError::EncodeError(format!("{:?}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut siblings = Vec::new();
+    let mut position = index;
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            siblings.push(level[sibling]);
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => action_merkle_node(left, right),
+                [single] => *single,
+                _ => unreachable!("chunks(2) yields one or two nodes"),
+            })
+            .collect();
+        position /= 2;
+    }
+
+    // Index and count are bounded by the u32 action count in the root
+    let to_u32 = |n: usize| {
+        u32::try_from(n).map_err(|_| MerkleError::EncodeError("action count overflow".into()))
+    };
+    Ok(ActionInclusionProof {
+        action_index: to_u32(index)?,
+        action_count: to_u32(count)?,
+        siblings,
+    })
+}
+
+/// Verify that `action` is included in the output committed to by `root`.
+///
+/// Returns `Ok(false)` for any proof that does not reproduce `root`,
+/// including malformed proofs (wrong number of siblings, index out of range).
+pub fn verify_action_inclusion(
+    root: &[u8; 32],
+    action: &ActionV1,
+    proof: &ActionInclusionProof,
+) -> Result<bool, MerkleError> {
+    if proof.action_index >= proof.action_count {
+        return Ok(false);
+    }
+
+    let mut node =
+        action_merkle_leaf(action).map_err(|e| MerkleError::EncodeError(format!("{:?}", e)))?;
+    let mut siblings = proof.siblings.iter();
+    let mut position = proof.action_index;
+    let mut width = proof.action_count;
+
+    while width > 1 {
+        let is_left = position & 1 == 0;
+        let is_unpaired_last = is_left && position == width - 1;
+        if !is_unpaired_last {
+            let Some(sibling) = siblings.next() else {
+                return Ok(false);
+            };
+            node = if is_left {
+                action_merkle_node(&node, sibling)
+            } else {
+                action_merkle_node(sibling, &node)
+            };
+        }
+        position /= 2;
+        width = width.div_ceil(2);
+    }
+
+    if siblings.next().is_some() {
+        return Ok(false);
+    }
+
+    Ok(action_merkle_root_from_top(proof.action_count, &node) == *root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel_core::{compute_action_merkle_root, ACTION_TYPE_NO_OP};
+
+    fn make_output(count: u8) -> AgentOutput {
+        AgentOutput {
+            actions: (0..count)
+                .map(|i| ActionV1 {
+                    action_type: ACTION_TYPE_NO_OP,
+                    target: [i; 32],
+                    payload: vec![],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_every_action_provable() {
+        for count in 1..=9u8 {
+            let output = make_output(count);
+            let root = compute_action_merkle_root(&output).unwrap();
+
+            for (index, action) in output.actions.iter().enumerate() {
+                let proof = build_action_inclusion_proof(&output, index).unwrap();
+                assert!(
+                    verify_action_inclusion(&root, action, &proof).unwrap(),
+                    "count {} index {}",
+                    count,
+                    index
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrong_action_or_index_rejected() {
+        let output = make_output(5);
+        let root = compute_action_merkle_root(&output).unwrap();
+        let proof = build_action_inclusion_proof(&output, 2).unwrap();
+
+        // Different action at the same position
+        assert!(!verify_action_inclusion(&root, &output.actions[3], &proof).unwrap());
+
+        // Same action claimed at another index or in a differently sized output
+        let moved = ActionInclusionProof {
+            action_index: 3,
+            ..proof.clone()
+        };
+        assert!(!verify_action_inclusion(&root, &output.actions[2], &moved).unwrap());
+        let resized = ActionInclusionProof {
+            action_count: 4,
+            ..proof.clone()
+        };
+        assert!(!verify_action_inclusion(&root, &output.actions[2], &resized).unwrap());
+
+        // Extra sibling
+        let mut padded = proof;
+        padded.siblings.push([0u8; 32]);
+        assert!(!verify_action_inclusion(&root, &output.actions[2], &padded).unwrap());
+    }
+
+    #[test]
+    fn test_index_out_of_range() {
+        let output = make_output(2);
+        assert!(matches!(
+            build_action_inclusion_proof(&output, 2),
+            Err(MerkleError::IndexOutOfRange { index: 2, count: 2 })
+        ));
+    }
+}
@@ -5,7 +5,7 @@
 
 #[cfg(feature = "prove")]
 use kernel_core::CanonicalDecode;
-use kernel_core::KernelJournalV1;
+use kernel_core::KernelJournal;
 
 /// Proving mode options.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -22,12 +22,31 @@ pub enum ProvingMode {
 /// Result of proof generation.
 #[derive(Debug, Clone)]
 pub struct ProveResult {
-    /// The journal bytes (209 bytes, contains execution result).
+    /// The journal bytes (209 bytes for v1, 214 for v2; contains execution result).
     pub journal_bytes: Vec<u8>,
     /// The seal bytes (proof data for on-chain verification).
     pub seal_bytes: Vec<u8>,
+    /// The decoded journal for inspection (v1 or v2, per the input's kernel_version).
+    pub journal: KernelJournal,
+    /// Guest cycles the execution consumed, excluding paging and padding.
+    pub user_cycles: u64,
+    /// Total cycles proven, including paging and padding.
+    pub total_cycles: u64,
+}
+
+/// Result of executing the guest without proving.
+#[derive(Debug, Clone)]
+pub struct ExecutionReport {
+    /// The journal bytes the guest committed.
+    pub journal_bytes: Vec<u8>,
     /// The decoded journal for inspection.
-    pub journal: KernelJournalV1,
+    pub journal: KernelJournal,
+    /// Guest cycles the execution consumed, excluding paging and padding.
+    pub user_cycles: u64,
+    /// Cycles a proof of this execution would cover, including paging and padding.
+    pub total_cycles: u64,
+    /// Number of segments the execution was split into.
+    pub segments: usize,
 }
 
 /// Errors that can occur during proving.
@@ -42,6 +61,9 @@ pub enum ProveError {
     #[error("Proof generation failed: {0}")]
     ProofGenerationFailed(String),
 
+    #[error("Guest execution failed: {0}")]
+    ExecutionFailed(String),
+
     #[error("Receipt verification failed: {0}")]
     ReceiptVerificationFailed(String),
 
@@ -56,13 +78,17 @@ pub enum ProveError {
 ///
 /// This function:
 /// 1. Loads the ELF binary from the bundle
-/// 2. Runs the zkVM prover with the input bytes
+/// 2. Runs the zkVM prover with the input bytes and constraint set bytes
 /// 3. Extracts the journal (execution result) and seal (proof)
 ///
+/// The guest enforces the provided constraint set and rejects the input
+/// unless its `constraint_set_hash` equals the hash of that set.
+///
 /// # Arguments
 ///
 /// * `elf_bytes` - The ELF binary bytes (from bundle.read_elf())
 /// * `input_bytes` - Encoded KernelInputV1 bytes
+/// * `constraint_set_bytes` - Encoded ConstraintSetV1 (60 bytes), ConstraintSetV2 or V3 bytes
 /// * `mode` - Proving mode (Groth16 for on-chain, Dev for testing)
 ///
 /// # Returns
@@ -76,16 +102,12 @@ pub enum ProveError {
 pub fn prove(
     elf_bytes: &[u8],
     input_bytes: &[u8],
+    constraint_set_bytes: &[u8],
     mode: ProvingMode,
 ) -> Result<ProveResult, ProveError> {
-    use risc0_zkvm::{default_prover, ExecutorEnv, ProverOpts};
+    use risc0_zkvm::{default_prover, ProverOpts};
 
-    // Build executor environment with input
-    let env = ExecutorEnv::builder()
-        .write(&input_bytes.to_vec())
-        .map_err(|e| ProveError::EnvBuildError(format!("Failed to write input: {}", e)))?
-        .build()
-        .map_err(|e| ProveError::EnvBuildError(e.to_string()))?;
+    let env = build_env(input_bytes, constraint_set_bytes, None)?;
 
     // Select prover options based on mode
     let opts = match mode {
@@ -99,13 +121,15 @@ pub fn prove(
         .prove_with_opts(env, elf_bytes, &opts)
         .map_err(|e| ProveError::ProofGenerationFailed(e.to_string()))?;
 
+    let user_cycles = prove_info.stats.user_cycles;
+    let total_cycles = prove_info.stats.total_cycles;
     let receipt = prove_info.receipt;
 
     // Extract journal bytes
     let journal_bytes = receipt.journal.bytes.clone();
 
     // Decode journal
-    let journal = KernelJournalV1::decode(&journal_bytes)
+    let journal = KernelJournal::decode(&journal_bytes)
         .map_err(|e| ProveError::JournalDecodeError(format!("{:?}", e)))?;
 
     // Extract seal bytes based on proof type
@@ -128,14 +152,86 @@ pub fn prove(
         journal_bytes,
         seal_bytes,
         journal,
+        user_cycles,
+        total_cycles,
+    })
+}
+
+/// Execute the guest without generating a proof and report its cycles.
+///
+/// Runs the same ELF, input and constraint set as [`prove`] in the RISC Zero
+/// executor. Use it to measure an agent before proving.
+///
+/// The constraint set's `step_budget` bounds only the steps an agent
+/// charges, so it cannot stop an agent that loops without charging any.
+/// `session_limit` caps the whole execution in cycles while it runs: an
+/// execution that reaches it stops with [`ProveError::ExecutionFailed`] and
+/// commits no journal.
+///
+/// # Feature
+///
+/// This function requires the `prove` feature to be enabled.
+#[cfg(feature = "prove")]
+pub fn execute_guest(
+    elf_bytes: &[u8],
+    input_bytes: &[u8],
+    constraint_set_bytes: &[u8],
+    session_limit: Option<u64>,
+) -> Result<ExecutionReport, ProveError> {
+    use risc0_zkvm::default_executor;
+
+    let env = build_env(input_bytes, constraint_set_bytes, session_limit)?;
+    let session = default_executor()
+        .execute(env, elf_bytes)
+        .map_err(|e| ProveError::ExecutionFailed(e.to_string()))?;
+
+    let journal_bytes = session.journal.bytes.clone();
+    let journal = KernelJournal::decode(&journal_bytes)
+        .map_err(|e| ProveError::JournalDecodeError(format!("{:?}", e)))?;
+
+    Ok(ExecutionReport {
+        journal_bytes,
+        journal,
+        user_cycles: session.segments.iter().map(|s| u64::from(s.cycles)).sum(),
+        total_cycles: session.cycles(),
+        segments: session.segments.len(),
     })
 }
 
+/// Build the executor environment with the input and constraint set.
+#[cfg(feature = "prove")]
+fn build_env(
+    input_bytes: &[u8],
+    constraint_set_bytes: &[u8],
+    session_limit: Option<u64>,
+) -> Result<risc0_zkvm::ExecutorEnv<'static>, ProveError> {
+    risc0_zkvm::ExecutorEnv::builder()
+        .write(&input_bytes.to_vec())
+        .map_err(|e| ProveError::EnvBuildError(format!("Failed to write input: {}", e)))?
+        .write(&constraint_set_bytes.to_vec())
+        .map_err(|e| ProveError::EnvBuildError(format!("Failed to write constraint set: {}", e)))?
+        .session_limit(session_limit)
+        .build()
+        .map_err(|e| ProveError::EnvBuildError(e.to_string()))
+}
+
+/// Stub implementation when prove feature is not enabled.
+#[cfg(not(feature = "prove"))]
+pub fn execute_guest(
+    _elf_bytes: &[u8],
+    _input_bytes: &[u8],
+    _constraint_set_bytes: &[u8],
+    _session_limit: Option<u64>,
+) -> Result<ExecutionReport, ProveError> {
+    Err(ProveError::FeatureNotEnabled)
+}
+
 /// Stub implementation when prove feature is not enabled.
 #[cfg(not(feature = "prove"))]
 pub fn prove(
     _elf_bytes: &[u8],
     _input_bytes: &[u8],
+    _constraint_set_bytes: &[u8],
     _mode: ProvingMode,
 ) -> Result<ProveResult, ProveError> {
     Err(ProveError::FeatureNotEnabled)
@@ -170,7 +266,14 @@ mod tests {
     #[cfg(not(feature = "prove"))]
     #[test]
     fn test_prove_without_feature() {
-        let result = prove(&[], &[], ProvingMode::Groth16);
+        let result = prove(&[], &[], &[], ProvingMode::Groth16);
+        assert!(matches!(result, Err(ProveError::FeatureNotEnabled)));
+    }
+
+    #[cfg(not(feature = "prove"))]
+    #[test]
+    fn test_execute_guest_without_feature() {
+        let result = execute_guest(&[], &[], &[], None);
         assert!(matches!(result, Err(ProveError::FeatureNotEnabled)));
     }
 }
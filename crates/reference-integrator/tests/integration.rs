@@ -298,4 +298,125 @@ mod cli_tests {
             serde_json::from_str(&stdout).expect("Output should be valid JSON");
         assert_eq!(json["success"], false);
     }
+
+    #[test]
+    fn test_check_constraints_lists_all_violations() {
+        use kernel_core::{ActionV1, ACTION_TYPE_NO_OP};
+        use reference_integrator::{
+            AgentOutput, CanonicalEncode, KernelInputV1, DEFAULT_CONSTRAINT_SET_HASH,
+        };
+
+        let binary = refint_binary();
+        if !binary.exists() {
+            eprintln!("Skipping CLI test: refint binary not found at {:?}", binary);
+            return;
+        }
+
+        let input = KernelInputV1 {
+            protocol_version: 1,
+            kernel_version: 1,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xAA; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
+            input_root: [0; 32],
+            execution_nonce: 1,
+            opaque_agent_inputs: vec![],
+        };
+        let bad_action = |action_type: u32, payload: Vec<u8>| ActionV1 {
+            action_type,
+            target: [0; 32],
+            payload,
+        };
+        let output = AgentOutput {
+            actions: vec![
+                bad_action(0xFF, vec![]),
+                bad_action(ACTION_TYPE_NO_OP, vec![]),
+                bad_action(ACTION_TYPE_NO_OP, vec![0x01]),
+            ],
+        };
+
+        let dir = std::env::temp_dir().join(format!("refint-check-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input_path = dir.join("input.bin");
+        let output_path = dir.join("output.bin");
+        std::fs::write(&input_path, input.encode().unwrap()).unwrap();
+        std::fs::write(&output_path, output.encode().unwrap()).unwrap();
+
+        let result = Command::new(&binary)
+            .args([
+                "check-constraints",
+                "--input",
+                input_path.to_str().unwrap(),
+                "--output",
+                output_path.to_str().unwrap(),
+                "--json",
+            ])
+            .output()
+            .expect("Failed to run refint");
+        std::fs::remove_dir_all(&dir).ok();
+
+        // Exit code 2 when any constraint is violated
+        assert_eq!(result.status.code(), Some(2));
+
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let json: serde_json::Value =
+            serde_json::from_str(&stdout).expect("Output should be valid JSON");
+        assert_eq!(json["success"], false);
+        let violations = json["violations"].as_array().unwrap();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0]["reason"], "UnknownActionType (0x02)");
+        assert_eq!(violations[0]["action_index"], 0);
+        assert_eq!(violations[1]["reason"], "InvalidActionPayload (0x0a)");
+        assert_eq!(violations[1]["action_index"], 2);
+    }
+
+    #[test]
+    fn test_compile_policy_writes_constraint_set() {
+        use reference_integrator::{compute_constraint_set_hash, CanonicalDecode, ConstraintSet};
+
+        let binary = refint_binary();
+        if !binary.exists() {
+            eprintln!("Skipping CLI test: refint binary not found at {:?}", binary);
+            return;
+        }
+
+        let dir = std::env::temp_dir().join(format!("refint-policy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let policy_path = dir.join("policy.toml");
+        let out_path = dir.join("constraint_set.bin");
+        std::fs::write(
+            &policy_path,
+            "[limits]\nmax_actions_per_output = 4\n\n[allowlists]\n\
+             call_targets = [\"0x2222222222222222222222222222222222222222\"]\n",
+        )
+        .unwrap();
+
+        let result = Command::new(&binary)
+            .args([
+                "compile-policy",
+                policy_path.to_str().unwrap(),
+                "--out",
+                out_path.to_str().unwrap(),
+                "--json",
+            ])
+            .output()
+            .expect("Failed to run refint");
+        let bytes = std::fs::read(&out_path);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(result.status.success());
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let json: serde_json::Value =
+            serde_json::from_str(&stdout).expect("Output should be valid JSON");
+        assert_eq!(json["success"], true);
+        assert_eq!(json["version"], 2);
+
+        let constraint_set = ConstraintSet::decode(&bytes.unwrap()).unwrap();
+        assert_eq!(constraint_set.max_actions_per_output(), 4);
+        let hash = compute_constraint_set_hash(&constraint_set).unwrap();
+        assert_eq!(
+            json["constraint_set_hash"],
+            format!("0x{}", hex::encode(hash))
+        );
+    }
 }
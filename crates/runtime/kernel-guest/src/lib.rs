@@ -6,15 +6,17 @@
 //!
 //! # Execution Flow
 //!
-//! 1. Decode input bytes → `KernelInputV1`
+//! 1. Decode input bytes in place → `KernelInputRef` (any input version;
+//!    the opaque agent inputs are borrowed, not copied)
 //! 2. Validate protocol and kernel versions
 //! 3. **Verify agent code hash matches injected agent** (P0.5)
-//! 4. Compute input commitment (SHA256)
-//! 5. Build `AgentContext` from kernel input
-//! 6. Call agent via `AgentEntrypoint` trait
-//! 7. Enforce constraints on agent output (UNSKIPPABLE)
-//! 8. Compute action commitment (SHA256)
-//! 9. Return encoded `KernelJournalV1`
+//! 4. **Verify constraint set hash matches enforced constraint set**
+//! 5. Compute input commitment (SHA256)
+//! 6. Build `AgentContext` from kernel input
+//! 7. Call agent via `AgentEntrypoint` trait, metering the steps it charges
+//! 8. Enforce the step budget and constraints on agent output (UNSKIPPABLE)
+//! 9. Compute action commitment (SHA256)
+//! 10. Return encoded `KernelJournalV1` (or `KernelJournalV2`, see below)
 //!
 //! # Agent Injection
 //!
@@ -22,6 +24,14 @@
 //! implement this trait to bind specific agents to the kernel without
 //! requiring kernel-guest to have dependencies on individual agent crates.
 //!
+//! The kernel calls [`AgentEntrypoint::try_run`]. An agent that rejects its
+//! inputs with an `AgentError` skips constraint enforcement and yields a
+//! journal with the `AgentError` execution status and the empty output
+//! commitment, so a malformed input is distinguishable from an agent that
+//! returned no actions. Only kernel v4 and later journals carry that status;
+//! the frozen v1-v3 journals commit the error as a `Failure`, with the
+//! global `InvalidOutputStructure` reason where the layout has one.
+//!
 //! # Agent Code Hash Binding (P0.5)
 //!
 //! The kernel verifies that `KernelInputV1.agent_code_hash` matches the
@@ -32,14 +42,136 @@
 //! - Verifiers can trust that the claimed agent actually executed
 //!
 //! If the hash doesn't match, `KernelError::AgentCodeHashMismatch` is returned.
+//!
+//! # Constraint Set Binding
+//!
+//! The kernel recomputes the hash of the [`ConstraintSet`] it is about to
+//! enforce (SHA-256 of its canonical encoding, 60 bytes for a
+//! `ConstraintSetV1`) and requires it to equal
+//! `KernelInputV1.constraint_set_hash`. Since that hash is copied into
+//! the journal, a verifier that checks `constraint_set_hash` knows exactly
+//! which constraints were enforced.
+//!
+//! If the hash doesn't match, `KernelError::ConstraintSetHashMismatch` is
+//! returned and no journal is produced. Inputs executed through
+//! [`kernel_main_with_agent`] must therefore carry
+//! [`constraints::DEFAULT_CONSTRAINT_SET_HASH`]. zkVM guests that enforce a
+//! host-provided constraint set use [`kernel_main_with_agent_and_constraint_bytes`].
+//!
+//! A `ConstraintSetV2` additionally restricts CALL targets and functions and
+//! TRANSFER_ERC20 tokens to its allowlists. A `ConstraintSetV3` may also
+//! limit executions and notional per rolling window, which requires a
+//! kernel version 5 input carrying a `StateSnapshotV2`, restrict the
+//! spenders and allowances of ERC20 `approve` calls, cap the native value
+//! CALLs send and budget the steps the agent charges.
+//!
+//! Guests that enforce custom `ConstraintRule`s build a [`RuleSet`] and use
+//! [`kernel_main_with_agent_and_rules`]; the input's `constraint_set_hash`
+//! must then be the hash of the rule set, which commits to every rule.
+//!
+//! # Journal Version
+//!
+//! The input's `kernel_version` selects the journal format. Version 1 inputs
+//! produce a 209-byte `KernelJournalV1`. Version 2 inputs produce a 214-byte
+//! `KernelJournalV2`, which additionally commits to the
+//! `ConstraintViolationReason` and violating action index on Failure.
+//!
+//! # Execution Hash-Chaining
+//!
+//! Version 3 inputs are `KernelInputV3`, which adds `prev_journal_hash`: the
+//! SHA-256 of the agent's previous journal, or zero for its first execution.
+//! The kernel copies it into a 246-byte `KernelJournalV3`, so consecutive
+//! journals form a hash chain that host tooling can verify back to genesis.
+//! The link itself is not checked in the guest.
+//!
+//! # Input Expiry
+//!
+//! Version 4 inputs are `KernelInputV4`, which adds `valid_until` to the
+//! chained layout. The kernel copies it into a 254-byte `KernelJournalV4` so
+//! contracts can reject stale proofs, and fails the execution with
+//! `InputExpired` when the input's `StateSnapshotV1` reports a `current_ts`
+//! past `valid_until`.
+//!
+//! # Typed State Snapshot
+//!
+//! Version 5 inputs are `KernelInputV5`, which adds a typed `state_snapshot`
+//! slot to the expiring layout. The kernel hands that snapshot to both the
+//! constraint engine and the agent (as `AgentContext::state_snapshot`), and
+//! never interprets `opaque_agent_inputs`. Earlier versions have no slot, so
+//! the constraint engine reads their snapshot from the first 36 bytes of the
+//! opaque inputs. Version 5 journals are `KernelJournalV5`, which shares the
+//! `KernelJournalV4` layout.
+//!
+//! # Step Budget
+//!
+//! A `ConstraintSetV3` may declare a `step_budget`. The kernel hands the
+//! agent a `StepMeter` holding the budget in its `AgentContext`, and the
+//! agent charges the steps it takes with `AgentContext::charge_steps`,
+//! which fails once the budget is exhausted so the agent can stop early.
+//! When the agent returns, the kernel fails the execution with
+//! `StepBudgetExceeded` if it charged more than the budget, whatever it
+//! returned, producing a Failure journal instead of an output.
+//!
+//! The steps charged depend only on the agent's code and inputs, so host
+//! and zkVM runs of the same input always agree on the outcome and the
+//! proof covers it. The budget bounds only the work the agent meters: an
+//! agent that loops without charging steps still runs until the prover's
+//! own limit. [`kernel_run_with_agent_and_rules`] reports the steps an
+//! agent charged in a [`KernelRun`], and `reference_integrator`'s
+//! `execute_guest` reports the cycles a whole guest run consumed.
+//!
+//! # Bounded Allocation
+//!
+//! Guests keep the RISC Zero platform allocator as their only global
+//! allocator: `risc0-zkvm` installs a bump allocator that hands out heap
+//! in order and never frees, so a guest must not declare its own
+//! `#[global_allocator]`. Inside the zkVM (`risc0` feature) the kernel
+//! reads how much of that heap has been handed out before and after it
+//! calls the agent, and aborts the guest when the agent allocated more than
+//! [`MAX_ALLOCATION_BYTES`]. Only the agent's own allocations count: the
+//! input buffers, the decoded input and the journal the kernel builds do
+//! not, so a batch of maximum-size inputs is bounded per step. Because
+//! nothing is freed, the bytes handed out are also the most the agent ever
+//! held, and they depend only on the input: the same input always exceeds
+//! the limit and no proof is produced. The check runs once the agent
+//! returns, so an allocation that exhausts the zkVM's memory first still
+//! aborts the guest through the platform allocator.
+//!
+//! On the host, [`BoundedAllocator`] wraps the system allocator, caps the
+//! bytes in use and records the peak. Host tests measure what a single call
+//! would take from the guest heap with [`measure_peak_allocation`], which
+//! replays the bump allocator: every allocation is counted with its
+//! alignment, a reallocation takes a fresh block, and frees return nothing.
+//! Host types are at least as large as their 32-bit guest counterparts, so
+//! an agent within the limit on the host is within it in the guest.
+//!
+//! # Batched Execution
+//!
+//! [`kernel_batch_main_with_agent_and_constraints`] executes a
+//! `KernelBatchInputV1` (up to `MAX_BATCH_STEPS` inputs with consecutive
+//! nonces) inside a single proof. Each step runs the flow above unchanged,
+//! and the proof commits to a `KernelBatchJournalV1` holding the hash of
+//! every step journal.
 
-use constraints::{enforce_constraints, ConstraintSetV1, EMPTY_OUTPUT_COMMITMENT};
+use constraints::{
+    compute_constraint_set_hash, enforce_rule_set_ref, ConstraintSet, RuleSet,
+    EMPTY_OUTPUT_COMMITMENT,
+};
+#[cfg(not(target_os = "zkvm"))]
+use core::cell::Cell;
+#[cfg(not(target_os = "zkvm"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
 use kernel_core::*;
-use kernel_sdk::agent::AgentContext;
+use kernel_sdk::agent::{AgentContext, AgentError};
+#[cfg(not(target_os = "zkvm"))]
+use std::alloc::{GlobalAlloc, Layout};
 
 // Re-export KernelError for wrapper crates to use.
 pub use kernel_core::KernelError;
 
+// Re-export the heap limit the kernel enforces on agents inside the zkVM.
+pub use kernel_core::MAX_ALLOCATION_BYTES;
+
 // ============================================================================
 // Agent Entrypoint Trait
 // ============================================================================
@@ -88,6 +220,258 @@ pub trait AgentEntrypoint {
     ///
     /// `AgentOutput` containing ordered actions to be executed.
     fn run(&self, ctx: &AgentContext, opaque_inputs: &[u8]) -> AgentOutput;
+
+    /// Executes the agent logic, allowing the agent to reject its inputs.
+    ///
+    /// This is the method the kernel calls. An `Err` produces a journal with
+    /// the `AgentError` status and an empty output. The default delegates
+    /// to [`run`](AgentEntrypoint::run) and never fails.
+    fn try_run(&self, ctx: &AgentContext, opaque_inputs: &[u8]) -> Result<AgentOutput, AgentError> {
+        Ok(self.run(ctx, opaque_inputs))
+    }
+}
+
+// ============================================================================
+// Metered Execution
+// ============================================================================
+
+/// Result of a metered kernel execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelRun {
+    /// Canonical encoding of the journal
+    pub journal: Vec<u8>,
+    /// Steps the agent charged to its `StepMeter`
+    pub agent_steps: u64,
+    /// Error the agent returned instead of an output, if any
+    pub agent_error: Option<AgentError>,
+}
+
+// ============================================================================
+// Bounded Allocation
+// ============================================================================
+
+/// Heap bytes the guest has allocated so far.
+///
+/// Read from the platform's bump allocator, which never frees, so the
+/// difference between two readings is exactly what was allocated in
+/// between. Guests must keep that default heap: with `risc0-zkvm`'s
+/// `heap-embedded-alloc` feature the platform reports zero.
+#[cfg(all(feature = "risc0", target_os = "zkvm"))]
+pub fn guest_heap_used() -> usize {
+    risc0_zkvm_platform::heap::used()
+}
+
+/// Abort the guest if the agent allocated more than [`MAX_ALLOCATION_BYTES`].
+///
+/// `heap_before` is the [`guest_heap_used`] reading taken right before the
+/// agent was called.
+///
+/// # Panics
+///
+/// Panics when the limit is exceeded, which aborts proof generation.
+#[cfg(all(feature = "risc0", target_os = "zkvm"))]
+fn enforce_agent_heap_limit(heap_before: usize) {
+    let allocated = guest_heap_used() - heap_before;
+    assert!(
+        allocated <= MAX_ALLOCATION_BYTES,
+        "agent allocated {} heap bytes, limit is {}",
+        allocated,
+        MAX_ALLOCATION_BYTES
+    );
+}
+
+/// Global allocator for host tests that bounds the bytes in use and tracks
+/// the peak.
+///
+/// Wraps another allocator (the system allocator by default). Allocations
+/// that would take the bytes in use past `limit` return null, which makes
+/// the allocating code abort with an allocation error. Not available inside
+/// the zkVM, where the platform allocator is the global allocator and the
+/// kernel bounds the agent's share of it.
+///
+/// # Example
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: kernel_guest::BoundedAllocator =
+///     kernel_guest::BoundedAllocator::new(usize::MAX);
+/// ```
+#[cfg(not(target_os = "zkvm"))]
+#[derive(Debug)]
+pub struct BoundedAllocator<A = std::alloc::System> {
+    inner: A,
+    limit: usize,
+    in_use: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+#[cfg(not(target_os = "zkvm"))]
+impl BoundedAllocator {
+    /// Bound the system allocator to `limit` bytes in use.
+    pub const fn new(limit: usize) -> Self {
+        Self::with_allocator(std::alloc::System, limit)
+    }
+}
+
+#[cfg(not(target_os = "zkvm"))]
+impl<A> BoundedAllocator<A> {
+    /// Bound `inner` to `limit` bytes in use.
+    pub const fn with_allocator(inner: A, limit: usize) -> Self {
+        Self {
+            inner,
+            limit,
+            in_use: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+
+    /// Maximum number of bytes in use at once.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Bytes currently allocated through this allocator.
+    pub fn in_use(&self) -> usize {
+        self.in_use.load(Ordering::Relaxed)
+    }
+
+    /// Largest number of bytes that were in use at once.
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    /// Account for `size` more bytes, unless that would exceed the limit.
+    fn reserve(&self, size: usize) -> bool {
+        let reserved = self
+            .in_use
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |in_use| {
+                in_use
+                    .checked_add(size)
+                    .filter(|&total| total <= self.limit)
+            });
+        match reserved {
+            Ok(previous) => {
+                self.peak.fetch_max(previous + size, Ordering::Relaxed);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Account for `size` bytes being freed.
+    fn release(&self, size: usize) {
+        self.in_use.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+#[cfg(not(target_os = "zkvm"))]
+unsafe impl<A: GlobalAlloc> GlobalAlloc for BoundedAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if !self.reserve(layout.size()) {
+            return core::ptr::null_mut();
+        }
+        let ptr = self.inner.alloc(layout);
+        if ptr.is_null() {
+            self.release(layout.size());
+        } else {
+            record_thread_allocation(layout);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if !self.reserve(layout.size()) {
+            return core::ptr::null_mut();
+        }
+        let ptr = self.inner.alloc_zeroed(layout);
+        if ptr.is_null() {
+            self.release(layout.size());
+        } else {
+            record_thread_allocation(layout);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        self.release(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let old_size = layout.size();
+        if new_size > old_size && !self.reserve(new_size - old_size) {
+            return core::ptr::null_mut();
+        }
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        match (new_ptr.is_null(), new_size > old_size) {
+            // Growth failed: return the reservation
+            (true, true) => self.release(new_size - old_size),
+            // Shrink succeeded: release the difference
+            (false, false) => self.release(old_size - new_size),
+            _ => {}
+        }
+        if !new_ptr.is_null() {
+            // The guest's bump allocator reallocates into a fresh block
+            record_thread_allocation(Layout::from_size_align_unchecked(new_size, layout.align()));
+        }
+        new_ptr
+    }
+}
+
+#[cfg(not(target_os = "zkvm"))]
+std::thread_local! {
+    /// Guest heap bytes this thread allocated within
+    /// [`measure_peak_allocation`], while it is measured.
+    static THREAD_ALLOCATION: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Word size of the zkVM guest, the minimum alignment of its heap.
+#[cfg(not(target_os = "zkvm"))]
+const GUEST_WORD_SIZE: usize = 4;
+
+/// Heap bytes the guest's bump allocator takes for `layout`, at most.
+///
+/// The size is rounded up to a whole word, plus the padding an alignment
+/// beyond a word can need.
+#[cfg(not(target_os = "zkvm"))]
+fn guest_allocation_size(layout: Layout) -> usize {
+    let align = layout.align().max(GUEST_WORD_SIZE);
+    layout.size().div_ceil(GUEST_WORD_SIZE) * GUEST_WORD_SIZE + (align - GUEST_WORD_SIZE)
+}
+
+/// Record an allocation made by the current thread while it is measured.
+#[cfg(not(target_os = "zkvm"))]
+fn record_thread_allocation(layout: Layout) {
+    // try_with: the thread-local may already be gone during thread teardown
+    let _ = THREAD_ALLOCATION.try_with(|current| {
+        if let Some(allocated) = current.get() {
+            current.set(Some(allocated + guest_allocation_size(layout)));
+        }
+    });
+}
+
+/// Run `f` and return the peak number of bytes it would take from the zkVM
+/// guest heap, counting allocations made through a [`BoundedAllocator`] on
+/// the current thread.
+///
+/// The guest heap never frees, so its peak is everything allocated: each
+/// allocation and reallocation counts in full, with word alignment, and
+/// frees are ignored. Only allocations made while `f` runs count. Returns
+/// zero when no `BoundedAllocator` is the global allocator. Not available
+/// inside the zkVM, where the kernel reads the heap itself.
+#[cfg(not(target_os = "zkvm"))]
+pub fn measure_peak_allocation(f: &mut dyn FnMut()) -> usize {
+    let outer = THREAD_ALLOCATION.with(|current| current.replace(Some(0)));
+    f();
+    let allocated = THREAD_ALLOCATION
+        .with(|current| current.replace(outer))
+        .expect("measurement scope is set");
+
+    // A measurement nested in another one also counts towards the outer one
+    if let Some(outer) = outer {
+        THREAD_ALLOCATION.with(|current| current.set(Some(outer + allocated)));
+    }
+    allocated
 }
 
 // ============================================================================
@@ -120,7 +504,7 @@ pub trait AgentEntrypoint {
 ///
 /// # Returns
 ///
-/// * `Ok(Vec<u8>)` - Canonical encoding of KernelJournalV1 (always produced)
+/// * `Ok(Vec<u8>)` - Canonical encoding of KernelJournalV1 or V2 (always produced)
 /// * `Err(KernelError)` - Critical failure (decoding, version mismatch, hash mismatch)
 ///
 /// # Determinism
@@ -131,7 +515,33 @@ pub fn kernel_main_with_agent(
     input_bytes: &[u8],
     agent: &dyn AgentEntrypoint,
 ) -> Result<Vec<u8>, KernelError> {
-    kernel_main_with_agent_and_constraints(input_bytes, agent, &ConstraintSetV1::default())
+    kernel_main_with_agent_and_constraints(input_bytes, agent, &ConstraintSet::default())
+}
+
+/// Execute kernel with an injected agent and a canonically encoded constraint set.
+///
+/// This is the entry point for zkVM guests whose constraint set is supplied
+/// by the host alongside the input. The bytes are decoded as a
+/// [`ConstraintSet`] of any version and then handled exactly like
+/// [`kernel_main_with_agent_and_constraints`].
+///
+/// # Arguments
+///
+/// * `input_bytes` - Canonical encoding of KernelInputV1
+/// * `constraint_set_bytes` - Canonical encoding of a ConstraintSetV1 (60 bytes), V2 or V3
+/// * `agent` - Agent implementation (provides code hash and execution logic)
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - Canonical encoding of KernelJournalV1 or V2 (always produced)
+/// * `Err(KernelError)` - Critical failure (decoding, version mismatch, hash mismatch)
+pub fn kernel_main_with_agent_and_constraint_bytes(
+    input_bytes: &[u8],
+    constraint_set_bytes: &[u8],
+    agent: &dyn AgentEntrypoint,
+) -> Result<Vec<u8>, KernelError> {
+    let constraint_set = ConstraintSet::decode(constraint_set_bytes)?;
+    kernel_main_with_agent_and_constraints(input_bytes, agent, &constraint_set)
 }
 
 /// Execute kernel with an injected agent and custom constraint set.
@@ -139,6 +549,9 @@ pub fn kernel_main_with_agent(
 /// This variant allows specifying a custom constraint set instead of
 /// using the default. Useful for testing and specialized deployments.
 ///
+/// The input's `constraint_set_hash` must equal the hash of `constraint_set`
+/// (see [`compute_constraint_set_hash`]).
+///
 /// # Arguments
 ///
 /// * `input_bytes` - Canonical encoding of KernelInputV1
@@ -147,16 +560,72 @@ pub fn kernel_main_with_agent(
 ///
 /// # Returns
 ///
-/// * `Ok(Vec<u8>)` - Canonical encoding of KernelJournalV1 (always produced)
+/// * `Ok(Vec<u8>)` - Canonical encoding of KernelJournalV1 or V2 (always produced)
 /// * `Err(KernelError)` - Critical failure (decoding, version mismatch, hash mismatch)
 pub fn kernel_main_with_agent_and_constraints(
     input_bytes: &[u8],
     agent: &dyn AgentEntrypoint,
-    constraint_set: &ConstraintSetV1,
+    constraint_set: &ConstraintSet,
+) -> Result<Vec<u8>, KernelError> {
+    kernel_main_with_agent_and_rules(input_bytes, agent, &RuleSet::new(constraint_set.clone()))
+}
+
+/// Execute kernel with an injected agent and a rule set.
+///
+/// Like [`kernel_main_with_agent_and_constraints`], but also enforces the
+/// rule set's custom rules. The input's `constraint_set_hash` must equal the
+/// hash of `rule_set`, which equals that of its constraint set when it has
+/// no custom rules.
+///
+/// # Arguments
+///
+/// * `input_bytes` - Canonical encoding of KernelInputV1
+/// * `agent` - Agent implementation (provides code hash and execution logic)
+/// * `rule_set` - Constraint set and custom rules to enforce
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - Canonical encoding of KernelJournalV1 or V2 (always produced)
+/// * `Err(KernelError)` - Critical failure (decoding, version mismatch, hash mismatch)
+pub fn kernel_main_with_agent_and_rules(
+    input_bytes: &[u8],
+    agent: &dyn AgentEntrypoint,
+    rule_set: &RuleSet,
 ) -> Result<Vec<u8>, KernelError> {
-    // 1. Decode input
-    let input = KernelInputV1::decode(input_bytes)?;
+    kernel_run_with_agent_and_rules(input_bytes, agent, rule_set).map(|run| run.journal)
+}
+
+/// Execute kernel with an injected agent and a rule set, reporting how the
+/// agent ran.
+///
+/// Like [`kernel_main_with_agent_and_rules`], but also reports the steps the
+/// agent charged and the error it returned, if any.
+///
+/// # Returns
+///
+/// * `Ok(KernelRun)` - Journal (always produced), agent steps and agent error
+/// * `Err(KernelError)` - Critical failure (decoding, version mismatch, hash mismatch)
+pub fn kernel_run_with_agent_and_rules(
+    input_bytes: &[u8],
+    agent: &dyn AgentEntrypoint,
+    rule_set: &RuleSet,
+) -> Result<KernelRun, KernelError> {
+    // 1. Decode input (any supported version) in place: the opaque agent
+    // inputs are borrowed from input_bytes rather than copied
+    let input = KernelInputRef::decode(input_bytes)?;
+    execute_input(&input, input_bytes, agent, rule_set)
+}
 
+/// Execute steps 2-11 for an input that has already been decoded.
+///
+/// `input_bytes` must be the canonical encoding of the decoded input; it is
+/// hashed into the input commitment.
+fn execute_input(
+    input: &KernelInputRef<'_>,
+    input_bytes: &[u8],
+    agent: &dyn AgentEntrypoint,
+    rule_set: &RuleSet,
+) -> Result<KernelRun, KernelError> {
     // 2. Validate versions (already checked in decode, but be explicit)
     if input.protocol_version != PROTOCOL_VERSION {
         return Err(KernelError::UnsupportedProtocolVersion {
@@ -165,7 +634,19 @@ pub fn kernel_main_with_agent_and_constraints(
         });
     }
 
-    if input.kernel_version != KERNEL_VERSION {
+    // Only v5 inputs have a typed snapshot slot
+    let snapshot_ok = input.state_snapshot.is_none() || input.kernel_version == KERNEL_VERSION_V5;
+    let version_ok = match (input.prev_journal_hash, input.valid_until) {
+        (Some(_), Some(_)) => {
+            input.kernel_version == KERNEL_VERSION_V4 || input.kernel_version == KERNEL_VERSION_V5
+        }
+        (Some(_), None) => input.kernel_version == KERNEL_VERSION_V3,
+        (None, None) => {
+            input.kernel_version == KERNEL_VERSION || input.kernel_version == KERNEL_VERSION_V2
+        }
+        (None, Some(_)) => false,
+    };
+    if !version_ok || !snapshot_ok {
         return Err(KernelError::UnsupportedKernelVersion {
             expected: KERNEL_VERSION,
             actual: input.kernel_version,
@@ -181,10 +662,22 @@ pub fn kernel_main_with_agent_and_constraints(
         return Err(KernelError::AgentCodeHashMismatch);
     }
 
-    // 4. Compute input commitment (over full input bytes)
+    // 4. Verify constraint set hash matches the enforced constraint set
+    //
+    // The journal commits to input.constraint_set_hash, so the constraints
+    // enforced below must be the ones that hash declares. A constraint set
+    // that cannot be canonically encoded can never match.
+    let enforced_hash = compute_constraint_set_hash(rule_set)
+        .map_err(|_| KernelError::ConstraintSetHashMismatch)?;
+    if input.constraint_set_hash != enforced_hash {
+        return Err(KernelError::ConstraintSetHashMismatch);
+    }
+
+    // 5. Compute input commitment (over full input bytes)
     let input_commitment = compute_input_commitment(input_bytes);
 
-    // 5. Build agent context from input (using kernel-sdk AgentContext)
+    // 6. Build agent context from input (using kernel-sdk AgentContext),
+    // metering the agent against the constraint set's step budget
     let agent_ctx = AgentContext::new(
         input.protocol_version,
         input.kernel_version,
@@ -193,30 +686,60 @@ pub fn kernel_main_with_agent_and_constraints(
         input.constraint_set_hash,
         input.input_root,
         input.execution_nonce,
-    );
+    )
+    .with_state_snapshot(input.state_snapshot)
+    .with_step_budget(rule_set.constraint_set().step_budget());
 
-    // 6. Execute agent via injected implementation
-    let agent_output = agent.run(&agent_ctx, &input.opaque_agent_inputs);
+    // 7. Execute agent via injected implementation, bounding the heap it
+    // allocates inside the zkVM
+    #[cfg(all(feature = "risc0", target_os = "zkvm"))]
+    let heap_before = guest_heap_used();
+    let agent_result = agent.try_run(&agent_ctx, input.opaque_agent_inputs);
+    #[cfg(all(feature = "risc0", target_os = "zkvm"))]
+    enforce_agent_heap_limit(heap_before);
+    let agent_steps = agent_ctx.step_meter.used();
 
-    // 7. ENFORCE CONSTRAINTS (UNSKIPPABLE)
+    // 8. ENFORCE CONSTRAINTS (UNSKIPPABLE)
     // This is the critical safety check that validates all agent actions.
-    let (validated_output, execution_status) =
-        match enforce_constraints(&input, &agent_output, constraint_set) {
-            Ok(validated) => {
-                // Constraints passed - use validated output
-                (validated, ExecutionStatus::Success)
-            }
-            Err(_violation) => {
-                // Constraints violated - use empty output and Failure status
-                // The violation details are not included in the journal for P0.3
-                // but could be logged or added in future versions.
-                (AgentOutput { actions: vec![] }, ExecutionStatus::Failure)
+    // Expiring inputs are additionally checked against their snapshot time,
+    // which v5 inputs carry in their typed snapshot slot. An agent over its
+    // step budget fails whatever it returned, and an agent that rejected its
+    // inputs produced no output to enforce.
+    let (validated_output, violation, agent_error) = match agent_result {
+        _ if agent_ctx.step_meter.is_exceeded() => (
+            AgentOutput { actions: vec![] },
+            Some(ConstraintViolation::global(
+                ConstraintViolationReason::StepBudgetExceeded,
+            )),
+            None,
+        ),
+        Ok(agent_output) => {
+            match enforce_rule_set_ref(input, &agent_output, rule_set) {
+                Ok(validated) => {
+                    // Constraints passed - use validated output
+                    (validated, None, None)
+                }
+                Err(violation) => {
+                    // Constraints violated - use empty output and Failure status.
+                    // The violation details are only committed by v2+ journals.
+                    (AgentOutput { actions: vec![] }, Some(violation), None)
+                }
             }
-        };
+        }
+        Err(agent_error) => {
+            // Agent error - use empty output and AgentError status
+            (AgentOutput { actions: vec![] }, None, Some(agent_error))
+        }
+    };
+    let execution_status = match (&violation, agent_error) {
+        (_, Some(_)) => ExecutionStatus::AgentError,
+        (None, None) => ExecutionStatus::Success,
+        (Some(_), None) => ExecutionStatus::Failure,
+    };
 
-    // 8. Compute action commitment
+    // 9. Compute action commitment
     // On Success: computed over validated output
-    // On Failure: computed over empty output (deterministic constant)
+    // On Failure or AgentError: computed over empty output (deterministic constant)
     let action_commitment = if execution_status == ExecutionStatus::Success {
         let output_bytes = validated_output
             .encode()
@@ -227,7 +750,93 @@ pub fn kernel_main_with_agent_and_constraints(
         EMPTY_OUTPUT_COMMITMENT
     };
 
-    // 9. Construct journal with all identity and commitment fields
+    // 10. Construct journal with all identity and commitment fields
+    let violation_action_index = match violation.as_ref().and_then(|v| v.action_index) {
+        Some(index) => Some(
+            u32::try_from(index)
+                .map_err(|_| KernelError::EncodingFailed(CodecError::ArithmeticOverflow))?,
+        ),
+        None => None,
+    };
+
+    if let (Some(prev_journal_hash), Some(valid_until)) =
+        (input.prev_journal_hash, input.valid_until)
+    {
+        // KernelJournalV5 is the KernelJournalV4 layout, so both versions
+        // build the same struct
+        let journal = KernelJournalV4 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: input.kernel_version,
+            agent_id: input.agent_id,
+            agent_code_hash: input.agent_code_hash,
+            constraint_set_hash: input.constraint_set_hash,
+            input_root: input.input_root,
+            execution_nonce: input.execution_nonce,
+            input_commitment,
+            action_commitment,
+            execution_status,
+            violation_reason: violation.as_ref().map(|v| v.reason),
+            violation_action_index,
+            prev_journal_hash,
+            valid_until,
+        };
+
+        // 11. Encode and return journal (always produced)
+        return encode_run(&journal, agent_steps, agent_error);
+    }
+
+    // Journals before kernel v4 are frozen at Success and Failure, so they
+    // commit an agent error as a global InvalidOutputStructure failure
+    let (execution_status, violation_reason) = if agent_error.is_some() {
+        (
+            ExecutionStatus::Failure,
+            Some(ConstraintViolationReason::InvalidOutputStructure),
+        )
+    } else {
+        (execution_status, violation.as_ref().map(|v| v.reason))
+    };
+
+    if let Some(prev_journal_hash) = input.prev_journal_hash {
+        let journal = KernelJournalV3 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V3,
+            agent_id: input.agent_id,
+            agent_code_hash: input.agent_code_hash,
+            constraint_set_hash: input.constraint_set_hash,
+            input_root: input.input_root,
+            execution_nonce: input.execution_nonce,
+            input_commitment,
+            action_commitment,
+            execution_status,
+            violation_reason,
+            violation_action_index,
+            prev_journal_hash,
+        };
+
+        // 11. Encode and return journal (always produced)
+        return encode_run(&journal, agent_steps, agent_error);
+    }
+
+    if input.kernel_version == KERNEL_VERSION_V2 {
+        let journal = KernelJournalV2 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V2,
+            agent_id: input.agent_id,
+            agent_code_hash: input.agent_code_hash,
+            constraint_set_hash: input.constraint_set_hash,
+            input_root: input.input_root,
+            execution_nonce: input.execution_nonce,
+            input_commitment,
+            action_commitment,
+            execution_status,
+            violation_reason,
+            violation_action_index,
+        };
+
+        // 11. Encode and return journal (always produced)
+        return encode_run(&journal, agent_steps, agent_error);
+    }
+
     let journal = KernelJournalV1 {
         protocol_version: PROTOCOL_VERSION,
         kernel_version: KERNEL_VERSION,
@@ -241,6 +850,157 @@ pub fn kernel_main_with_agent_and_constraints(
         execution_status,
     };
 
-    // 10. Encode and return journal (always produced)
+    // 11. Encode and return journal (always produced)
+    encode_run(&journal, agent_steps, agent_error)
+}
+
+/// Encode `journal` into the [`KernelRun`] of an execution.
+fn encode_run(
+    journal: &impl CanonicalEncode,
+    agent_steps: u64,
+    agent_error: Option<AgentError>,
+) -> Result<KernelRun, KernelError> {
+    Ok(KernelRun {
+        journal: journal.encode().map_err(KernelError::EncodingFailed)?,
+        agent_steps,
+        agent_error,
+    })
+}
+
+// ============================================================================
+// Batched Execution
+// ============================================================================
+
+/// Execute every step of a batch and return each step's encoded journal.
+///
+/// Steps run in order through the same path as
+/// [`kernel_main_with_agent_and_constraints`], so constraints are enforced
+/// per step and a constraint violation yields a Failure step journal rather
+/// than aborting the batch. Any critical error in any step aborts the whole
+/// batch.
+///
+/// Before executing, the batch is checked to share `kernel_version`,
+/// `agent_id`, `agent_code_hash` and `constraint_set_hash` across steps, and
+/// to use consecutive nonces.
+///
+/// # Arguments
+///
+/// * `batch` - Batch input decoded in place
+/// * `agent` - Agent implementation (provides code hash and execution logic)
+/// * `constraint_set` - Constraint set enforced on every step
+pub fn execute_batch_steps(
+    batch: &KernelBatchInputRef<'_>,
+    agent: &dyn AgentEntrypoint,
+    constraint_set: &ConstraintSet,
+) -> Result<Vec<Vec<u8>>, KernelError> {
+    execute_batch_steps_with_rules(batch, agent, &RuleSet::new(constraint_set.clone()))
+}
+
+/// [`execute_batch_steps`] enforcing a rule set on every step.
+pub fn execute_batch_steps_with_rules(
+    batch: &KernelBatchInputRef<'_>,
+    agent: &dyn AgentEntrypoint,
+    rule_set: &RuleSet,
+) -> Result<Vec<Vec<u8>>, KernelError> {
+    let first = &batch
+        .steps
+        .first()
+        .ok_or(KernelError::Codec(CodecError::InvalidBatchSize {
+            count: 0,
+            limit: MAX_BATCH_STEPS,
+        }))?
+        .input;
+
+    for (index, pair) in batch.steps.windows(2).enumerate() {
+        let (prev, step) = (&pair[0].input, &pair[1].input);
+        let step_index = (index + 1) as u32;
+
+        if step.kernel_version != first.kernel_version
+            || step.agent_id != first.agent_id
+            || step.agent_code_hash != first.agent_code_hash
+            || step.constraint_set_hash != first.constraint_set_hash
+        {
+            return Err(KernelError::BatchStepMismatch { step: step_index });
+        }
+        if prev.execution_nonce.checked_add(1) != Some(step.execution_nonce) {
+            return Err(KernelError::NonConsecutiveBatchNonce { step: step_index });
+        }
+    }
+
+    batch
+        .steps
+        .iter()
+        // Each step's bytes in the batch are its standalone input encoding
+        .map(|step| execute_input(&step.input, step.bytes, agent, rule_set).map(|run| run.journal))
+        .collect()
+}
+
+/// Execute a batch of inputs with an injected agent and custom constraint set.
+///
+/// Runs [`execute_batch_steps`] and commits to the result in a
+/// `KernelBatchJournalV1` holding the SHA-256 hash of every step journal.
+/// A step journal produced here is byte-identical to the journal of the same
+/// input executed on its own.
+///
+/// # Arguments
+///
+/// * `batch_bytes` - Canonical encoding of KernelBatchInputV1
+/// * `agent` - Agent implementation (provides code hash and execution logic)
+/// * `constraint_set` - Constraint set enforced on every step
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - Canonical encoding of KernelBatchJournalV1
+/// * `Err(KernelError)` - Critical failure in decoding, batch validation or any step
+pub fn kernel_batch_main_with_agent_and_constraints(
+    batch_bytes: &[u8],
+    agent: &dyn AgentEntrypoint,
+    constraint_set: &ConstraintSet,
+) -> Result<Vec<u8>, KernelError> {
+    kernel_batch_main_with_agent_and_rules(
+        batch_bytes,
+        agent,
+        &RuleSet::new(constraint_set.clone()),
+    )
+}
+
+/// Execute a batch of inputs with an injected agent and a rule set.
+///
+/// The batch counterpart of [`kernel_main_with_agent_and_rules`]; otherwise
+/// identical to [`kernel_batch_main_with_agent_and_constraints`].
+pub fn kernel_batch_main_with_agent_and_rules(
+    batch_bytes: &[u8],
+    agent: &dyn AgentEntrypoint,
+    rule_set: &RuleSet,
+) -> Result<Vec<u8>, KernelError> {
+    let batch = KernelBatchInputRef::decode(batch_bytes)?;
+    let step_journals = execute_batch_steps_with_rules(&batch, agent, rule_set)?;
+
+    let first = &batch.steps[0].input;
+    let journal = KernelBatchJournalV1 {
+        protocol_version: PROTOCOL_VERSION,
+        kernel_version: first.kernel_version,
+        agent_id: first.agent_id,
+        agent_code_hash: first.agent_code_hash,
+        constraint_set_hash: first.constraint_set_hash,
+        first_execution_nonce: first.execution_nonce,
+        step_journal_hashes: step_journals
+            .iter()
+            .map(|journal| compute_journal_hash(journal))
+            .collect(),
+    };
+
     journal.encode().map_err(KernelError::EncodingFailed)
 }
+
+/// Execute a batch with an injected agent and a canonically encoded constraint set.
+///
+/// The batch counterpart of [`kernel_main_with_agent_and_constraint_bytes`].
+pub fn kernel_batch_main_with_agent_and_constraint_bytes(
+    batch_bytes: &[u8],
+    constraint_set_bytes: &[u8],
+    agent: &dyn AgentEntrypoint,
+) -> Result<Vec<u8>, KernelError> {
+    let constraint_set = ConstraintSet::decode(constraint_set_bytes)?;
+    kernel_batch_main_with_agent_and_constraints(batch_bytes, agent, &constraint_set)
+}
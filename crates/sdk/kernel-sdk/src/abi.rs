@@ -0,0 +1,704 @@
+//! Solidity ABI encoding of contract call arguments.
+//!
+//! Encodes static words, `bytes`, `string`, dynamic arrays, fixed arrays and
+//! (nested) tuples with the standard head/tail layout: every value of a
+//! sequence gets a head slot, static values are stored in place, and dynamic
+//! values store an offset (from the start of the sequence) to their encoding
+//! in the tail.
+//!
+//! Values are borrowed, so building them does not allocate. The encoded
+//! length is computed before anything is written and is bounded by
+//! [`MAX_CALLDATA_BYTES`]; encoding then allocates exactly once.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use kernel_sdk::abi::{encode, AbiValue};
+//!
+//! // exactInput((bytes,address,uint256,uint256,uint256))
+//! let params = [
+//!     AbiValue::Bytes(&path),
+//!     AbiValue::address(&recipient),
+//!     AbiValue::uint(deadline),
+//!     AbiValue::uint(amount_in),
+//!     AbiValue::uint(amount_out_min),
+//! ];
+//! let args = encode(&[AbiValue::Tuple(&params)])?;
+//! ```
+
+use crate::keccak::keccak256;
+use crate::types::{address_to_bytes32, MAX_ACTION_PAYLOAD_BYTES};
+use crate::u256::U256;
+use alloc::vec::Vec;
+
+/// Size of an ABI word.
+pub const WORD_SIZE: usize = 32;
+
+/// Maximum calldata length (selector included) that fits in a CALL action.
+///
+/// The CALL payload spends three words on the value, offset and length
+/// before the calldata, which is padded to a whole number of words.
+pub const MAX_CALLDATA_BYTES: usize = MAX_ACTION_PAYLOAD_BYTES - 3 * WORD_SIZE;
+
+// ============================================================================
+// Values
+// ============================================================================
+
+/// A Solidity ABI value.
+///
+/// Composite values borrow their elements, so nested values are built from
+/// slices: `AbiValue::Tuple(&[AbiValue::uint(1), AbiValue::Bytes(b"..")])`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbiValue<'a> {
+    /// Static 32-byte word: `uintN`, `intN`, `address`, `bool` or `bytesN`.
+    Word([u8; 32]),
+    /// Dynamic `bytes`.
+    Bytes(&'a [u8]),
+    /// Dynamic `string`, encoded as its UTF-8 bytes.
+    String(&'a str),
+    /// Dynamic array `T[]`. All elements must have the same type.
+    Array(&'a [AbiValue<'a>]),
+    /// Fixed-size array `T[k]`. All elements must have the same type.
+    FixedArray(&'a [AbiValue<'a>]),
+    /// Tuple `(T1,...,Tn)`, which is also how structs are encoded.
+    Tuple(&'a [AbiValue<'a>]),
+}
+
+/// Error returned when values cannot be ABI-encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbiError {
+    /// The encoding would exceed [`MAX_CALLDATA_BYTES`].
+    TooLarge,
+    /// The elements of an array do not all have the same type.
+    MixedArrayElements,
+}
+
+impl<'a> AbiValue<'a> {
+    /// `address`, left-padded to 32 bytes.
+    #[inline]
+    #[must_use]
+    pub fn address(addr: &[u8; 20]) -> Self {
+        Self::Word(address_to_bytes32(addr))
+    }
+
+    /// `uintN` for any N up to 128 (big-endian, right-aligned).
+    #[inline]
+    #[must_use]
+    pub fn uint(val: u128) -> Self {
+        let mut word = [0u8; 32];
+        word[16..32].copy_from_slice(&val.to_be_bytes());
+        Self::Word(word)
+    }
+
+    /// `uint256` over its full range.
+    #[inline]
+    #[must_use]
+    pub fn uint256(val: U256) -> Self {
+        Self::Word(val.to_be_bytes())
+    }
+
+    /// `intN` for any N up to 128 (two's complement, sign-extended).
+    #[inline]
+    #[must_use]
+    pub fn int(val: i128) -> Self {
+        let mut word = if val < 0 { [0xffu8; 32] } else { [0u8; 32] };
+        word[16..32].copy_from_slice(&val.to_be_bytes());
+        Self::Word(word)
+    }
+
+    /// `bool` (0 or 1, right-aligned).
+    #[inline]
+    #[must_use]
+    pub fn bool(val: bool) -> Self {
+        let mut word = [0u8; 32];
+        word[31] = u8::from(val);
+        Self::Word(word)
+    }
+
+    /// `bytesN` for N = `val.len()`, right-padded with zeros.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `val` is longer than 32 bytes.
+    #[inline]
+    #[must_use]
+    pub fn fixed_bytes(val: &[u8]) -> Self {
+        assert!(val.len() <= WORD_SIZE, "bytesN holds at most 32 bytes");
+        let mut word = [0u8; 32];
+        word[..val.len()].copy_from_slice(val);
+        Self::Word(word)
+    }
+
+    /// Whether the value is encoded in the tail, behind an offset.
+    ///
+    /// `bytes`, `string` and `T[]` are always dynamic; fixed arrays and
+    /// tuples are dynamic if any of their elements is.
+    #[must_use]
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            Self::Word(_) => false,
+            Self::Bytes(_) | Self::String(_) | Self::Array(_) => true,
+            Self::FixedArray(elems) | Self::Tuple(elems) => elems.iter().any(Self::is_dynamic),
+        }
+    }
+
+    /// Length of the value's encoding in bytes.
+    ///
+    /// For a dynamic value this is the length of its tail, excluding the
+    /// offset word in the enclosing head.
+    pub fn encoded_len(&self) -> Result<usize, AbiError> {
+        match self {
+            Self::Word(_) => Ok(WORD_SIZE),
+            Self::Bytes(data) => bytes_len(data.len()),
+            Self::String(s) => bytes_len(s.len()),
+            Self::Array(elems) => {
+                check_same_type(elems)?;
+                sequence_len(elems)?
+                    .checked_add(WORD_SIZE)
+                    .ok_or(AbiError::TooLarge)
+            }
+            Self::FixedArray(elems) => {
+                check_same_type(elems)?;
+                sequence_len(elems)
+            }
+            Self::Tuple(elems) => sequence_len(elems),
+        }
+    }
+
+    /// Length of the value's head slot in an enclosing sequence.
+    fn head_len(&self) -> Result<usize, AbiError> {
+        if self.is_dynamic() {
+            Ok(WORD_SIZE)
+        } else {
+            self.encoded_len()
+        }
+    }
+
+    /// Append the value's encoding (its tail, if dynamic) to `out`.
+    pub(crate) fn write(&self, out: &mut Vec<u8>) -> Result<(), AbiError> {
+        match self {
+            Self::Word(word) => out.extend_from_slice(word),
+            Self::Bytes(data) => write_bytes(data, out),
+            Self::String(s) => write_bytes(s.as_bytes(), out),
+            Self::Array(elems) => {
+                out.extend_from_slice(&usize_word(elems.len()));
+                write_sequence(elems, out)?;
+            }
+            Self::FixedArray(elems) | Self::Tuple(elems) => write_sequence(elems, out)?,
+        }
+        Ok(())
+    }
+}
+
+impl From<U256> for AbiValue<'_> {
+    fn from(val: U256) -> Self {
+        Self::uint256(val)
+    }
+}
+
+// ============================================================================
+// Function Selectors
+// ============================================================================
+
+/// Function selector of a canonical signature such as
+/// `"transfer(address,uint256)"`: the first four bytes of its Keccak-256
+/// hash, big-endian.
+///
+/// Prefer the [`selector!`](crate::selector) macro, which evaluates this at
+/// compile time so that a malformed signature fails the build.
+///
+/// # Panics
+///
+/// Panics if `signature` is not canonical: it must be a name followed by a
+/// balanced, parenthesised parameter list, contain no whitespace, and spell
+/// out `uint256`/`int256` rather than the `uint`/`int` aliases (which hash
+/// to a different selector).
+#[must_use]
+pub const fn function_selector(signature: &str) -> u32 {
+    let sig = signature.as_bytes();
+    check_signature(sig);
+    let hash = keccak256(sig);
+    u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]])
+}
+
+/// Panic unless `sig` is a canonical function signature.
+const fn check_signature(sig: &[u8]) {
+    let mut i = 0;
+    while i < sig.len() && sig[i] != b'(' {
+        let c = sig[i];
+        if !(c.is_ascii_alphanumeric() || c == b'_' || c == b'$') {
+            panic!("invalid character in function name");
+        }
+        i += 1;
+    }
+    if i == 0 {
+        panic!("function signature has no name");
+    }
+    if i == sig.len() || sig[sig.len() - 1] != b')' {
+        panic!("function signature must end with a parameter list");
+    }
+
+    let mut depth = 0usize;
+    let mut token_start = i;
+    while i < sig.len() {
+        let c = sig[i];
+        if c.is_ascii_whitespace() {
+            panic!("function signature must not contain whitespace");
+        }
+        let is_delimiter = matches!(c, b'(' | b')' | b',' | b'[' | b']');
+        if is_delimiter {
+            if is_alias(sig, token_start, i) {
+                panic!("use uint256/int256 instead of the uint/int alias");
+            }
+            token_start = i + 1;
+        }
+        if c == b'(' {
+            depth += 1;
+        } else if c == b')' {
+            if depth == 0 {
+                panic!("unbalanced parentheses in function signature");
+            }
+            depth -= 1;
+            if depth == 0 && i != sig.len() - 1 {
+                panic!("unexpected characters after parameter list");
+            }
+        }
+        i += 1;
+    }
+    if depth != 0 {
+        panic!("unbalanced parentheses in function signature");
+    }
+}
+
+/// Whether `sig[start..end]` is the `uint` or `int` type alias.
+const fn is_alias(sig: &[u8], start: usize, end: usize) -> bool {
+    match end - start {
+        3 => sig[start] == b'i' && sig[start + 1] == b'n' && sig[start + 2] == b't',
+        4 => sig[start] == b'u' && is_alias(sig, start + 1, end),
+        _ => false,
+    }
+}
+
+// ============================================================================
+// Encoding
+// ============================================================================
+
+/// ABI-encode `values` as a function's argument list (without selector).
+///
+/// Returns [`AbiError::TooLarge`] if the encoding would exceed
+/// [`MAX_CALLDATA_BYTES`]; nothing is allocated in that case.
+pub fn encode(values: &[AbiValue<'_>]) -> Result<Vec<u8>, AbiError> {
+    let len = sequence_len(values)?;
+    if len > MAX_CALLDATA_BYTES {
+        return Err(AbiError::TooLarge);
+    }
+    let mut out = Vec::with_capacity(len);
+    write_sequence(values, &mut out)?;
+    debug_assert_eq!(out.len(), len);
+    Ok(out)
+}
+
+/// ABI-encode `values` and prefix them with a 4-byte function selector.
+pub fn encode_call(selector: u32, values: &[AbiValue<'_>]) -> Result<Vec<u8>, AbiError> {
+    let len = sequence_len(values)?
+        .checked_add(4)
+        .ok_or(AbiError::TooLarge)?;
+    if len > MAX_CALLDATA_BYTES {
+        return Err(AbiError::TooLarge);
+    }
+    let mut out = Vec::with_capacity(len);
+    out.extend_from_slice(&selector.to_be_bytes());
+    write_sequence(values, &mut out)?;
+    Ok(out)
+}
+
+/// 32-byte big-endian word holding `n` (a length or an offset).
+#[inline]
+pub(crate) fn usize_word(n: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..32].copy_from_slice(&(n as u64).to_be_bytes());
+    word
+}
+
+/// Encoded length of `bytes`/`string` data: length word plus padded data.
+fn bytes_len(len: usize) -> Result<usize, AbiError> {
+    len.checked_next_multiple_of(WORD_SIZE)
+        .and_then(|padded| padded.checked_add(WORD_SIZE))
+        .ok_or(AbiError::TooLarge)
+}
+
+/// Encoded length of a sequence: all heads plus all tails.
+fn sequence_len(elems: &[AbiValue<'_>]) -> Result<usize, AbiError> {
+    elems.iter().try_fold(0usize, |total, elem| {
+        let len = if elem.is_dynamic() {
+            elem.encoded_len()?
+                .checked_add(WORD_SIZE)
+                .ok_or(AbiError::TooLarge)?
+        } else {
+            elem.encoded_len()?
+        };
+        total.checked_add(len).ok_or(AbiError::TooLarge)
+    })
+}
+
+/// Reject arrays whose elements differ in kind or shape.
+fn check_same_type(elems: &[AbiValue<'_>]) -> Result<(), AbiError> {
+    let Some(first) = elems.first() else {
+        return Ok(());
+    };
+    let same = |elem: &AbiValue<'_>| match (first, elem) {
+        (AbiValue::Tuple(a), AbiValue::Tuple(b))
+        | (AbiValue::FixedArray(a), AbiValue::FixedArray(b)) => {
+            a.len() == b.len() && elem.is_dynamic() == first.is_dynamic()
+        }
+        _ => core::mem::discriminant(first) == core::mem::discriminant(elem),
+    };
+    if elems.iter().all(same) {
+        Ok(())
+    } else {
+        Err(AbiError::MixedArrayElements)
+    }
+}
+
+/// Append `data` as `bytes`: length word, then the data padded to a word.
+fn write_bytes(data: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(&usize_word(data.len()));
+    out.extend_from_slice(data);
+    let padding = data.len().next_multiple_of(WORD_SIZE) - data.len();
+    out.resize(out.len() + padding, 0);
+}
+
+/// Append a sequence: the heads of all elements, then the dynamic tails.
+fn write_sequence(elems: &[AbiValue<'_>], out: &mut Vec<u8>) -> Result<(), AbiError> {
+    let mut tail_offset = elems
+        .iter()
+        .try_fold(0usize, |total, elem| Ok(total + elem.head_len()?))?;
+    for elem in elems {
+        if elem.is_dynamic() {
+            out.extend_from_slice(&usize_word(tail_offset));
+            tail_offset += elem.encoded_len()?;
+        } else {
+            elem.write(out)?;
+        }
+    }
+    for elem in elems.iter().filter(|elem| elem.is_dynamic()) {
+        elem.write(out)?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decode a hex string of whitespace-separated words.
+    fn words(hex: &str) -> Vec<u8> {
+        let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        digits
+            .chunks(2)
+            .map(|pair| {
+                let s = core::str::from_utf8(pair).unwrap();
+                u8::from_str_radix(s, 16).unwrap()
+            })
+            .collect()
+    }
+
+    // Vectors from the Solidity ABI specification examples.
+
+    #[test]
+    fn test_encode_static_args() {
+        // baz(uint32,bool) with (69, true)
+        let calldata =
+            encode_call(0xcdcd77c0, &[AbiValue::uint(69), AbiValue::bool(true)]).unwrap();
+        let expected = words(
+            "cdcd77c0
+             0000000000000000000000000000000000000000000000000000000000000045
+             0000000000000000000000000000000000000000000000000000000000000001",
+        );
+        assert_eq!(calldata, expected);
+    }
+
+    #[test]
+    fn test_encode_fixed_array_of_bytes3() {
+        // bar(bytes3[2]) with (["abc", "def"])
+        let elems = [AbiValue::fixed_bytes(b"abc"), AbiValue::fixed_bytes(b"def")];
+        let calldata = encode_call(0xfce353f6, &[AbiValue::FixedArray(&elems)]).unwrap();
+        let expected = words(
+            "fce353f6
+             6162630000000000000000000000000000000000000000000000000000000000
+             6465660000000000000000000000000000000000000000000000000000000000",
+        );
+        assert_eq!(calldata, expected);
+    }
+
+    #[test]
+    fn test_encode_bytes_bool_array() {
+        // sam(bytes,bool,uint256[]) with ("dave", true, [1, 2, 3])
+        let list = [AbiValue::uint(1), AbiValue::uint(2), AbiValue::uint(3)];
+        let calldata = encode_call(
+            0xa5643bf2,
+            &[
+                AbiValue::Bytes(b"dave"),
+                AbiValue::bool(true),
+                AbiValue::Array(&list),
+            ],
+        )
+        .unwrap();
+        let expected = words(
+            "a5643bf2
+             0000000000000000000000000000000000000000000000000000000000000060
+             0000000000000000000000000000000000000000000000000000000000000001
+             00000000000000000000000000000000000000000000000000000000000000a0
+             0000000000000000000000000000000000000000000000000000000000000004
+             6461766500000000000000000000000000000000000000000000000000000000
+             0000000000000000000000000000000000000000000000000000000000000003
+             0000000000000000000000000000000000000000000000000000000000000001
+             0000000000000000000000000000000000000000000000000000000000000002
+             0000000000000000000000000000000000000000000000000000000000000003",
+        );
+        assert_eq!(calldata, expected);
+    }
+
+    #[test]
+    fn test_encode_mixed_static_and_dynamic() {
+        // f(uint256,uint32[],bytes10,bytes)
+        // with (0x123, [0x456, 0x789], "1234567890", "Hello, world!")
+        let list = [AbiValue::uint(0x456), AbiValue::uint(0x789)];
+        let calldata = encode_call(
+            0x8be65246,
+            &[
+                AbiValue::uint(0x123),
+                AbiValue::Array(&list),
+                AbiValue::fixed_bytes(b"1234567890"),
+                AbiValue::Bytes(b"Hello, world!"),
+            ],
+        )
+        .unwrap();
+        let expected = words(
+            "8be65246
+             0000000000000000000000000000000000000000000000000000000000000123
+             0000000000000000000000000000000000000000000000000000000000000080
+             3132333435363738393000000000000000000000000000000000000000000000
+             00000000000000000000000000000000000000000000000000000000000000e0
+             0000000000000000000000000000000000000000000000000000000000000002
+             0000000000000000000000000000000000000000000000000000000000000456
+             0000000000000000000000000000000000000000000000000000000000000789
+             000000000000000000000000000000000000000000000000000000000000000d
+             48656c6c6f2c20776f726c642100000000000000000000000000000000000000",
+        );
+        assert_eq!(calldata, expected);
+    }
+
+    #[test]
+    fn test_encode_nested_dynamic_arrays() {
+        // g(uint256[][],string[]) with ([[1, 2], [3]], ["one", "two", "three"])
+        let first = [AbiValue::uint(1), AbiValue::uint(2)];
+        let second = [AbiValue::uint(3)];
+        let nested = [AbiValue::Array(&first), AbiValue::Array(&second)];
+        let strings = [
+            AbiValue::String("one"),
+            AbiValue::String("two"),
+            AbiValue::String("three"),
+        ];
+        let calldata = encode_call(
+            0x2289b18c,
+            &[AbiValue::Array(&nested), AbiValue::Array(&strings)],
+        )
+        .unwrap();
+        let expected = words(
+            "2289b18c
+             0000000000000000000000000000000000000000000000000000000000000040
+             0000000000000000000000000000000000000000000000000000000000000140
+             0000000000000000000000000000000000000000000000000000000000000002
+             0000000000000000000000000000000000000000000000000000000000000040
+             00000000000000000000000000000000000000000000000000000000000000a0
+             0000000000000000000000000000000000000000000000000000000000000002
+             0000000000000000000000000000000000000000000000000000000000000001
+             0000000000000000000000000000000000000000000000000000000000000002
+             0000000000000000000000000000000000000000000000000000000000000001
+             0000000000000000000000000000000000000000000000000000000000000003
+             0000000000000000000000000000000000000000000000000000000000000003
+             0000000000000000000000000000000000000000000000000000000000000060
+             00000000000000000000000000000000000000000000000000000000000000a0
+             00000000000000000000000000000000000000000000000000000000000000e0
+             0000000000000000000000000000000000000000000000000000000000000003
+             6f6e650000000000000000000000000000000000000000000000000000000000
+             0000000000000000000000000000000000000000000000000000000000000003
+             74776f0000000000000000000000000000000000000000000000000000000000
+             0000000000000000000000000000000000000000000000000000000000000005
+             7468726565000000000000000000000000000000000000000000000000000000",
+        );
+        assert_eq!(calldata, expected);
+    }
+
+    #[test]
+    fn test_encode_static_tuple_in_place() {
+        // A tuple of static values is encoded in place, without an offset
+        let pair = [AbiValue::uint(1), AbiValue::bool(true)];
+        let encoded = encode(&[AbiValue::Tuple(&pair), AbiValue::uint(2)]).unwrap();
+        let expected = words(
+            "0000000000000000000000000000000000000000000000000000000000000001
+             0000000000000000000000000000000000000000000000000000000000000001
+             0000000000000000000000000000000000000000000000000000000000000002",
+        );
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_encode_uniswap_exact_input() {
+        // exactInput((bytes path,address recipient,uint256 deadline,
+        //             uint256 amountIn,uint256 amountOutMinimum))
+        let mut path = Vec::new();
+        path.extend_from_slice(&[0xaa; 20]);
+        path.extend_from_slice(&[0x00, 0x01, 0xf4]); // fee 500
+        path.extend_from_slice(&[0xbb; 20]);
+        let params = [
+            AbiValue::Bytes(&path),
+            AbiValue::address(&[0xcc; 20]),
+            AbiValue::uint(1_700_000_000),
+            AbiValue::uint(1_000_000),
+            AbiValue::uint(990_000),
+        ];
+        let calldata = encode_call(0xc04b8d59, &[AbiValue::Tuple(&params)]).unwrap();
+        let expected = words(
+            "c04b8d59
+             0000000000000000000000000000000000000000000000000000000000000020
+             00000000000000000000000000000000000000000000000000000000000000a0
+             000000000000000000000000cccccccccccccccccccccccccccccccccccccccc
+             000000000000000000000000000000000000000000000000000000006553f100
+             00000000000000000000000000000000000000000000000000000000000f4240
+             00000000000000000000000000000000000000000000000000000000000f1b30
+             000000000000000000000000000000000000000000000000000000000000002b
+             aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa0001f4bbbbbbbbbbbbbbbbbb
+             bbbbbbbbbbbbbbbbbbbbbb000000000000000000000000000000000000000000",
+        );
+        assert_eq!(calldata, expected);
+    }
+
+    #[test]
+    fn test_encode_empty_values() {
+        assert_eq!(encode(&[]).unwrap(), Vec::<u8>::new());
+        assert_eq!(
+            encode(&[AbiValue::Bytes(&[]), AbiValue::Array(&[])]).unwrap(),
+            words(
+                "0000000000000000000000000000000000000000000000000000000000000040
+                 0000000000000000000000000000000000000000000000000000000000000060
+                 0000000000000000000000000000000000000000000000000000000000000000
+                 0000000000000000000000000000000000000000000000000000000000000000"
+            )
+        );
+    }
+
+    #[test]
+    fn test_encode_int_sign_extended() {
+        let AbiValue::Word(word) = AbiValue::int(-1) else {
+            panic!("int is a word");
+        };
+        assert_eq!(word, [0xff; 32]);
+        let AbiValue::Word(word) = AbiValue::int(-2) else {
+            panic!("int is a word");
+        };
+        assert_eq!(word[31], 0xfe);
+        assert!(word[..31].iter().all(|&b| b == 0xff));
+    }
+
+    #[test]
+    fn test_encode_rejects_mixed_array() {
+        let mixed = [AbiValue::uint(1), AbiValue::Bytes(b"x")];
+        assert_eq!(
+            encode(&[AbiValue::Array(&mixed)]),
+            Err(AbiError::MixedArrayElements)
+        );
+        let a = [AbiValue::uint(1)];
+        let b = [AbiValue::uint(1), AbiValue::uint(2)];
+        let tuples = [AbiValue::Tuple(&a), AbiValue::Tuple(&b)];
+        assert_eq!(
+            encode(&[AbiValue::FixedArray(&tuples)]),
+            Err(AbiError::MixedArrayElements)
+        );
+    }
+
+    #[test]
+    fn test_encode_rejects_oversized() {
+        let data = [0u8; MAX_CALLDATA_BYTES];
+        assert_eq!(encode(&[AbiValue::Bytes(&data)]), Err(AbiError::TooLarge));
+
+        // Largest bytes argument that still fits next to a selector
+        let fits = MAX_CALLDATA_BYTES - 4 - 2 * WORD_SIZE;
+        let fits = fits - fits % WORD_SIZE;
+        let calldata = encode_call(0x12345678, &[AbiValue::Bytes(&data[..fits])]).unwrap();
+        assert!(calldata.len() <= MAX_CALLDATA_BYTES);
+    }
+
+    // ====================================================================
+    // Function selectors
+    // ====================================================================
+
+    #[test]
+    fn test_function_selector_known_values() {
+        assert_eq!(function_selector("transfer(address,uint256)"), 0xa9059cbb);
+        assert_eq!(function_selector("approve(address,uint256)"), 0x095ea7b3);
+        // Selectors of the Solidity specification examples above
+        assert_eq!(function_selector("baz(uint32,bool)"), 0xcdcd77c0);
+        assert_eq!(function_selector("bar(bytes3[2])"), 0xfce353f6);
+        assert_eq!(function_selector("sam(bytes,bool,uint256[])"), 0xa5643bf2);
+        assert_eq!(
+            function_selector("f(uint256,uint32[],bytes10,bytes)"),
+            0x8be65246
+        );
+        assert_eq!(function_selector("g(uint256[][],string[])"), 0x2289b18c);
+        // Tuple parameter
+        assert_eq!(
+            function_selector("exactInput((bytes,address,uint256,uint256,uint256))"),
+            0xc04b8d59
+        );
+        assert_eq!(function_selector("f()"), 0x26121ff0);
+    }
+
+    #[test]
+    fn test_selector_macro_is_const() {
+        const SELECTOR: u32 = crate::selector!("transfer(address,uint256)");
+        assert_eq!(SELECTOR, 0xa9059cbb);
+        assert_eq!(crate::selector!("approve(address,uint256)"), 0x095ea7b3);
+    }
+
+    #[test]
+    #[should_panic(expected = "whitespace")]
+    fn test_function_selector_rejects_whitespace() {
+        let _ = function_selector("transfer(address, uint256)");
+    }
+
+    #[test]
+    #[should_panic(expected = "uint256/int256")]
+    fn test_function_selector_rejects_uint_alias() {
+        let _ = function_selector("transfer(address,uint)");
+    }
+
+    #[test]
+    #[should_panic(expected = "uint256/int256")]
+    fn test_function_selector_rejects_int_alias_in_array() {
+        let _ = function_selector("f(int[2])");
+    }
+
+    #[test]
+    #[should_panic(expected = "parameter list")]
+    fn test_function_selector_rejects_missing_params() {
+        let _ = function_selector("transfer");
+    }
+
+    #[test]
+    #[should_panic(expected = "unbalanced")]
+    fn test_function_selector_rejects_unbalanced() {
+        let _ = function_selector("f((uint256)");
+    }
+
+    #[test]
+    #[should_panic(expected = "no name")]
+    fn test_function_selector_rejects_empty_name() {
+        let _ = function_selector("(uint256)");
+    }
+}
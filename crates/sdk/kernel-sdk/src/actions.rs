@@ -17,6 +17,25 @@
 //!     .build();
 //! ```
 //!
+//! Dynamic arguments (`bytes`, `string`, arrays, tuples containing them) are
+//! ABI-encoded with head/tail layout via [`AbiValue`]:
+//!
+//! ```ignore
+//! use kernel_sdk::abi::AbiValue;
+//!
+//! // exactInput((bytes,address,uint256,uint256,uint256))
+//! let action = CallBuilder::new(router)
+//!     .selector(0xc04b8d59)
+//!     .param(AbiValue::Tuple(&[
+//!         AbiValue::Bytes(&path),
+//!         AbiValue::address(&recipient),
+//!         AbiValue::uint(deadline),
+//!         AbiValue::uint(amount_in),
+//!         AbiValue::uint(amount_out_min),
+//!     ]))
+//!     .build();
+//! ```
+//!
 //! # ERC20 Helpers
 //!
 //! ```ignore
@@ -27,7 +46,9 @@
 //! ```
 
 use alloc::vec::Vec;
+use crate::abi::{usize_word, AbiError, AbiValue, MAX_CALLDATA_BYTES, WORD_SIZE};
 use crate::types::{ActionV1, address_to_bytes32, call_action};
+use crate::u256::U256;
 
 // ============================================================================
 // CallBuilder
@@ -38,11 +59,25 @@ use crate::types::{ActionV1, address_to_bytes32, call_action};
 /// Constructs ABI-encoded calldata for contract calls, then wraps it
 /// in an `ActionV1` via [`call_action()`].
 ///
-/// All parameter methods encode values as 32-byte ABI words.
+/// Parameters are ABI-encoded in order after the selector. Static values
+/// are written in place; dynamic values get an offset word in the head and
+/// their encoding in the tail, which `build` appends after the last head.
+///
+/// Calldata is bounded by [`MAX_CALLDATA_BYTES`]. A parameter that would
+/// exceed it, or that cannot be encoded, is dropped and makes
+/// [`try_build`](CallBuilder::try_build) return the error.
 pub struct CallBuilder {
     target: [u8; 20],
     value: u128,
+    /// Selector and parameter heads
     calldata: Vec<u8>,
+    /// Start of the parameter heads in `calldata`
+    args_start: usize,
+    /// Encodings of the dynamic parameters
+    tail: Vec<u8>,
+    /// Head position of each dynamic parameter and its offset in `tail`
+    dynamic_params: Vec<(usize, usize)>,
+    error: Option<AbiError>,
 }
 
 impl CallBuilder {
@@ -54,75 +89,120 @@ impl CallBuilder {
             target,
             value: 0,
             calldata: Vec::new(),
+            args_start: 0,
+            tail: Vec::new(),
+            dynamic_params: Vec::new(),
+            error: None,
         }
     }
 
     /// Append a 4-byte function selector (big-endian).
+    ///
+    /// Must be called before any parameter is added.
     #[inline]
     #[must_use]
     pub fn selector(mut self, sel: u32) -> Self {
         self.calldata.extend_from_slice(&sel.to_be_bytes());
+        self.args_start = self.calldata.len();
         self
     }
 
     /// Append an address parameter (left-padded to 32 bytes).
     #[inline]
     #[must_use]
-    pub fn param_address(mut self, addr: &[u8; 20]) -> Self {
-        self.calldata.extend_from_slice(&address_to_bytes32(addr));
-        self
+    pub fn param_address(self, addr: &[u8; 20]) -> Self {
+        self.param(AbiValue::address(addr))
     }
 
     /// Append a u256 parameter from a u64 value (big-endian, right-aligned in 32 bytes).
     #[inline]
     #[must_use]
-    pub fn param_u256_from_u64(mut self, val: u64) -> Self {
-        let mut word = [0u8; 32];
-        word[24..32].copy_from_slice(&val.to_be_bytes());
-        self.calldata.extend_from_slice(&word);
-        self
+    pub fn param_u256_from_u64(self, val: u64) -> Self {
+        self.param(AbiValue::uint(u128::from(val)))
     }
 
     /// Append a u256 parameter from a u128 value (big-endian, right-aligned in 32 bytes).
     #[inline]
     #[must_use]
-    pub fn param_u256(mut self, val: u128) -> Self {
-        let mut word = [0u8; 32];
-        word[16..32].copy_from_slice(&val.to_be_bytes());
-        self.calldata.extend_from_slice(&word);
-        self
+    pub fn param_u256(self, val: u128) -> Self {
+        self.param(AbiValue::uint(val))
+    }
+
+    /// Append a u256 parameter over its full range (big-endian, 32 bytes).
+    #[inline]
+    #[must_use]
+    pub fn param_uint256(self, val: U256) -> Self {
+        self.param(AbiValue::uint256(val))
     }
 
     /// Append a bytes32 parameter.
     #[inline]
     #[must_use]
-    pub fn param_bytes32(mut self, val: &[u8; 32]) -> Self {
-        self.calldata.extend_from_slice(val);
-        self
+    pub fn param_bytes32(self, val: &[u8; 32]) -> Self {
+        self.param(AbiValue::Word(*val))
     }
 
     /// Append a bool parameter (0 or 1, right-aligned in 32 bytes).
     #[inline]
     #[must_use]
-    pub fn param_bool(mut self, val: bool) -> Self {
-        let mut word = [0u8; 32];
-        if val {
-            word[31] = 1;
-        }
-        self.calldata.extend_from_slice(&word);
-        self
+    pub fn param_bool(self, val: bool) -> Self {
+        self.param(AbiValue::bool(val))
     }
 
     /// Append a u16 parameter (big-endian, right-aligned in 32 bytes).
     #[inline]
     #[must_use]
-    pub fn param_u16(mut self, val: u16) -> Self {
-        let mut word = [0u8; 32];
-        word[30..32].copy_from_slice(&val.to_be_bytes());
-        self.calldata.extend_from_slice(&word);
+    pub fn param_u16(self, val: u16) -> Self {
+        self.param(AbiValue::uint(u128::from(val)))
+    }
+
+    /// Append a dynamic `bytes` parameter.
+    #[inline]
+    #[must_use]
+    pub fn param_bytes(self, val: &[u8]) -> Self {
+        self.param(AbiValue::Bytes(val))
+    }
+
+    /// Append a dynamic `string` parameter.
+    #[inline]
+    #[must_use]
+    pub fn param_string(self, val: &str) -> Self {
+        self.param(AbiValue::String(val))
+    }
+
+    /// Append a parameter of any ABI type.
+    #[must_use]
+    pub fn param(mut self, val: AbiValue<'_>) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        if let Err(err) = self.push_param(&val) {
+            self.error = Some(err);
+        }
         self
     }
 
+    /// Encode `val` into the head (and tail, if dynamic), within the limit.
+    fn push_param(&mut self, val: &AbiValue<'_>) -> Result<(), AbiError> {
+        let len = val.encoded_len()?;
+        let dynamic = val.is_dynamic();
+        let added = if dynamic { WORD_SIZE + len } else { len };
+        let total = self.calldata.len() + self.tail.len();
+        if total.saturating_add(added) > MAX_CALLDATA_BYTES {
+            return Err(AbiError::TooLarge);
+        }
+
+        if dynamic {
+            let head_pos = self.calldata.len();
+            self.dynamic_params.push((head_pos, self.tail.len()));
+            self.calldata.extend_from_slice(&[0u8; WORD_SIZE]);
+            self.tail.reserve_exact(len);
+            val.write(&mut self.tail)
+        } else {
+            val.write(&mut self.calldata)
+        }
+    }
+
     /// Set the ETH value (in wei) to send with the call.
     #[inline]
     #[must_use]
@@ -135,11 +215,34 @@ impl CallBuilder {
     ///
     /// Uses [`call_action()`] internally, so the output is byte-identical
     /// to manually constructing the action.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a parameter could not be encoded; use
+    /// [`try_build`](CallBuilder::try_build) to handle that case.
     #[inline]
     #[must_use]
     pub fn build(self) -> ActionV1 {
+        match self.try_build() {
+            Ok(action) => action,
+            Err(err) => panic!("CallBuilder: cannot encode calldata: {:?}", err),
+        }
+    }
+
+    /// Build the final `ActionV1`, or return the first encoding error.
+    pub fn try_build(mut self) -> Result<ActionV1, AbiError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let head_len = self.calldata.len() - self.args_start;
+        for &(head_pos, tail_offset) in &self.dynamic_params {
+            let offset = usize_word(head_len + tail_offset);
+            self.calldata[head_pos..head_pos + WORD_SIZE].copy_from_slice(&offset);
+        }
+        self.calldata.extend_from_slice(&self.tail);
+
         let target = address_to_bytes32(&self.target);
-        call_action(target, self.value, &self.calldata)
+        Ok(call_action(target, self.value, &self.calldata))
     }
 }
 
@@ -154,14 +257,14 @@ impl CallBuilder {
 pub mod erc20 {
     use super::*;
 
-    /// ERC20 approve function selector: `keccak256("approve(address,uint256)")[:4]`
-    const APPROVE_SELECTOR: u32 = 0x095ea7b3;
+    /// ERC20 approve function selector.
+    const APPROVE_SELECTOR: u32 = crate::selector!("approve(address,uint256)");
 
-    /// ERC20 transfer function selector: `keccak256("transfer(address,uint256)")[:4]`
-    const TRANSFER_SELECTOR: u32 = 0xa9059cbb;
+    /// ERC20 transfer function selector.
+    const TRANSFER_SELECTOR: u32 = crate::selector!("transfer(address,uint256)");
 
-    /// ERC20 transferFrom function selector: `keccak256("transferFrom(address,address,uint256)")[:4]`
-    const TRANSFER_FROM_SELECTOR: u32 = 0x23b872dd;
+    /// ERC20 transferFrom function selector.
+    const TRANSFER_FROM_SELECTOR: u32 = crate::selector!("transferFrom(address,address,uint256)");
 
     /// Build an ERC20 `approve(spender, amount)` action.
     ///
@@ -169,10 +272,20 @@ pub mod erc20 {
     #[inline]
     #[must_use]
     pub fn approve(token: &[u8; 20], spender: &[u8; 20], amount: u64) -> ActionV1 {
+        approve_u256(token, spender, U256::from_u64(amount))
+    }
+
+    /// Build an ERC20 `approve(spender, amount)` action with a full
+    /// 256-bit amount.
+    ///
+    /// Targets the `token` contract.
+    #[inline]
+    #[must_use]
+    pub fn approve_u256(token: &[u8; 20], spender: &[u8; 20], amount: U256) -> ActionV1 {
         CallBuilder::new(*token)
             .selector(APPROVE_SELECTOR)
             .param_address(spender)
-            .param_u256_from_u64(amount)
+            .param_uint256(amount)
             .build()
     }
 
@@ -182,10 +295,20 @@ pub mod erc20 {
     #[inline]
     #[must_use]
     pub fn transfer(token: &[u8; 20], to: &[u8; 20], amount: u64) -> ActionV1 {
+        transfer_u256(token, to, U256::from_u64(amount))
+    }
+
+    /// Build an ERC20 `transfer(to, amount)` action with a full 256-bit
+    /// amount.
+    ///
+    /// Targets the `token` contract.
+    #[inline]
+    #[must_use]
+    pub fn transfer_u256(token: &[u8; 20], to: &[u8; 20], amount: U256) -> ActionV1 {
         CallBuilder::new(*token)
             .selector(TRANSFER_SELECTOR)
             .param_address(to)
-            .param_u256_from_u64(amount)
+            .param_uint256(amount)
             .build()
     }
 
@@ -199,12 +322,27 @@ pub mod erc20 {
         from: &[u8; 20],
         to: &[u8; 20],
         amount: u64,
+    ) -> ActionV1 {
+        transfer_from_u256(token, from, to, U256::from_u64(amount))
+    }
+
+    /// Build an ERC20 `transferFrom(from, to, amount)` action with a full
+    /// 256-bit amount.
+    ///
+    /// Targets the `token` contract.
+    #[inline]
+    #[must_use]
+    pub fn transfer_from_u256(
+        token: &[u8; 20],
+        from: &[u8; 20],
+        to: &[u8; 20],
+        amount: U256,
     ) -> ActionV1 {
         CallBuilder::new(*token)
             .selector(TRANSFER_FROM_SELECTOR)
             .param_address(from)
             .param_address(to)
-            .param_u256_from_u64(amount)
+            .param_uint256(amount)
             .build()
     }
 }
@@ -322,6 +460,78 @@ mod tests {
         assert_eq!(&action.payload[param_start..param_start + 32], &val);
     }
 
+    #[test]
+    fn test_call_builder_dynamic_params_match_abi_encoding() {
+        let path = [0x5au8; 43];
+        let list = [AbiValue::uint(7), AbiValue::uint(8)];
+        let action = CallBuilder::new([0x11u8; 20])
+            .selector(0x12345678)
+            .param_u256(1)
+            .param_bytes(&path)
+            .param_address(&[0x22u8; 20])
+            .param(AbiValue::Array(&list))
+            .param_string("memo")
+            .build();
+
+        let expected = crate::abi::encode_call(
+            0x12345678,
+            &[
+                AbiValue::uint(1),
+                AbiValue::Bytes(&path),
+                AbiValue::address(&[0x22u8; 20]),
+                AbiValue::Array(&list),
+                AbiValue::String("memo"),
+            ],
+        )
+        .unwrap();
+        let builder_calldata = &action.payload[96..96 + expected.len()];
+        assert_eq!(builder_calldata, expected.as_slice());
+
+        // First dynamic offset skips the five heads
+        let mut offset = [0u8; 32];
+        offset[31] = 5 * 32;
+        assert_eq!(&builder_calldata[36..68], &offset);
+    }
+
+    #[test]
+    fn test_call_builder_static_tuple_in_place() {
+        let pair = [AbiValue::uint(1), AbiValue::bool(true)];
+        let action = CallBuilder::new([0x11u8; 20])
+            .selector(0x12345678)
+            .param(AbiValue::Tuple(&pair))
+            .build();
+        let builder_action = CallBuilder::new([0x11u8; 20])
+            .selector(0x12345678)
+            .param_u256(1)
+            .param_bool(true)
+            .build();
+        assert_eq!(action, builder_action);
+    }
+
+    #[test]
+    fn test_call_builder_rejects_oversized_calldata() {
+        let data = [0u8; MAX_CALLDATA_BYTES];
+        let result = CallBuilder::new([0x11u8; 20])
+            .selector(0x12345678)
+            .param_bytes(&data)
+            .param_u256(1)
+            .try_build();
+        assert_eq!(result, Err(AbiError::TooLarge));
+
+        let mixed = [AbiValue::uint(1), AbiValue::String("x")];
+        let result = CallBuilder::new([0x11u8; 20])
+            .param(AbiValue::Array(&mixed))
+            .try_build();
+        assert_eq!(result, Err(AbiError::MixedArrayElements));
+    }
+
+    #[test]
+    #[should_panic(expected = "TooLarge")]
+    fn test_call_builder_build_panics_on_error() {
+        let data = [0u8; MAX_CALLDATA_BYTES];
+        let _ = CallBuilder::new([0x11u8; 20]).param_bytes(&data).build();
+    }
+
     // ========================================================================
     // ERC20 Helper Tests
     // ========================================================================
@@ -393,6 +603,40 @@ mod tests {
         assert_eq!(&action.payload[144..164], &to);
     }
 
+    #[test]
+    fn test_erc20_u256_amounts() {
+        let token = [0x22u8; 20];
+        let to = [0x33u8; 20];
+
+        // u64 helpers are byte-identical to the u256 ones
+        assert_eq!(
+            erc20::transfer(&token, &to, 42),
+            erc20::transfer_u256(&token, &to, U256::from_u64(42))
+        );
+
+        // 1e30 does not fit in u64: 1,000,000,000,000 tokens with 18 decimals
+        let amount = U256::from_u128(1_000_000_000_000_000_000_000_000_000_000);
+        let action = erc20::approve_u256(&token, &to, amount);
+        assert_eq!(&action.payload[132..164], &amount.to_be_bytes());
+
+        let action = erc20::transfer_from_u256(&token, &to, &token, U256::MAX);
+        assert_eq!(&action.payload[164..196], &[0xffu8; 32]);
+    }
+
+    #[test]
+    fn test_call_builder_param_uint256() {
+        let action = CallBuilder::new([0x11u8; 20])
+            .selector(0x12345678)
+            .param_uint256(U256::from_u128(1000))
+            .build();
+        let expected = CallBuilder::new([0x11u8; 20])
+            .selector(0x12345678)
+            .param_u256(1000)
+            .build();
+        assert_eq!(action, expected);
+        assert_eq!(AbiValue::from(U256::MAX), AbiValue::Word([0xff; 32]));
+    }
+
     // ========================================================================
     // Byte-Identity Tests
     // ========================================================================
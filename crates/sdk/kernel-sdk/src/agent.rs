@@ -18,6 +18,22 @@
 //! - No other entrypoints are recognized by the kernel
 //! - Panics abort execution and invalidate the proof
 //!
+//! # Fallible Entrypoint
+//!
+//! An agent that can reject its inputs may instead implement
+//! [`TryAgentEntrypoint`] and return an [`AgentError`]. The kernel then
+//! commits an `AgentError` execution status, so integrators can tell a
+//! malformed input apart from an agent that decided to do nothing.
+//!
+//! # Step Budget
+//!
+//! A constraint set may declare a `step_budget`. Agents charge the steps
+//! they take with [`AgentContext::charge_steps`], typically one per loop
+//! iteration, and stop with `?` once it reports the budget exhausted. The
+//! count depends only on the agent's code and inputs, so every run of the
+//! same input charges the same steps, and the kernel fails any execution
+//! whose agent charged more than the budget.
+//!
 //! # Example
 //!
 //! ```ignore
@@ -30,7 +46,8 @@
 //! }
 //! ```
 
-use crate::types::AgentOutput;
+use crate::types::{AgentOutput, StateSnapshot};
+use core::cell::Cell;
 
 /// Execution context provided to agents by the kernel.
 ///
@@ -40,7 +57,7 @@ use crate::types::AgentOutput;
 ///
 /// # Design Rationale
 ///
-/// - All fields are owned/Copy types (no lifetimes)
+/// - All fields are owned types (no lifetimes)
 /// - `opaque_inputs` is passed as a separate argument to the entrypoint
 /// - This keeps the context a clean "header" structure
 ///
@@ -56,7 +73,7 @@ use crate::types::AgentOutput;
 /// - Identifiers and hashes are correctly formatted
 /// - Size limits are enforced
 #[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AgentContext {
     /// Protocol version for wire format compatibility.
     ///
@@ -98,6 +115,21 @@ pub struct AgentContext {
     /// Must be strictly increasing across executions for the same agent.
     /// Used by the settlement layer to prevent replay attacks.
     pub execution_nonce: u64,
+
+    /// State snapshot from the input's typed slot.
+    ///
+    /// Set for kernel version 5 inputs that carry a snapshot; this is the
+    /// same snapshot the constraint engine checks cooldown, drawdown and
+    /// expiry against. `None` for earlier input versions, whose snapshot
+    /// (if any) is the first 36 bytes of `opaque_inputs`.
+    ///
+    /// A [`StateSnapshot::V2`] additionally carries the rolling-window usage.
+    pub state_snapshot: Option<StateSnapshot>,
+
+    /// Steps the agent has charged against the constraint set's step budget.
+    ///
+    /// Charge it through [`AgentContext::charge_steps`].
+    pub step_meter: StepMeter,
 }
 
 impl AgentContext {
@@ -123,9 +155,39 @@ impl AgentContext {
             constraint_set_hash,
             input_root,
             execution_nonce,
+            state_snapshot: None,
+            step_meter: StepMeter::new(None),
         }
     }
 
+    /// Attach the input's typed state snapshot.
+    ///
+    /// This is called by the kernel, not by agents.
+    #[doc(hidden)]
+    pub fn with_state_snapshot(mut self, state_snapshot: Option<StateSnapshot>) -> Self {
+        self.state_snapshot = state_snapshot;
+        self
+    }
+
+    /// Meter the agent against the constraint set's step budget.
+    ///
+    /// This is called by the kernel, not by agents.
+    #[doc(hidden)]
+    pub fn with_step_budget(mut self, step_budget: Option<u64>) -> Self {
+        self.step_meter = StepMeter::new(step_budget);
+        self
+    }
+
+    /// Charge `steps` against the step budget.
+    ///
+    /// Returns [`AgentError::StepBudgetExceeded`] once the agent has charged
+    /// more than the budget; return it with `?` to stop. The kernel fails
+    /// the execution with `StepBudgetExceeded` whatever the agent returns.
+    #[inline]
+    pub fn charge_steps(&self, steps: u64) -> Result<(), AgentError> {
+        self.step_meter.charge(steps)
+    }
+
     /// Check if the protocol version is supported.
     ///
     /// Returns true if `protocol_version == 1`.
@@ -143,6 +205,53 @@ impl AgentContext {
     }
 }
 
+/// Steps an agent charged in one execution, with the budget they count against.
+///
+/// The kernel attaches a meter to every [`AgentContext`], holding the
+/// enforced constraint set's step budget or `None` when it declares none.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StepMeter {
+    budget: Option<u64>,
+    used: Cell<u64>,
+}
+
+impl StepMeter {
+    /// Create a meter with nothing charged (`None` = unlimited).
+    pub const fn new(budget: Option<u64>) -> Self {
+        Self {
+            budget,
+            used: Cell::new(0),
+        }
+    }
+
+    /// Steps the agent may charge, if limited.
+    #[inline]
+    pub fn budget(&self) -> Option<u64> {
+        self.budget
+    }
+
+    /// Steps charged so far.
+    #[inline]
+    pub fn used(&self) -> u64 {
+        self.used.get()
+    }
+
+    /// Whether more steps were charged than the budget allows.
+    #[inline]
+    pub fn is_exceeded(&self) -> bool {
+        self.budget.is_some_and(|budget| self.used.get() > budget)
+    }
+
+    /// Charge `steps`, failing once the budget is exceeded.
+    pub fn charge(&self, steps: u64) -> Result<(), AgentError> {
+        self.used.set(self.used.get().saturating_add(steps));
+        if self.is_exceeded() {
+            return Err(AgentError::StepBudgetExceeded);
+        }
+        Ok(())
+    }
+}
+
 /// Type alias for the canonical agent entrypoint function.
 ///
 /// Agents must implement a function with this signature and expose it
@@ -158,10 +267,13 @@ impl AgentContext {
 /// - `opaque_inputs`: Agent-specific input data (max 64,000 bytes)
 /// - Returns: `AgentOutput` containing ordered actions
 ///
-/// # Opaque Inputs Convention
+/// # State Snapshot
 ///
-/// If cooldown or drawdown constraints are enabled, the **first 36 bytes**
-/// of `opaque_inputs` must contain a `StateSnapshotV1`:
+/// If cooldown or drawdown constraints are enabled, the input must carry a
+/// `StateSnapshotV1`. Kernel version 5 inputs carry it in a typed slot,
+/// exposed to the agent as [`AgentContext::state_snapshot`], and leave all
+/// of `opaque_inputs` to the agent. For earlier input versions the
+/// **first 36 bytes** of `opaque_inputs` must contain the snapshot:
 ///
 /// | Offset | Field             | Type | Size |
 /// |--------|-------------------|------|------|
@@ -175,6 +287,39 @@ impl AgentContext {
 /// constraint engine. See `spec/constraints.md` for full details.
 pub type AgentEntrypoint = extern "Rust" fn(&AgentContext, &[u8]) -> AgentOutput;
 
+/// Reason an agent rejected its inputs.
+///
+/// Returned by a [`TryAgentEntrypoint`]. The kernel maps any error to the
+/// `AgentError` execution status and commits to an empty output without
+/// evaluating constraints. The error itself is not committed to the journal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AgentError {
+    /// `opaque_inputs` could not be decoded by the agent.
+    InvalidInput,
+    /// The agent requires a state snapshot but the input carried none.
+    MissingStateSnapshot,
+    /// The agent charged more steps than its step budget.
+    ///
+    /// The kernel commits this as a `StepBudgetExceeded` failure rather than
+    /// the `AgentError` status.
+    StepBudgetExceeded,
+    /// Agent-defined error code.
+    Custom(u32),
+}
+
+/// Type alias for the fallible agent entrypoint function.
+///
+/// Same contract as [`AgentEntrypoint`], except that the agent may reject
+/// its inputs with an [`AgentError`] instead of returning an empty output.
+///
+/// # Signature
+///
+/// ```ignore
+/// fn agent_main(ctx: &AgentContext, opaque_inputs: &[u8]) -> Result<AgentOutput, AgentError>
+/// ```
+pub type TryAgentEntrypoint =
+    extern "Rust" fn(&AgentContext, &[u8]) -> Result<AgentOutput, AgentError>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,11 +345,10 @@ mod tests {
     }
 
     #[test]
-    fn test_agent_context_copy() {
+    fn test_agent_context_clone() {
         let ctx = AgentContext::new(1, 1, [0x42u8; 32], [0u8; 32], [0u8; 32], [0u8; 32], 42);
 
-        // AgentContext is Copy
-        let ctx2 = ctx;
+        let ctx2 = ctx.clone();
         assert_eq!(ctx.agent_id, ctx2.agent_id);
         assert_eq!(ctx.execution_nonce, ctx2.execution_nonce);
     }
@@ -212,7 +356,48 @@ mod tests {
     #[test]
     fn test_agent_context_repr_c() {
         // Verify the struct has a predictable size
-        // 4 + 4 + 32 + 32 + 32 + 32 + 8 = 144 bytes
-        assert_eq!(core::mem::size_of::<AgentContext>(), 144);
+        // 4 + 4 + 32 + 32 + 32 + 32 + 8 = 144 bytes, then the snapshot slot
+        // and the step meter
+        assert_eq!(
+            core::mem::size_of::<AgentContext>(),
+            144 + core::mem::size_of::<Option<StateSnapshot>>() + core::mem::size_of::<StepMeter>()
+        );
+    }
+
+    #[test]
+    fn test_agent_context_state_snapshot() {
+        let ctx = AgentContext::new(1, 5, [0x42u8; 32], [0u8; 32], [0u8; 32], [0u8; 32], 42);
+        assert_eq!(ctx.state_snapshot, None);
+
+        let snapshot = StateSnapshot::V1(crate::types::StateSnapshotV1 {
+            snapshot_version: 1,
+            last_execution_ts: 1_000,
+            current_ts: 2_000,
+            current_equity: 90_000,
+            peak_equity: 100_000,
+        });
+        let ctx = ctx.with_state_snapshot(Some(snapshot));
+        assert_eq!(ctx.state_snapshot, Some(snapshot));
+    }
+
+    #[test]
+    fn test_step_meter() {
+        let ctx = AgentContext::new(1, 5, [0x42u8; 32], [0u8; 32], [0u8; 32], [0u8; 32], 42);
+        assert_eq!(ctx.step_meter.budget(), None);
+        assert_eq!(ctx.charge_steps(u64::MAX), Ok(()));
+        assert!(!ctx.step_meter.is_exceeded());
+
+        let ctx = ctx.with_step_budget(Some(10));
+        assert_eq!(ctx.step_meter.used(), 0);
+        assert_eq!(ctx.charge_steps(4), Ok(()));
+        assert_eq!(ctx.charge_steps(6), Ok(()));
+        assert_eq!(ctx.step_meter.used(), 10);
+        assert!(!ctx.step_meter.is_exceeded());
+
+        // Exhausted for good once over the budget
+        assert_eq!(ctx.charge_steps(1), Err(AgentError::StepBudgetExceeded));
+        assert_eq!(ctx.charge_steps(0), Err(AgentError::StepBudgetExceeded));
+        assert!(ctx.step_meter.is_exceeded());
+        assert_eq!(ctx.step_meter.used(), 11);
     }
 }
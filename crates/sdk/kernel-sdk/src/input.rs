@@ -0,0 +1,506 @@
+//! Typed agent input fields.
+//!
+//! [`InputField`] is the wire format behind [`agent_input!`](crate::agent_input):
+//! every field type reads itself from a cursor, writes itself to a buffer and
+//! describes itself in an [`InputSchema`].
+//!
+//! # Encoding
+//!
+//! | Type | Encoding |
+//! |------|----------|
+//! | `u8`, `u16`, `u32`, `u64`, `u128` | Little-endian, fixed width |
+//! | `i8`, `i16`, `i32`, `i64`, `i128` | Little-endian two's complement, fixed width |
+//! | `bool` | One byte, `0x00` or `0x01` |
+//! | `[T; N]` | `N` elements back to back |
+//! | [`BoundedVec<T, MAX>`](BoundedVec) | `u32` element count (LE), then the elements |
+//! | `agent_input!` struct | Fields in declaration order |
+//!
+//! Decoding rejects a `bool` other than `0x00`/`0x01` and a `BoundedVec`
+//! count above `MAX`, so every value has exactly one encoding.
+//!
+//! # Example
+//!
+//! ```
+//! use kernel_sdk::input::BoundedVec;
+//!
+//! kernel_sdk::agent_input! {
+//!     struct Leg {
+//!         asset_id: u32,
+//!         size: i64,
+//!     }
+//! }
+//!
+//! kernel_sdk::agent_input! {
+//!     struct RebalanceInput {
+//!         vault: [u8; 20],
+//!         legs: BoundedVec<Leg, 4>,
+//!     }
+//! }
+//!
+//! let legs = BoundedVec::from_vec(vec![Leg { asset_id: 1, size: -5 }]).unwrap();
+//! let input = RebalanceInput { vault: [0x11; 20], legs };
+//! let bytes = input.encode();
+//! assert_eq!(bytes.len(), 20 + 4 + 12);
+//!
+//! let decoded = RebalanceInput::decode(&bytes).unwrap();
+//! assert_eq!(decoded.legs[0].size, -5);
+//! assert_eq!(RebalanceInput::MAX_ENCODED_SIZE, 20 + 4 + 4 * 12);
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+
+use crate::bytes::{read_bool_u8_at, read_slice_at, read_u32_le_at};
+
+// ============================================================================
+// Input Field Trait
+// ============================================================================
+
+/// A type that can appear as a field of an [`agent_input!`](crate::agent_input)
+/// struct.
+///
+/// `agent_input!` implements this trait for the structs it generates, so
+/// input structs nest.
+pub trait InputField: Sized {
+    /// Schema description of this type.
+    const TYPE: FieldType;
+
+    /// Smallest encoding in bytes.
+    const MIN_SIZE: usize;
+
+    /// Largest encoding in bytes.
+    const MAX_SIZE: usize;
+
+    /// Read a value at `offset`, advancing it past the value.
+    ///
+    /// Returns `None` on truncated or non-canonical input, in which case
+    /// `offset` may have advanced partway.
+    fn read(bytes: &[u8], offset: &mut usize) -> Option<Self>;
+
+    /// Append the encoding of this value to `buf`.
+    fn write(&self, buf: &mut Vec<u8>);
+
+    /// Length of the encoding of this value in bytes.
+    fn encoded_len(&self) -> usize {
+        Self::MIN_SIZE
+    }
+
+    /// Read `N` consecutive values. Overridden for `u8` to copy a slice.
+    #[doc(hidden)]
+    fn read_array<const N: usize>(bytes: &[u8], offset: &mut usize) -> Option<[Self; N]> {
+        let mut items = Vec::with_capacity(N);
+        for _ in 0..N {
+            items.push(Self::read(bytes, offset)?);
+        }
+        items.try_into().ok()
+    }
+
+    /// Write consecutive values. Overridden for `u8` to copy a slice.
+    #[doc(hidden)]
+    fn write_slice(items: &[Self], buf: &mut Vec<u8>) {
+        for item in items {
+            item.write(buf);
+        }
+    }
+}
+
+// ============================================================================
+// Scalar Fields
+// ============================================================================
+
+macro_rules! impl_int_field {
+    ($($ty:ty => $variant:ident),* $(,)?) => {$(
+        impl InputField for $ty {
+            const TYPE: FieldType = FieldType::$variant;
+            const MIN_SIZE: usize = core::mem::size_of::<$ty>();
+            const MAX_SIZE: usize = core::mem::size_of::<$ty>();
+
+            #[inline]
+            fn read(bytes: &[u8], offset: &mut usize) -> Option<Self> {
+                let raw = read_slice_at(bytes, offset, Self::MIN_SIZE)?;
+                Some(<$ty>::from_le_bytes(raw.try_into().ok()?))
+            }
+
+            #[inline]
+            fn write(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+impl_int_field! {
+    u16 => U16,
+    u32 => U32,
+    u64 => U64,
+    u128 => U128,
+    i8 => I8,
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    i128 => I128,
+}
+
+impl InputField for u8 {
+    const TYPE: FieldType = FieldType::U8;
+    const MIN_SIZE: usize = 1;
+    const MAX_SIZE: usize = 1;
+
+    #[inline]
+    fn read(bytes: &[u8], offset: &mut usize) -> Option<Self> {
+        crate::bytes::read_u8_at(bytes, offset)
+    }
+
+    #[inline]
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.push(*self);
+    }
+
+    fn read_array<const N: usize>(bytes: &[u8], offset: &mut usize) -> Option<[Self; N]> {
+        read_slice_at(bytes, offset, N)?.try_into().ok()
+    }
+
+    fn write_slice(items: &[Self], buf: &mut Vec<u8>) {
+        buf.extend_from_slice(items);
+    }
+}
+
+impl InputField for bool {
+    const TYPE: FieldType = FieldType::Bool;
+    const MIN_SIZE: usize = 1;
+    const MAX_SIZE: usize = 1;
+
+    #[inline]
+    fn read(bytes: &[u8], offset: &mut usize) -> Option<Self> {
+        read_bool_u8_at(bytes, offset)
+    }
+
+    #[inline]
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.push(u8::from(*self));
+    }
+}
+
+// ============================================================================
+// Composite Fields
+// ============================================================================
+
+impl<T: InputField, const N: usize> InputField for [T; N] {
+    const TYPE: FieldType = FieldType::Array {
+        element: &T::TYPE,
+        len: N,
+    };
+    const MIN_SIZE: usize = T::MIN_SIZE.saturating_mul(N);
+    const MAX_SIZE: usize = T::MAX_SIZE.saturating_mul(N);
+
+    fn read(bytes: &[u8], offset: &mut usize) -> Option<Self> {
+        T::read_array(bytes, offset)
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        T::write_slice(self, buf);
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.iter().map(InputField::encoded_len).sum()
+    }
+}
+
+/// A `Vec` holding at most `MAX` elements.
+///
+/// Encoded as a `u32` little-endian element count followed by the elements.
+/// Decoding rejects a count above `MAX` before allocating, which bounds the
+/// memory an input can claim.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BoundedVec<T, const MAX: usize>(Vec<T>);
+
+impl<T, const MAX: usize> BoundedVec<T, MAX> {
+    /// Maximum number of elements.
+    pub const MAX_LEN: usize = MAX;
+
+    /// Create an empty vector.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Wrap `items`. Returns `None` if it holds more than `MAX` elements.
+    #[must_use]
+    pub fn from_vec(items: Vec<T>) -> Option<Self> {
+        if items.len() > MAX {
+            return None;
+        }
+        Some(Self(items))
+    }
+
+    /// Append `item`, or return it if the vector is full.
+    pub fn try_push(&mut self, item: T) -> Result<(), T> {
+        if self.0.len() >= MAX {
+            return Err(item);
+        }
+        self.0.push(item);
+        Ok(())
+    }
+
+    /// The elements as a slice.
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+
+    /// Unwrap into the underlying `Vec`.
+    #[must_use]
+    pub fn into_vec(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T, const MAX: usize> Default for BoundedVec<T, MAX> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const MAX: usize> Deref for BoundedVec<T, MAX> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T: InputField, const MAX: usize> InputField for BoundedVec<T, MAX> {
+    const TYPE: FieldType = FieldType::Vec {
+        element: &T::TYPE,
+        max_len: MAX,
+    };
+    const MIN_SIZE: usize = 4;
+    const MAX_SIZE: usize = T::MAX_SIZE.saturating_mul(MAX).saturating_add(4);
+
+    fn read(bytes: &[u8], offset: &mut usize) -> Option<Self> {
+        let len = usize::try_from(read_u32_le_at(bytes, offset)?).ok()?;
+        if len > MAX {
+            return None;
+        }
+        let mut items = Vec::with_capacity(len);
+        for _ in 0..len {
+            items.push(T::read(bytes, offset)?);
+        }
+        Some(Self(items))
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        // MAX_LEN fits in u32 for any vector that fits in an agent input
+        buf.extend_from_slice(&(self.0.len() as u32).to_le_bytes());
+        T::write_slice(&self.0, buf);
+    }
+
+    fn encoded_len(&self) -> usize {
+        4 + self.0.iter().map(InputField::encoded_len).sum::<usize>()
+    }
+}
+
+// ============================================================================
+// Schema
+// ============================================================================
+
+/// Type of an input field, as described by [`InputField::TYPE`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    /// `u8`
+    U8,
+    /// `u16`
+    U16,
+    /// `u32`
+    U32,
+    /// `u64`
+    U64,
+    /// `u128`
+    U128,
+    /// `i8`
+    I8,
+    /// `i16`
+    I16,
+    /// `i32`
+    I32,
+    /// `i64`
+    I64,
+    /// `i128`
+    I128,
+    /// `bool`
+    Bool,
+    /// `[T; N]`
+    Array {
+        /// Element type.
+        element: &'static FieldType,
+        /// Number of elements.
+        len: usize,
+    },
+    /// `BoundedVec<T, MAX>`
+    Vec {
+        /// Element type.
+        element: &'static FieldType,
+        /// Maximum number of elements.
+        max_len: usize,
+    },
+    /// A nested `agent_input!` struct.
+    Struct(&'static InputSchema),
+}
+
+/// Field layout of an `agent_input!` struct, available as `Name::SCHEMA`.
+///
+/// Formats as the struct declaration, e.g.
+/// `Leg { asset_id: u32, size: i64 }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputSchema {
+    /// Struct name.
+    pub name: &'static str,
+    /// Fields in encoding order.
+    pub fields: &'static [FieldSchema],
+}
+
+/// A named field of an [`InputSchema`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldSchema {
+    /// Field name.
+    pub name: &'static str,
+    /// Field type.
+    pub ty: FieldType,
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::U8 => f.write_str("u8"),
+            FieldType::U16 => f.write_str("u16"),
+            FieldType::U32 => f.write_str("u32"),
+            FieldType::U64 => f.write_str("u64"),
+            FieldType::U128 => f.write_str("u128"),
+            FieldType::I8 => f.write_str("i8"),
+            FieldType::I16 => f.write_str("i16"),
+            FieldType::I32 => f.write_str("i32"),
+            FieldType::I64 => f.write_str("i64"),
+            FieldType::I128 => f.write_str("i128"),
+            FieldType::Bool => f.write_str("bool"),
+            FieldType::Array { element, len } => write!(f, "[{}; {}]", element, len),
+            FieldType::Vec { element, max_len } => {
+                write!(f, "BoundedVec<{}, {}>", element, max_len)
+            }
+            FieldType::Struct(schema) => f.write_str(schema.name),
+        }
+    }
+}
+
+impl fmt::Display for InputSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {{", self.name)?;
+        for (i, field) in self.fields.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{}{}: {}", sep, field.name, field.ty)?;
+        }
+        if self.fields.is_empty() {
+            f.write_str("}")
+        } else {
+            f.write_str(" }")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_int_fields_little_endian() {
+        let mut buf = Vec::new();
+        (-2i64).write(&mut buf);
+        0x0102u16.write(&mut buf);
+        assert_eq!(
+            buf,
+            [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02, 0x01]
+        );
+
+        let mut offset = 0;
+        assert_eq!(i64::read(&buf, &mut offset), Some(-2));
+        assert_eq!(u16::read(&buf, &mut offset), Some(0x0102));
+        assert_eq!(u8::read(&buf, &mut offset), None);
+        assert_eq!(offset, 10);
+
+        let mut buf = Vec::new();
+        i128::MIN.write(&mut buf);
+        u128::MAX.write(&mut buf);
+        let mut offset = 0;
+        assert_eq!(i128::read(&buf, &mut offset), Some(i128::MIN));
+        assert_eq!(u128::read(&buf, &mut offset), Some(u128::MAX));
+    }
+
+    #[test]
+    fn test_bool_field_rejects_non_canonical() {
+        let mut offset = 0;
+        assert_eq!(bool::read(&[0x02], &mut offset), None);
+        assert_eq!(offset, 0);
+        assert_eq!(bool::read(&[0x01], &mut offset), Some(true));
+    }
+
+    #[test]
+    fn test_array_fields() {
+        let mut buf = Vec::new();
+        [0xAAu8; 3].write(&mut buf);
+        [1u16, 2].write(&mut buf);
+        assert_eq!(buf, [0xAA, 0xAA, 0xAA, 0x01, 0x00, 0x02, 0x00]);
+        assert_eq!(<[u16; 2]>::MIN_SIZE, 4);
+
+        let mut offset = 0;
+        assert_eq!(<[u8; 3]>::read(&buf, &mut offset), Some([0xAA; 3]));
+        assert_eq!(<[u16; 2]>::read(&buf, &mut offset), Some([1, 2]));
+        assert_eq!(<[u8; 1]>::read(&buf, &mut offset), None);
+    }
+
+    #[test]
+    fn test_bounded_vec() {
+        assert!(BoundedVec::<u8, 2>::from_vec(vec![1, 2, 3]).is_none());
+
+        let mut items = BoundedVec::<u8, 2>::new();
+        assert_eq!(items.try_push(1), Ok(()));
+        assert_eq!(items.try_push(2), Ok(()));
+        assert_eq!(items.try_push(3), Err(3));
+        assert_eq!(items.as_slice(), &[1, 2]);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items.into_vec(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_bounded_vec_field() {
+        let items = BoundedVec::<i32, 3>::from_vec(vec![-1, 7]).unwrap();
+        let mut buf = Vec::new();
+        items.write(&mut buf);
+        assert_eq!(buf.len(), items.encoded_len());
+        assert_eq!(&buf[..4], &2u32.to_le_bytes());
+        assert_eq!(<BoundedVec<i32, 3>>::MAX_SIZE, 4 + 3 * 4);
+
+        let mut offset = 0;
+        assert_eq!(BoundedVec::<i32, 3>::read(&buf, &mut offset), Some(items));
+        assert_eq!(offset, buf.len());
+
+        // Count above MAX is rejected even though the elements are present
+        let mut offset = 0;
+        assert_eq!(BoundedVec::<i32, 1>::read(&buf, &mut offset), None);
+
+        // Truncated elements
+        let mut offset = 0;
+        assert_eq!(BoundedVec::<i32, 3>::read(&buf[..10], &mut offset), None);
+    }
+
+    #[test]
+    fn test_field_type_display() {
+        assert_eq!(i64::TYPE.to_string(), "i64");
+        assert_eq!(<[u8; 20]>::TYPE.to_string(), "[u8; 20]");
+        assert_eq!(
+            <BoundedVec<[u16; 2], 8>>::TYPE.to_string(),
+            "BoundedVec<[u16; 2], 8>"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Keccak-256 as used by Ethereum.
+//!
+//! This is the original Keccak submission (padding byte `0x01`), not the
+//! standardised SHA3-256 (padding byte `0x06`); the two produce different
+//! digests. [`keccak256`] is a `const fn`, so digests of string literals,
+//! such as function selectors, are computed at compile time.
+//!
+//! # Example
+//!
+//! ```
+//! use kernel_sdk::keccak::keccak256;
+//!
+//! const DIGEST: [u8; 32] = keccak256(b"transfer(address,uint256)");
+//! assert_eq!(DIGEST[..4], [0xa9, 0x05, 0x9c, 0xbb]);
+//! ```
+
+/// Bytes absorbed per permutation (1600 - 2 * 256 bits).
+const RATE: usize = 136;
+
+/// Iota step round constants.
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+/// Rho step rotation offsets, in pi step lane order.
+const ROTATIONS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+/// Pi step lane order.
+const PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// Keccak-256 digest of `data`.
+#[must_use]
+pub const fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut state = [0u64; 25];
+
+    let mut offset = 0;
+    while data.len() - offset >= RATE {
+        state = absorb(state, data, offset);
+        state = keccak_f(state);
+        offset += RATE;
+    }
+
+    // Final block: remaining bytes, then pad10*1 with the Keccak domain byte
+    let mut block = [0u8; RATE];
+    let mut i = 0;
+    while offset + i < data.len() {
+        block[i] = data[offset + i];
+        i += 1;
+    }
+    block[i] ^= 0x01;
+    block[RATE - 1] ^= 0x80;
+    state = absorb(state, &block, 0);
+    state = keccak_f(state);
+
+    let mut digest = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        digest[i] = (state[i / 8] >> (8 * (i % 8))) as u8;
+        i += 1;
+    }
+    digest
+}
+
+/// XOR one rate-sized block starting at `start` into the state lanes.
+const fn absorb(mut state: [u64; 25], data: &[u8], start: usize) -> [u64; 25] {
+    let mut i = 0;
+    while i < RATE {
+        state[i / 8] ^= (data[start + i] as u64) << (8 * (i % 8));
+        i += 1;
+    }
+    state
+}
+
+/// The Keccak-f[1600] permutation.
+const fn keccak_f(mut state: [u64; 25]) -> [u64; 25] {
+    let mut round = 0;
+    while round < 24 {
+        // Theta
+        let mut columns = [0u64; 5];
+        let mut x = 0;
+        while x < 5 {
+            columns[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+            x += 1;
+        }
+        let mut x = 0;
+        while x < 5 {
+            let d = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            let mut y = 0;
+            while y < 25 {
+                state[y + x] ^= d;
+                y += 5;
+            }
+            x += 1;
+        }
+
+        // Rho and pi
+        let mut carry = state[1];
+        let mut i = 0;
+        while i < 24 {
+            let lane = PI_LANES[i];
+            let next = state[lane];
+            state[lane] = carry.rotate_left(ROTATIONS[i]);
+            carry = next;
+            i += 1;
+        }
+
+        // Chi
+        let mut y = 0;
+        while y < 25 {
+            let row = [
+                state[y],
+                state[y + 1],
+                state[y + 2],
+                state[y + 3],
+                state[y + 4],
+            ];
+            let mut x = 0;
+            while x < 5 {
+                state[y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+                x += 1;
+            }
+            y += 5;
+        }
+
+        // Iota
+        state[0] ^= ROUND_CONSTANTS[round];
+        round += 1;
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(hex: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_keccak256_short_inputs() {
+        assert_eq!(
+            keccak256(b""),
+            digest("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            keccak256(b"abc"),
+            digest("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+        );
+    }
+
+    #[test]
+    fn test_keccak256_block_boundaries() {
+        // One byte short of a block: both padding bits land in the last byte
+        assert_eq!(
+            keccak256(&[b'a'; RATE - 1]),
+            digest("34367dc248bbd832f4e3e69dfaac2f92638bd0bbd18f2912ba4ef454919cf446")
+        );
+        // Exactly one block: padding takes a second block
+        assert_eq!(
+            keccak256(&[b'a'; RATE]),
+            digest("a6c4d403279fe3e0af03729caada8374b5ca54d8065329a3ebcaeb4b60aa386e")
+        );
+    }
+
+    #[test]
+    fn test_keccak256_multiple_blocks() {
+        let mut data = [0u8; 300];
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        assert_eq!(
+            keccak256(&data),
+            digest("a679e749a6af300c36e7ff2255d220864eab27b382f9cfdc5aa4d13563ba36ff")
+        );
+    }
+
+    #[test]
+    fn test_keccak256_is_const() {
+        const DIGEST: [u8; 32] = keccak256(b"abc");
+        assert_eq!(DIGEST, keccak256(b"abc"));
+    }
+}
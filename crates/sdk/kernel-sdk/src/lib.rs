@@ -16,8 +16,10 @@
 //!
 //! - [`agent`] - Agent context and entrypoint definitions
 //! - [`types`] - Action types, AgentOutput, and helper constructors
-//! - [`math`] - Deterministic math helpers (checked arithmetic, basis points)
+//! - [`math`] - Deterministic math helpers (checked arithmetic, basis points, fixed-point)
 //! - [`bytes`] - Safe byte manipulation utilities
+//! - [`input`] - Typed agent input fields for [`agent_input!`]
+//! - [`keccak`] - Compile-time Keccak-256, used by [`selector!`]
 //!
 //! # Canonical Entrypoint
 //!
@@ -92,12 +94,16 @@ extern crate alloc;
 // Public Modules
 // ============================================================================
 
+pub mod abi;
 pub mod actions;
 pub mod agent;
 pub mod bytes;
+pub mod input;
+pub mod keccak;
 pub mod math;
 pub mod oracle;
 pub mod types;
+pub mod u256;
 
 #[cfg(any(test, feature = "testing"))]
 pub mod testing;
@@ -118,11 +124,14 @@ pub mod testing;
 /// If you need `vec![]`, you can still use `alloc::vec![]` directly.
 pub mod prelude {
     // Agent context + entrypoint type
-    pub use crate::agent::{AgentContext, AgentEntrypoint};
+    pub use crate::agent::{
+        AgentContext, AgentEntrypoint, AgentError, StepMeter, TryAgentEntrypoint,
+    };
 
     // Core types
     pub use crate::types::{
-        ActionV1, AgentOutput, MAX_ACTIONS_PER_OUTPUT, MAX_ACTION_PAYLOAD_BYTES,
+        ActionV1, AgentOutput, StateSnapshot, StateSnapshotV1, StateSnapshotV2,
+        MAX_ACTIONS_PER_OUTPUT, MAX_ACTION_PAYLOAD_BYTES,
     };
 
     // Action type constants (re-exported from kernel-core)
@@ -154,8 +163,21 @@ pub mod prelude {
         saturating_mul_u64,
         saturating_sub_u64,
         BPS_DENOMINATOR,
+        // Fixed-point decimals
+        Fixed18,
+        Fixed6,
+        Fixed8,
+        FixedPoint,
+        // Explicit rounding
+        Rounding,
     };
 
+    // 256-bit integer
+    pub use crate::u256::U256;
+
+    // Agent input fields
+    pub use crate::input::{BoundedVec, InputField};
+
     // Byte helpers (fixed offset)
     pub use crate::bytes::{
         is_zero_bytes32, read_bytes20, read_bytes32, read_slice, read_u16_le, read_u32_le,
@@ -168,7 +190,8 @@ pub mod prelude {
         read_u32_le_at, read_u64_le_at, read_u8_at,
     };
 
-    // Action builder
+    // Action builder and ABI values
+    pub use crate::abi::{AbiError, AbiValue};
     pub use crate::actions::CallBuilder;
 
     // Oracle helpers
@@ -186,30 +209,33 @@ pub mod prelude {
 // Re-exports at Crate Root
 // ============================================================================
 
-pub use agent::{AgentContext, AgentEntrypoint};
+pub use agent::{AgentContext, AgentEntrypoint, AgentError, StepMeter, TryAgentEntrypoint};
 pub use types::{ActionV1, AgentOutput};
 
 // ============================================================================
 // Agent Input Macro
 // ============================================================================
 
-/// Declarative macro that generates a struct with a `decode()` method
-/// for parsing fixed-size agent inputs from opaque byte slices.
+/// Declarative macro that generates a struct with `decode()` and `encode()`
+/// methods for parsing agent inputs from opaque byte slices.
 ///
 /// This eliminates 30-100 lines of manual byte parsing that agents
 /// typically need to write.
 ///
 /// # Supported Types
 ///
-/// | Type | Size | Reader |
-/// |------|------|--------|
-/// | `u8` | 1 | `read_u8_at` |
-/// | `u16` | 2 | `read_u16_le_at` |
-/// | `u32` | 4 | `read_u32_le_at` |
-/// | `u64` | 8 | `read_u64_le_at` |
-/// | `bool` | 1 | `read_bool_u8_at` |
-/// | `[u8; 20]` | 20 | `read_bytes20_at` |
-/// | `[u8; 32]` | 32 | `read_bytes32_at` |
+/// Any [`InputField`](input::InputField) type can be a field:
+///
+/// | Type | Size |
+/// |------|------|
+/// | `u8`, `u16`, `u32`, `u64`, `u128` | 1, 2, 4, 8, 16 (little-endian) |
+/// | `i8`, `i16`, `i32`, `i64`, `i128` | 1, 2, 4, 8, 16 (little-endian) |
+/// | `bool` | 1 |
+/// | `[T; N]`, e.g. `[u8; 20]` | `N` elements |
+/// | [`BoundedVec<T, MAX>`](input::BoundedVec) | 4-byte count + elements |
+/// | another `agent_input!` struct | its fields |
+///
+/// See [`input`] for the exact encoding.
 ///
 /// # Example
 ///
@@ -227,93 +253,133 @@ pub use types::{ActionV1, AgentOutput};
 ///
 /// // Generated:
 /// // - MarketInput::ENCODED_SIZE == 73
+/// // - MarketInput::MIN_ENCODED_SIZE / MAX_ENCODED_SIZE
+/// // - MarketInput::SCHEMA: InputSchema
 /// // - MarketInput::decode(bytes) -> Option<MarketInput>
+/// // - MarketInput.encode() -> Vec<u8>
+/// // - impl InputField for MarketInput
 /// ```
+///
+/// `ENCODED_SIZE` exists only for structs without `BoundedVec` fields;
+/// using it on a variable-length struct is a compile error.
 #[macro_export]
 macro_rules! agent_input {
     (
+        $(#[$attr:meta])*
         struct $name:ident {
-            $( $field:ident : $ty:tt ),* $(,)?
+            $( $field:ident : $ty:ty ),* $(,)?
         }
     ) => {
+        $(#[$attr])*
         struct $name {
-            $( $field: $crate::_agent_input_field_type!($ty), )*
+            $( $field: $ty, )*
         }
 
+        #[allow(dead_code)]
         impl $name {
-            pub const ENCODED_SIZE: usize = 0 $( + $crate::_agent_input_field_size!($ty) )*;
+            /// Smallest encoded size in bytes.
+            pub const MIN_ENCODED_SIZE: usize =
+                <Self as $crate::input::InputField>::MIN_SIZE;
+
+            /// Largest encoded size in bytes.
+            pub const MAX_ENCODED_SIZE: usize =
+                <Self as $crate::input::InputField>::MAX_SIZE;
+
+            /// Exact encoded size in bytes (fixed-size structs only).
+            pub const ENCODED_SIZE: usize = {
+                assert!(
+                    Self::MIN_ENCODED_SIZE == Self::MAX_ENCODED_SIZE,
+                    "ENCODED_SIZE is only defined for fixed-size inputs"
+                );
+                Self::MIN_ENCODED_SIZE
+            };
+
+            /// Field layout of this struct.
+            pub const SCHEMA: $crate::input::InputSchema = $crate::input::InputSchema {
+                name: stringify!($name),
+                fields: &[
+                    $(
+                        $crate::input::FieldSchema {
+                            name: stringify!($field),
+                            ty: <$ty as $crate::input::InputField>::TYPE,
+                        },
+                    )*
+                ],
+            };
 
+            /// Decode from bytes, rejecting truncated input and trailing bytes.
             pub fn decode(bytes: &[u8]) -> Option<Self> {
-                if bytes.len() != Self::ENCODED_SIZE {
+                if bytes.len() < Self::MIN_ENCODED_SIZE || bytes.len() > Self::MAX_ENCODED_SIZE {
                     return None;
                 }
                 let mut offset = 0usize;
-                $(
-                    let $field = $crate::_agent_input_read!($ty, bytes, offset)?;
-                )*
-                Some(Self { $( $field, )* })
+                let value = <Self as $crate::input::InputField>::read(bytes, &mut offset)?;
+                if offset != bytes.len() {
+                    return None;
+                }
+                Some(value)
             }
 
             /// Encode this struct into canonical bytes (inverse of `decode`).
-            pub fn encode(&self) -> alloc::vec::Vec<u8> {
-                let mut buf = alloc::vec::Vec::with_capacity(Self::ENCODED_SIZE);
-                $( $crate::_agent_input_write!($ty, &mut buf, &self.$field); )*
+            pub fn encode(&self) -> $crate::prelude::Vec<u8> {
+                let mut buf = $crate::prelude::Vec::with_capacity(
+                    $crate::input::InputField::encoded_len(self),
+                );
+                $crate::input::InputField::write(self, &mut buf);
                 buf
             }
         }
-    };
-}
 
-/// Internal helper macro: map syntax token to Rust type.
-#[macro_export]
-#[doc(hidden)]
-macro_rules! _agent_input_field_type {
-    (u8)        => { u8 };
-    (u16)       => { u16 };
-    (u32)       => { u32 };
-    (u64)       => { u64 };
-    (bool)      => { bool };
-    ([u8; 20])  => { [u8; 20] };
-    ([u8; 32])  => { [u8; 32] };
-}
+        impl $crate::input::InputField for $name {
+            const TYPE: $crate::input::FieldType = $crate::input::FieldType::Struct(&Self::SCHEMA);
+            const MIN_SIZE: usize =
+                0usize $( .saturating_add(<$ty as $crate::input::InputField>::MIN_SIZE) )*;
+            const MAX_SIZE: usize =
+                0usize $( .saturating_add(<$ty as $crate::input::InputField>::MAX_SIZE) )*;
 
-/// Internal helper macro: map syntax token to byte size.
-#[macro_export]
-#[doc(hidden)]
-macro_rules! _agent_input_field_size {
-    (u8)        => { 1 };
-    (u16)       => { 2 };
-    (u32)       => { 4 };
-    (u64)       => { 8 };
-    (bool)      => { 1 };
-    ([u8; 20])  => { 20 };
-    ([u8; 32])  => { 32 };
-}
+            fn read(bytes: &[u8], offset: &mut usize) -> Option<Self> {
+                $(
+                    let $field = <$ty as $crate::input::InputField>::read(bytes, offset)?;
+                )*
+                Some(Self { $( $field, )* })
+            }
 
-/// Internal helper macro: map syntax token to reader call.
-#[macro_export]
-#[doc(hidden)]
-macro_rules! _agent_input_read {
-    (u8,        $bytes:expr, $offset:ident) => { $crate::bytes::read_u8_at($bytes, &mut $offset) };
-    (u16,       $bytes:expr, $offset:ident) => { $crate::bytes::read_u16_le_at($bytes, &mut $offset) };
-    (u32,       $bytes:expr, $offset:ident) => { $crate::bytes::read_u32_le_at($bytes, &mut $offset) };
-    (u64,       $bytes:expr, $offset:ident) => { $crate::bytes::read_u64_le_at($bytes, &mut $offset) };
-    (bool,      $bytes:expr, $offset:ident) => { $crate::bytes::read_bool_u8_at($bytes, &mut $offset) };
-    ([u8; 20],  $bytes:expr, $offset:ident) => { $crate::bytes::read_bytes20_at($bytes, &mut $offset) };
-    ([u8; 32],  $bytes:expr, $offset:ident) => { $crate::bytes::read_bytes32_at($bytes, &mut $offset) };
+            fn write(&self, buf: &mut $crate::prelude::Vec<u8>) {
+                $( $crate::input::InputField::write(&self.$field, buf); )*
+            }
+
+            fn encoded_len(&self) -> usize {
+                0usize $( + $crate::input::InputField::encoded_len(&self.$field) )*
+            }
+        }
+    };
 }
 
-/// Internal helper macro: map syntax token to writer call.
+// ============================================================================
+// Selector Macro
+// ============================================================================
+
+/// Compute a Solidity function selector at compile time.
+///
+/// Expands to a `u32` constant holding the first four bytes of the
+/// Keccak-256 hash of the signature, ready for [`CallBuilder::selector`]:
+///
+/// ```
+/// const OPEN_POSITION: u32 = kernel_sdk::selector!("openPosition(bool,uint256,uint256,uint256)");
+/// assert_eq!(OPEN_POSITION, 0x04ba41cb);
+/// ```
+///
+/// The signature must be canonical (no whitespace, no parameter names,
+/// `uint256` rather than `uint`); otherwise compilation fails. See
+/// [`abi::function_selector`].
+///
+/// [`CallBuilder::selector`]: actions::CallBuilder::selector
 #[macro_export]
-#[doc(hidden)]
-macro_rules! _agent_input_write {
-    (u8,        $buf:expr, $val:expr) => { $buf.push(*$val) };
-    (u16,       $buf:expr, $val:expr) => { $buf.extend_from_slice(&$val.to_le_bytes()) };
-    (u32,       $buf:expr, $val:expr) => { $buf.extend_from_slice(&$val.to_le_bytes()) };
-    (u64,       $buf:expr, $val:expr) => { $buf.extend_from_slice(&$val.to_le_bytes()) };
-    (bool,      $buf:expr, $val:expr) => { $buf.push(if *$val { 0x01 } else { 0x00 }) };
-    ([u8; 20],  $buf:expr, $val:expr) => { $buf.extend_from_slice($val) };
-    ([u8; 32],  $buf:expr, $val:expr) => { $buf.extend_from_slice($val) };
+macro_rules! selector {
+    ($signature:literal) => {{
+        const SELECTOR: u32 = $crate::abi::function_selector($signature);
+        SELECTOR
+    }};
 }
 
 // ============================================================================
@@ -326,7 +392,12 @@ macro_rules! _agent_input_write {
 /// This macro generates:
 /// - A wrapper struct implementing `kernel_guest::AgentEntrypoint`
 /// - `pub fn kernel_main(input: &[u8]) -> Result<Vec<u8>, KernelError>`
-/// - `pub fn kernel_main_with_constraints(input: &[u8], cs: &ConstraintSetV1) -> Result<Vec<u8>, KernelError>`
+/// - `pub fn kernel_main_with_constraints(input: &[u8], cs: &ConstraintSet) -> Result<Vec<u8>, KernelError>`
+/// - `pub fn kernel_main_with_constraint_bytes(input: &[u8], cs_bytes: &[u8]) -> Result<Vec<u8>, KernelError>`
+/// - `pub fn kernel_main_with_rules(input: &[u8], rules: &RuleSet) -> Result<Vec<u8>, KernelError>`
+/// - `pub fn kernel_batch_main_with_constraints(batch: &[u8], cs: &ConstraintSet) -> Result<Vec<u8>, KernelError>`
+/// - `pub fn kernel_batch_main_with_constraint_bytes(batch: &[u8], cs_bytes: &[u8]) -> Result<Vec<u8>, KernelError>`
+/// - `pub fn kernel_batch_main_with_rules(batch: &[u8], rules: &RuleSet) -> Result<Vec<u8>, KernelError>`
 /// - Re-export of `KernelError`
 ///
 /// # Requirements
@@ -341,8 +412,44 @@ macro_rules! _agent_input_write {
 /// // In your agent crate's lib.rs, after defining agent_main:
 /// kernel_sdk::agent_entrypoint!(agent_main);
 /// ```
+///
+/// An agent whose `agent_main` is a [`TryAgentEntrypoint`] is bound with
+/// `try`, so that errors it returns produce an `AgentError` journal:
+///
+/// ```ignore
+/// kernel_sdk::agent_entrypoint!(try agent_main);
+/// ```
 #[macro_export]
 macro_rules! agent_entrypoint {
+    (try $agent_fn:ident) => {
+        struct __KernelAgentWrapper;
+
+        impl ::kernel_guest::AgentEntrypoint for __KernelAgentWrapper {
+            fn code_hash(&self) -> [u8; 32] {
+                AGENT_CODE_HASH
+            }
+
+            fn run(
+                &self,
+                ctx: &$crate::agent::AgentContext,
+                opaque_inputs: &[u8],
+            ) -> $crate::types::AgentOutput {
+                $agent_fn(ctx, opaque_inputs).unwrap_or($crate::types::AgentOutput {
+                    actions: ::alloc::vec::Vec::new(),
+                })
+            }
+
+            fn try_run(
+                &self,
+                ctx: &$crate::agent::AgentContext,
+                opaque_inputs: &[u8],
+            ) -> ::core::result::Result<$crate::types::AgentOutput, $crate::agent::AgentError> {
+                $agent_fn(ctx, opaque_inputs)
+            }
+        }
+
+        $crate::_agent_entrypoint_kernel_fns!();
+    };
     ($agent_fn:ident) => {
         struct __KernelAgentWrapper;
 
@@ -360,6 +467,15 @@ macro_rules! agent_entrypoint {
             }
         }
 
+        $crate::_agent_entrypoint_kernel_fns!();
+    };
+}
+
+/// Internal helper macro: kernel functions bound to `__KernelAgentWrapper`.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! _agent_entrypoint_kernel_fns {
+    () => {
         /// Execute kernel with this agent.
         pub fn kernel_main(
             input_bytes: &[u8],
@@ -370,7 +486,7 @@ macro_rules! agent_entrypoint {
         /// Execute kernel with this agent and custom constraints.
         pub fn kernel_main_with_constraints(
             input_bytes: &[u8],
-            constraint_set: &::constraints::ConstraintSetV1,
+            constraint_set: &::constraints::ConstraintSet,
         ) -> ::core::result::Result<::alloc::vec::Vec<u8>, ::kernel_guest::KernelError> {
             ::kernel_guest::kernel_main_with_agent_and_constraints(
                 input_bytes,
@@ -379,6 +495,66 @@ macro_rules! agent_entrypoint {
             )
         }
 
+        /// Execute kernel with this agent and a canonically encoded constraint set.
+        pub fn kernel_main_with_constraint_bytes(
+            input_bytes: &[u8],
+            constraint_set_bytes: &[u8],
+        ) -> ::core::result::Result<::alloc::vec::Vec<u8>, ::kernel_guest::KernelError> {
+            ::kernel_guest::kernel_main_with_agent_and_constraint_bytes(
+                input_bytes,
+                constraint_set_bytes,
+                &__KernelAgentWrapper,
+            )
+        }
+
+        /// Execute kernel with this agent and a rule set (constraints plus custom rules).
+        pub fn kernel_main_with_rules(
+            input_bytes: &[u8],
+            rule_set: &::constraints::RuleSet,
+        ) -> ::core::result::Result<::alloc::vec::Vec<u8>, ::kernel_guest::KernelError> {
+            ::kernel_guest::kernel_main_with_agent_and_rules(
+                input_bytes,
+                &__KernelAgentWrapper,
+                rule_set,
+            )
+        }
+
+        /// Execute a batch of inputs with this agent and custom constraints.
+        pub fn kernel_batch_main_with_constraints(
+            batch_bytes: &[u8],
+            constraint_set: &::constraints::ConstraintSet,
+        ) -> ::core::result::Result<::alloc::vec::Vec<u8>, ::kernel_guest::KernelError> {
+            ::kernel_guest::kernel_batch_main_with_agent_and_constraints(
+                batch_bytes,
+                &__KernelAgentWrapper,
+                constraint_set,
+            )
+        }
+
+        /// Execute a batch of inputs with this agent and a canonically encoded constraint set.
+        pub fn kernel_batch_main_with_constraint_bytes(
+            batch_bytes: &[u8],
+            constraint_set_bytes: &[u8],
+        ) -> ::core::result::Result<::alloc::vec::Vec<u8>, ::kernel_guest::KernelError> {
+            ::kernel_guest::kernel_batch_main_with_agent_and_constraint_bytes(
+                batch_bytes,
+                constraint_set_bytes,
+                &__KernelAgentWrapper,
+            )
+        }
+
+        /// Execute a batch of inputs with this agent and a rule set.
+        pub fn kernel_batch_main_with_rules(
+            batch_bytes: &[u8],
+            rule_set: &::constraints::RuleSet,
+        ) -> ::core::result::Result<::alloc::vec::Vec<u8>, ::kernel_guest::KernelError> {
+            ::kernel_guest::kernel_batch_main_with_agent_and_rules(
+                batch_bytes,
+                &__KernelAgentWrapper,
+                rule_set,
+            )
+        }
+
         /// Re-export KernelError for convenience.
         pub use ::kernel_guest::KernelError;
     };
@@ -408,12 +584,12 @@ pub const SDK_VERSION: u32 = ((SDK_VERSION_MAJOR as u32) << 16)
 pub const MIN_KERNEL_VERSION: u32 = 1;
 
 /// Maximum supported kernel version.
-pub const MAX_KERNEL_VERSION: u32 = 1;
+pub const MAX_KERNEL_VERSION: u32 = 5;
 
 /// Check if a kernel version is supported by this SDK.
 #[inline]
 pub fn is_kernel_version_supported(version: u32) -> bool {
-    version >= MIN_KERNEL_VERSION && version <= MAX_KERNEL_VERSION
+    (MIN_KERNEL_VERSION..=MAX_KERNEL_VERSION).contains(&version)
 }
 
 #[cfg(test)]
@@ -431,8 +607,12 @@ mod tests {
     #[test]
     fn test_kernel_version_supported() {
         assert!(is_kernel_version_supported(1));
+        assert!(is_kernel_version_supported(2));
+        assert!(is_kernel_version_supported(3));
+        assert!(is_kernel_version_supported(4));
+        assert!(is_kernel_version_supported(5));
         assert!(!is_kernel_version_supported(0));
-        assert!(!is_kernel_version_supported(2));
+        assert!(!is_kernel_version_supported(6));
     }
 
     // ====================================================================
@@ -644,6 +824,129 @@ mod tests {
         assert_eq!(re_encoded, input);
     }
 
+    // ====================================================================
+    // agent_input! signed, variable-length and nested fields
+    // ====================================================================
+
+    agent_input! {
+        #[derive(Debug, PartialEq)]
+        struct LegInput {
+            asset_id: u32,
+            size: i64,
+        }
+    }
+
+    agent_input! {
+        #[derive(Debug, PartialEq)]
+        struct NestedInput {
+            pnl: i128,
+            notional: u128,
+            weights: [u16; 3],
+            legs: input::BoundedVec<LegInput, 2>,
+            tag: [u8; 4],
+        }
+    }
+
+    fn sample_nested_input() -> NestedInput {
+        let legs = alloc::vec![
+            LegInput {
+                asset_id: 7,
+                size: -1,
+            },
+            LegInput {
+                asset_id: 8,
+                size: 300,
+            },
+        ];
+        NestedInput {
+            pnl: -2,
+            notional: 1 << 100,
+            weights: [1, 2, 3],
+            legs: input::BoundedVec::from_vec(legs).unwrap(),
+            tag: *b"perp",
+        }
+    }
+
+    #[test]
+    fn test_agent_input_fixed_sizes() {
+        assert_eq!(TestInput::MIN_ENCODED_SIZE, 53);
+        assert_eq!(TestInput::MAX_ENCODED_SIZE, 53);
+        assert_eq!(LegInput::ENCODED_SIZE, 12);
+    }
+
+    #[test]
+    fn test_agent_input_variable_sizes() {
+        // i128 + u128 + [u16; 3] + vec count + [u8; 4]
+        assert_eq!(NestedInput::MIN_ENCODED_SIZE, 16 + 16 + 6 + 4 + 4);
+        assert_eq!(NestedInput::MAX_ENCODED_SIZE, 16 + 16 + 6 + 4 + 2 * 12 + 4);
+    }
+
+    #[test]
+    fn test_agent_input_nested_roundtrip() {
+        let input = sample_nested_input();
+        let bytes = input.encode();
+        assert_eq!(bytes.len(), NestedInput::MAX_ENCODED_SIZE);
+        assert_eq!(NestedInput::decode(&bytes), Some(input));
+
+        let mut empty = sample_nested_input();
+        empty.legs = input::BoundedVec::new();
+        let bytes = empty.encode();
+        assert_eq!(bytes.len(), NestedInput::MIN_ENCODED_SIZE);
+        assert_eq!(NestedInput::decode(&bytes), Some(empty));
+    }
+
+    #[test]
+    fn test_agent_input_nested_layout() {
+        let bytes = sample_nested_input().encode();
+        assert_eq!(&bytes[..16], &(-2i128).to_le_bytes());
+        assert_eq!(&bytes[16..32], &(1u128 << 100).to_le_bytes());
+        // Vec count follows the fixed-size prefix
+        assert_eq!(&bytes[38..42], &2u32.to_le_bytes());
+        // Nested structs are inlined field by field
+        assert_eq!(&bytes[42..46], &7u32.to_le_bytes());
+        assert_eq!(&bytes[46..54], &(-1i64).to_le_bytes());
+        assert_eq!(&bytes[bytes.len() - 4..], b"perp");
+    }
+
+    #[test]
+    fn test_agent_input_variable_decode_rejects() {
+        let mut bytes = sample_nested_input().encode();
+
+        // Count above MAX
+        bytes[38..42].copy_from_slice(&3u32.to_le_bytes());
+        assert!(NestedInput::decode(&bytes).is_none());
+
+        // Shorter count leaves trailing bytes
+        bytes[38..42].copy_from_slice(&1u32.to_le_bytes());
+        assert!(NestedInput::decode(&bytes).is_none());
+
+        // Truncated element data
+        let bytes = sample_nested_input().encode();
+        assert!(NestedInput::decode(&bytes[..bytes.len() - 5]).is_none());
+    }
+
+    #[test]
+    fn test_agent_input_schema() {
+        use alloc::string::ToString;
+
+        assert_eq!(
+            TestInput::SCHEMA.to_string(),
+            "TestInput { addr1: [u8; 20], addr2: [u8; 20], amount: u64, rate: u32, flag: u8 }"
+        );
+        assert_eq!(
+            NestedInput::SCHEMA.to_string(),
+            "NestedInput { pnl: i128, notional: u128, weights: [u16; 3], \
+             legs: BoundedVec<LegInput, 2>, tag: [u8; 4] }"
+        );
+        assert_eq!(
+            NestedInput::SCHEMA.fields[3].ty,
+            input::FieldType::Vec {
+                element: &input::FieldType::Struct(&LegInput::SCHEMA),
+                max_len: 2,
+            }
+        );
+    }
+
     /// Ensure all prelude exports compile and are accessible.
     /// This catches accidental rename/missing export regressions.
     #[test]
@@ -22,8 +22,24 @@
 //! - `calculate_bps(1, 3)` returns `Some(3333)` (not 3334)
 //!
 //! If you need different rounding behavior (e.g., round-half-up for prices),
-//! implement separate helpers; do not modify these canonical floor-division
-//! functions.
+//! use [`FixedPoint`] or [`U256`](crate::u256::U256), which take an explicit
+//! [`Rounding`] mode; do not modify these canonical floor-division functions.
+//!
+//! # Fixed-Point Decimals
+//!
+//! [`FixedPoint<D>`](FixedPoint) holds a decimal with `D` fractional digits,
+//! such as a 1e8-scaled price ([`Fixed8`]) or a 6-decimal USDC amount
+//! ([`Fixed6`]). Multiplication, division and rescaling round explicitly:
+//!
+//! ```
+//! use kernel_sdk::math::{Fixed6, Fixed8, Rounding};
+//!
+//! // 100 USDC at $2,500.5 per unit, in 8 decimals
+//! let margin = Fixed6::from_int(100).unwrap().rescale::<8>(Rounding::Down).unwrap();
+//! let price = Fixed8::from_raw(250_050_000_000);
+//! let size = margin.checked_div(price, Rounding::Down).unwrap();
+//! assert_eq!(size.raw(), 3_999_200); // 0.03999200
+//! ```
 //!
 //! # Example
 //!
@@ -39,6 +55,10 @@
 //! assert_eq!(sum, Some(300));
 //! ```
 
+use core::fmt;
+
+use crate::u256::U256;
+
 // ============================================================================
 // Checked Arithmetic
 // ============================================================================
@@ -99,6 +119,24 @@ pub fn checked_mul_div_u64(a: u64, b: u64, denom: u64) -> Option<u64> {
     a.checked_mul(b).map(|v| v / denom)
 }
 
+/// Rounding mode for divisions that take one explicitly.
+///
+/// The `u64` helpers in this module always round [`Down`](Rounding::Down);
+/// [`FixedPoint`] and
+/// [`U256::checked_mul_div`](crate::u256::U256::checked_mul_div) let the
+/// caller choose.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Toward zero (floor).
+    Down,
+    /// Away from zero (ceiling).
+    Up,
+    /// To the nearest value, ties rounding up.
+    HalfUp,
+    /// To the nearest value, ties rounding to an even result.
+    HalfEven,
+}
+
 // ============================================================================
 // Saturating Arithmetic
 // ============================================================================
@@ -229,6 +267,189 @@ pub fn drawdown_bps(current_equity: u64, peak_equity: u64) -> Option<u64> {
     checked_mul_div_u64(drawdown, BPS_DENOMINATOR, peak_equity)
 }
 
+// ============================================================================
+// Fixed-Point Decimals
+// ============================================================================
+
+/// Unsigned fixed-point decimal with `DECIMALS` fractional digits.
+///
+/// Stores `value * 10^DECIMALS` as a `u128` (the raw value). Addition and
+/// subtraction are exact; multiplication, division and conversion to fewer
+/// decimals take a [`Rounding`] mode. Intermediate products are computed in
+/// 256 bits, so only results that do not fit in `u128` fail.
+///
+/// Values at different scales are different types; convert between them
+/// with [`rescale`](FixedPoint::rescale).
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedPoint<const DECIMALS: u32> {
+    raw: u128,
+}
+
+/// Decimal with 6 fractional digits, the scale of USDC amounts.
+pub type Fixed6 = FixedPoint<6>;
+
+/// Decimal with 8 fractional digits, the scale of oracle prices.
+pub type Fixed8 = FixedPoint<8>;
+
+/// Decimal with 18 fractional digits, the scale of most ERC20 amounts.
+pub type Fixed18 = FixedPoint<18>;
+
+impl<const DECIMALS: u32> FixedPoint<DECIMALS> {
+    /// Raw value of one: `10^DECIMALS`.
+    pub const SCALE: u128 = 10u128.pow(DECIMALS);
+
+    /// Zero.
+    pub const ZERO: Self = Self { raw: 0 };
+
+    /// One.
+    pub const ONE: Self = Self { raw: Self::SCALE };
+
+    /// Largest representable value.
+    pub const MAX: Self = Self { raw: u128::MAX };
+
+    /// Create from a raw value already scaled by `10^DECIMALS`.
+    #[inline]
+    #[must_use]
+    pub const fn from_raw(raw: u128) -> Self {
+        Self { raw }
+    }
+
+    /// The raw value, scaled by `10^DECIMALS`.
+    #[inline]
+    #[must_use]
+    pub const fn raw(self) -> u128 {
+        self.raw
+    }
+
+    /// Create from a whole number. Returns `None` on overflow.
+    #[inline]
+    #[must_use]
+    pub fn from_int(val: u128) -> Option<Self> {
+        val.checked_mul(Self::SCALE).map(Self::from_raw)
+    }
+
+    /// Create from the fraction `numerator / denominator`.
+    ///
+    /// Returns `None` if `denominator` is zero or the result overflows.
+    #[must_use]
+    pub fn from_ratio(numerator: u128, denominator: u128, rounding: Rounding) -> Option<Self> {
+        mul_div_u128(numerator, Self::SCALE, denominator, rounding).map(Self::from_raw)
+    }
+
+    /// The whole-number part, rounded according to `rounding`.
+    #[must_use]
+    pub fn to_int(self, rounding: Rounding) -> Option<u128> {
+        self.to_scaled(0, rounding)
+    }
+
+    /// The raw value at `decimals` fractional digits instead of `DECIMALS`.
+    ///
+    /// Use this when the target scale is only known at runtime, such as an
+    /// exchange's size decimals. Returns `None` on overflow.
+    #[must_use]
+    pub fn to_scaled(self, decimals: u32, rounding: Rounding) -> Option<u128> {
+        if decimals >= DECIMALS {
+            let factor = 10u128.checked_pow(decimals - DECIMALS)?;
+            self.raw.checked_mul(factor)
+        } else {
+            let factor = 10u128.checked_pow(DECIMALS - decimals)?;
+            mul_div_u128(self.raw, 1, factor, rounding)
+        }
+    }
+
+    /// Convert to another scale, rounding if `TO` has fewer decimals.
+    ///
+    /// Returns `None` if the value does not fit at the new scale.
+    #[must_use]
+    pub fn rescale<const TO: u32>(self, rounding: Rounding) -> Option<FixedPoint<TO>> {
+        self.to_scaled(TO, rounding).map(FixedPoint::from_raw)
+    }
+
+    /// Checked addition. Returns `None` on overflow.
+    #[inline]
+    #[must_use]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.raw.checked_add(rhs.raw).map(Self::from_raw)
+    }
+
+    /// Checked subtraction. Returns `None` on underflow.
+    #[inline]
+    #[must_use]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.raw.checked_sub(rhs.raw).map(Self::from_raw)
+    }
+
+    /// Saturating addition. Returns [`FixedPoint::MAX`] on overflow.
+    #[inline]
+    #[must_use]
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self::from_raw(self.raw.saturating_add(rhs.raw))
+    }
+
+    /// Saturating subtraction. Returns zero on underflow.
+    #[inline]
+    #[must_use]
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self::from_raw(self.raw.saturating_sub(rhs.raw))
+    }
+
+    /// Checked multiplication. Returns `None` on overflow.
+    #[must_use]
+    pub fn checked_mul(self, rhs: Self, rounding: Rounding) -> Option<Self> {
+        mul_div_u128(self.raw, rhs.raw, Self::SCALE, rounding).map(Self::from_raw)
+    }
+
+    /// Checked division. Returns `None` if `rhs` is zero or on overflow.
+    #[must_use]
+    pub fn checked_div(self, rhs: Self, rounding: Rounding) -> Option<Self> {
+        mul_div_u128(self.raw, Self::SCALE, rhs.raw, rounding).map(Self::from_raw)
+    }
+
+    /// Multiply by a fraction in basis points (10000 = 100%).
+    ///
+    /// Returns `None` on overflow.
+    #[must_use]
+    pub fn checked_mul_bps(self, bps: u64, rounding: Rounding) -> Option<Self> {
+        let bps = u128::from(bps);
+        mul_div_u128(self.raw, bps, u128::from(BPS_DENOMINATOR), rounding).map(Self::from_raw)
+    }
+
+    /// Round to a multiple of `tick`, such as an exchange's price tick.
+    ///
+    /// Returns `None` if `tick` is zero or on overflow.
+    #[must_use]
+    pub fn round_to_tick(self, tick: Self, rounding: Rounding) -> Option<Self> {
+        let ticks = mul_div_u128(self.raw, 1, tick.raw, rounding)?;
+        ticks.checked_mul(tick.raw).map(Self::from_raw)
+    }
+}
+
+/// `a * b / denom` with a 256-bit intermediate and explicit rounding.
+fn mul_div_u128(a: u128, b: u128, denom: u128, rounding: Rounding) -> Option<u128> {
+    U256::from_u128(a)
+        .checked_mul_div(U256::from_u128(b), U256::from_u128(denom), rounding)?
+        .to_u128()
+}
+
+/// Formats the value with all `DECIMALS` fractional digits.
+impl<const DECIMALS: u32> fmt::Display for FixedPoint<DECIMALS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let int = self.raw / Self::SCALE;
+        let frac = self.raw % Self::SCALE;
+        if DECIMALS == 0 {
+            write!(f, "{}", int)
+        } else {
+            write!(f, "{}.{:0width$}", int, frac, width = DECIMALS as usize)
+        }
+    }
+}
+
+impl<const DECIMALS: u32> fmt::Debug for FixedPoint<DECIMALS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,4 +530,140 @@ mod tests {
         assert_eq!(drawdown_bps(50, 0), None); // Invalid peak
     }
 
+    #[test]
+    fn test_fixed_point_constructors() {
+        assert_eq!(Fixed6::SCALE, 1_000_000);
+        assert_eq!(Fixed18::ONE.raw(), 1_000_000_000_000_000_000);
+        assert_eq!(Fixed8::from_int(3), Some(Fixed8::from_raw(300_000_000)));
+        assert_eq!(Fixed18::from_int(u128::MAX), None);
+
+        // 1/3 at 6 decimals
+        assert_eq!(
+            Fixed6::from_ratio(1, 3, Rounding::Down).unwrap().raw(),
+            333_333
+        );
+        assert_eq!(
+            Fixed6::from_ratio(1, 3, Rounding::Up).unwrap().raw(),
+            333_334
+        );
+        assert_eq!(
+            Fixed6::from_ratio(2, 3, Rounding::HalfUp).unwrap().raw(),
+            666_667
+        );
+        assert_eq!(Fixed6::from_ratio(1, 0, Rounding::Down), None);
+    }
+
+    #[test]
+    fn test_fixed_point_mul_div() {
+        let a = Fixed6::from_raw(1_500_000); // 1.5
+        let b = Fixed6::from_raw(2_000_001); // 2.000001
+
+        // 3.0000015 at 6 decimals
+        assert_eq!(a.checked_mul(b, Rounding::Down).unwrap().raw(), 3_000_001);
+        assert_eq!(a.checked_mul(b, Rounding::Up).unwrap().raw(), 3_000_002);
+        assert_eq!(a.checked_mul(b, Rounding::HalfUp).unwrap().raw(), 3_000_002);
+        assert_eq!(
+            a.checked_mul(b, Rounding::HalfEven).unwrap().raw(),
+            3_000_002
+        );
+
+        // 2.5000005: half-even rounds the tie toward the even digit
+        let c = Fixed6::from_raw(5_000_001);
+        let half = Fixed6::from_raw(500_000);
+        assert_eq!(
+            c.checked_mul(half, Rounding::HalfEven).unwrap().raw(),
+            2_500_000
+        );
+        assert_eq!(
+            c.checked_mul(half, Rounding::HalfUp).unwrap().raw(),
+            2_500_001
+        );
+
+        // 1 / 3
+        let one = Fixed6::ONE;
+        let three = Fixed6::from_int(3).unwrap();
+        assert_eq!(
+            one.checked_div(three, Rounding::Down).unwrap().raw(),
+            333_333
+        );
+        assert_eq!(one.checked_div(three, Rounding::Up).unwrap().raw(), 333_334);
+        assert_eq!(one.checked_div(Fixed6::ZERO, Rounding::Down), None);
+
+        // Intermediate products beyond u128 are fine if the result fits
+        let big = Fixed18::from_int(10u128.pow(19)).unwrap();
+        assert_eq!(big.checked_mul(Fixed18::ONE, Rounding::Down), Some(big));
+        assert_eq!(big.checked_mul(big, Rounding::Down), None);
+    }
+
+    #[test]
+    fn test_fixed_point_add_sub() {
+        let a = Fixed8::from_raw(150);
+        let b = Fixed8::from_raw(50);
+        assert_eq!(a.checked_add(b), Some(Fixed8::from_raw(200)));
+        assert_eq!(b.checked_sub(a), None);
+        assert_eq!(b.saturating_sub(a), Fixed8::ZERO);
+        assert_eq!(Fixed8::MAX.checked_add(b), None);
+        assert_eq!(Fixed8::MAX.saturating_add(b), Fixed8::MAX);
+    }
+
+    #[test]
+    fn test_fixed_point_rescale() {
+        let usdc = Fixed6::from_raw(1_234_567); // 1.234567
+        let up: Fixed18 = usdc.rescale(Rounding::Down).unwrap();
+        assert_eq!(up.raw(), 1_234_567_000_000_000_000);
+        assert_eq!(up.rescale::<6>(Rounding::Down), Some(usdc));
+
+        let price = Fixed8::from_raw(123_456_789); // 1.23456789
+        assert_eq!(price.rescale::<6>(Rounding::Down).unwrap().raw(), 1_234_567);
+        assert_eq!(price.rescale::<6>(Rounding::Up).unwrap().raw(), 1_234_568);
+        assert_eq!(
+            price.rescale::<6>(Rounding::HalfUp).unwrap().raw(),
+            1_234_568
+        );
+
+        // Scaling up can overflow
+        assert_eq!(Fixed6::MAX.rescale::<18>(Rounding::Down), None);
+
+        assert_eq!(price.to_scaled(2, Rounding::Down), Some(123));
+        assert_eq!(price.to_scaled(10, Rounding::Down), Some(12_345_678_900));
+        assert_eq!(price.to_int(Rounding::Down), Some(1));
+        assert_eq!(price.to_int(Rounding::Up), Some(2));
+        assert_eq!(price.to_scaled(60, Rounding::Down), None);
+    }
+
+    #[test]
+    fn test_fixed_point_bps_and_ticks() {
+        let mark = Fixed8::from_raw(5_000_012_345);
+        assert_eq!(
+            mark.checked_mul_bps(300, Rounding::Down).unwrap().raw(),
+            150_000_370
+        );
+        assert_eq!(
+            mark.checked_mul_bps(300, Rounding::Up).unwrap().raw(),
+            150_000_371
+        );
+
+        let tick = Fixed8::from_raw(100);
+        let price = Fixed8::from_raw(5_150_012_715);
+        assert_eq!(
+            price.round_to_tick(tick, Rounding::Down).unwrap().raw(),
+            5_150_012_700
+        );
+        assert_eq!(
+            price.round_to_tick(tick, Rounding::Up).unwrap().raw(),
+            5_150_012_800
+        );
+        assert_eq!(price.round_to_tick(Fixed8::ZERO, Rounding::Down), None);
+    }
+
+    #[test]
+    fn test_fixed_point_display() {
+        use alloc::string::ToString;
+
+        assert_eq!(Fixed6::from_raw(1_500_000).to_string(), "1.500000");
+        assert_eq!(Fixed8::from_raw(1).to_string(), "0.00000001");
+        assert_eq!(FixedPoint::<0>::from_raw(42).to_string(), "42");
+        assert_eq!(alloc::format!("{:?}", Fixed6::ONE), "1.000000");
+    }
+
 }
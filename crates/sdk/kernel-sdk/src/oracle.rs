@@ -415,6 +415,8 @@ mod tests {
             constraint_set_hash: [0; 32],
             input_root: expected_hash,
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: crate::agent::StepMeter::default(),
         };
 
         assert!(
@@ -434,6 +436,8 @@ mod tests {
             constraint_set_hash: [0; 32],
             input_root: [0xFF; 32], // wrong hash
             execution_nonce: 1,
+            state_snapshot: None,
+            step_meter: crate::agent::StepMeter::default(),
         };
 
         assert!(
@@ -22,10 +22,10 @@
 
 use alloc::vec::Vec;
 use crate::agent::AgentContext;
-use crate::types::{ActionV1, AgentOutput};
+use crate::types::{ActionV1, AgentOutput, StateSnapshot};
 use kernel_core::{
-    CanonicalDecode, CanonicalEncode, ExecutionStatus, KernelError, KernelInputV1,
-    KernelJournalV1,
+    CanonicalDecode, CanonicalEncode, ConstraintViolation, ExecutionStatus, KernelError,
+    KernelInputV1, KernelJournalV1,
 };
 
 // ============================================================================
@@ -117,6 +117,8 @@ pub fn bytes32(hex: &str) -> [u8; 32] {
 /// - `constraint_set_hash`: `[0; 32]`
 /// - `input_root`: `[0; 32]`
 /// - `execution_nonce`: 1
+/// - `state_snapshot`: `None`
+/// - `step_budget`: `None` (unlimited)
 pub struct ContextBuilder {
     protocol_version: u32,
     kernel_version: u32,
@@ -125,6 +127,8 @@ pub struct ContextBuilder {
     constraint_set_hash: [u8; 32],
     input_root: [u8; 32],
     execution_nonce: u64,
+    state_snapshot: Option<StateSnapshot>,
+    step_budget: Option<u64>,
 }
 
 impl ContextBuilder {
@@ -138,6 +142,8 @@ impl ContextBuilder {
             constraint_set_hash: [0; 32],
             input_root: [0; 32],
             execution_nonce: 1,
+            state_snapshot: None,
+            step_budget: None,
         }
     }
 
@@ -171,6 +177,21 @@ impl ContextBuilder {
         self
     }
 
+    /// Set the typed state snapshot.
+    ///
+    /// Also sets `kernel_version` to 5, the only version whose inputs carry one.
+    pub fn state_snapshot(mut self, snapshot: impl Into<StateSnapshot>) -> Self {
+        self.kernel_version = kernel_core::KERNEL_VERSION_V5;
+        self.state_snapshot = Some(snapshot.into());
+        self
+    }
+
+    /// Set the step budget the agent is metered against.
+    pub fn step_budget(mut self, budget: u64) -> Self {
+        self.step_budget = Some(budget);
+        self
+    }
+
     /// Build the `AgentContext`.
     pub fn build(self) -> AgentContext {
         AgentContext::new(
@@ -182,6 +203,8 @@ impl ContextBuilder {
             self.input_root,
             self.execution_nonce,
         )
+        .with_state_snapshot(self.state_snapshot)
+        .with_step_budget(self.step_budget)
     }
 }
 
@@ -210,14 +233,20 @@ impl Default for ContextBuilder {
 pub struct TestHarness {
     ctx: ContextBuilder,
     opaque_input: Vec<u8>,
+    allocation_probe: Option<AllocationProbe>,
 }
 
+/// Function that runs its argument and returns the peak number of heap bytes
+/// it used, such as `kernel_guest::measure_peak_allocation`.
+pub type AllocationProbe = fn(&mut dyn FnMut()) -> usize;
+
 impl TestHarness {
     /// Create a new TestHarness with default context and empty input.
     pub fn new() -> Self {
         Self {
             ctx: ContextBuilder::new(),
             opaque_input: Vec::new(),
+            allocation_probe: None,
         }
     }
 
@@ -245,30 +274,56 @@ impl TestHarness {
         self
     }
 
+    /// Meter the agent against a step budget.
+    ///
+    /// Only applies to [`execute`](Self::execute); the steps the agent
+    /// charged are in the result's `context.step_meter`.
+    pub fn step_budget(mut self, budget: u64) -> Self {
+        self.ctx = self.ctx.step_budget(budget);
+        self
+    }
+
     /// Set the opaque agent input bytes.
     pub fn input(mut self, bytes: impl AsRef<[u8]>) -> Self {
         self.opaque_input = bytes.as_ref().to_vec();
         self
     }
 
+    /// Measure the peak heap use of the agent or kernel call with `probe`.
+    ///
+    /// The result's `peak_allocation` is only set when a probe is given.
+    /// `kernel_guest::measure_peak_allocation` reports what the call would
+    /// take from the zkVM guest heap, replaying allocations made through a
+    /// `kernel_guest::BoundedAllocator`, which the test binary must install
+    /// as its global allocator.
+    pub fn allocation_probe(mut self, probe: AllocationProbe) -> Self {
+        self.allocation_probe = Some(probe);
+        self
+    }
+
     /// Execute an agent function directly and return a `TestResult`.
     ///
     /// This calls the agent function with a constructed `AgentContext` and
     /// the configured input bytes.
     pub fn execute(self, agent_fn: fn(&AgentContext, &[u8]) -> AgentOutput) -> TestResult {
         let context = self.ctx.build();
-        let output = agent_fn(&context, &self.opaque_input);
+        let (output, peak_allocation) = run_measured(self.allocation_probe, || {
+            agent_fn(&context, &self.opaque_input)
+        });
         TestResult {
             output,
             context,
             input_bytes: self.opaque_input,
+            peak_allocation,
         }
     }
 
     /// Execute a kernel function and return a `KernelTestResult`.
     ///
     /// Builds a `KernelInputV1`, encodes it, calls the kernel function,
-    /// and decodes the resulting journal.
+    /// and decodes the resulting journal. The input carries
+    /// `constraints::DEFAULT_CONSTRAINT_SET_HASH`, matching the default
+    /// constraint set enforced by `kernel_main`.
     pub fn execute_kernel(
         self,
         kernel_fn: fn(&[u8]) -> Result<Vec<u8>, KernelError>,
@@ -278,7 +333,7 @@ impl TestHarness {
             kernel_version: 1,
             agent_id: self.ctx.agent_id,
             agent_code_hash: self.ctx.agent_code_hash,
-            constraint_set_hash: self.ctx.constraint_set_hash,
+            constraint_set_hash: constraints::DEFAULT_CONSTRAINT_SET_HASH,
             input_root: self.ctx.input_root,
             execution_nonce: self.ctx.execution_nonce,
             opaque_agent_inputs: self.opaque_input,
@@ -288,8 +343,9 @@ impl TestHarness {
             .encode()
             .expect("TestHarness: failed to encode KernelInputV1");
 
-        let journal_bytes = kernel_fn(&input_bytes)
-            .expect("TestHarness: kernel_fn returned error");
+        let (journal_bytes, peak_allocation) =
+            run_measured(self.allocation_probe, || kernel_fn(&input_bytes));
+        let journal_bytes = journal_bytes.expect("TestHarness: kernel_fn returned error");
 
         let journal = KernelJournalV1::decode(&journal_bytes)
             .expect("TestHarness: failed to decode KernelJournalV1");
@@ -299,21 +355,27 @@ impl TestHarness {
             journal_bytes,
             input,
             input_bytes,
+            peak_allocation,
         }
     }
 
     /// Execute a kernel function with custom constraints and return a `KernelTestResult`.
+    ///
+    /// The input's `constraint_set_hash` is computed from `constraint_set`.
     pub fn execute_kernel_with_constraints(
         self,
-        kernel_fn: fn(&[u8], &constraints::ConstraintSetV1) -> Result<Vec<u8>, KernelError>,
-        constraint_set: &constraints::ConstraintSetV1,
+        kernel_fn: fn(&[u8], &constraints::ConstraintSet) -> Result<Vec<u8>, KernelError>,
+        constraint_set: &constraints::ConstraintSet,
     ) -> KernelTestResult {
+        let constraint_set_hash = constraints::compute_constraint_set_hash(constraint_set)
+            .expect("TestHarness: failed to encode ConstraintSet");
+
         let input = KernelInputV1 {
             protocol_version: 1,
             kernel_version: 1,
             agent_id: self.ctx.agent_id,
             agent_code_hash: self.ctx.agent_code_hash,
-            constraint_set_hash: self.ctx.constraint_set_hash,
+            constraint_set_hash,
             input_root: self.ctx.input_root,
             execution_nonce: self.ctx.execution_nonce,
             opaque_agent_inputs: self.opaque_input,
@@ -323,8 +385,10 @@ impl TestHarness {
             .encode()
             .expect("TestHarness: failed to encode KernelInputV1");
 
-        let journal_bytes = kernel_fn(&input_bytes, constraint_set)
-            .expect("TestHarness: kernel_fn returned error");
+        let (journal_bytes, peak_allocation) = run_measured(self.allocation_probe, || {
+            kernel_fn(&input_bytes, constraint_set)
+        });
+        let journal_bytes = journal_bytes.expect("TestHarness: kernel_fn returned error");
 
         let journal = KernelJournalV1::decode(&journal_bytes)
             .expect("TestHarness: failed to decode KernelJournalV1");
@@ -334,6 +398,7 @@ impl TestHarness {
             journal_bytes,
             input,
             input_bytes,
+            peak_allocation,
         }
     }
 }
@@ -356,6 +421,8 @@ pub struct TestResult {
     pub context: AgentContext,
     /// The raw input bytes that were passed to the agent.
     pub input_bytes: Vec<u8>,
+    /// Peak heap bytes the agent allocated, if an allocation probe was set.
+    pub peak_allocation: Option<usize>,
 }
 
 impl TestResult {
@@ -433,6 +500,15 @@ impl TestResult {
         );
     }
 
+    /// Assert that the agent's peak heap use was at most `limit` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no allocation probe was set or the peak exceeds `limit`.
+    pub fn assert_peak_allocation_at_most(&self, limit: usize) {
+        assert_peak_allocation_at_most(self.peak_allocation, limit);
+    }
+
     /// Assert that the output has no actions.
     pub fn assert_empty(&self) {
         assert!(
@@ -444,7 +520,12 @@ impl TestResult {
 
     /// Assert determinism: running the same agent function again produces identical output.
     pub fn assert_deterministic(&self, agent_fn: fn(&AgentContext, &[u8]) -> AgentOutput) {
-        let output2 = agent_fn(&self.context, &self.input_bytes);
+        // Rerun on a fresh step meter so the first run's steps are not counted twice
+        let context = self
+            .context
+            .clone()
+            .with_step_budget(self.context.step_meter.budget());
+        let output2 = agent_fn(&context, &self.input_bytes);
         assert_eq!(
             self.output, output2,
             "agent is not deterministic: outputs differ on re-execution"
@@ -459,6 +540,58 @@ impl TestResult {
             index
         );
     }
+
+    // ========================================================================
+    // Constraint Diagnostics
+    // ========================================================================
+
+    /// Check the output against `constraint_set` and return every violation.
+    ///
+    /// Runs `constraints::diagnose_constraints` on the input the agent saw,
+    /// so all problems show up at once instead of one per kernel run. Empty
+    /// when the kernel would accept the output.
+    pub fn constraint_violations(
+        &self,
+        constraint_set: &constraints::ConstraintSet,
+    ) -> Vec<ConstraintViolation> {
+        let input = KernelInputV1 {
+            protocol_version: self.context.protocol_version,
+            kernel_version: self.context.kernel_version,
+            agent_id: self.context.agent_id,
+            agent_code_hash: self.context.agent_code_hash,
+            constraint_set_hash: self.context.constraint_set_hash,
+            input_root: self.context.input_root,
+            execution_nonce: self.context.execution_nonce,
+            opaque_agent_inputs: self.input_bytes.clone(),
+        };
+        constraints::diagnose_constraints(&input, &self.output, constraint_set)
+    }
+
+    /// Assert that the output satisfies `constraint_set`.
+    ///
+    /// # Panics
+    ///
+    /// Panics listing every violation if any constraint fails.
+    pub fn assert_constraints_pass(&self, constraint_set: &constraints::ConstraintSet) {
+        let violations = self.constraint_violations(constraint_set);
+        assert!(
+            violations.is_empty(),
+            "expected output to satisfy constraints, got {} violations: {:?}",
+            violations.len(),
+            violations
+        );
+    }
+
+    /// Assert that checking against `constraint_set` yields exactly
+    /// `expected` violations, in evaluation order.
+    pub fn assert_violations(
+        &self,
+        constraint_set: &constraints::ConstraintSet,
+        expected: &[ConstraintViolation],
+    ) {
+        let violations = self.constraint_violations(constraint_set);
+        assert_eq!(violations, expected, "constraint violations mismatch");
+    }
 }
 
 // ============================================================================
@@ -505,6 +638,8 @@ pub struct KernelTestResult {
     pub input: KernelInputV1,
     /// The raw input bytes that were passed to the kernel.
     pub input_bytes: Vec<u8>,
+    /// Peak heap bytes the kernel allocated, if an allocation probe was set.
+    pub peak_allocation: Option<usize>,
 }
 
 impl KernelTestResult {
@@ -523,6 +658,11 @@ impl KernelTestResult {
         self.journal.execution_status == ExecutionStatus::Failure
     }
 
+    /// Check if the agent rejected its input.
+    pub fn is_agent_error(&self) -> bool {
+        self.journal.execution_status == ExecutionStatus::AgentError
+    }
+
     /// Get the action commitment from the journal.
     pub fn action_commitment(&self) -> [u8; 32] {
         self.journal.action_commitment
@@ -542,7 +682,8 @@ impl KernelTestResult {
         assert_eq!(
             self.journal.execution_status,
             ExecutionStatus::Success,
-            "expected Success, got Failure"
+            "expected Success, got {:?}",
+            self.journal.execution_status
         );
     }
 
@@ -551,7 +692,18 @@ impl KernelTestResult {
         assert_eq!(
             self.journal.execution_status,
             ExecutionStatus::Failure,
-            "expected Failure, got Success"
+            "expected Failure, got {:?}",
+            self.journal.execution_status
+        );
+    }
+
+    /// Assert that the agent rejected its input.
+    pub fn assert_agent_error(&self) {
+        assert_eq!(
+            self.journal.execution_status,
+            ExecutionStatus::AgentError,
+            "expected AgentError, got {:?}",
+            self.journal.execution_status
         );
     }
 
@@ -568,6 +720,15 @@ impl KernelTestResult {
         );
     }
 
+    /// Assert that the kernel's peak heap use was at most `limit` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no allocation probe was set or the peak exceeds `limit`.
+    pub fn assert_peak_allocation_at_most(&self, limit: usize) {
+        assert_peak_allocation_at_most(self.peak_allocation, limit);
+    }
+
     /// Assert that the agent_id in the journal matches the expected value.
     pub fn assert_agent_id(&self, expected: &[u8; 32]) {
         assert_eq!(
@@ -601,6 +762,28 @@ impl KernelTestResult {
     }
 }
 
+/// Run `f`, measuring its peak heap use if a probe is configured.
+fn run_measured<R>(probe: Option<AllocationProbe>, f: impl FnOnce() -> R) -> (R, Option<usize>) {
+    let Some(probe) = probe else {
+        return (f(), None);
+    };
+    let mut f = Some(f);
+    let mut result = None;
+    let peak = probe(&mut || result = f.take().map(|f| f()));
+    let result = result.expect("TestHarness: allocation probe did not run the call");
+    (result, Some(peak))
+}
+
+fn assert_peak_allocation_at_most(peak_allocation: Option<usize>, limit: usize) {
+    let peak = peak_allocation.expect("no allocation probe set on the TestHarness");
+    assert!(
+        peak <= limit,
+        "peak allocation {} bytes exceeds {} bytes",
+        peak,
+        limit
+    );
+}
+
 // ============================================================================
 // Snapshot Implementation (std only)
 // ============================================================================
@@ -677,8 +860,8 @@ mod tests {
 
     #[test]
     fn test_hex_bytes_empty() {
-        assert_eq!(hex_bytes(""), alloc::vec![]);
-        assert_eq!(hex_bytes("0x"), alloc::vec![]);
+        assert_eq!(hex_bytes(""), Vec::<u8>::new());
+        assert_eq!(hex_bytes("0x"), Vec::<u8>::new());
     }
 
     #[test]
@@ -743,6 +926,32 @@ mod tests {
         assert_eq!(ctx.constraint_set_hash, [0xDD; 32]);
     }
 
+    fn looping_agent(ctx: &AgentContext, input: &[u8]) -> AgentOutput {
+        for _ in input {
+            if ctx.charge_steps(1).is_err() {
+                break;
+            }
+        }
+        AgentOutput {
+            actions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_harness_step_budget() {
+        let result = TestHarness::new()
+            .step_budget(2)
+            .input([0u8; 5])
+            .execute(looping_agent);
+        assert_eq!(result.context.step_meter.used(), 3);
+        assert!(result.context.step_meter.is_exceeded());
+        result.assert_deterministic(looping_agent);
+
+        let result = TestHarness::new().input([0u8; 5]).execute(looping_agent);
+        assert_eq!(result.context.step_meter.used(), 5);
+        assert!(!result.context.step_meter.is_exceeded());
+    }
+
     // ========================================================================
     // TestHarness + TestResult Tests
     // ========================================================================
@@ -769,6 +978,34 @@ mod tests {
         }
     }
 
+    /// Probe reporting a fixed peak after running the call.
+    fn fixed_probe(f: &mut dyn FnMut()) -> usize {
+        f();
+        1234
+    }
+
+    #[test]
+    fn test_harness_allocation_probe() {
+        let result = TestHarness::new().execute(dummy_agent);
+        assert_eq!(result.peak_allocation, None);
+
+        let result = TestHarness::new()
+            .allocation_probe(fixed_probe)
+            .execute(dummy_agent);
+        assert_eq!(result.action_count(), 1);
+        assert_eq!(result.peak_allocation, Some(1234));
+        result.assert_peak_allocation_at_most(1234);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds")]
+    fn test_harness_peak_allocation_over_limit() {
+        TestHarness::new()
+            .allocation_probe(fixed_probe)
+            .execute(dummy_agent)
+            .assert_peak_allocation_at_most(1000);
+    }
+
     #[test]
     fn test_harness_execute_basic() {
         let result = TestHarness::new().execute(dummy_agent);
@@ -867,4 +1104,60 @@ mod tests {
         let result = TestHarness::new().execute(dummy_agent);
         result.assert_payload(0, &[1, 2, 3]);
     }
+
+    #[test]
+    fn test_result_constraint_violations() {
+        use kernel_core::ConstraintViolationReason;
+
+        fn bad_agent(_ctx: &AgentContext, _input: &[u8]) -> AgentOutput {
+            AgentOutput {
+                actions: alloc::vec![
+                    no_op_action(),
+                    ActionV1 {
+                        action_type: 0xFF,
+                        target: [0; 32],
+                        payload: alloc::vec![],
+                    },
+                    ActionV1 {
+                        action_type: ACTION_TYPE_NO_OP,
+                        target: [0; 32],
+                        payload: alloc::vec![0x01],
+                    },
+                ],
+            }
+        }
+
+        let constraint_set = constraints::ConstraintSet::default();
+        TestHarness::new()
+            .execute(empty_agent)
+            .assert_constraints_pass(&constraint_set);
+
+        let result = TestHarness::new().execute(bad_agent);
+        result.assert_violations(
+            &constraint_set,
+            &[
+                ConstraintViolation::action(ConstraintViolationReason::UnknownActionType, 1),
+                ConstraintViolation::action(ConstraintViolationReason::InvalidActionPayload, 2),
+            ],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "got 2 violations")]
+    fn test_result_assert_constraints_pass_lists_violations() {
+        fn bad_agent(_ctx: &AgentContext, _input: &[u8]) -> AgentOutput {
+            let bad = ActionV1 {
+                action_type: 0xFF,
+                target: [0; 32],
+                payload: alloc::vec![],
+            };
+            AgentOutput {
+                actions: alloc::vec![bad.clone(), bad],
+            }
+        }
+
+        TestHarness::new()
+            .execute(bad_agent)
+            .assert_constraints_pass(&constraints::ConstraintSet::default());
+    }
 }
@@ -35,7 +35,10 @@
 use alloc::vec::Vec;
 
 // Re-export core types from kernel-core
-pub use kernel_core::{ActionV1, AgentOutput, MAX_ACTIONS_PER_OUTPUT, MAX_ACTION_PAYLOAD_BYTES};
+pub use kernel_core::{
+    ActionV1, AgentOutput, StateSnapshot, StateSnapshotV1, StateSnapshotV2, MAX_ACTIONS_PER_OUTPUT,
+    MAX_ACTION_PAYLOAD_BYTES,
+};
 
 // ============================================================================
 // Action Type Constants (re-exported from kernel-core)
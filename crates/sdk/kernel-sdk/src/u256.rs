@@ -0,0 +1,661 @@
+//! 256-bit unsigned integer for token amounts and on-chain values.
+//!
+//! [`U256`] covers the full `uint256` range, so 18-decimal token amounts
+//! can be handled without truncating to `u64`. Like the [`math`](crate::math)
+//! helpers, all arithmetic is explicit: operations are checked (returning
+//! `None` on overflow, underflow or division by zero) or saturating, and
+//! there are no panicking operators.
+//!
+//! [`U256::checked_mul_div`] computes `a * b / denom` with a 512-bit
+//! intermediate product and an explicit [`Rounding`] mode.
+//!
+//! # Example
+//!
+//! ```
+//! use kernel_sdk::math::Rounding;
+//! use kernel_sdk::u256::U256;
+//!
+//! // 1.5 tokens with 18 decimals, priced at 2,000.25 (8 decimals)
+//! let amount = U256::from_u128(1_500_000_000_000_000_000);
+//! let price = U256::from_u64(200_025_000_000);
+//! let value = amount
+//!     .checked_mul_div(price, U256::from_u64(100_000_000), Rounding::Down)
+//!     .unwrap();
+//! assert_eq!(value, U256::from_u128(3_000_375_000_000_000_000_000));
+//! ```
+
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::math::Rounding;
+
+/// 256-bit unsigned integer.
+///
+/// Stored as four 64-bit limbs, least significant first. Converts to and
+/// from the 32-byte big-endian ABI word with
+/// [`to_be_bytes`](U256::to_be_bytes) and [`from_be_bytes`](U256::from_be_bytes).
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct U256([u64; 4]);
+
+impl U256 {
+    /// Zero.
+    pub const ZERO: Self = Self([0; 4]);
+
+    /// One.
+    pub const ONE: Self = Self([1, 0, 0, 0]);
+
+    /// Largest value, `2^256 - 1`.
+    pub const MAX: Self = Self([u64::MAX; 4]);
+
+    /// Create from a `u64`.
+    #[inline]
+    #[must_use]
+    pub const fn from_u64(val: u64) -> Self {
+        Self([val, 0, 0, 0])
+    }
+
+    /// Create from a `u128`.
+    #[inline]
+    #[must_use]
+    pub const fn from_u128(val: u128) -> Self {
+        Self([val as u64, (val >> 64) as u64, 0, 0])
+    }
+
+    /// Convert to `u64`, or `None` if the value does not fit.
+    #[inline]
+    #[must_use]
+    pub fn to_u64(self) -> Option<u64> {
+        match self.0 {
+            [low, 0, 0, 0] => Some(low),
+            _ => None,
+        }
+    }
+
+    /// Convert to `u128`, or `None` if the value does not fit.
+    #[inline]
+    #[must_use]
+    pub fn to_u128(self) -> Option<u128> {
+        match self.0 {
+            [low, high, 0, 0] => Some((u128::from(high) << 64) | u128::from(low)),
+            _ => None,
+        }
+    }
+
+    /// Create from a 32-byte big-endian word (ABI `uint256` encoding).
+    #[must_use]
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let start = 24 - 8 * i;
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&bytes[start..start + 8]);
+            *limb = u64::from_be_bytes(buf);
+        }
+        Self(limbs)
+    }
+
+    /// Encode as a 32-byte big-endian word (ABI `uint256` encoding).
+    #[must_use]
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, limb) in self.0.iter().enumerate() {
+            let start = 24 - 8 * i;
+            bytes[start..start + 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Create from 32 little-endian bytes, the byte order of agent inputs.
+    #[must_use]
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Self {
+        let mut be = bytes;
+        be.reverse();
+        Self::from_be_bytes(be)
+    }
+
+    /// Encode as 32 little-endian bytes.
+    #[must_use]
+    pub fn to_le_bytes(self) -> [u8; 32] {
+        let mut bytes = self.to_be_bytes();
+        bytes.reverse();
+        bytes
+    }
+
+    /// Create from a big-endian byte slice of at most 32 bytes.
+    ///
+    /// Returns `None` if the slice is longer than 32 bytes.
+    #[must_use]
+    pub fn from_be_slice(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() > 32 {
+            return None;
+        }
+        let mut word = [0u8; 32];
+        word[32 - bytes.len()..].copy_from_slice(bytes);
+        Some(Self::from_be_bytes(word))
+    }
+
+    /// Whether the value is zero.
+    #[inline]
+    #[must_use]
+    pub fn is_zero(self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    /// Number of significant bits (0 for zero).
+    #[must_use]
+    pub fn bits(self) -> u32 {
+        for i in (0..4).rev() {
+            if self.0[i] != 0 {
+                return 64 * i as u32 + (64 - self.0[i].leading_zeros());
+            }
+        }
+        0
+    }
+
+    // ========================================================================
+    // Checked Arithmetic
+    // ========================================================================
+
+    /// Checked addition. Returns `None` on overflow.
+    #[must_use]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        let (sum, carry) = self.overflowing_add(rhs);
+        if carry {
+            None
+        } else {
+            Some(sum)
+        }
+    }
+
+    /// Checked subtraction. Returns `None` on underflow.
+    #[must_use]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        let (diff, borrow) = self.overflowing_sub(rhs);
+        if borrow {
+            None
+        } else {
+            Some(diff)
+        }
+    }
+
+    /// Checked multiplication. Returns `None` on overflow.
+    #[must_use]
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let product = widening_mul(self, rhs);
+        if product[4..] != [0; 4] {
+            return None;
+        }
+        Some(Self([product[0], product[1], product[2], product[3]]))
+    }
+
+    /// Checked division (floor). Returns `None` if `rhs` is zero.
+    #[must_use]
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.checked_div_rem(rhs).map(|(quotient, _)| quotient)
+    }
+
+    /// Checked remainder. Returns `None` if `rhs` is zero.
+    #[must_use]
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        self.checked_div_rem(rhs).map(|(_, remainder)| remainder)
+    }
+
+    /// Checked quotient and remainder. Returns `None` if `rhs` is zero.
+    #[must_use]
+    pub fn checked_div_rem(self, rhs: Self) -> Option<(Self, Self)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        if let (Some(a), Some(b)) = (self.to_u128(), rhs.to_u128()) {
+            return Some((Self::from_u128(a / b), Self::from_u128(a % b)));
+        }
+        let (quotient, remainder) = div_rem_wide(&self.0, rhs);
+        Some((
+            Self([quotient[0], quotient[1], quotient[2], quotient[3]]),
+            remainder,
+        ))
+    }
+
+    /// Checked multiply-then-divide: `self * mul / denom`.
+    ///
+    /// The product is computed in 512 bits, so only the final quotient has
+    /// to fit in 256 bits. The quotient is rounded according to `rounding`.
+    ///
+    /// Returns `None` if `denom` is zero or the result overflows.
+    #[must_use]
+    pub fn checked_mul_div(self, mul: Self, denom: Self, rounding: Rounding) -> Option<Self> {
+        if denom.is_zero() {
+            return None;
+        }
+        // Native arithmetic when the product fits in u128
+        let narrow = match (self.to_u128(), mul.to_u128(), denom.to_u128()) {
+            (Some(a), Some(b), Some(d)) => a
+                .checked_mul(b)
+                .map(|product| (Self::from_u128(product / d), Self::from_u128(product % d))),
+            _ => None,
+        };
+        let (quotient, remainder) = match narrow {
+            Some(result) => result,
+            None => {
+                let product = widening_mul(self, mul);
+                let (quotient, remainder) = div_rem_wide(&product, denom);
+                if quotient[4..] != [0; 4] {
+                    return None;
+                }
+                let quotient = Self([quotient[0], quotient[1], quotient[2], quotient[3]]);
+                (quotient, remainder)
+            }
+        };
+
+        // Compare remainder with denom / 2 without overflowing 2 * remainder
+        let rest = denom.wrapping_sub(remainder);
+        let round_up = match rounding {
+            Rounding::Down => false,
+            Rounding::Up => !remainder.is_zero(),
+            Rounding::HalfUp => !remainder.is_zero() && remainder >= rest,
+            Rounding::HalfEven => remainder > rest || (remainder == rest && quotient.0[0] & 1 == 1),
+        };
+        if round_up {
+            quotient.checked_add(Self::ONE)
+        } else {
+            Some(quotient)
+        }
+    }
+
+    // ========================================================================
+    // Saturating Arithmetic
+    // ========================================================================
+
+    /// Saturating addition. Returns [`U256::MAX`] on overflow.
+    #[must_use]
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        self.checked_add(rhs).unwrap_or(Self::MAX)
+    }
+
+    /// Saturating subtraction. Returns zero on underflow.
+    #[must_use]
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs).unwrap_or(Self::ZERO)
+    }
+
+    /// Saturating multiplication. Returns [`U256::MAX`] on overflow.
+    #[must_use]
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        self.checked_mul(rhs).unwrap_or(Self::MAX)
+    }
+
+    // ========================================================================
+    // Internal Helpers
+    // ========================================================================
+
+    fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let mut limbs = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (sum, c1) = self.0[i].overflowing_add(rhs.0[i]);
+            let (sum, c2) = sum.overflowing_add(u64::from(carry));
+            *limb = sum;
+            carry = c1 || c2;
+        }
+        (Self(limbs), carry)
+    }
+
+    fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let mut limbs = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (diff, b1) = self.0[i].overflowing_sub(rhs.0[i]);
+            let (diff, b2) = diff.overflowing_sub(u64::from(borrow));
+            *limb = diff;
+            borrow = b1 || b2;
+        }
+        (Self(limbs), borrow)
+    }
+
+    fn wrapping_sub(self, rhs: Self) -> Self {
+        self.overflowing_sub(rhs).0
+    }
+
+    /// Shift left by one bit, returning the bit shifted out.
+    fn shl1(self) -> (Self, bool) {
+        let mut limbs = [0u64; 4];
+        let mut carry = 0u64;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = (self.0[i] << 1) | carry;
+            carry = self.0[i] >> 63;
+        }
+        (Self(limbs), carry != 0)
+    }
+
+    /// Divide by a `u64`, returning the quotient and remainder.
+    fn div_rem_u64(self, divisor: u64) -> (Self, u64) {
+        let mut limbs = [0u64; 4];
+        let mut remainder = 0u128;
+        for i in (0..4).rev() {
+            let current = (remainder << 64) | u128::from(self.0[i]);
+            limbs[i] = (current / u128::from(divisor)) as u64;
+            remainder = current % u128::from(divisor);
+        }
+        (Self(limbs), remainder as u64)
+    }
+}
+
+/// Full 512-bit product of two 256-bit values, least significant limb first.
+fn widening_mul(a: U256, b: U256) -> [u64; 8] {
+    let mut product = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let current =
+                u128::from(a.0[i]) * u128::from(b.0[j]) + u128::from(product[i + j]) + carry;
+            product[i + j] = current as u64;
+            carry = current >> 64;
+        }
+        product[i + 4] = carry as u64;
+    }
+    product
+}
+
+/// Divide a multi-limb numerator (least significant limb first, at most 8
+/// limbs) by a non-zero `divisor` using binary long division.
+fn div_rem_wide(numerator: &[u64], divisor: U256) -> ([u64; 8], U256) {
+    let mut quotient = [0u64; 8];
+    let mut remainder = U256::ZERO;
+    // Leading zero limbs contribute nothing
+    let len = numerator
+        .iter()
+        .rposition(|&limb| limb != 0)
+        .map_or(0, |i| i + 1);
+    for bit in (0..len * 64).rev() {
+        let (shifted, overflow) = remainder.shl1();
+        remainder = shifted;
+        remainder.0[0] |= (numerator[bit / 64] >> (bit % 64)) & 1;
+        // With the shifted-out bit the remainder is at least 2^256 > divisor
+        if overflow || remainder >= divisor {
+            remainder = remainder.wrapping_sub(divisor);
+            quotient[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+    (quotient, remainder)
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<u8> for U256 {
+    fn from(val: u8) -> Self {
+        Self::from_u64(u64::from(val))
+    }
+}
+
+impl From<u16> for U256 {
+    fn from(val: u16) -> Self {
+        Self::from_u64(u64::from(val))
+    }
+}
+
+impl From<u32> for U256 {
+    fn from(val: u32) -> Self {
+        Self::from_u64(u64::from(val))
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(val: u64) -> Self {
+        Self::from_u64(val)
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(val: u128) -> Self {
+        Self::from_u128(val)
+    }
+}
+
+/// Formats the value in decimal.
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 2^256 - 1 has 78 decimal digits
+        let mut digits = [0u8; 78];
+        let mut start = digits.len();
+        let mut value = *self;
+        loop {
+            let (quotient, digit) = value.div_rem_u64(10);
+            start -= 1;
+            digits[start] = b'0' + digit as u8;
+            value = quotient;
+            if value.is_zero() {
+                break;
+            }
+        }
+        // Only ASCII digits were written
+        let text = core::str::from_utf8(&digits[start..]).map_err(|_| fmt::Error)?;
+        f.pad_integral(true, "", text)
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    /// 1 token with 18 decimals.
+    const WAD: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_u256_conversions() {
+        assert_eq!(U256::from_u64(7).to_u64(), Some(7));
+        assert_eq!(U256::from_u128(u128::MAX).to_u128(), Some(u128::MAX));
+        assert_eq!(U256::from_u128(u128::MAX).to_u64(), None);
+        assert_eq!(U256::MAX.to_u128(), None);
+        assert_eq!(U256::from(5u32), U256::from_u64(5));
+    }
+
+    #[test]
+    fn test_u256_byte_order() {
+        let value = U256::from_u128(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10);
+        let be = value.to_be_bytes();
+        assert_eq!(&be[..16], &[0u8; 16]);
+        assert_eq!(be[16], 0x01);
+        assert_eq!(be[31], 0x10);
+        assert_eq!(U256::from_be_bytes(be), value);
+
+        let le = value.to_le_bytes();
+        assert_eq!(le[0], 0x10);
+        assert_eq!(U256::from_le_bytes(le), value);
+
+        assert_eq!(
+            U256::from_be_slice(&[0x01, 0x00]),
+            Some(U256::from_u64(256))
+        );
+        assert_eq!(U256::from_be_slice(&[0u8; 33]), None);
+        assert_eq!(U256::from_be_bytes([0xff; 32]), U256::MAX);
+    }
+
+    #[test]
+    fn test_u256_checked_add_sub() {
+        assert_eq!(
+            U256::from_u64(u64::MAX).checked_add(U256::ONE),
+            Some(U256::from_u128(1u128 << 64))
+        );
+        assert_eq!(U256::MAX.checked_add(U256::ONE), None);
+        assert_eq!(
+            U256::from_u128(1u128 << 64).checked_sub(U256::ONE),
+            Some(U256::from_u64(u64::MAX))
+        );
+        assert_eq!(U256::ZERO.checked_sub(U256::ONE), None);
+        assert_eq!(U256::MAX.saturating_add(U256::ONE), U256::MAX);
+        assert_eq!(U256::ONE.saturating_sub(U256::MAX), U256::ZERO);
+    }
+
+    #[test]
+    fn test_u256_checked_mul() {
+        let wad = U256::from_u128(WAD);
+        let squared = wad.checked_mul(wad).unwrap();
+        assert_eq!(squared.to_string(), "1000000000000000000000000000000000000");
+        assert_eq!(U256::MAX.checked_mul(U256::from_u64(2)), None);
+        assert_eq!(U256::MAX.checked_mul(U256::ONE), Some(U256::MAX));
+        assert_eq!(U256::MAX.saturating_mul(U256::from_u64(2)), U256::MAX);
+    }
+
+    #[test]
+    fn test_u256_checked_div_rem() {
+        assert_eq!(U256::ONE.checked_div(U256::ZERO), None);
+        assert_eq!(U256::ONE.checked_rem(U256::ZERO), None);
+        assert_eq!(
+            U256::from_u64(17).checked_div_rem(U256::from_u64(5)),
+            Some((U256::from_u64(3), U256::from_u64(2)))
+        );
+
+        // Values beyond u128 take the long-division path
+        let big = U256::from_u128(u128::MAX)
+            .checked_mul(U256::from_u64(1000))
+            .unwrap();
+        assert_eq!(
+            big.checked_div_rem(U256::from_u64(1000)),
+            Some((U256::from_u128(u128::MAX), U256::ZERO))
+        );
+        assert_eq!(U256::MAX.checked_div(U256::MAX), Some(U256::ONE));
+        assert_eq!(
+            U256::MAX.checked_rem(U256::from_u64(10)),
+            Some(U256::from_u64(5))
+        );
+    }
+
+    #[test]
+    fn test_u256_mul_div_wide_intermediate() {
+        // MAX * MAX overflows 256 bits, the quotient does not
+        assert_eq!(
+            U256::MAX.checked_mul_div(U256::MAX, U256::MAX, Rounding::Down),
+            Some(U256::MAX)
+        );
+        assert_eq!(
+            U256::MAX.checked_mul_div(U256::from_u64(2), U256::ONE, Rounding::Down),
+            None
+        );
+        assert_eq!(
+            U256::ONE.checked_mul_div(U256::ONE, U256::ZERO, Rounding::Down),
+            None
+        );
+
+        // 18-decimal amounts: 3.5 * 2.25 = 7.875
+        let a = U256::from_u128(3 * WAD + WAD / 2);
+        let b = U256::from_u128(2 * WAD + WAD / 4);
+        assert_eq!(
+            a.checked_mul_div(b, U256::from_u128(WAD), Rounding::Down),
+            Some(U256::from_u128(7 * WAD + 875 * WAD / 1000))
+        );
+    }
+
+    #[test]
+    fn test_u256_mul_div_rounding() {
+        let ten = U256::from_u64(10);
+        let three = U256::from_u64(3);
+        let four = U256::from_u64(4);
+        let one = U256::ONE;
+
+        // 10 / 3 = 3.33
+        assert_eq!(ten.checked_mul_div(one, three, Rounding::Down), Some(three));
+        assert_eq!(ten.checked_mul_div(one, three, Rounding::Up), Some(four));
+        assert_eq!(
+            ten.checked_mul_div(one, three, Rounding::HalfUp),
+            Some(three)
+        );
+
+        // 10 / 4 = 2.5 rounds half up to 3, half even to 2
+        let (two, three) = (U256::from_u64(2), U256::from_u64(3));
+        assert_eq!(ten.checked_mul_div(one, four, Rounding::Down), Some(two));
+        assert_eq!(
+            ten.checked_mul_div(one, four, Rounding::HalfUp),
+            Some(three)
+        );
+        assert_eq!(
+            ten.checked_mul_div(one, four, Rounding::HalfEven),
+            Some(two)
+        );
+        // 14 / 4 = 3.5 rounds half even to 4
+        assert_eq!(
+            U256::from_u64(14).checked_mul_div(one, four, Rounding::HalfEven),
+            Some(four)
+        );
+        // 11 / 4 = 2.75 rounds to nearest in both half modes
+        let eleven = U256::from_u64(11);
+        assert_eq!(
+            eleven.checked_mul_div(one, four, Rounding::HalfUp),
+            Some(three)
+        );
+        assert_eq!(
+            eleven.checked_mul_div(one, four, Rounding::HalfEven),
+            Some(three)
+        );
+
+        // Exact division never rounds
+        assert_eq!(
+            U256::from_u64(12).checked_mul_div(one, four, Rounding::Up),
+            Some(three)
+        );
+
+        // (2^129 - 1)(2^129 + 1) / 4 = MAX + 3/4: rounding up overflows
+        let mut low = [0xffu8; 17];
+        low[0] = 0x01;
+        let mut high = [0u8; 17];
+        high[0] = 0x02;
+        high[16] = 0x01;
+        let (low, high) = (
+            U256::from_be_slice(&low).unwrap(),
+            U256::from_be_slice(&high).unwrap(),
+        );
+        assert_eq!(
+            low.checked_mul_div(high, four, Rounding::Down),
+            Some(U256::MAX)
+        );
+        assert_eq!(low.checked_mul_div(high, four, Rounding::Up), None);
+        assert_eq!(low.checked_mul_div(high, four, Rounding::HalfUp), None);
+
+        let max_minus_one = U256::MAX.wrapping_sub(one);
+        assert_eq!(
+            U256::MAX.checked_mul_div(max_minus_one, max_minus_one, Rounding::Up),
+            Some(U256::MAX)
+        );
+    }
+
+    #[test]
+    fn test_u256_ordering() {
+        assert!(U256::from_u128(1u128 << 64) > U256::from_u64(u64::MAX));
+        assert!(U256::MAX > U256::from_u128(u128::MAX));
+        assert!(U256::ZERO < U256::ONE);
+        assert_eq!(U256::MAX.bits(), 256);
+        assert_eq!(U256::from_u64(1).bits(), 1);
+        assert_eq!(U256::ZERO.bits(), 0);
+    }
+
+    #[test]
+    fn test_u256_display() {
+        assert_eq!(U256::ZERO.to_string(), "0");
+        assert_eq!(U256::from_u64(1234).to_string(), "1234");
+        assert_eq!(
+            U256::MAX.to_string(),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+        assert_eq!(alloc::format!("{:?}", U256::from_u64(42)), "42");
+    }
+}
@@ -78,7 +78,7 @@ pub fn build_defi_kernel_input(
         kernel_version: KERNEL_VERSION,
         agent_id,
         agent_code_hash: defi_yield_farmer::AGENT_CODE_HASH,
-        constraint_set_hash: [0u8; 32], // Default constraints
+        constraint_set_hash: constraints::DEFAULT_CONSTRAINT_SET_HASH,
         input_root: [0u8; 32],
         execution_nonce,
         opaque_agent_inputs: opaque_inputs,
@@ -90,7 +90,7 @@ pub fn build_defi_kernel_input(
 /// We run the agent function directly to get the exact output that matches
 /// the commitment in the proof journal.
 pub fn compute_defi_agent_output_bytes(opaque_inputs: &[u8]) -> Vec<u8> {
-    use kernel_sdk::prelude::{AgentContext, AgentOutput};
+    use kernel_sdk::prelude::{AgentContext, AgentOutput, StepMeter};
     use kernel_core::CanonicalEncode;
 
     let ctx = AgentContext {
@@ -101,9 +101,12 @@ pub fn compute_defi_agent_output_bytes(opaque_inputs: &[u8]) -> Vec<u8> {
         constraint_set_hash: [0u8; 32],
         input_root: [0u8; 32],
         execution_nonce: 1,
+        state_snapshot: None,
+        step_meter: StepMeter::default(),
     };
 
-    let output: AgentOutput = defi_yield_farmer::agent_main(&ctx, opaque_inputs);
+    let output: AgentOutput =
+        defi_yield_farmer::agent_main(&ctx, opaque_inputs).expect("agent rejected input");
     output.encode().expect("encode agent output")
 }
 
@@ -196,6 +199,8 @@ mod e2e {
         let env = ExecutorEnv::builder()
             .write(&input_bytes)
             .expect("failed to write input")
+            .write(&crate::default_constraint_set_bytes())
+            .expect("failed to write constraint set")
             .build()
             .expect("failed to build executor env");
 
@@ -353,6 +358,8 @@ mod e2e {
         let env = ExecutorEnv::builder()
             .write(&input_bytes)
             .expect("write input")
+            .write(&crate::default_constraint_set_bytes())
+            .expect("write constraint set")
             .build()
             .expect("build env");
 
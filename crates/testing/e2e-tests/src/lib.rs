@@ -8,6 +8,11 @@
 //! 1. **Success Path**: Valid input produces valid proof with yield agent
 //! 2. **Hash Mismatch**: Wrong agent_code_hash fails during guest execution
 //! 3. **Empty Output**: Invalid input (wrong size) produces empty output
+//! 4. **Determinism**: The same input produces the same journal
+//! 5. **Heap Limit**: A maximum-size input stays within the guest heap limit
+//!    and produces the host kernel's journal
+//! 6. **Batch**: The batch guest proves a batch with the host kernel's journal,
+//!    including a full batch of maximum-size inputs
 //!
 //! # Running Tests
 //!
@@ -36,11 +41,22 @@ pub mod phase3_yield;
 #[cfg(feature = "defi-e2e")]
 pub mod defi_e2e;
 
+use constraints::{ConstraintSetV1, DEFAULT_CONSTRAINT_SET_HASH};
 use kernel_core::{
     compute_action_commitment, AgentOutput, CanonicalEncode, KernelInputV1, KERNEL_VERSION,
     PROTOCOL_VERSION,
 };
 
+/// Canonical encoding of the default constraint set.
+///
+/// The zkVM guests read these bytes after the input and enforce them; the
+/// input helpers below declare the matching `DEFAULT_CONSTRAINT_SET_HASH`.
+pub fn default_constraint_set_bytes() -> Vec<u8> {
+    ConstraintSetV1::default()
+        .encode()
+        .expect("default constraint set must encode")
+}
+
 /// Helper to construct a valid KernelInputV1 with the correct agent_code_hash for yield agent.
 ///
 /// Uses `example_yield_agent::AGENT_CODE_HASH` to ensure hash verification passes.
@@ -56,7 +72,7 @@ pub fn make_valid_input(vault: [u8; 20], yield_source: [u8; 20], amount: u64) ->
         kernel_version: KERNEL_VERSION,
         agent_id: [0x42; 32],
         agent_code_hash: example_yield_agent::AGENT_CODE_HASH,
-        constraint_set_hash: [0xbb; 32],
+        constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
         input_root: [0xcc; 32],
         execution_nonce: 1,
         opaque_agent_inputs,
@@ -81,7 +97,7 @@ pub fn make_input_with_wrong_hash(
         kernel_version: KERNEL_VERSION,
         agent_id: [0x42; 32],
         agent_code_hash: [0x00; 32], // Wrong hash - all zeros
-        constraint_set_hash: [0xbb; 32],
+        constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
         input_root: [0xcc; 32],
         execution_nonce: 1,
         opaque_agent_inputs,
@@ -97,7 +113,7 @@ pub fn make_input_with_invalid_size(opaque_agent_inputs: Vec<u8>) -> KernelInput
         kernel_version: KERNEL_VERSION,
         agent_id: [0x42; 32],
         agent_code_hash: example_yield_agent::AGENT_CODE_HASH,
-        constraint_set_hash: [0xbb; 32],
+        constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
         input_root: [0xcc; 32],
         execution_nonce: 1,
         opaque_agent_inputs,
@@ -140,10 +156,11 @@ mod zkvm_tests {
     use super::*;
     use constraints::EMPTY_OUTPUT_COMMITMENT;
     use kernel_core::{
-        compute_input_commitment, CanonicalDecode, ExecutionStatus, KernelJournalV1,
+        compute_input_commitment, CanonicalDecode, ExecutionStatus, KernelBatchInputV1,
+        KernelBatchJournalV1, KernelJournalV1, MAX_AGENT_INPUT_BYTES, MAX_BATCH_STEPS,
     };
-    use risc0_methods::{ZKVM_GUEST_ELF, ZKVM_GUEST_ID};
-    use risc0_zkvm::{default_prover, ExecutorEnv, ProverOpts};
+    use risc0_methods::{ZKVM_GUEST_BATCH_ELF, ZKVM_GUEST_BATCH_ID, ZKVM_GUEST_ELF, ZKVM_GUEST_ID};
+    use risc0_zkvm::{default_executor, default_prover, ExecutorEnv, ProverOpts};
 
     /// Test 1: Successful execution with yield agent produces valid proof.
     ///
@@ -169,6 +186,8 @@ mod zkvm_tests {
         let env = ExecutorEnv::builder()
             .write(&input_bytes)
             .expect("failed to write input")
+            .write(&crate::default_constraint_set_bytes())
+            .expect("failed to write constraint set")
             .build()
             .expect("failed to build executor env");
 
@@ -211,7 +230,7 @@ mod zkvm_tests {
             journal.agent_code_hash,
             example_yield_agent::AGENT_CODE_HASH
         );
-        assert_eq!(journal.constraint_set_hash, [0xbb; 32]);
+        assert_eq!(journal.constraint_set_hash, DEFAULT_CONSTRAINT_SET_HASH);
         assert_eq!(journal.input_root, [0xcc; 32]);
         assert_eq!(journal.execution_nonce, 1);
 
@@ -283,6 +302,8 @@ mod zkvm_tests {
         let env = ExecutorEnv::builder()
             .write(&input_bytes)
             .expect("failed to write input")
+            .write(&crate::default_constraint_set_bytes())
+            .expect("failed to write constraint set")
             .build()
             .expect("failed to build executor env");
 
@@ -330,6 +351,8 @@ mod zkvm_tests {
         let env = ExecutorEnv::builder()
             .write(&input_bytes)
             .expect("failed to write input")
+            .write(&crate::default_constraint_set_bytes())
+            .expect("failed to write constraint set")
             .build()
             .expect("failed to build executor env");
 
@@ -401,6 +424,8 @@ mod zkvm_tests {
             let env = ExecutorEnv::builder()
                 .write(&input_bytes)
                 .expect("failed to write input")
+                .write(&crate::default_constraint_set_bytes())
+                .expect("failed to write constraint set")
                 .build()
                 .expect("failed to build executor env");
 
@@ -421,6 +446,148 @@ mod zkvm_tests {
 
         println!("Determinism verified: both runs produced identical journals");
     }
+
+    /// Test 5: Host and guest agree on a maximum-size input.
+    ///
+    /// The guest charges only the agent's allocations against
+    /// `MAX_ALLOCATION_BYTES`, so reading and decoding a 64,000-byte input
+    /// does not abort it. The host measures the same agent within the limit
+    /// (see kernel-host-tests), and the guest commits the host's journal.
+    #[test]
+    fn test_e2e_max_size_input_matches_host() {
+        let input = make_input_with_invalid_size(vec![0xab; MAX_AGENT_INPUT_BYTES]);
+        let input_bytes = input.encode().expect("encode should succeed");
+        let constraint_set_bytes = crate::default_constraint_set_bytes();
+
+        let host_journal = example_yield_agent::kernel_main_with_constraint_bytes(
+            &input_bytes,
+            &constraint_set_bytes,
+        )
+        .expect("host kernel execution failed");
+
+        let env = ExecutorEnv::builder()
+            .write(&input_bytes)
+            .expect("failed to write input")
+            .write(&constraint_set_bytes)
+            .expect("failed to write constraint set")
+            .build()
+            .expect("failed to build executor env");
+
+        // Executing is enough: a guest over the heap limit aborts here
+        let session = default_executor()
+            .execute(env, ZKVM_GUEST_ELF)
+            .expect("guest execution failed");
+
+        assert_eq!(
+            session.journal.bytes, host_journal,
+            "guest journal differs from host journal"
+        );
+
+        println!("Maximum-size input test passed!");
+    }
+
+    /// Build a batch of consecutive-nonce steps from `inputs`.
+    fn make_batch(inputs: Vec<KernelInputV1>) -> Vec<u8> {
+        let steps = inputs
+            .into_iter()
+            .enumerate()
+            .map(|(i, input)| KernelInputV1 {
+                execution_nonce: input.execution_nonce + i as u64,
+                ..input
+            })
+            .collect();
+        KernelBatchInputV1 {
+            protocol_version: PROTOCOL_VERSION,
+            steps,
+        }
+        .encode()
+        .expect("encode should succeed")
+    }
+
+    /// Test 6: The batch guest proves a batch and commits the host's journal.
+    #[test]
+    fn test_e2e_batch_matches_host() {
+        let batch_bytes = make_batch(vec![
+            make_valid_input([0x11; 20], [0x22; 20], 1_000),
+            make_input_with_invalid_size(vec![1, 2, 3]),
+            make_valid_input([0x33; 20], [0x44; 20], 2_000),
+        ]);
+        let constraint_set_bytes = crate::default_constraint_set_bytes();
+
+        let host_journal = example_yield_agent::kernel_batch_main_with_constraint_bytes(
+            &batch_bytes,
+            &constraint_set_bytes,
+        )
+        .expect("host batch execution failed");
+
+        let env = ExecutorEnv::builder()
+            .write(&batch_bytes)
+            .expect("failed to write batch")
+            .write(&constraint_set_bytes)
+            .expect("failed to write constraint set")
+            .build()
+            .expect("failed to build executor env");
+
+        println!("Starting zkVM proof generation (batch)...");
+        let prove_info = default_prover()
+            .prove_with_opts(env, ZKVM_GUEST_BATCH_ELF, &ProverOpts::groth16())
+            .expect("proof generation failed");
+
+        let receipt = prove_info.receipt;
+        receipt
+            .verify(ZKVM_GUEST_BATCH_ID)
+            .expect("receipt verification failed");
+
+        assert_eq!(
+            receipt.journal.bytes, host_journal,
+            "guest batch journal differs from host batch journal"
+        );
+        let journal = KernelBatchJournalV1::decode(&receipt.journal.bytes)
+            .expect("KernelBatchJournalV1 decode failed");
+        assert_eq!(journal.step_journal_hashes.len(), 3);
+
+        println!("Batch test passed!");
+    }
+
+    /// Test 7: A full batch of maximum-size inputs stays within the heap limit.
+    ///
+    /// Every step is charged only for the agent's own allocations, so the
+    /// batch and its decoded steps do not count against `MAX_ALLOCATION_BYTES`.
+    #[test]
+    fn test_e2e_full_batch_of_max_size_inputs() {
+        let batch_bytes = make_batch(
+            (0..MAX_BATCH_STEPS)
+                .map(|_| make_input_with_invalid_size(vec![0xab; MAX_AGENT_INPUT_BYTES]))
+                .collect(),
+        );
+        let constraint_set_bytes = crate::default_constraint_set_bytes();
+
+        let host_journal = example_yield_agent::kernel_batch_main_with_constraint_bytes(
+            &batch_bytes,
+            &constraint_set_bytes,
+        )
+        .expect("host batch execution failed");
+
+        let env = ExecutorEnv::builder()
+            .write(&batch_bytes)
+            .expect("failed to write batch")
+            .write(&constraint_set_bytes)
+            .expect("failed to write constraint set")
+            .build()
+            .expect("failed to build executor env");
+
+        // Executing is enough: a guest over the heap limit aborts here
+        let session = default_executor()
+            .execute(env, ZKVM_GUEST_BATCH_ELF)
+            .expect("guest batch execution failed");
+
+        assert_eq!(
+            session.journal.bytes, host_journal,
+            "guest batch journal differs from host batch journal"
+        );
+
+        println!("Full batch test passed!");
+    }
 }
 
 // ============================================================================
@@ -77,7 +77,7 @@ pub fn build_kernel_input(
         kernel_version: KERNEL_VERSION,
         agent_id,
         agent_code_hash: example_yield_agent::AGENT_CODE_HASH,
-        constraint_set_hash: [0xbb; 32], // Default constraint set
+        constraint_set_hash: constraints::DEFAULT_CONSTRAINT_SET_HASH,
         input_root: [0; 32],
         execution_nonce,
         opaque_agent_inputs: build_yield_agent_input(
@@ -280,6 +280,8 @@ mod zkvm_tests {
         let env = ExecutorEnv::builder()
             .write(&input_bytes)
             .expect("failed to write input")
+            .write(&crate::default_constraint_set_bytes())
+            .expect("failed to write constraint set")
             .build()
             .expect("failed to build executor env");
 
@@ -517,6 +519,8 @@ mod onchain_tests {
         let env = ExecutorEnv::builder()
             .write(&input_bytes)
             .expect("failed to write input")
+            .write(&crate::default_constraint_set_bytes())
+            .expect("failed to write constraint set")
             .build()
             .expect("failed to build executor env");
 
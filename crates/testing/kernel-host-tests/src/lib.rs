@@ -4,9 +4,15 @@ pub use example_yield_agent::AGENT_CODE_HASH;
 // Re-export the DeFi yield farmer agent code hash.
 pub use defi_yield_farmer::AGENT_CODE_HASH as DEFI_AGENT_CODE_HASH;
 
+// Route test allocations through a BoundedAllocator so tests can measure peak
+// heap use. The limit is left open: the test runner itself allocates freely.
+#[cfg(test)]
+#[global_allocator]
+static ALLOCATOR: kernel_guest::BoundedAllocator = kernel_guest::BoundedAllocator::new(usize::MAX);
+
 #[cfg(test)]
 mod tests {
-    use constraints::EMPTY_OUTPUT_COMMITMENT;
+    use constraints::{DEFAULT_CONSTRAINT_SET_HASH, EMPTY_OUTPUT_COMMITMENT};
     use kernel_core::codec::{
         ensure_no_trailing_bytes, get_bytes32, get_u32_le, get_u64_le, put_bytes32, put_u32_le,
         put_u64_le,
@@ -35,7 +41,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: AGENT_CODE_HASH, // Correct hash from linked agent
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 1,
             opaque_agent_inputs,
@@ -49,7 +55,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: AGENT_CODE_HASH,
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 1,
             opaque_agent_inputs,
@@ -76,7 +82,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: [0xde; 32], // Wrong hash - will fail hash check
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 1,
             opaque_agent_inputs,
@@ -435,7 +441,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x11; 32],
             agent_code_hash: AGENT_CODE_HASH, // Must match linked agent
-            constraint_set_hash: [0x33; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0x44; 32],
             execution_nonce: 9999,
             opaque_agent_inputs: vec![0u8; 48], // Valid size for yield agent (all zeros)
@@ -448,7 +454,7 @@ mod tests {
         // Verify identity fields are copied to journal
         assert_eq!(journal.agent_id, [0x11; 32]);
         assert_eq!(journal.agent_code_hash, AGENT_CODE_HASH);
-        assert_eq!(journal.constraint_set_hash, [0x33; 32]);
+        assert_eq!(journal.constraint_set_hash, DEFAULT_CONSTRAINT_SET_HASH);
         assert_eq!(journal.input_root, [0x44; 32]);
         assert_eq!(journal.execution_nonce, 9999);
     }
@@ -575,7 +581,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: AGENT_CODE_HASH, // Must match linked agent
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 1,
             opaque_agent_inputs: opaque_agent_inputs.clone(),
@@ -1048,6 +1054,19 @@ mod tests {
         assert_eq!(ConstraintViolationReason::InvalidStateSnapshot.code(), 0x08);
         assert_eq!(ConstraintViolationReason::InvalidConstraintSet.code(), 0x09);
         assert_eq!(ConstraintViolationReason::InvalidActionPayload.code(), 0x0A);
+        assert_eq!(ConstraintViolationReason::InputExpired.code(), 0x0B);
+        assert_eq!(ConstraintViolationReason::CallNotAllowed.code(), 0x0C);
+        assert_eq!(
+            ConstraintViolationReason::WindowExecutionsExceeded.code(),
+            0x0D
+        );
+        assert_eq!(
+            ConstraintViolationReason::WindowNotionalExceeded.code(),
+            0x0E
+        );
+        assert_eq!(ConstraintViolationReason::ApprovalNotAllowed.code(), 0x0F);
+        assert_eq!(ConstraintViolationReason::NativeValueExceeded.code(), 0x10);
+        assert_eq!(ConstraintViolationReason::StepBudgetExceeded.code(), 0x11);
     }
 
     #[test]
@@ -1064,12 +1083,12 @@ mod tests {
 
     #[test]
     fn test_kernel_main_with_constraints_success() {
-        use constraints::ConstraintSetV1;
+        use constraints::ConstraintSet;
         use example_yield_agent::kernel_main_with_constraints;
 
         let input = make_input([0x11; 20], [0x22; 20], 1000);
         let input_bytes = input.encode().unwrap();
-        let constraints = ConstraintSetV1::default();
+        let constraints = ConstraintSet::default();
 
         let journal_bytes = kernel_main_with_constraints(&input_bytes, &constraints).unwrap();
         let journal = KernelJournalV1::decode(&journal_bytes).unwrap();
@@ -1138,7 +1157,7 @@ mod tests {
 
     #[test]
     fn test_enforce_constraints_call_action() {
-        use constraints::{enforce_constraints, ConstraintSetV1, ACTION_TYPE_CALL};
+        use constraints::{enforce_constraints, ConstraintSet, ACTION_TYPE_CALL};
 
         let input = make_input_raw(vec![]);
 
@@ -1164,7 +1183,7 @@ mod tests {
                 payload,
             }],
         };
-        let constraints = ConstraintSetV1::default();
+        let constraints = ConstraintSet::default();
 
         let result = enforce_constraints(&input, &output, &constraints);
         assert!(result.is_ok());
@@ -1172,7 +1191,7 @@ mod tests {
 
     #[test]
     fn test_enforce_constraints_unknown_action_type() {
-        use constraints::{enforce_constraints, ConstraintSetV1};
+        use constraints::{enforce_constraints, ConstraintSet};
 
         let input = make_input_raw(vec![]);
         let output = AgentOutput {
@@ -1182,7 +1201,7 @@ mod tests {
                 payload: vec![],
             }],
         };
-        let constraints = ConstraintSetV1::default();
+        let constraints = ConstraintSet::default();
 
         let result = enforce_constraints(&input, &output, &constraints);
         assert!(result.is_err());
@@ -1196,7 +1215,7 @@ mod tests {
 
     #[test]
     fn test_enforce_constraints_too_many_actions() {
-        use constraints::{enforce_constraints, ConstraintSetV1, ACTION_TYPE_CALL};
+        use constraints::{enforce_constraints, ConstraintSet, ACTION_TYPE_CALL};
 
         let input = make_input_raw(vec![]);
         let output = AgentOutput {
@@ -1209,7 +1228,7 @@ mod tests {
                 65 // 65 actions, max is 64
             ],
         };
-        let constraints = ConstraintSetV1::default();
+        let constraints = ConstraintSet::default();
 
         let result = enforce_constraints(&input, &output, &constraints);
         assert!(result.is_err());
@@ -1220,6 +1239,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_enforce_approval_policy_on_sdk_approve() {
+        use constraints::{
+            call_calldata, enforce_constraints, ApprovalPolicy, ConstraintSet, ConstraintSetV3,
+            Erc20Approval,
+        };
+        use kernel_sdk::actions::erc20;
+
+        let token = [0xa0; 20];
+        let adapter = [0x1d; 20];
+        let approve = erc20::approve(&token, &adapter, 5_000);
+        let approval = Erc20Approval::decode(call_calldata(&approve.payload).unwrap()).unwrap();
+        assert_eq!(approval.spender, adapter);
+
+        let constraints = ConstraintSet::V3(ConstraintSetV3 {
+            allowed_call_targets: vec![token],
+            approval_policy: Some(ApprovalPolicy {
+                allowed_spenders: vec![adapter],
+                max_amount: 5_000,
+                allow_unlimited: false,
+            }),
+            ..ConstraintSetV3::default()
+        });
+        let input = make_input_raw(vec![]);
+        let run = |action: ActionV1| {
+            enforce_constraints(
+                &input,
+                &AgentOutput {
+                    actions: vec![action],
+                },
+                &constraints,
+            )
+        };
+
+        assert!(run(approve).is_ok());
+        let violation = run(erc20::approve(&token, &[0xbe; 20], 5_000)).unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::ApprovalNotAllowed
+        );
+        assert_eq!(violation.action_index, Some(0));
+        assert_eq!(
+            run(erc20::approve(&token, &adapter, 5_001))
+                .unwrap_err()
+                .reason,
+            ConstraintViolationReason::ApprovalNotAllowed
+        );
+    }
+
+    #[test]
+    fn test_enforce_native_value_on_sdk_call() {
+        use constraints::{
+            call_value, enforce_constraints, ConstraintSet, ConstraintSetV3, NativeValueLimits,
+        };
+        use kernel_sdk::actions::CallBuilder;
+
+        let target = [0x1d; 20];
+        let one_ether = 1_000_000_000_000_000_000u128;
+        let call = |value: u128| CallBuilder::new(target).value(value).build();
+        let mut expected = [0u8; 32];
+        expected[16..32].copy_from_slice(&one_ether.to_be_bytes());
+        assert_eq!(call_value(&call(one_ether)), Some(expected));
+
+        let constraints = ConstraintSet::V3(ConstraintSetV3 {
+            allowed_call_targets: vec![target],
+            native_value: Some(NativeValueLimits {
+                max_value_per_action: one_ether,
+                max_value_per_output: one_ether * 3 / 2,
+            }),
+            ..ConstraintSetV3::default()
+        });
+        let input = make_input_raw(vec![]);
+        let run = |actions: Vec<ActionV1>| {
+            enforce_constraints(&input, &AgentOutput { actions }, &constraints)
+        };
+
+        assert!(run(vec![call(one_ether)]).is_ok());
+        let violation = run(vec![call(0), call(one_ether + 1)]).unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::NativeValueExceeded
+        );
+        assert_eq!(violation.action_index, Some(1));
+        let violation = run(vec![call(one_ether), call(one_ether)]).unwrap_err();
+        assert_eq!(
+            violation.reason,
+            ConstraintViolationReason::NativeValueExceeded
+        );
+        assert_eq!(violation.action_index, None);
+    }
+
     #[test]
     fn test_journal_failure_has_empty_commitment() {
         use constraints::EMPTY_OUTPUT_COMMITMENT;
@@ -1230,7 +1340,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: [0xaa; 32],
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 1,
             input_commitment: [0xdd; 32],
@@ -1252,7 +1362,7 @@ mod tests {
 
     #[test]
     fn test_invalid_constraint_set_max_actions_too_large() {
-        use constraints::{enforce_constraints, ConstraintSetV1, ACTION_TYPE_CALL};
+        use constraints::{enforce_constraints, ConstraintSet, ConstraintSetV1, ACTION_TYPE_CALL};
 
         let input = make_input_raw(vec![]);
         let output = AgentOutput {
@@ -1264,10 +1374,10 @@ mod tests {
         };
 
         // max_actions_per_output exceeds protocol limit (64)
-        let constraints = ConstraintSetV1 {
+        let constraints = ConstraintSet::V1(ConstraintSetV1 {
             max_actions_per_output: 65,
             ..ConstraintSetV1::default()
-        };
+        });
 
         let result = enforce_constraints(&input, &output, &constraints);
         assert!(result.is_err());
@@ -1281,7 +1391,7 @@ mod tests {
 
     #[test]
     fn test_invalid_constraint_set_drawdown_too_large() {
-        use constraints::{enforce_constraints, ConstraintSetV1, ACTION_TYPE_CALL};
+        use constraints::{enforce_constraints, ConstraintSet, ConstraintSetV1, ACTION_TYPE_CALL};
 
         let input = make_input_raw(vec![]);
         let output = AgentOutput {
@@ -1293,10 +1403,10 @@ mod tests {
         };
 
         // max_drawdown_bps exceeds 10000 (100%)
-        let constraints = ConstraintSetV1 {
+        let constraints = ConstraintSet::V1(ConstraintSetV1 {
             max_drawdown_bps: 10_001,
             ..ConstraintSetV1::default()
-        };
+        });
 
         let result = enforce_constraints(&input, &output, &constraints);
         assert!(result.is_err());
@@ -1320,7 +1430,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: AGENT_CODE_HASH, // Must match linked agent
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 1,
             opaque_agent_inputs: vec![0u8; 48], // Valid 48-byte input
@@ -1336,7 +1446,7 @@ mod tests {
         // Verify identity fields are preserved
         assert_eq!(journal.agent_id, [0x42; 32]);
         assert_eq!(journal.agent_code_hash, AGENT_CODE_HASH);
-        assert_eq!(journal.constraint_set_hash, [0xbb; 32]);
+        assert_eq!(journal.constraint_set_hash, DEFAULT_CONSTRAINT_SET_HASH);
         assert_eq!(journal.input_root, [0xcc; 32]);
         assert_eq!(journal.execution_nonce, 1);
 
@@ -1352,7 +1462,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: AGENT_CODE_HASH, // Must match linked agent
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 2,
             opaque_agent_inputs: vec![0, 2, 3, 4, 5], // Only 5 bytes - invalid
@@ -1377,7 +1487,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x11; 32],
             agent_code_hash: AGENT_CODE_HASH, // Must match linked agent
-            constraint_set_hash: [0x33; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0x44; 32],
             execution_nonce: 100,
             opaque_agent_inputs: vec![0u8; 48], // Valid 48-byte input
@@ -1412,7 +1522,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: AGENT_CODE_HASH, // Must match linked agent
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 4,
             opaque_agent_inputs: opaque_inputs,
@@ -1486,7 +1596,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: wrong_hash, // WRONG hash
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 1,
             opaque_agent_inputs: opaque_inputs,
@@ -1507,12 +1617,12 @@ mod tests {
     #[test]
     fn test_agent_code_hash_with_constraints() {
         // Test that agent code hash verification works with custom constraints too.
-        use constraints::ConstraintSetV1;
+        use constraints::ConstraintSet;
         use example_yield_agent::kernel_main_with_constraints;
 
         // With valid hash - should succeed
         let valid_input = make_input([0x11; 20], [0x22; 20], 1000);
-        let constraints = ConstraintSetV1::default();
+        let constraints = ConstraintSet::default();
         let input_bytes = valid_input.encode().unwrap();
         let result = kernel_main_with_constraints(&input_bytes, &constraints);
         assert!(result.is_ok(), "Expected success with valid hash");
@@ -1528,7 +1638,7 @@ mod tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: [0xff; 32], // WRONG hash
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 2,
             opaque_agent_inputs: opaque_inputs,
@@ -1542,6 +1652,870 @@ mod tests {
         );
     }
 
+    // ========================================================================
+    // Constraint Set Hash Binding Tests
+    // ========================================================================
+
+    #[test]
+    fn test_constraint_set_hash_mismatch_fails() {
+        // kernel_main enforces the default constraint set, so any other
+        // declared constraint_set_hash must be rejected.
+        let mut input = make_input([0x11; 20], [0x22; 20], 1000);
+        input.constraint_set_hash = [0xbb; 32];
+
+        let input_bytes = input.encode().unwrap();
+        let result = kernel_main(&input_bytes);
+
+        assert!(
+            matches!(result, Err(KernelError::ConstraintSetHashMismatch)),
+            "Expected ConstraintSetHashMismatch but got: {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_custom_constraint_set_requires_matching_hash() {
+        use constraints::{compute_constraint_set_hash, ConstraintSet, ConstraintSetV1};
+        use example_yield_agent::kernel_main_with_constraints;
+
+        let constraints = ConstraintSet::V1(ConstraintSetV1 {
+            max_actions_per_output: 8,
+            ..ConstraintSetV1::default()
+        });
+        let custom_hash = compute_constraint_set_hash(&constraints).unwrap();
+        assert_ne!(custom_hash, DEFAULT_CONSTRAINT_SET_HASH);
+
+        // Input declaring the default hash cannot be executed under custom constraints
+        let input = make_input([0x11; 20], [0x22; 20], 1000);
+        let result = kernel_main_with_constraints(&input.encode().unwrap(), &constraints);
+        assert!(matches!(
+            result,
+            Err(KernelError::ConstraintSetHashMismatch)
+        ));
+
+        // Input declaring the custom hash executes and commits that hash
+        let mut input = make_input([0x11; 20], [0x22; 20], 1000);
+        input.constraint_set_hash = custom_hash;
+        let journal_bytes =
+            kernel_main_with_constraints(&input.encode().unwrap(), &constraints).unwrap();
+        let journal = KernelJournalV1::decode(&journal_bytes).unwrap();
+
+        assert_eq!(journal.constraint_set_hash, custom_hash);
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+    }
+
+    #[test]
+    fn test_kernel_main_with_constraint_bytes() {
+        use constraints::ConstraintSetV1;
+        use example_yield_agent::kernel_main_with_constraint_bytes;
+
+        let input_bytes = make_input([0x11; 20], [0x22; 20], 1000).encode().unwrap();
+        let constraint_set_bytes = ConstraintSetV1::default().encode().unwrap();
+
+        // Encoded default constraint set matches the default hash
+        let journal_bytes =
+            kernel_main_with_constraint_bytes(&input_bytes, &constraint_set_bytes).unwrap();
+        assert_eq!(
+            journal_bytes,
+            kernel_main(&input_bytes).unwrap(),
+            "Encoded default constraint set must behave like kernel_main"
+        );
+
+        // Malformed constraint set bytes are a codec error
+        let result = kernel_main_with_constraint_bytes(&input_bytes, &constraint_set_bytes[..59]);
+        assert!(matches!(
+            result,
+            Err(KernelError::Codec(CodecError::InvalidLength))
+        ));
+    }
+
+    #[test]
+    fn test_kernel_main_with_allowlist_constraint_bytes() {
+        use constraints::{compute_constraint_set_hash, AllowedCall, ConstraintSetV2};
+        use example_yield_agent::kernel_main_with_constraint_bytes;
+
+        let yield_source = [0x22; 20];
+        let run = |constraint_set: ConstraintSetV2| {
+            let mut input = make_input([0x11; 20], yield_source, 1000);
+            input.kernel_version = KERNEL_VERSION_V2;
+            input.constraint_set_hash = compute_constraint_set_hash(&constraint_set).unwrap();
+            let journal_bytes = kernel_main_with_constraint_bytes(
+                &input.encode().unwrap(),
+                &constraint_set.encode().unwrap(),
+            )
+            .unwrap();
+            KernelJournalV2::decode(&journal_bytes).unwrap()
+        };
+
+        // Only withdraw(address) is allowed: the selector-less deposit is rejected
+        let journal = run(ConstraintSetV2 {
+            allowed_calls: vec![AllowedCall {
+                target: yield_source,
+                selector: [0x51, 0xcf, 0xf8, 0xd9],
+            }],
+            ..ConstraintSetV2::default()
+        });
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::CallNotAllowed)
+        );
+        assert_eq!(journal.violation_action_index, Some(0));
+
+        // Allowing the whole yield source admits both calls
+        let journal = run(ConstraintSetV2 {
+            allowed_call_targets: vec![yield_source],
+            ..ConstraintSetV2::default()
+        });
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+    }
+
+    /// Custom rule rejecting every CALL to one target.
+    struct DenyCallTarget([u8; 20]);
+
+    impl constraints::ConstraintRule for DenyCallTarget {
+        fn id(&self) -> &str {
+            "test.deny-call-target"
+        }
+
+        fn params(&self) -> Vec<u8> {
+            self.0.to_vec()
+        }
+
+        fn check_action(
+            &self,
+            _ctx: &constraints::RuleContext<'_>,
+            action: &ActionV1,
+            index: usize,
+        ) -> Result<(), ConstraintViolation> {
+            if action.action_type == ACTION_TYPE_CALL && action.target[12..] == self.0 {
+                return Err(ConstraintViolation::action(
+                    ConstraintViolationReason::CallNotAllowed,
+                    index,
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_kernel_main_with_custom_rules() {
+        use constraints::{compute_constraint_set_hash, ConstraintSet, RuleSet};
+        use example_yield_agent::kernel_main_with_rules;
+
+        let yield_source = [0x22; 20];
+        let rules = RuleSet::new(ConstraintSet::default()).with_rule(DenyCallTarget(yield_source));
+        let mut input = make_input([0x11; 20], yield_source, 1000);
+        input.kernel_version = KERNEL_VERSION_V2;
+
+        // The rule set's hash commits to its rules, so the default hash no
+        // longer matches
+        assert!(matches!(
+            kernel_main_with_rules(&input.encode().unwrap(), &rules),
+            Err(KernelError::ConstraintSetHashMismatch)
+        ));
+
+        input.constraint_set_hash = compute_constraint_set_hash(&rules).unwrap();
+        let journal_bytes = kernel_main_with_rules(&input.encode().unwrap(), &rules).unwrap();
+        let journal = KernelJournalV2::decode(&journal_bytes).unwrap();
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::CallNotAllowed)
+        );
+        assert_eq!(journal.violation_action_index, Some(0));
+
+        // Denying another target admits the agent's calls
+        let rules = RuleSet::new(ConstraintSet::default()).with_rule(DenyCallTarget([0x33; 20]));
+        input.constraint_set_hash = compute_constraint_set_hash(&rules).unwrap();
+        let journal_bytes = kernel_main_with_rules(&input.encode().unwrap(), &rules).unwrap();
+        let journal = KernelJournalV2::decode(&journal_bytes).unwrap();
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+    }
+
+    // ========================================================================
+    // KernelJournalV2 (Violation Details) Tests
+    // ========================================================================
+
+    /// Agent emitting a valid NO_OP followed by a NO_OP with a (forbidden) payload.
+    struct BadSecondActionAgent;
+
+    const BAD_SECOND_ACTION_AGENT_HASH: [u8; 32] = [0x5a; 32];
+
+    impl kernel_guest::AgentEntrypoint for BadSecondActionAgent {
+        fn code_hash(&self) -> [u8; 32] {
+            BAD_SECOND_ACTION_AGENT_HASH
+        }
+
+        fn run(
+            &self,
+            _ctx: &kernel_sdk::agent::AgentContext,
+            _opaque_inputs: &[u8],
+        ) -> AgentOutput {
+            let no_op = |payload: Vec<u8>| ActionV1 {
+                action_type: ACTION_TYPE_NO_OP,
+                target: [0u8; 32],
+                payload,
+            };
+            AgentOutput {
+                actions: vec![no_op(vec![]), no_op(vec![0x01])],
+            }
+        }
+    }
+
+    #[test]
+    fn test_kernel_v2_success_journal() {
+        let v1_input = make_input([0x11; 20], [0x22; 20], 1000);
+        let mut v2_input = v1_input.clone();
+        v2_input.kernel_version = KERNEL_VERSION_V2;
+
+        let journal_bytes = kernel_main(&v2_input.encode().unwrap()).unwrap();
+        assert_eq!(journal_bytes.len(), 214);
+        let journal = KernelJournalV2::decode(&journal_bytes).unwrap();
+        assert_eq!(journal.kernel_version, KERNEL_VERSION_V2);
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+        assert_eq!(journal.violation_reason, None);
+        assert_eq!(journal.violation_action_index, None);
+
+        // Same agent output, so same action commitment as the v1 journal
+        let v1_journal =
+            KernelJournalV1::decode(&kernel_main(&v1_input.encode().unwrap()).unwrap()).unwrap();
+        assert_eq!(journal.action_commitment, v1_journal.action_commitment);
+    }
+
+    #[test]
+    fn test_kernel_v2_global_violation_committed() {
+        use constraints::{compute_constraint_set_hash, ConstraintSet, ConstraintSetV1};
+        use example_yield_agent::kernel_main_with_constraints;
+
+        // The yield agent emits two actions, so a limit of one fails globally
+        let constraints = ConstraintSet::V1(ConstraintSetV1 {
+            max_actions_per_output: 1,
+            ..ConstraintSetV1::default()
+        });
+        let mut input = make_input([0x11; 20], [0x22; 20], 1000);
+        input.constraint_set_hash = compute_constraint_set_hash(&constraints).unwrap();
+
+        // v1 journal is unchanged: Failure without details
+        let v1_bytes =
+            kernel_main_with_constraints(&input.encode().unwrap(), &constraints).unwrap();
+        assert_eq!(v1_bytes.len(), 209);
+        let v1_journal = KernelJournalV1::decode(&v1_bytes).unwrap();
+        assert_eq!(v1_journal.execution_status, ExecutionStatus::Failure);
+
+        input.kernel_version = KERNEL_VERSION_V2;
+        let v2_bytes =
+            kernel_main_with_constraints(&input.encode().unwrap(), &constraints).unwrap();
+        let journal = KernelJournal::decode(&v2_bytes).unwrap();
+
+        assert_eq!(journal.execution_status(), ExecutionStatus::Failure);
+        assert_eq!(journal.action_commitment(), EMPTY_OUTPUT_COMMITMENT);
+        assert_eq!(
+            journal.violation_reason(),
+            Some(ConstraintViolationReason::InvalidOutputStructure)
+        );
+        assert_eq!(journal.violation_action_index(), None);
+    }
+
+    #[test]
+    fn test_kernel_v2_action_violation_committed() {
+        let mut input = make_input_raw(vec![]);
+        input.kernel_version = KERNEL_VERSION_V2;
+        input.agent_code_hash = BAD_SECOND_ACTION_AGENT_HASH;
+
+        let journal_bytes =
+            kernel_guest::kernel_main_with_agent(&input.encode().unwrap(), &BadSecondActionAgent)
+                .unwrap();
+        let journal = KernelJournalV2::decode(&journal_bytes).unwrap();
+
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::InvalidActionPayload)
+        );
+        assert_eq!(journal.violation_action_index, Some(1));
+    }
+
+    /// Build a chained (kernel version 3) input from the yield-agent v1 input.
+    fn make_chained_input(execution_nonce: u64, prev_journal_hash: [u8; 32]) -> KernelInputV3 {
+        let base = make_input([0x11; 20], [0x22; 20], 1000);
+        KernelInputV3 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V3,
+            agent_id: base.agent_id,
+            agent_code_hash: base.agent_code_hash,
+            constraint_set_hash: base.constraint_set_hash,
+            input_root: base.input_root,
+            execution_nonce,
+            prev_journal_hash,
+            opaque_agent_inputs: base.opaque_agent_inputs,
+        }
+    }
+
+    #[test]
+    fn test_kernel_v3_commits_prev_journal_hash() {
+        let genesis_input = make_chained_input(1, GENESIS_PREV_JOURNAL_HASH);
+        let genesis_bytes = kernel_main(&genesis_input.encode().unwrap()).unwrap();
+        assert_eq!(genesis_bytes.len(), 246);
+        let genesis = KernelJournalV3::decode(&genesis_bytes).unwrap();
+        assert_eq!(genesis.kernel_version, KERNEL_VERSION_V3);
+        assert_eq!(genesis.execution_status, ExecutionStatus::Success);
+        assert_eq!(genesis.prev_journal_hash, GENESIS_PREV_JOURNAL_HASH);
+        assert_eq!(
+            genesis.input_commitment,
+            compute_input_commitment(&genesis_input.encode().unwrap())
+        );
+
+        // The next execution links to the hash of the genesis journal
+        let next_input = make_chained_input(2, compute_journal_hash(&genesis_bytes));
+        let next =
+            KernelJournal::decode(&kernel_main(&next_input.encode().unwrap()).unwrap()).unwrap();
+        assert_eq!(
+            next.prev_journal_hash(),
+            Some(compute_journal_hash(&genesis_bytes))
+        );
+
+        // Same agent output, so same action commitment as the v1 journal
+        let v1_input = make_input([0x11; 20], [0x22; 20], 1000);
+        let v1_journal =
+            KernelJournalV1::decode(&kernel_main(&v1_input.encode().unwrap()).unwrap()).unwrap();
+        assert_eq!(genesis.action_commitment, v1_journal.action_commitment);
+    }
+
+    #[test]
+    fn test_kernel_v3_commits_violation_details() {
+        use constraints::{compute_constraint_set_hash, ConstraintSet, ConstraintSetV1};
+        use example_yield_agent::kernel_main_with_constraints;
+
+        let constraints = ConstraintSet::V1(ConstraintSetV1 {
+            max_actions_per_output: 1,
+            ..ConstraintSetV1::default()
+        });
+        let mut input = make_chained_input(1, [0x77; 32]);
+        input.constraint_set_hash = compute_constraint_set_hash(&constraints).unwrap();
+
+        let journal_bytes =
+            kernel_main_with_constraints(&input.encode().unwrap(), &constraints).unwrap();
+        let journal = KernelJournalV3::decode(&journal_bytes).unwrap();
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::InvalidOutputStructure)
+        );
+        assert_eq!(journal.prev_journal_hash, [0x77; 32]);
+    }
+
+    fn make_expiring_input(opaque_agent_inputs: Vec<u8>, valid_until: u64) -> KernelInputV4 {
+        let base = make_chained_input(1, [0x77; 32]);
+        KernelInputV4 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V4,
+            agent_id: base.agent_id,
+            agent_code_hash: base.agent_code_hash,
+            constraint_set_hash: base.constraint_set_hash,
+            input_root: base.input_root,
+            execution_nonce: base.execution_nonce,
+            prev_journal_hash: base.prev_journal_hash,
+            valid_until,
+            opaque_agent_inputs,
+        }
+    }
+
+    /// Encode a StateSnapshotV1 observed at `current_ts`.
+    fn make_snapshot(current_ts: u64) -> Vec<u8> {
+        let mut snapshot = Vec::with_capacity(36);
+        snapshot.extend_from_slice(&1u32.to_le_bytes()); // snapshot_version
+        snapshot.extend_from_slice(&1000u64.to_le_bytes()); // last_execution_ts
+        snapshot.extend_from_slice(&current_ts.to_le_bytes()); // current_ts
+        snapshot.extend_from_slice(&100_000u64.to_le_bytes()); // current_equity
+        snapshot.extend_from_slice(&100_000u64.to_le_bytes()); // peak_equity
+        snapshot
+    }
+
+    #[test]
+    fn test_kernel_v4_commits_valid_until() {
+        let opaque = make_input([0x11; 20], [0x22; 20], 1000).opaque_agent_inputs;
+        let input = make_expiring_input(opaque, 1_700_000_000);
+        let journal_bytes = kernel_main(&input.encode().unwrap()).unwrap();
+        assert_eq!(journal_bytes.len(), 254);
+
+        let journal = KernelJournalV4::decode(&journal_bytes).unwrap();
+        assert_eq!(journal.kernel_version, KERNEL_VERSION_V4);
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+        assert_eq!(journal.prev_journal_hash, [0x77; 32]);
+        assert_eq!(journal.valid_until, 1_700_000_000);
+        assert_eq!(
+            KernelJournal::decode(&journal_bytes).unwrap().valid_until(),
+            Some(1_700_000_000)
+        );
+    }
+
+    #[test]
+    fn test_kernel_v4_rejects_expired_snapshot() {
+        // Snapshot taken one second after the input expired
+        let input = make_expiring_input(make_snapshot(2001), 2000);
+        let journal_bytes = kernel_main(&input.encode().unwrap()).unwrap();
+        let journal = KernelJournalV4::decode(&journal_bytes).unwrap();
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::InputExpired)
+        );
+        assert_eq!(journal.violation_action_index, None);
+        assert_eq!(journal.action_commitment, EMPTY_OUTPUT_COMMITMENT);
+
+        // Still valid at exactly valid_until
+        let input = make_expiring_input(make_snapshot(2000), 2000);
+        let journal =
+            KernelJournalV4::decode(&kernel_main(&input.encode().unwrap()).unwrap()).unwrap();
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+    }
+
+    /// Agent emitting one NO_OP if its context carries a state snapshot.
+    struct SnapshotProbeAgent;
+
+    const SNAPSHOT_PROBE_AGENT_HASH: [u8; 32] = [0x5b; 32];
+
+    impl kernel_guest::AgentEntrypoint for SnapshotProbeAgent {
+        fn code_hash(&self) -> [u8; 32] {
+            SNAPSHOT_PROBE_AGENT_HASH
+        }
+
+        fn run(&self, ctx: &kernel_sdk::agent::AgentContext, _opaque_inputs: &[u8]) -> AgentOutput {
+            let actions = match ctx.state_snapshot {
+                Some(_) => vec![ActionV1 {
+                    action_type: ACTION_TYPE_NO_OP,
+                    target: [0u8; 32],
+                    payload: vec![],
+                }],
+                None => vec![],
+            };
+            AgentOutput { actions }
+        }
+    }
+
+    /// Build a kernel version 5 input for the snapshot probe agent.
+    fn make_snapshot_slot_input(
+        state_snapshot: Option<StateSnapshot>,
+        opaque_agent_inputs: Vec<u8>,
+        valid_until: u64,
+    ) -> KernelInputV5 {
+        let base = make_expiring_input(opaque_agent_inputs, valid_until);
+        KernelInputV5 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V5,
+            agent_id: base.agent_id,
+            agent_code_hash: SNAPSHOT_PROBE_AGENT_HASH,
+            constraint_set_hash: base.constraint_set_hash,
+            input_root: base.input_root,
+            execution_nonce: base.execution_nonce,
+            prev_journal_hash: base.prev_journal_hash,
+            valid_until: base.valid_until,
+            state_snapshot,
+            opaque_agent_inputs: base.opaque_agent_inputs,
+        }
+    }
+
+    #[test]
+    fn test_kernel_v5_passes_typed_snapshot() {
+        let run = |input: &KernelInputV5| {
+            let journal_bytes =
+                kernel_guest::kernel_main_with_agent(&input.encode().unwrap(), &SnapshotProbeAgent)
+                    .unwrap();
+            assert_eq!(journal_bytes.len(), 254);
+            KernelJournalV4::decode(&journal_bytes).unwrap()
+        };
+        let snapshot = StateSnapshot::decode(&make_snapshot(2000));
+
+        // The agent sees the snapshot and the constraints accept it
+        let journal = run(&make_snapshot_slot_input(snapshot, vec![], 2000));
+        assert_eq!(journal.kernel_version, KERNEL_VERSION_V5);
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+        let one_no_op = AgentOutput {
+            actions: vec![ActionV1 {
+                action_type: ACTION_TYPE_NO_OP,
+                target: [0u8; 32],
+                payload: vec![],
+            }],
+        };
+        assert_eq!(
+            journal.action_commitment,
+            compute_action_commitment(&one_no_op.encode().unwrap())
+        );
+
+        // Expiry is checked against the typed snapshot
+        let journal = run(&make_snapshot_slot_input(
+            StateSnapshot::decode(&make_snapshot(2001)),
+            vec![],
+            2000,
+        ));
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::InputExpired)
+        );
+
+        // Opaque inputs are never interpreted as a snapshot
+        let journal = run(&make_snapshot_slot_input(None, make_snapshot(2001), 2000));
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+        assert_eq!(journal.action_commitment, EMPTY_OUTPUT_COMMITMENT);
+    }
+
+    #[test]
+    fn test_kernel_v5_enforces_rolling_window() {
+        use constraints::{compute_constraint_set_hash, ConstraintSetV3, RollingWindowLimits};
+
+        let constraint_set = ConstraintSetV3 {
+            rolling_window: Some(RollingWindowLimits {
+                window_seconds: 600,
+                max_executions: 2,
+                max_notional: u64::MAX,
+            }),
+            ..ConstraintSetV3::default()
+        };
+        let run = |snapshot: Option<StateSnapshot>| {
+            let mut input = make_snapshot_slot_input(snapshot, vec![], 2000);
+            input.constraint_set_hash = compute_constraint_set_hash(&constraint_set).unwrap();
+            let journal_bytes = kernel_guest::kernel_main_with_agent_and_constraint_bytes(
+                &input.encode().unwrap(),
+                &constraint_set.encode().unwrap(),
+                &SnapshotProbeAgent,
+            )
+            .unwrap();
+            KernelJournalV4::decode(&journal_bytes).unwrap()
+        };
+        let windowed = |window_start_ts: u64, window_executions: u32| {
+            Some(StateSnapshot::V2(StateSnapshotV2 {
+                snapshot_version: 2,
+                last_execution_ts: 1000,
+                current_ts: 2000,
+                current_equity: 100_000,
+                peak_equity: 100_000,
+                window_start_ts,
+                window_executions,
+                window_notional: 0,
+            }))
+        };
+
+        // One execution left in the window
+        let journal = run(windowed(1500, 1));
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+
+        // The window is full
+        let journal = run(windowed(1500, 2));
+        assert_eq!(journal.kernel_version, KERNEL_VERSION_V5);
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::WindowExecutionsExceeded)
+        );
+        assert_eq!(journal.action_commitment, EMPTY_OUTPUT_COMMITMENT);
+
+        // A full window that started a whole window length ago has elapsed
+        let journal = run(windowed(2000 - 600, 2));
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+
+        // A V1 snapshot carries no window usage
+        let journal = run(StateSnapshot::decode(&make_snapshot(2000)));
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::InvalidStateSnapshot)
+        );
+    }
+
+    /// Agent that charges one step per opaque input byte, then emits one NO_OP.
+    struct SteppingAgent;
+
+    impl kernel_guest::AgentEntrypoint for SteppingAgent {
+        fn code_hash(&self) -> [u8; 32] {
+            SNAPSHOT_PROBE_AGENT_HASH
+        }
+
+        fn run(&self, ctx: &kernel_sdk::agent::AgentContext, opaque_inputs: &[u8]) -> AgentOutput {
+            self.try_run(ctx, opaque_inputs)
+                .unwrap_or(AgentOutput { actions: vec![] })
+        }
+
+        fn try_run(
+            &self,
+            ctx: &kernel_sdk::agent::AgentContext,
+            opaque_inputs: &[u8],
+        ) -> Result<AgentOutput, kernel_sdk::agent::AgentError> {
+            for _ in opaque_inputs {
+                ctx.charge_steps(1)?;
+            }
+            Ok(AgentOutput {
+                actions: vec![ActionV1 {
+                    action_type: ACTION_TYPE_NO_OP,
+                    target: [0u8; 32],
+                    payload: vec![],
+                }],
+            })
+        }
+    }
+
+    #[test]
+    fn test_kernel_enforces_step_budget() {
+        use constraints::{compute_constraint_set_hash, ConstraintSetV3, RuleSet};
+        use kernel_guest::kernel_run_with_agent_and_rules;
+
+        let rules = RuleSet::new(ConstraintSetV3 {
+            step_budget: Some(1_000),
+            ..ConstraintSetV3::default()
+        });
+        let snapshot = StateSnapshot::decode(&make_snapshot(2000));
+        let run = |steps: usize| {
+            let mut input = make_snapshot_slot_input(snapshot, vec![0u8; steps], 2000);
+            input.constraint_set_hash = compute_constraint_set_hash(&rules).unwrap();
+            let input_bytes = input.encode().unwrap();
+            let run =
+                kernel_run_with_agent_and_rules(&input_bytes, &SteppingAgent, &rules).unwrap();
+            assert_eq!(
+                kernel_guest::kernel_main_with_agent_and_rules(
+                    &input_bytes,
+                    &SteppingAgent,
+                    &rules
+                )
+                .unwrap(),
+                run.journal
+            );
+            (KernelJournalV4::decode(&run.journal).unwrap(), run)
+        };
+
+        // Within budget, the agent's NO_OP is committed
+        let (journal, within) = run(1_000);
+        assert_eq!(within.agent_steps, 1_000);
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+        assert_ne!(journal.action_commitment, EMPTY_OUTPUT_COMMITMENT);
+
+        // The agent stops at the first step over budget, and the execution
+        // fails deterministically instead of reporting an agent error
+        let (journal, over) = run(5_000);
+        assert_eq!(over.agent_steps, 1_001);
+        assert_eq!(over.agent_error, None);
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::StepBudgetExceeded)
+        );
+        assert_eq!(journal.violation_action_index, None);
+        assert_eq!(journal.action_commitment, EMPTY_OUTPUT_COMMITMENT);
+        assert_eq!(run(5_000).1, over);
+    }
+
+    /// Agent that ignores an exhausted step budget and returns its output.
+    struct OverspendingAgent;
+
+    impl kernel_guest::AgentEntrypoint for OverspendingAgent {
+        fn code_hash(&self) -> [u8; 32] {
+            SNAPSHOT_PROBE_AGENT_HASH
+        }
+
+        fn run(&self, ctx: &kernel_sdk::agent::AgentContext, opaque_inputs: &[u8]) -> AgentOutput {
+            let _ = ctx.charge_steps(u64::MAX);
+            SnapshotProbeAgent.run(ctx, opaque_inputs)
+        }
+    }
+
+    #[test]
+    fn test_kernel_fails_agent_over_step_budget_that_returns_output() {
+        use constraints::{compute_constraint_set_hash, ConstraintSetV3, RuleSet};
+
+        let rules = RuleSet::new(ConstraintSetV3 {
+            step_budget: Some(1),
+            ..ConstraintSetV3::default()
+        });
+        let snapshot = StateSnapshot::decode(&make_snapshot(2000));
+        let mut input = make_snapshot_slot_input(snapshot, vec![], 2000);
+        input.constraint_set_hash = compute_constraint_set_hash(&rules).unwrap();
+
+        let journal_bytes = kernel_guest::kernel_main_with_agent_and_rules(
+            &input.encode().unwrap(),
+            &OverspendingAgent,
+            &rules,
+        )
+        .unwrap();
+        let journal = KernelJournalV4::decode(&journal_bytes).unwrap();
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::StepBudgetExceeded)
+        );
+        assert_eq!(journal.action_commitment, EMPTY_OUTPUT_COMMITMENT);
+    }
+
+    /// Agent that rejects every input, or emits one NO_OP when run infallibly.
+    struct RejectingAgent;
+
+    impl kernel_guest::AgentEntrypoint for RejectingAgent {
+        fn code_hash(&self) -> [u8; 32] {
+            SNAPSHOT_PROBE_AGENT_HASH
+        }
+
+        fn run(
+            &self,
+            _ctx: &kernel_sdk::agent::AgentContext,
+            _opaque_inputs: &[u8],
+        ) -> AgentOutput {
+            AgentOutput {
+                actions: vec![ActionV1 {
+                    action_type: ACTION_TYPE_NO_OP,
+                    target: [0u8; 32],
+                    payload: vec![],
+                }],
+            }
+        }
+
+        fn try_run(
+            &self,
+            _ctx: &kernel_sdk::agent::AgentContext,
+            _opaque_inputs: &[u8],
+        ) -> Result<AgentOutput, kernel_sdk::agent::AgentError> {
+            Err(kernel_sdk::agent::AgentError::Custom(7))
+        }
+    }
+
+    #[test]
+    fn test_kernel_commits_agent_error() {
+        use constraints::RuleSet;
+        use kernel_guest::kernel_run_with_agent_and_rules;
+        use kernel_sdk::agent::AgentError;
+
+        let snapshot = StateSnapshot::decode(&make_snapshot(2000));
+        let input_bytes = make_snapshot_slot_input(snapshot, vec![], 2000)
+            .encode()
+            .unwrap();
+        let rules = RuleSet::new(constraints::ConstraintSet::default());
+
+        // The kernel calls try_run: the error is committed as AgentError with
+        // the empty output and no violation details
+        let run = kernel_run_with_agent_and_rules(&input_bytes, &RejectingAgent, &rules).unwrap();
+        assert_eq!(run.agent_error, Some(AgentError::Custom(7)));
+        let journal = KernelJournalV4::decode(&run.journal).unwrap();
+        assert_eq!(journal.execution_status, ExecutionStatus::AgentError);
+        assert_eq!(journal.violation_reason, None);
+        assert_eq!(journal.violation_action_index, None);
+        assert_eq!(journal.action_commitment, EMPTY_OUTPUT_COMMITMENT);
+
+        // Agents that only implement run never produce an agent error
+        let run =
+            kernel_run_with_agent_and_rules(&input_bytes, &SnapshotProbeAgent, &rules).unwrap();
+        assert_eq!(run.agent_error, None);
+        let journal = KernelJournalV4::decode(&run.journal).unwrap();
+        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+    }
+
+    #[test]
+    fn test_unsupported_kernel_version_rejected() {
+        let mut input = make_input([0x11; 20], [0x22; 20], 1000);
+        input.kernel_version = 3;
+
+        assert!(matches!(
+            input.encode(),
+            Err(CodecError::InvalidVersion {
+                expected: 1,
+                actual: 3
+            })
+        ));
+    }
+
+    // ========================================================================
+    // Batched Execution Tests
+    // ========================================================================
+
+    /// Build a batch of `count` yield-agent steps with consecutive nonces.
+    fn make_batch(count: u64) -> KernelBatchInputV1 {
+        KernelBatchInputV1 {
+            protocol_version: PROTOCOL_VERSION,
+            steps: (0..count)
+                .map(|i| {
+                    let mut step = make_input([0x11; 20], [0x22; 20], 1000 + i);
+                    step.execution_nonce = 5 + i;
+                    step
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_batch_commits_standalone_step_journals() {
+        use constraints::ConstraintSet;
+        use example_yield_agent::kernel_batch_main_with_constraints;
+
+        let batch = make_batch(3);
+        let journal_bytes =
+            kernel_batch_main_with_constraints(&batch.encode().unwrap(), &ConstraintSet::default())
+                .unwrap();
+        let journal = KernelBatchJournalV1::decode(&journal_bytes).unwrap();
+
+        assert_eq!(journal.first_execution_nonce, 5);
+        assert_eq!(journal.agent_code_hash, AGENT_CODE_HASH);
+        assert_eq!(journal.constraint_set_hash, DEFAULT_CONSTRAINT_SET_HASH);
+        assert_eq!(journal.step_journal_hashes.len(), 3);
+
+        // Each committed hash matches the journal of the step run on its own
+        for (step, hash) in batch.steps.iter().zip(&journal.step_journal_hashes) {
+            let standalone = kernel_main(&step.encode().unwrap()).unwrap();
+            assert_eq!(compute_journal_hash(&standalone), *hash);
+        }
+    }
+
+    #[test]
+    fn test_batch_step_failure_does_not_abort_batch() {
+        use constraints::{compute_constraint_set_hash, ConstraintSet, ConstraintSetV1};
+        use example_yield_agent::{
+            kernel_batch_main_with_constraints, kernel_main_with_constraints,
+        };
+
+        // The yield agent emits two actions; a limit of one fails every step
+        let constraints = ConstraintSet::V1(ConstraintSetV1 {
+            max_actions_per_output: 1,
+            ..ConstraintSetV1::default()
+        });
+        let mut batch = make_batch(2);
+        for step in &mut batch.steps {
+            step.constraint_set_hash = compute_constraint_set_hash(&constraints).unwrap();
+        }
+
+        let journal_bytes =
+            kernel_batch_main_with_constraints(&batch.encode().unwrap(), &constraints).unwrap();
+        let journal = KernelBatchJournalV1::decode(&journal_bytes).unwrap();
+        assert_eq!(journal.step_journal_hashes.len(), 2);
+
+        for (step, hash) in batch.steps.iter().zip(&journal.step_journal_hashes) {
+            let standalone =
+                kernel_main_with_constraints(&step.encode().unwrap(), &constraints).unwrap();
+            let standalone_journal = KernelJournalV1::decode(&standalone).unwrap();
+            assert_eq!(
+                standalone_journal.execution_status,
+                ExecutionStatus::Failure
+            );
+            assert_eq!(compute_journal_hash(&standalone), *hash);
+        }
+    }
+
+    #[test]
+    fn test_batch_rejects_nonce_gap_and_mixed_steps() {
+        use constraints::ConstraintSet;
+        use example_yield_agent::kernel_batch_main_with_constraints;
+
+        let mut gapped = make_batch(3);
+        gapped.steps[2].execution_nonce += 1;
+        assert!(matches!(
+            kernel_batch_main_with_constraints(
+                &gapped.encode().unwrap(),
+                &ConstraintSet::default()
+            ),
+            Err(KernelError::NonConsecutiveBatchNonce { step: 2 })
+        ));
+
+        let mut mixed = make_batch(2);
+        mixed.steps[1].agent_id = [0x43; 32];
+        assert!(matches!(
+            kernel_batch_main_with_constraints(&mixed.encode().unwrap(), &ConstraintSet::default()),
+            Err(KernelError::BatchStepMismatch { step: 1 })
+        ));
+    }
+
     #[test]
     fn test_agent_code_hash_constant_is_stable() {
         // Verify the agent code hash is a 32-byte value (sanity check).
@@ -1560,6 +2534,285 @@ mod tests {
         );
     }
 
+    // ========================================================================
+    // Bounded Allocation Tests
+    // ========================================================================
+
+    #[test]
+    fn test_bounded_allocator_rejects_over_limit() {
+        use std::alloc::{GlobalAlloc, Layout};
+
+        let allocator = kernel_guest::BoundedAllocator::new(1024);
+        let small = Layout::from_size_align(512, 8).unwrap();
+        let large = Layout::from_size_align(2048, 8).unwrap();
+
+        unsafe {
+            let a = allocator.alloc(small);
+            assert!(!a.is_null());
+            assert_eq!(allocator.in_use(), 512);
+
+            // 512 + 1024 exceeds the limit
+            let b = allocator.alloc_zeroed(Layout::from_size_align(1024, 8).unwrap());
+            assert!(b.is_null());
+            assert!(allocator.alloc(large).is_null());
+            assert_eq!(allocator.in_use(), 512);
+
+            // Growing past the limit fails and keeps the original block
+            assert!(allocator.realloc(a, small, 2048).is_null());
+            assert_eq!(allocator.in_use(), 512);
+
+            let a = allocator.realloc(a, small, 1024);
+            assert!(!a.is_null());
+            assert_eq!(allocator.in_use(), 1024);
+
+            let shrunk = Layout::from_size_align(1024, 8).unwrap();
+            let a = allocator.realloc(a, shrunk, 256);
+            assert!(!a.is_null());
+            assert_eq!(allocator.in_use(), 256);
+
+            allocator.dealloc(a, Layout::from_size_align(256, 8).unwrap());
+        }
+
+        assert_eq!(allocator.in_use(), 0);
+        assert_eq!(allocator.peak(), 1024);
+        assert_eq!(allocator.limit(), 1024);
+    }
+
+    #[test]
+    fn test_measure_peak_allocation() {
+        let mut inner_peak = 0;
+        let outer_peak = kernel_guest::measure_peak_allocation(&mut || {
+            let held = vec![0u8; 4096];
+            inner_peak = kernel_guest::measure_peak_allocation(&mut || {
+                let temp = vec![0u8; 1024];
+                drop(temp);
+            });
+            drop(held);
+        });
+
+        assert_eq!(inner_peak, 1024);
+        assert_eq!(outer_peak, 4096 + 1024);
+        assert_eq!(kernel_guest::measure_peak_allocation(&mut || {}), 0);
+    }
+
+    #[test]
+    fn test_measure_peak_allocation_replays_guest_heap() {
+        // Frees return nothing, sizes round up to a whole word, wider
+        // alignment may need padding and a reallocation takes a fresh block
+        let allocated = kernel_guest::measure_peak_allocation(&mut || {
+            drop(vec![0u8; 1024]);
+            drop(vec![0u8; 1024]);
+            std::hint::black_box(Box::new(0u8));
+            std::hint::black_box(Box::new(0u64));
+            let mut grown = Vec::<u8>::with_capacity(10);
+            grown.reserve_exact(20);
+        });
+
+        assert_eq!(allocated, 1024 + 1024 + 4 + (8 + 4) + 12 + 20);
+    }
+
+    #[test]
+    fn test_agent_peak_allocation_with_max_size_input() {
+        // Only the agent's allocations count against the guest heap limit,
+        // so a maximum-size input does not use it up
+        let result = kernel_sdk::testing::TestHarness::new()
+            .input(vec![0xab; MAX_AGENT_INPUT_BYTES])
+            .allocation_probe(kernel_guest::measure_peak_allocation)
+            .execute(example_yield_agent::agent_main);
+
+        result.assert_action_count(0);
+        result.assert_peak_allocation_at_most(MAX_ALLOCATION_BYTES);
+    }
+
+    #[test]
+    fn test_kernel_peak_allocation_within_limit() {
+        let input = make_input([0x11; 20], [0x22; 20], 1000);
+
+        let result = kernel_sdk::testing::TestHarness::new()
+            .agent_id(input.agent_id)
+            .code_hash(input.agent_code_hash)
+            .input_root(input.input_root)
+            .nonce(input.execution_nonce)
+            .input(&input.opaque_agent_inputs)
+            .allocation_probe(kernel_guest::measure_peak_allocation)
+            .execute_kernel(kernel_main);
+
+        result.assert_success();
+        let peak = result.peak_allocation.unwrap();
+        assert!(peak > 0);
+        result.assert_peak_allocation_at_most(MAX_ALLOCATION_BYTES);
+    }
+
+    // ========================================================================
+    // Serde JSON Representation Tests
+    // ========================================================================
+
+    fn load_vectors(name: &str) -> serde_json::Value {
+        let path = format!(
+            "{}/../../../tests/vectors/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        let json = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        serde_json::from_str(&json).unwrap()
+    }
+
+    /// Vector fields with the `0x` prefix the serde representation adds to
+    /// byte fields (every string that is neither an enum variant name nor
+    /// the decimal-string `max_position_notional`).
+    fn with_hex_prefix(mut fields: serde_json::Value) -> serde_json::Value {
+        for (key, value) in fields.as_object_mut().unwrap().iter_mut() {
+            if let Some(s) = value.as_str() {
+                if key != "max_position_notional" && s.bytes().all(|c| c.is_ascii_hexdigit()) {
+                    *value = format!("0x{}", s).into();
+                }
+            }
+        }
+        fields
+    }
+
+    /// Deserialize each vector's fields, check the canonical encoding, and
+    /// check serialization reproduces the fields.
+    fn check_json_vectors<T>(name: &str)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + CanonicalEncode,
+    {
+        let vectors = load_vectors(name);
+        for vector in vectors["vectors"].as_array().unwrap() {
+            let fields = vector["fields"].clone();
+            let value: T = serde_json::from_value(fields.clone()).unwrap();
+            assert_eq!(
+                value.encode().unwrap(),
+                hex_to_vec(vector["encoded_hex"].as_str().unwrap()),
+                "{} {}",
+                name,
+                vector["name"]
+            );
+            assert_eq!(
+                serde_json::to_value(&value).unwrap(),
+                with_hex_prefix(fields),
+                "{} {}",
+                name,
+                vector["name"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_serde_kernel_input_v1_vectors() {
+        check_json_vectors::<KernelInputV1>("kernel_input_v1.json");
+    }
+
+    #[test]
+    fn test_serde_kernel_journal_vectors() {
+        check_json_vectors::<KernelJournalV1>("kernel_journal_v1.json");
+        check_json_vectors::<KernelJournalV2>("kernel_journal_v2.json");
+    }
+
+    #[test]
+    fn test_serde_constraint_vectors() {
+        use constraints::{ConstraintSetV1, StateSnapshotV1};
+
+        let vectors = load_vectors("constraints/constraint_vectors.json");
+        for vector in vectors["vectors"].as_array().unwrap() {
+            let constraint_set: ConstraintSetV1 =
+                serde_json::from_value(vector["constraint_set"].clone()).unwrap();
+            assert_eq!(
+                serde_json::to_value(&constraint_set).unwrap(),
+                with_hex_prefix(vector["constraint_set"].clone())
+            );
+
+            let snapshot: Option<StateSnapshotV1> =
+                serde_json::from_value(vector["state_snapshot"].clone()).unwrap();
+            assert_eq!(
+                serde_json::to_value(snapshot).unwrap(),
+                vector["state_snapshot"]
+            );
+
+            // The vectors name the payload `payload_hex`; the type calls it `payload`
+            let actions: Vec<serde_json::Value> = vector["proposed_actions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|action| {
+                    serde_json::json!({
+                        "action_type": action["action_type"],
+                        "target": action["target"],
+                        "payload": action["payload_hex"],
+                    })
+                })
+                .collect();
+            let output: AgentOutput =
+                serde_json::from_value(serde_json::json!({ "actions": actions })).unwrap();
+            for (action, json) in output.actions.iter().zip(&actions) {
+                assert_eq!(
+                    serde_json::to_value(action).unwrap(),
+                    with_hex_prefix(json.clone())
+                );
+            }
+            let json = serde_json::to_string(&output).unwrap();
+            assert_eq!(serde_json::from_str::<AgentOutput>(&json).unwrap(), output);
+        }
+    }
+
+    #[test]
+    fn test_serde_json_representation() {
+        let journal = KernelJournalV2 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION_V2,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xaa; 32],
+            constraint_set_hash: [0xbb; 32],
+            input_root: [0xcc; 32],
+            execution_nonce: 7,
+            input_commitment: [0xdd; 32],
+            action_commitment: [0xee; 32],
+            execution_status: ExecutionStatus::Failure,
+            violation_reason: Some(ConstraintViolationReason::InvalidActionPayload),
+            violation_action_index: Some(2),
+        };
+        let json = serde_json::to_value(&journal).unwrap();
+        assert_eq!(json["agent_id"], format!("0x{}", "42".repeat(32)));
+        assert_eq!(json["execution_status"], "Failure");
+        assert_eq!(json["violation_reason"], "0x0a");
+        assert_eq!(json["violation_action_index"], 2);
+        assert_eq!(
+            serde_json::from_value::<KernelJournalV2>(json).unwrap(),
+            journal
+        );
+
+        // Batch journals carry a list of hashes
+        let batch = KernelBatchJournalV1 {
+            protocol_version: PROTOCOL_VERSION,
+            kernel_version: KERNEL_VERSION,
+            agent_id: [0x42; 32],
+            agent_code_hash: [0xaa; 32],
+            constraint_set_hash: [0xbb; 32],
+            first_execution_nonce: 1,
+            step_journal_hashes: vec![[0x01; 32], [0x02; 32]],
+        };
+        let json = serde_json::to_value(&batch).unwrap();
+        assert_eq!(
+            json["step_journal_hashes"][1],
+            format!("0x{}", "02".repeat(32))
+        );
+        assert_eq!(
+            serde_json::from_value::<KernelBatchJournalV1>(json).unwrap(),
+            batch
+        );
+
+        // Malformed byte fields and unknown reason codes are rejected
+        let mut bad = serde_json::to_value(&journal).unwrap();
+        bad["agent_id"] = "0x4242".into();
+        assert!(serde_json::from_value::<KernelJournalV2>(bad).is_err());
+        let mut bad = serde_json::to_value(&journal).unwrap();
+        bad["input_root"] = format!("0x{}", "zz".repeat(32)).into();
+        assert!(serde_json::from_value::<KernelJournalV2>(bad).is_err());
+        let mut bad = serde_json::to_value(&journal).unwrap();
+        bad["violation_reason"] = "0x00".into();
+        assert!(serde_json::from_value::<KernelJournalV2>(bad).is_err());
+    }
+
     // ========================================================================
     // Test Helpers
     // ========================================================================
@@ -1586,7 +2839,7 @@ mod tests {
 
 #[cfg(test)]
 mod defi_yield_farmer_tests {
-    use constraints::EMPTY_OUTPUT_COMMITMENT;
+    use constraints::{DEFAULT_CONSTRAINT_SET_HASH, EMPTY_OUTPUT_COMMITMENT};
     use kernel_core::*;
     use defi_yield_farmer::kernel_main as defi_kernel_main;
 
@@ -1624,7 +2877,7 @@ mod defi_yield_farmer_tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: DEFI_AGENT_CODE_HASH,
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 1,
             opaque_agent_inputs: opaque,
@@ -1712,14 +2965,46 @@ mod defi_yield_farmer_tests {
 
     #[test]
     fn test_defi_yield_farmer_invalid_input_size() {
-        // Wrong input size -> empty output
+        // Wrong input size -> agent error, distinct from a no-action Success.
+        // The frozen v1 journal commits it as a Failure.
         let opaque = vec![0u8; 10]; // 10 bytes, not 89
         let input = make_defi_input(opaque);
         let input_bytes = input.encode().unwrap();
         let journal_bytes = defi_kernel_main(&input_bytes).expect("kernel execution");
         let journal = KernelJournalV1::decode(&journal_bytes).unwrap();
 
-        assert_eq!(journal.execution_status, ExecutionStatus::Success);
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(journal.action_commitment, EMPTY_OUTPUT_COMMITMENT);
+        assert_eq!(journal_bytes[208], 0x02);
+    }
+
+    #[test]
+    fn test_defi_yield_farmer_unknown_flag_v2() {
+        // Unknown action flag -> agent error, committed by the frozen v2
+        // journal as a global InvalidOutputStructure failure
+        let opaque = make_defi_opaque_input(
+            [0x11u8; 20],
+            [0x22u8; 20],
+            [0x33u8; 20],
+            500_000,
+            0,
+            500,
+            200,
+            8000,
+            99,   // unknown flag
+        );
+        let mut input = make_defi_input(opaque);
+        input.kernel_version = KERNEL_VERSION_V2;
+        let input_bytes = input.encode().unwrap();
+        let journal_bytes = defi_kernel_main(&input_bytes).expect("kernel execution");
+        let journal = KernelJournalV2::decode(&journal_bytes).unwrap();
+
+        assert_eq!(journal.execution_status, ExecutionStatus::Failure);
+        assert_eq!(
+            journal.violation_reason,
+            Some(ConstraintViolationReason::InvalidOutputStructure)
+        );
+        assert_eq!(journal.violation_action_index, None);
         assert_eq!(journal.action_commitment, EMPTY_OUTPUT_COMMITMENT);
     }
 
@@ -1813,7 +3098,7 @@ mod defi_yield_farmer_tests {
             kernel_version: KERNEL_VERSION,
             agent_id: [0x42; 32],
             agent_code_hash: [0xde; 32], // WRONG hash
-            constraint_set_hash: [0xbb; 32],
+            constraint_set_hash: DEFAULT_CONSTRAINT_SET_HASH,
             input_root: [0xcc; 32],
             execution_nonce: 1,
             opaque_agent_inputs: opaque,